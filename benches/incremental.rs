@@ -0,0 +1,83 @@
+//! Latency of analyzing a module of about 20,000 lines from scratch compared to
+//! analyzing it again after typical edits.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use std::fmt::Write;
+use yang_utils::incremental::IncrementalModule;
+
+const CONTAINERS: usize = 800;
+
+/// A module with a typedef and a container of about 25 lines for each of
+/// `CONTAINERS`.
+fn module() -> String {
+    let mut source = String::from(
+        "module bench {\n  namespace \"urn:bench\";\n  prefix b;\n\n  typedef percent {\n    type uint8 {\n      range \"0..100\";\n    }\n  }\n",
+    );
+    for index in 0..CONTAINERS {
+        write!(
+            source,
+            r#"
+  typedef counter-{index} {{
+    type uint32 {{
+      range "0..{index}000";
+    }}
+    description "Counter {index}.";
+  }}
+
+  container system-{index} {{
+    description "System {index}.";
+    leaf load {{
+      type percent;
+      description
+        "The load of
+         system {index}.";
+    }}
+    leaf count {{
+      type counter-{index};
+    }}
+    container limits {{
+      leaf max {{ type uint16 {{ range "1..1024"; }} }}
+      leaf min {{ type uint16; }}
+      leaf-list names {{ type string {{ length "1..64"; }} }}
+    }}
+  }}
+"#
+        )
+        .unwrap();
+    }
+    source.push_str("}\n");
+    source
+}
+
+fn bench_incremental(c: &mut Criterion) {
+    let source = module();
+    c.bench_function("analyze", |b| {
+        b.iter(|| IncrementalModule::new("bench.yang", black_box(source.clone())))
+    });
+
+    // Edits of a nested leaf, a typedef many definitions depend on and an insertion
+    // of a new definition, each undone again so every iteration starts alike.
+    let middle = source.find(&format!("system-{}", CONTAINERS / 2)).unwrap();
+    let description = middle + source[middle..].find("system ").unwrap();
+    let percent = source.find("0..100").unwrap();
+    let end = source.len() - 2;
+    let edits = [
+        ("keystroke", description..description + 6, "System"),
+        ("typedef", percent..percent + 6, "0..90"),
+        ("insert", end..end, "  leaf extra { type percent; }\n"),
+    ];
+    for (name, range, text) in edits {
+        let mut module = IncrementalModule::new("bench.yang", source.clone());
+        c.bench_function(name, |b| {
+            b.iter(|| {
+                module.edit(range.clone(), text);
+                let undo = range.start..range.start + text.len();
+                module.edit(undo, &source[range.clone()]);
+                black_box(module.diagnostics())
+            })
+        });
+    }
+}
+
+criterion_group!(benches, bench_incremental);
+criterion_main!(benches);
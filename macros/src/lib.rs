@@ -0,0 +1,103 @@
+//! `include_yang!` generates Rust types for a YANG module at compile time.
+//!
+//! ```ignore
+//! include_yang!("models/acme-system.yang", search_path = "models", feature = "ssh");
+//! include_yang!("models/acme-system.yang", prune_obsolete = true);
+//! ```
+//!
+//! Paths are relative to the directory of the crate's `Cargo.toml`. Diagnostics
+//! of the module become compile errors pointing to the file and line in the
+//! module, and the crate is rebuilt when the module or any module it imports
+//! changes.
+
+use proc_macro::TokenStream;
+use proc_macro2::Span;
+use quote::quote;
+use std::path::{Path, PathBuf};
+use syn::parse::{Parse, ParseStream};
+use syn::{parse_macro_input, Ident, LitBool, LitStr, Token};
+use yang_utils::bindings::Builder;
+
+struct IncludeYang {
+    path: LitStr,
+    search_paths: Vec<LitStr>,
+    features: Vec<LitStr>,
+    prune_obsolete: bool,
+}
+
+impl Parse for IncludeYang {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut include = IncludeYang {
+            path: input.parse()?,
+            search_paths: vec![],
+            features: vec![],
+            prune_obsolete: false,
+        };
+        while !input.is_empty() {
+            input.parse::<Token![,]>()?;
+            if input.is_empty() {
+                break;
+            }
+            let option: Ident = input.parse()?;
+            input.parse::<Token![=]>()?;
+            match option.to_string().as_str() {
+                "search_path" => include.search_paths.push(input.parse()?),
+                "feature" => include.features.push(input.parse()?),
+                "prune_obsolete" => include.prune_obsolete = input.parse::<LitBool>()?.value,
+                _ => {
+                    return Err(syn::Error::new(
+                        option.span(),
+                        "expected `search_path`, `feature` or `prune_obsolete`",
+                    ))
+                }
+            }
+        }
+        Ok(include)
+    }
+}
+
+fn manifest_path(path: &LitStr) -> PathBuf {
+    let manifest_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap_or_default();
+    Path::new(&manifest_dir).join(path.value())
+}
+
+#[proc_macro]
+pub fn include_yang(input: TokenStream) -> TokenStream {
+    let include = parse_macro_input!(input as IncludeYang);
+
+    let mut builder = Builder::new(manifest_path(&include.path));
+    for search_path in &include.search_paths {
+        builder = builder.search_path(manifest_path(search_path));
+    }
+    for feature in &include.features {
+        builder = builder.feature(feature.value());
+    }
+    builder = builder.prune_obsolete(include.prune_obsolete);
+    let bindings = match builder.generate() {
+        Ok(bindings) => bindings,
+        Err(error) => {
+            return syn::Error::new(include.path.span(), error.to_string())
+                .to_compile_error()
+                .into();
+        }
+    };
+
+    let source: proc_macro2::TokenStream = match bindings.source().parse() {
+        Ok(source) => source,
+        Err(error) => {
+            return syn::Error::new(include.path.span(), error.to_string())
+                .to_compile_error()
+                .into();
+        }
+    };
+    // Including the modules as bytes makes cargo track them for rebuilds.
+    let dependencies = bindings.dependencies().iter().map(|dependency| {
+        let dependency = LitStr::new(&dependency.to_string_lossy(), Span::call_site());
+        quote! { const _: &[u8] = include_bytes!(#dependency); }
+    });
+    quote! {
+        #(#dependencies)*
+        #source
+    }
+    .into()
+}
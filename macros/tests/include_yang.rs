@@ -0,0 +1,50 @@
+mod acme {
+    yang_utils_macros::include_yang!("../tests/corpus/acme-system.yang");
+}
+
+mod features {
+    yang_utils_macros::include_yang!("tests/models/features.yang", feature = "ssh");
+}
+
+mod obsolete {
+    yang_utils_macros::include_yang!("tests/models/obsolete.yang", prune_obsolete = true);
+}
+
+#[test]
+fn test_include_yang() {
+    let mut system = acme::System {
+        host_name: Some("router".to_string()),
+        domain_search: vec!["example.com".to_string()],
+        login: acme::Login {
+            message: None,
+            user: Default::default(),
+        },
+    };
+    system.login.user.insert(
+        "admin".to_string(),
+        acme::User {
+            name: "admin".to_string(),
+            full_name: None,
+            class: Some("wheel".to_string()),
+        },
+    );
+    let root = acme::AcmeSystem {
+        system: system.clone(),
+    };
+    assert_eq!(root.system.login.user["admin"].class.as_deref(), Some("wheel"));
+}
+
+#[test]
+fn test_features() {
+    let server = features::Server {
+        port: 22,
+        ssh_key: Some(features::KeyType::Rsa),
+    };
+    assert_eq!(server.ssh_key.map(|key| key as i32), Some(1));
+}
+
+#[test]
+fn test_prune_obsolete() {
+    let server = obsolete::Server { port: 8080 };
+    assert_eq!(server.port, 8080);
+}
@@ -0,0 +1,443 @@
+//! Generation of Rust bindings for a YANG module, for use from `build.rs` or the
+//! `include_yang!` macro.
+//!
+//! ```no_run
+//! // build.rs
+//! let bindings = yang_utils::bindings::Builder::new("models/acme-system.yang")
+//!     .search_path("models")
+//!     .generate()
+//!     .unwrap_or_else(|error| panic!("{}", error));
+//! bindings.emit_cargo_directives();
+//! let out_dir = std::env::var("OUT_DIR").unwrap();
+//! bindings
+//!     .write_to_file(std::path::Path::new(&out_dir).join("acme_system.rs"))
+//!     .unwrap();
+//! ```
+
+use crate::codegen::generate;
+use crate::errors::ErrorContext;
+use crate::repository::{LoadError, ModuleKind, Repository};
+use crate::schema::{compile, enable_features, prune_obsolete};
+use miette::Severity;
+use std::collections::HashSet;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+pub use crate::errors::TextEdit;
+
+/// A diagnostic reported for a YANG file, with a 1-based line and column.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub path: PathBuf,
+    pub line: usize,
+    pub column: usize,
+    pub is_error: bool,
+    pub message: String,
+    /// A suggestion how to fix the problem, like the keyword probably meant.
+    pub help: Option<String>,
+    /// Edits of the file fixing the problem, which can be applied automatically.
+    pub fixes: Vec<TextEdit>,
+    /// Other places in the file the problem involves, like an earlier definition
+    /// of the same name.
+    pub related: Vec<Related>,
+}
+
+/// A place in the file of a [`Diagnostic`] it involves, with a 1-based line and
+/// column.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Related {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}:{}: {}: {}",
+            self.path.display(),
+            self.line,
+            self.column,
+            if self.is_error { "error" } else { "warning" },
+            self.message
+        )?;
+        for related in &self.related {
+            write!(
+                f,
+                "\n  {}:{}:{}: note: {}",
+                self.path.display(),
+                related.line,
+                related.column,
+                related.message
+            )?;
+        }
+        if let Some(help) = &self.help {
+            write!(f, "\n  help: {}", help)?;
+        }
+        Ok(())
+    }
+}
+
+/// The 1-based line and column of `offset` in `source`.
+pub(crate) fn line_column(source: &str, offset: usize) -> (usize, usize) {
+    let before = &source[..offset.min(source.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = before[before.rfind('\n').map_or(0, |index| index + 1)..]
+        .chars()
+        .count()
+        + 1;
+    (line, column)
+}
+
+pub(crate) fn diagnostics(
+    path: &Path,
+    source: &str,
+    error_context: &ErrorContext,
+) -> Vec<Diagnostic> {
+    error_context
+        .diagnostics_with_fixes()
+        .map(|(diagnostic, fixes)| {
+            let labels = diagnostic.labels.as_deref().unwrap_or_default();
+            let offset = labels.first().map_or(0, |label| label.offset());
+            let (line, column) = line_column(source, offset);
+            let related = labels
+                .iter()
+                .skip(1)
+                .map(|label| {
+                    let (line, column) = line_column(source, label.offset());
+                    Related {
+                        line,
+                        column,
+                        message: label.label().unwrap_or_default().to_string(),
+                    }
+                })
+                .collect();
+            Diagnostic {
+                path: path.to_path_buf(),
+                line,
+                column,
+                is_error: diagnostic.severity != Some(Severity::Warning),
+                message: diagnostic.message.clone(),
+                help: diagnostic.help.clone(),
+                fixes: fixes.to_vec(),
+                related,
+            }
+        })
+        .collect()
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Io {
+        path: PathBuf,
+        error: io::Error,
+    },
+    /// A module is invalid or could not be translated.
+    Yang(Vec<Diagnostic>),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io { path, error } => write!(f, "{}: {}", path.display(), error),
+            Error::Yang(diagnostics) => {
+                for (index, diagnostic) in diagnostics.iter().enumerate() {
+                    if index > 0 {
+                        writeln!(f)?;
+                    }
+                    write!(f, "{}", diagnostic)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<LoadError> for Error {
+    fn from(error: LoadError) -> Self {
+        match error {
+            LoadError::Io(path, error) => Error::Io { path, error },
+            LoadError::Invalid {
+                path,
+                source,
+                error_context,
+            } => Error::Yang(diagnostics(&path, &source, &error_context)),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Builder {
+    path: PathBuf,
+    search_paths: Vec<PathBuf>,
+    features: Option<HashSet<String>>,
+    strict: bool,
+    prune_obsolete: bool,
+}
+
+impl Builder {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            search_paths: vec![],
+            features: None,
+            strict: false,
+            prune_obsolete: false,
+        }
+    }
+
+    /// Adds a directory to search imported and included modules in. The directory
+    /// of the module itself is always searched last.
+    pub fn search_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.search_paths.push(path.into());
+        self
+    }
+
+    /// Enables `feature`. Without enabled features all features are enabled,
+    /// otherwise nodes depending on other features are left out.
+    pub fn feature(mut self, feature: impl Into<String>) -> Self {
+        self.features
+            .get_or_insert_with(HashSet::new)
+            .insert(feature.into());
+        self
+    }
+
+    /// Enables strict mode, in which statements out of the order of the YANG grammar,
+    /// invalid `revision`, `yang-version` and `namespace` arguments and unknown
+    /// keywords are errors.
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Leaves out the nodes whose status is obsolete, directly or through one of
+    /// their ancestors.
+    pub fn prune_obsolete(mut self, prune_obsolete: bool) -> Self {
+        self.prune_obsolete = prune_obsolete;
+        self
+    }
+
+    pub fn generate(&self) -> Result<Bindings, Error> {
+        let mut repository = Repository::new(self.search_paths.clone()).strict(self.strict);
+        let index = repository.load(&self.path)?;
+        let loaded = &repository.modules[index];
+        let ModuleKind::Module(module) = &loaded.kind else {
+            return Err(Error::Yang(vec![Diagnostic {
+                path: loaded.path.clone(),
+                line: 1,
+                column: 1,
+                is_error: true,
+                message: "Bindings can only be generated for modules".to_string(),
+                help: None,
+                fixes: vec![],
+                related: vec![],
+            }]));
+        };
+
+        let mut error_context = ErrorContext::new();
+        let schema = compile(module, &repository, &mut error_context).and_then(|mut schema| {
+            if let Some(features) = &self.features {
+                enable_features(&mut schema, &|_, feature| features.contains(feature));
+            }
+            if self.prune_obsolete {
+                prune_obsolete(&mut schema);
+            }
+            generate(&schema, &mut error_context)
+        });
+        let Ok(source) = schema else {
+            return Err(Error::Yang(diagnostics(
+                &loaded.path,
+                &loaded.source,
+                &error_context,
+            )));
+        };
+
+        let warnings = repository
+            .modules
+            .iter()
+            .flat_map(|module| diagnostics(&module.path, &module.source, &module.error_context))
+            .chain(diagnostics(&loaded.path, &loaded.source, &error_context))
+            .collect();
+        Ok(Bindings {
+            source,
+            dependencies: repository.paths().map(Path::to_path_buf).collect(),
+            warnings,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Bindings {
+    source: String,
+    dependencies: Vec<PathBuf>,
+    warnings: Vec<Diagnostic>,
+}
+
+impl Bindings {
+    /// The generated Rust source.
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Paths of the module and of all modules it imports or includes.
+    pub fn dependencies(&self) -> &[PathBuf] {
+        &self.dependencies
+    }
+
+    pub fn warnings(&self) -> &[Diagnostic] {
+        &self.warnings
+    }
+
+    pub fn write_to_file(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, &self.source)
+    }
+
+    /// Prints `cargo:rerun-if-changed` for all dependencies and `cargo:warning`
+    /// for all warnings, for use in `build.rs`.
+    pub fn emit_cargo_directives(&self) {
+        for dependency in &self.dependencies {
+            println!("cargo:rerun-if-changed={}", dependency.display());
+        }
+        for warning in &self.warnings {
+            println!("cargo:warning={}", warning);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn corpus(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/corpus")
+            .join(name)
+    }
+
+    #[test]
+    fn test_generate() {
+        let bindings = Builder::new(corpus("acme-system.yang")).generate().unwrap();
+        assert!(bindings.source().contains("pub struct System {"));
+        assert_eq!(bindings.dependencies(), [corpus("acme-system.yang")]);
+        assert!(bindings.warnings().is_empty());
+    }
+
+    #[test]
+    fn test_features() {
+        let directory = std::env::temp_dir().join("yang-utils-bindings-test");
+        fs::create_dir_all(&directory).unwrap();
+        let path = directory.join("features.yang");
+        fs::write(
+            &path,
+            "module features {\n  namespace urn:features;\n  prefix f;\n  feature a;\n  \
+             feature b;\n  leaf x { if-feature a; type string; }\n  \
+             leaf y { if-feature \"f:b\"; type string; }\n}\n",
+        )
+        .unwrap();
+
+        let source = Builder::new(&path).generate().unwrap().source;
+        assert!(source.contains("pub x:") && source.contains("pub y:"));
+        let source = Builder::new(&path).feature("b").generate().unwrap().source;
+        assert!(!source.contains("pub x:") && source.contains("pub y:"));
+    }
+
+    #[test]
+    fn test_prune_obsolete() {
+        let directory = std::env::temp_dir().join("yang-utils-bindings-test-obsolete");
+        fs::create_dir_all(&directory).unwrap();
+        let path = directory.join("obsolete.yang");
+        fs::write(
+            &path,
+            "module obsolete {\n  namespace urn:obsolete;\n  prefix o;\n  \
+             container c {\n    leaf x { type string; }\n    \
+             container old { status obsolete; leaf y { type string; } }\n  }\n}\n",
+        )
+        .unwrap();
+
+        let source = Builder::new(&path).generate().unwrap().source;
+        assert!(source.contains("pub struct Old {"));
+        let source = Builder::new(&path)
+            .prune_obsolete(true)
+            .generate()
+            .unwrap()
+            .source;
+        assert!(source.contains("pub x:") && !source.contains("Old"));
+    }
+
+    #[test]
+    fn test_error() {
+        let directory = std::env::temp_dir().join("yang-utils-bindings-test-error");
+        fs::create_dir_all(&directory).unwrap();
+        let path = directory.join("invalid.yang");
+        fs::write(
+            &path,
+            "module invalid {\n  namespace urn:invalid;\n  prefix i;\n  leaf x { type unknown; }\n}\n",
+        )
+        .unwrap();
+
+        let error = Builder::new(&path).generate().unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            format!("{}:4:17: error: Type unknown not found", path.display())
+        );
+    }
+
+    #[test]
+    fn test_related() {
+        let directory = std::env::temp_dir().join("yang-utils-bindings-test-related");
+        fs::create_dir_all(&directory).unwrap();
+        let path = directory.join("related.yang");
+        fs::write(
+            &path,
+            "module related {\n  namespace urn:related;\n  prefix r;\n  \
+             typedef name { type string; }\n  typedef name { type uint8; }\n}\n",
+        )
+        .unwrap();
+
+        let Err(Error::Yang(diagnostics)) = Builder::new(&path).generate() else {
+            panic!("expected diagnostics");
+        };
+        assert_eq!(
+            diagnostics[0].related,
+            [Related {
+                line: 4,
+                column: 11,
+                message: "previously defined here".to_string(),
+            }]
+        );
+        assert_eq!(
+            diagnostics[0].to_string(),
+            format!(
+                "{0}:5:11: error: Typedef name is already defined\n  \
+                 {0}:4:11: note: previously defined here",
+                path.display()
+            )
+        );
+    }
+
+    #[test]
+    fn test_strict() {
+        let directory = std::env::temp_dir().join("yang-utils-bindings-test-strict");
+        fs::create_dir_all(&directory).unwrap();
+        let path = directory.join("strict.yang");
+        fs::write(
+            &path,
+            "module strict {\n  prefix s;\n  namespace urn:strict;\n  \
+             leaf x { type string; colour blue; }\n}\n",
+        )
+        .unwrap();
+
+        assert!(Builder::new(&path).generate().is_ok());
+        let error = Builder::new(&path).strict(true).generate().unwrap_err();
+        assert!(error.to_string().contains(&format!(
+            "{}:4:25: error: Unknown keyword colour",
+            path.display()
+        )));
+        assert!(!error.to_string().contains("Unexpected keyword colour"));
+    }
+}
@@ -0,0 +1,1513 @@
+//! The `yang-utils` command-line tool, covering what pipelines commonly use pyang
+//! for.
+//!
+//! ```text
+//! yang-utils -p models check models/acme-system.yang
+//! yang-utils check --fix models/acme-system.yang
+//! yang-utils lint --config yang-lint.json models/*.yang
+//! yang-utils format --in-place models/*.yang
+//! yang-utils -p models tree --tree-depth 2 models/acme-system.yang
+//! yang-utils -p models convert models/acme-system.yang -o acme-system.yin
+//! yang-utils -p models --features acme-system:ssh validate -m models/acme-system.yang config.json
+//! yang-utils get -m models/acme-system.yang config.json /acme-system:system/host-name
+//! yang-utils edit -m models/acme-system.yang config.json --replace /acme-system:system/host-name=r1
+//! yang-utils compat old/acme-system.yang models/acme-system.yang
+//! ```
+//!
+//! Diagnostics are printed to standard error as `path:line:column: severity: message`,
+//! followed by a line with a suggestion how to fix the problem if there is one.
+//! The exit code is 0 on success, 1 if a problem was found and 2 on invalid usage.
+
+use crate::bindings::{diagnostics, line_column, Diagnostic, Error, TextEdit};
+use crate::compat::{self, Compatibility};
+use crate::data::conditions::check_conditions;
+use crate::data::json::{parse_json_modules, write_json, write_json_node};
+use crate::data::path::InstancePath;
+use crate::data::xml::parse_xml_modules;
+use crate::data::{top_level_owner, DataError, DataTree};
+use crate::errors::ErrorContext;
+use crate::format::{format, format_statement, is_formatted, FormatOptions};
+use crate::lint::{lint, LintConfig};
+use crate::parser::model_mapper::Unmapper;
+use crate::parser::parser::{parse, ParseError};
+use crate::repository::{ModuleKind, Repository};
+use crate::schema::{compile, enable_features, merge_augments, prune_obsolete, Schema};
+use crate::tree::{render_tree, TreeOptions};
+use crate::yin::{parse_yin, write_yin, YinContext};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::ffi::OsString;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+#[derive(Debug, Parser)]
+#[command(
+    name = "yang-utils",
+    version,
+    about = "Check, format and convert YANG modules"
+)]
+struct Cli {
+    /// Directories to search imported and included modules in. May be given
+    /// multiple times, or as a list separated like `PATH`.
+    #[arg(short = 'p', long = "path", global = true, value_name = "DIR")]
+    search_paths: Vec<OsString>,
+    /// Features to enable, as `module:feature,...` for one module or `feature,...`
+    /// for the modules not named. All features of a module are enabled if neither
+    /// applies to it, none if given as `module:`.
+    #[arg(long, global = true, value_name = "FEATURES")]
+    features: Vec<String>,
+    /// Check modules in strict mode, reporting violations of the statement order
+    /// and header rules of the YANG grammar and unknown keywords.
+    #[arg(long, global = true)]
+    strict: bool,
+    /// Leave out the nodes whose status is obsolete.
+    #[arg(long, global = true)]
+    prune_obsolete: bool,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Compile modules and print their errors and warnings.
+    Check {
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+        /// Rewrite the files with the suggested fixes applied. Only the problems
+        /// remaining afterwards are reported.
+        #[arg(long)]
+        fix: bool,
+    },
+    /// Check modules against the guidelines of RFC 8407 for module authors.
+    Lint {
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+        /// JSON file mapping rule names to `allow`, `warn` or `deny`. All rules
+        /// warn if not given.
+        #[arg(long)]
+        config: Option<PathBuf>,
+    },
+    /// Print modules in the canonical format, keeping their comments.
+    Format {
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+        /// Rewrite the files instead of printing them.
+        #[arg(short, long, conflicts_with = "check")]
+        in_place: bool,
+        /// Only check that the files are formatted.
+        #[arg(long)]
+        check: bool,
+        /// Number of spaces per indentation level.
+        #[arg(long, default_value_t = 2)]
+        indent: usize,
+        /// Line width text arguments are reflowed to.
+        #[arg(long, default_value_t = 70)]
+        line_width: usize,
+        /// Keep text arguments like descriptions as they are.
+        #[arg(long)]
+        no_reflow: bool,
+        /// Keep the order of the statements.
+        #[arg(long)]
+        keep_order: bool,
+    },
+    /// Print the RFC 8340 tree diagram of modules.
+    Tree {
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+        /// Maximum number of levels printed, deeper levels are replaced by `...`.
+        #[arg(long)]
+        tree_depth: Option<usize>,
+        /// Schema node identifier of the subtree to print, like `/system/login`.
+        #[arg(long)]
+        tree_path: Option<String>,
+        #[command(flatten)]
+        view: ViewArgs,
+    },
+    /// Convert a module from YANG to YIN or back.
+    Convert {
+        file: PathBuf,
+        /// File to write to instead of printing.
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        /// The output format. Defaults to the format of the output file, or to
+        /// the other format than the one of the input.
+        #[arg(short, long)]
+        to: Option<Syntax>,
+    },
+    /// Validate JSON or XML instance data against a set of modules.
+    Validate {
+        /// Module the data is validated against. May be given multiple times.
+        #[arg(short, long = "module", required = true)]
+        modules: Vec<PathBuf>,
+        /// Instance data, `.json` files in the RFC 7951 encoding and `.xml` files.
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+        #[command(flatten)]
+        view: ViewArgs,
+    },
+    /// Print the nodes of JSON or XML instance data at instance identifiers like
+    /// `/acme-system:system/host-name`, in the JSON encoding.
+    Get {
+        /// Module the data is validated against. May be given multiple times.
+        #[arg(short, long = "module", required = true)]
+        modules: Vec<PathBuf>,
+        /// Instance data, a `.json` file in the RFC 7951 encoding or an `.xml` file.
+        file: PathBuf,
+        #[arg(required = true)]
+        paths: Vec<String>,
+    },
+    /// Edit JSON or XML instance data and print the result in the JSON encoding.
+    /// The nodes are created and replaced first, then deleted.
+    Edit {
+        /// Module the data is validated against. May be given multiple times.
+        #[arg(short, long = "module", required = true)]
+        modules: Vec<PathBuf>,
+        /// Instance data, a `.json` file in the RFC 7951 encoding or an `.xml` file.
+        file: PathBuf,
+        /// Create the node at an instance identifier and its missing ancestors,
+        /// failing if it exists. The value of a leaf follows a `=`.
+        #[arg(long, value_name = "PATH[=VALUE]")]
+        create: Vec<String>,
+        /// Create the node at an instance identifier, or replace it if it exists.
+        #[arg(long, value_name = "PATH[=VALUE]")]
+        replace: Vec<String>,
+        /// Delete the node at an instance identifier.
+        #[arg(long, value_name = "PATH")]
+        delete: Vec<String>,
+        /// File to write to instead of printing.
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Compare two revisions of a module by the update rules of RFC 7950,
+    /// section 11.
+    Compat { old: PathBuf, new: PathBuf },
+    /// Run the language server over standard input and output.
+    #[cfg(feature = "lsp")]
+    Lsp,
+}
+
+#[derive(Debug, Args)]
+struct ViewArgs {
+    /// Only use the configuration data, without state data, rpcs and
+    /// notifications.
+    #[arg(long, conflicts_with = "state_only")]
+    config_only: bool,
+    /// Only use the state data, with the configuration nodes containing it.
+    #[arg(long)]
+    state_only: bool,
+}
+
+impl ViewArgs {
+    /// Whether the configuration or the state data is used, `None` if both are.
+    fn config(&self) -> Option<bool> {
+        match (self.config_only, self.state_only) {
+            (true, _) => Some(true),
+            (_, true) => Some(false),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+enum Syntax {
+    Yang,
+    Yin,
+}
+
+impl Syntax {
+    fn of(path: &Path) -> Self {
+        if path.extension().is_some_and(|extension| extension == "yin") {
+            Syntax::Yin
+        } else {
+            Syntax::Yang
+        }
+    }
+}
+
+/// The features enabled by the `--features` arguments, by the name of their module.
+/// Features given without a module are under `None`.
+#[derive(Debug, PartialEq)]
+struct Features(HashMap<Option<String>, HashSet<String>>);
+
+impl Features {
+    /// Whether `feature` of `module` is enabled, which all features of a module
+    /// are that no argument applies to.
+    fn is_enabled(&self, module: &str, feature: &str) -> bool {
+        self.0
+            .get(&Some(module.to_string()))
+            .or_else(|| self.0.get(&None))
+            .is_none_or(|features| features.contains(feature))
+    }
+}
+
+/// The features enabled by the `--features` arguments, `None` if all are.
+fn parse_features(arguments: &[String]) -> Option<Features> {
+    if arguments.is_empty() {
+        return None;
+    }
+    let mut features: HashMap<Option<String>, HashSet<String>> = HashMap::new();
+    for argument in arguments {
+        let (module, names) = match argument.split_once(':') {
+            Some((module, names)) => (Some(module.to_string()), names),
+            None => (None, argument.as_str()),
+        };
+        features.entry(module).or_default().extend(
+            names
+                .split(',')
+                .filter(|name| !name.is_empty())
+                .map(str::to_string),
+        );
+    }
+    Some(Features(features))
+}
+
+/// Splits an argument `PATH=VALUE` at the first `=` outside the predicates of the
+/// path.
+fn split_assignment(argument: &str) -> (&str, Option<&str>) {
+    let mut quote = None;
+    let mut depth = 0;
+    for (index, char) in argument.char_indices() {
+        match (quote, char) {
+            (Some(open), _) if char == open => quote = None,
+            (Some(_), _) => {}
+            (None, '\'' | '"') => quote = Some(char),
+            (None, '[') => depth += 1,
+            (None, ']') => depth -= 1,
+            (None, '=') if depth == 0 => {
+                return (&argument[..index], Some(&argument[index + 1..]));
+            }
+            _ => {}
+        }
+    }
+    (argument, None)
+}
+
+/// Index of the schema in `schemas` of the module qualifying the first node of
+/// the instance identifier `path`.
+fn path_owner(schemas: &[&Schema], path: &str) -> usize {
+    let module = InstancePath::parse(path)
+        .ok()
+        .and_then(|path| path.steps.into_iter().next())
+        .and_then(|step| step.module);
+    top_level_owner(schemas, |schema| Some(&schema.name) == module.as_ref())
+}
+
+/// Applies `fixes` to `source`, skipping those overlapping a preceding one.
+/// Returns the fixed source and the number of fixes applied.
+fn apply_fixes(source: &str, mut fixes: Vec<TextEdit>) -> (String, usize) {
+    fixes.sort_by_key(|fix| fix.span);
+    fixes.dedup();
+    let mut fixed = String::new();
+    let mut end = 0;
+    let mut applied = 0;
+    for fix in fixes {
+        if fix.span.0 < end {
+            continue;
+        }
+        fixed.push_str(&source[end..fix.span.0]);
+        fixed.push_str(&fix.text);
+        end = fix.span.1;
+        applied += 1;
+    }
+    fixed.push_str(&source[end..]);
+    (fixed, applied)
+}
+
+fn parse_error(path: &Path, source: &str, error: ParseError) -> Error {
+    let (line, column) = line_column(source, error.span.map_or(0, |span| span.0));
+    Error::Yang(vec![Diagnostic {
+        path: path.to_path_buf(),
+        line,
+        column,
+        is_error: true,
+        message: error.message,
+        help: None,
+        fixes: vec![],
+        related: vec![],
+    }])
+}
+
+struct Runner<'w> {
+    search_paths: Vec<PathBuf>,
+    features: Option<Features>,
+    strict: bool,
+    prune_obsolete: bool,
+    /// Whether schemas are restricted to their configuration or state data.
+    view: Option<bool>,
+    out: &'w mut dyn Write,
+    err: &'w mut dyn Write,
+    /// The fixes of the diagnostics reported so far, by file.
+    fixes: Vec<(PathBuf, TextEdit)>,
+}
+
+impl Runner<'_> {
+    fn repository(&self) -> Repository {
+        Repository::new(self.search_paths.clone()).strict(self.strict)
+    }
+
+    /// Prints `diagnostics`, returning whether none of them is an error.
+    fn report(&mut self, diagnostics: &[Diagnostic]) -> io::Result<bool> {
+        for diagnostic in diagnostics {
+            writeln!(self.err, "{}", diagnostic)?;
+        }
+        self.collect_fixes(diagnostics);
+        Ok(diagnostics.iter().all(|diagnostic| !diagnostic.is_error))
+    }
+
+    fn report_error(&mut self, error: Error) -> io::Result<bool> {
+        writeln!(self.err, "{}", error)?;
+        if let Error::Yang(diagnostics) = &error {
+            self.collect_fixes(diagnostics);
+        }
+        Ok(false)
+    }
+
+    fn collect_fixes(&mut self, diagnostics: &[Diagnostic]) {
+        for diagnostic in diagnostics {
+            for fix in &diagnostic.fixes {
+                self.fixes.push((diagnostic.path.clone(), fix.clone()));
+            }
+        }
+    }
+
+    fn read(&mut self, path: &Path) -> io::Result<Option<String>> {
+        match fs::read_to_string(path) {
+            Ok(source) => Ok(Some(source)),
+            Err(error) => {
+                self.report_error(Error::Io {
+                    path: path.to_path_buf(),
+                    error,
+                })?;
+                Ok(None)
+            }
+        }
+    }
+
+    fn write(&mut self, path: &Path, contents: &str) -> io::Result<bool> {
+        match fs::write(path, contents) {
+            Ok(()) => Ok(true),
+            Err(error) => self.report_error(Error::Io {
+                path: path.to_path_buf(),
+                error,
+            }),
+        }
+    }
+
+    /// Loads the module or submodule at `path` into `repository`, printing the
+    /// warnings of all modules loaded with it. Returns the index of the module.
+    fn load(&mut self, repository: &mut Repository, path: &Path) -> io::Result<Option<usize>> {
+        let loaded = repository.modules.len();
+        let result = repository.load(path);
+        let warnings: Vec<Diagnostic> = repository.modules[loaded..]
+            .iter()
+            .flat_map(|module| diagnostics(&module.path, &module.source, &module.error_context))
+            .collect();
+        self.report(&warnings)?;
+        match result {
+            Ok(index) => Ok(Some(index)),
+            Err(error) => {
+                self.report_error(error.into())?;
+                Ok(None)
+            }
+        }
+    }
+
+    /// Loads and compiles the module at `path`, with only the enabled features.
+    fn compile(&mut self, repository: &mut Repository, path: &Path) -> io::Result<Option<Schema>> {
+        let Some(index) = self.load(repository, path)? else {
+            return Ok(None);
+        };
+        let loaded = &repository.modules[index];
+        let ModuleKind::Module(module) = &loaded.kind else {
+            self.report(&[Diagnostic {
+                path: loaded.path.clone(),
+                line: 1,
+                column: 1,
+                is_error: true,
+                message: format!("{} is a submodule, expected a module", loaded.name),
+                help: None,
+                fixes: vec![],
+                related: vec![],
+            }])?;
+            return Ok(None);
+        };
+        let mut error_context = ErrorContext::new();
+        let schema = compile(module, repository, &mut error_context);
+        self.report(&diagnostics(&loaded.path, &loaded.source, &error_context))?;
+        let Ok(mut schema) = schema else {
+            return Ok(None);
+        };
+        if let Some(features) = &self.features {
+            enable_features(&mut schema, &|module, feature| {
+                features.is_enabled(module, feature)
+            });
+        }
+        if self.prune_obsolete {
+            prune_obsolete(&mut schema);
+        }
+        match self.view {
+            Some(true) => Ok(Some(schema.config_view())),
+            Some(false) => Ok(Some(schema.state_view())),
+            None => Ok(Some(schema)),
+        }
+    }
+
+    fn check(&mut self, files: &[PathBuf], fix: bool) -> io::Result<bool> {
+        let mut success = true;
+        if fix {
+            success &= self.fix(files)?;
+        }
+        let mut repository = self.repository();
+        for file in files {
+            let Some(index) = self.load(&mut repository, file)? else {
+                success = false;
+                continue;
+            };
+            // Submodules are compiled as part of the module they belong to.
+            if let ModuleKind::Module(_) = repository.modules[index].kind {
+                success &= self.compile(&mut repository, file)?.is_some();
+            }
+        }
+        Ok(success)
+    }
+
+    /// Rewrites `files` with the fixes of the problems found in them applied. The
+    /// problems themselves are not printed, so the ones remaining can be reported
+    /// by checking the files again.
+    fn fix(&mut self, files: &[PathBuf]) -> io::Result<bool> {
+        let (mut out, mut err) = (io::sink(), io::sink());
+        let mut quiet = Runner {
+            search_paths: self.search_paths.clone(),
+            features: None,
+            strict: self.strict,
+            prune_obsolete: false,
+            view: None,
+            out: &mut out,
+            err: &mut err,
+            fixes: vec![],
+        };
+        quiet.check(files, false)?;
+        self.fixes = quiet.fixes;
+        let mut success = true;
+        for file in files {
+            let fixes: Vec<TextEdit> = self
+                .fixes
+                .iter()
+                .filter(|(path, _)| path == file)
+                .map(|(_, fix)| fix.clone())
+                .collect();
+            if fixes.is_empty() {
+                continue;
+            }
+            let Some(source) = self.read(file)? else {
+                success = false;
+                continue;
+            };
+            let (fixed, applied) = apply_fixes(&source, fixes);
+            if self.write(file, &fixed)? {
+                writeln!(self.err, "{}: applied {} fixes", file.display(), applied)?;
+            } else {
+                success = false;
+            }
+        }
+        Ok(success)
+    }
+
+    fn lint(&mut self, files: &[PathBuf], config: Option<&Path>) -> io::Result<bool> {
+        let config = match config {
+            Some(path) => {
+                let Some(input) = self.read(path)? else {
+                    return Ok(false);
+                };
+                match LintConfig::parse(&input) {
+                    Ok(config) => config,
+                    Err(message) => {
+                        writeln!(self.err, "{}: {}", path.display(), message)?;
+                        return Ok(false);
+                    }
+                }
+            }
+            None => LintConfig::default(),
+        };
+        let mut repository = self.repository();
+        let mut success = true;
+        for file in files {
+            let Some(index) = self.load(&mut repository, file)? else {
+                success = false;
+                continue;
+            };
+            let loaded = &repository.modules[index];
+            let mut error_context = ErrorContext::new();
+            lint(&loaded.kind, &loaded.source, &config, &mut error_context);
+            error_context.sort_errors();
+            let diagnostics = diagnostics(&loaded.path, &loaded.source, &error_context);
+            success &= self.report(&diagnostics)?;
+        }
+        Ok(success)
+    }
+
+    fn format(
+        &mut self,
+        files: &[PathBuf],
+        in_place: bool,
+        check: bool,
+        options: &FormatOptions,
+    ) -> io::Result<bool> {
+        let mut success = true;
+        for file in files {
+            let Some(source) = self.read(file)? else {
+                success = false;
+                continue;
+            };
+            if check {
+                match is_formatted(&source, options) {
+                    Ok(true) => {}
+                    Ok(false) => {
+                        writeln!(self.err, "{}: not formatted", file.display())?;
+                        success = false;
+                    }
+                    Err(error) => {
+                        success &= self.report_error(parse_error(file, &source, error))?;
+                    }
+                }
+                continue;
+            }
+            let formatted = match format(&source, options) {
+                Ok(formatted) => formatted,
+                Err(error) => {
+                    success &= self.report_error(parse_error(file, &source, error))?;
+                    continue;
+                }
+            };
+            if in_place {
+                if formatted != source {
+                    success &= self.write(file, &formatted)?;
+                }
+            } else {
+                self.out.write_all(formatted.as_bytes())?;
+            }
+        }
+        Ok(success)
+    }
+
+    fn tree(&mut self, files: &[PathBuf], options: &TreeOptions) -> io::Result<bool> {
+        let mut repository = self.repository();
+        let mut success = true;
+        for file in files {
+            match self.compile(&mut repository, file)? {
+                Some(schema) => self
+                    .out
+                    .write_all(render_tree(&schema, options).as_bytes())?,
+                None => success = false,
+            }
+        }
+        Ok(success)
+    }
+
+    /// The namespaces and extensions of the modules imported by the module at
+    /// `path`. If the module cannot be loaded, the namespaces are assumed to
+    /// follow the IETF convention and extensions to take no argument.
+    fn yin_context(&self, path: &Path) -> YinContext {
+        let mut repository = self.repository();
+        let mut context = YinContext::default();
+        if repository.load(path).is_ok() {
+            for loaded in &repository.modules {
+                if let ModuleKind::Module(module) = &loaded.kind {
+                    context.add_module(&module.unmap("module"));
+                }
+            }
+        }
+        context
+    }
+
+    fn convert(
+        &mut self,
+        file: &Path,
+        output: Option<&Path>,
+        to: Option<Syntax>,
+    ) -> io::Result<bool> {
+        let Some(source) = self.read(file)? else {
+            return Ok(false);
+        };
+        let from = Syntax::of(file);
+        let to = to.or(output.map(Syntax::of)).unwrap_or(match from {
+            Syntax::Yang => Syntax::Yin,
+            Syntax::Yin => Syntax::Yang,
+        });
+        let context = self.yin_context(file);
+        let statement = match from {
+            Syntax::Yang => parse(&source),
+            Syntax::Yin => parse_yin(&source, &context),
+        };
+        let statement = match statement {
+            Ok(statement) => statement,
+            Err(error) => return self.report_error(parse_error(file, &source, error)),
+        };
+        let converted = match to {
+            // Text arguments are kept as they are, only the YIN markup is converted.
+            Syntax::Yang => format_statement(
+                &statement,
+                &FormatOptions {
+                    reflow_text: false,
+                    ..FormatOptions::default()
+                },
+            ),
+            Syntax::Yin => write_yin(&statement, &context),
+        };
+        match output {
+            Some(output) => self.write(output, &converted),
+            None => {
+                self.out.write_all(converted.as_bytes())?;
+                Ok(true)
+            }
+        }
+    }
+
+    /// Loads and compiles `modules`, returning `None` if one of them fails.
+    fn compile_modules(&mut self, modules: &[PathBuf]) -> io::Result<Option<Vec<Schema>>> {
+        let mut repository = self.repository();
+        let mut schemas = vec![];
+        for module in modules {
+            match self.compile(&mut repository, module)? {
+                Some(schema) => schemas.push(schema),
+                None => return Ok(None),
+            }
+        }
+        merge_augments(&mut schemas);
+        Ok(Some(schemas))
+    }
+
+    fn report_data_errors(&mut self, file: &Path, errors: Vec<DataError>) -> io::Result<bool> {
+        for error in &errors {
+            let message = match &error.app_tag {
+                Some(app_tag) => format!("{} (error-app-tag {})", error.message, app_tag),
+                None => error.message.clone(),
+            };
+            if error.path.is_empty() {
+                writeln!(self.err, "{}: {}", file.display(), message)?;
+            } else {
+                writeln!(self.err, "{}: {}: {}", file.display(), error.path, message)?;
+            }
+        }
+        Ok(errors.is_empty())
+    }
+
+    /// Reads and validates the instance data in `file`, returning a tree for each
+    /// schema of `schemas`, or `None` after reporting the errors.
+    fn read_data(&mut self, file: &Path, schemas: &[&Schema]) -> io::Result<Option<Vec<DataTree>>> {
+        let Some(input) = self.read(file)? else {
+            return Ok(None);
+        };
+        let trees = match file.extension().and_then(|extension| extension.to_str()) {
+            Some("json") => parse_json_modules(&input, schemas),
+            Some("xml") => parse_xml_modules(&input, schemas),
+            _ => {
+                writeln!(
+                    self.err,
+                    "{}: unknown data format, expected a .json or .xml file",
+                    file.display()
+                )?;
+                return Ok(None);
+            }
+        };
+        match trees {
+            Ok(trees) => Ok(Some(trees)),
+            Err(errors) => {
+                self.report_data_errors(file, errors)?;
+                Ok(None)
+            }
+        }
+    }
+
+    fn validate(&mut self, modules: &[PathBuf], files: &[PathBuf]) -> io::Result<bool> {
+        let Some(schemas) = self.compile_modules(modules)? else {
+            return Ok(false);
+        };
+        let schemas: Vec<&Schema> = schemas.iter().collect();
+
+        let mut success = true;
+        for file in files {
+            let Some(trees) = self.read_data(file, &schemas)? else {
+                success = false;
+                continue;
+            };
+            let errors = trees
+                .iter()
+                .zip(&schemas)
+                .flat_map(|(tree, schema)| check_conditions(tree, schema))
+                .map(|error| DataError {
+                    path: error.path,
+                    message: error.message,
+                    app_tag: error.app_tag,
+                })
+                .collect();
+            success &= self.report_data_errors(file, errors)?;
+        }
+        Ok(success)
+    }
+
+    fn get(&mut self, modules: &[PathBuf], file: &Path, paths: &[String]) -> io::Result<bool> {
+        let Some(schemas) = self.compile_modules(modules)? else {
+            return Ok(false);
+        };
+        let schemas: Vec<&Schema> = schemas.iter().collect();
+        let Some(trees) = self.read_data(file, &schemas)? else {
+            return Ok(false);
+        };
+
+        let mut success = true;
+        for path in paths {
+            let index = path_owner(&schemas, path);
+            match trees[index].get(schemas[index], path) {
+                Ok(Some((node, schema_node))) => self
+                    .out
+                    .write_all(write_json_node(node, schema_node).as_bytes())?,
+                Ok(None) => {
+                    writeln!(self.err, "{}: {} does not exist", file.display(), path)?;
+                    success = false;
+                }
+                Err(message) => {
+                    writeln!(self.err, "{}: {}: {}", file.display(), path, message)?;
+                    success = false;
+                }
+            }
+        }
+        Ok(success)
+    }
+
+    fn edit(
+        &mut self,
+        modules: &[PathBuf],
+        file: &Path,
+        edits: &Edits,
+        output: Option<&Path>,
+    ) -> io::Result<bool> {
+        let Some(schemas) = self.compile_modules(modules)? else {
+            return Ok(false);
+        };
+        let schemas: Vec<&Schema> = schemas.iter().collect();
+        let Some(mut trees) = self.read_data(file, &schemas)? else {
+            return Ok(false);
+        };
+
+        let mut success = true;
+        let assignments = edits
+            .create
+            .iter()
+            .map(|argument| (argument, false))
+            .chain(edits.replace.iter().map(|argument| (argument, true)));
+        for (argument, replace) in assignments {
+            let (path, value) = split_assignment(argument);
+            let index = path_owner(&schemas, path);
+            let result = if replace {
+                trees[index].replace(schemas[index], path, value)
+            } else {
+                trees[index].insert(schemas[index], path, value)
+            };
+            if let Err(message) = result {
+                writeln!(self.err, "{}: {}", argument, message)?;
+                success = false;
+            }
+        }
+        for path in &edits.delete {
+            let index = path_owner(&schemas, path);
+            if let Err(message) = trees[index].delete(schemas[index], path) {
+                writeln!(self.err, "{}: {}", path, message)?;
+                success = false;
+            }
+        }
+        if !success {
+            return Ok(false);
+        }
+
+        let json = write_json(&trees, &schemas);
+        match output {
+            Some(output) => self.write(output, &json),
+            None => {
+                self.out.write_all(json.as_bytes())?;
+                Ok(true)
+            }
+        }
+    }
+
+    fn compat(&mut self, old: &Path, new: &Path) -> io::Result<bool> {
+        let builder = self
+            .search_paths
+            .iter()
+            .fold(compat::Builder::new(old, new), |builder, path| {
+                builder.search_path(path)
+            });
+        let changes = match builder.compare() {
+            Ok(changes) => changes,
+            Err(error) => return self.report_error(error),
+        };
+        for change in &changes {
+            writeln!(self.out, "{}", change)?;
+        }
+        Ok(changes
+            .iter()
+            .all(|change| change.compatibility == Compatibility::Compatible))
+    }
+}
+
+/// The edits of the `edit` command, as `PATH[=VALUE]` and `PATH` arguments.
+struct Edits {
+    create: Vec<String>,
+    replace: Vec<String>,
+    delete: Vec<String>,
+}
+
+/// Runs the command of `cli`, printing its output to `out` and diagnostics to
+/// `err`. Returns whether no problem was found.
+fn run(cli: Cli, out: &mut dyn Write, err: &mut dyn Write) -> io::Result<bool> {
+    let mut runner = Runner {
+        search_paths: cli.search_paths.iter().flat_map(env::split_paths).collect(),
+        features: parse_features(&cli.features),
+        strict: cli.strict,
+        prune_obsolete: cli.prune_obsolete,
+        view: None,
+        out,
+        err,
+        fixes: vec![],
+    };
+    match cli.command {
+        Command::Check { files, fix } => runner.check(&files, fix),
+        Command::Lint { files, config } => runner.lint(&files, config.as_deref()),
+        Command::Format {
+            files,
+            in_place,
+            check,
+            indent,
+            line_width,
+            no_reflow,
+            keep_order,
+        } => {
+            let options = FormatOptions {
+                indent,
+                line_width,
+                reflow_text: !no_reflow,
+                sort_statements: !keep_order,
+            };
+            runner.format(&files, in_place, check, &options)
+        }
+        Command::Tree {
+            files,
+            tree_depth,
+            tree_path,
+            view,
+        } => {
+            let options = TreeOptions {
+                depth: tree_depth,
+                path: tree_path,
+            };
+            runner.view = view.config();
+            runner.tree(&files, &options)
+        }
+        Command::Convert { file, output, to } => runner.convert(&file, output.as_deref(), to),
+        Command::Validate {
+            modules,
+            files,
+            view,
+        } => {
+            runner.view = view.config();
+            runner.validate(&modules, &files)
+        }
+        Command::Get {
+            modules,
+            file,
+            paths,
+        } => runner.get(&modules, &file, &paths),
+        Command::Edit {
+            modules,
+            file,
+            create,
+            replace,
+            delete,
+            output,
+        } => {
+            let edits = Edits {
+                create,
+                replace,
+                delete,
+            };
+            runner.edit(&modules, &file, &edits, output.as_deref())
+        }
+        Command::Compat { old, new } => runner.compat(&old, &new),
+        #[cfg(feature = "lsp")]
+        Command::Lsp => crate::lsp::run(runner.search_paths, runner.strict)
+            .map(|()| true)
+            .map_err(io::Error::other),
+    }
+}
+
+/// Entry point of the `yang-utils` binary.
+pub fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let mut stdout = io::stdout().lock();
+    let mut stderr = io::stderr().lock();
+    match run(cli, &mut stdout, &mut stderr) {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        // The output was closed early, like by `head`.
+        Err(error) if error.kind() == io::ErrorKind::BrokenPipe => ExitCode::SUCCESS,
+        Err(error) => {
+            let _ = writeln!(stderr, "yang-utils: {}", error);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MODULE: &str = r#"module example {
+  namespace "urn:example";
+  prefix ex;
+
+  feature tls;
+
+  container system {
+    leaf hostname {
+      type string;
+    }
+    leaf mtu {
+      type uint16;
+      must ". >= 68";
+    }
+    leaf tls-port {
+      if-feature tls;
+      type uint16;
+    }
+  }
+}
+"#;
+
+    /// Writes `files` to a fresh directory named after `test`.
+    fn directory(test: &str, files: &[(&str, &str)]) -> PathBuf {
+        let directory = env::temp_dir().join(format!("yang-utils-cli-test-{}", test));
+        let _ = fs::remove_dir_all(&directory);
+        fs::create_dir_all(&directory).unwrap();
+        for (name, contents) in files {
+            fs::write(directory.join(name), contents).unwrap();
+        }
+        directory
+    }
+
+    /// Runs `args` in `directory`, returning whether it succeeded, its output and
+    /// its diagnostics, with the directory replaced by `DIR`.
+    fn run_in(directory: &Path, args: &[&str]) -> (bool, String, String) {
+        let args = args.iter().map(|arg| {
+            if arg.starts_with('-') || !arg.contains('.') {
+                arg.to_string()
+            } else {
+                directory.join(arg).display().to_string()
+            }
+        });
+        let cli = Cli::try_parse_from(["yang-utils".to_string()].into_iter().chain(args)).unwrap();
+        let mut out = vec![];
+        let mut err = vec![];
+        let success = run(cli, &mut out, &mut err).unwrap();
+        let prefix = format!("{}/", directory.display());
+        let text = |output: Vec<u8>| String::from_utf8(output).unwrap().replace(&prefix, "DIR/");
+        (success, text(out), text(err))
+    }
+
+    #[test]
+    fn test_arguments() {
+        Cli::try_parse_from(["yang-utils", "format", "-i", "--check", "a.yang"]).unwrap_err();
+        Cli::try_parse_from(["yang-utils", "validate", "data.json"]).unwrap_err();
+        let cli =
+            Cli::try_parse_from(["yang-utils", "tree", "a.yang", "-p", "x", "-p", "y"]).unwrap();
+        assert_eq!(cli.search_paths, ["x", "y"]);
+        let features =
+            parse_features(&["example:tls,ssh".to_string(), "other:".to_string()]).unwrap();
+        assert!(features.is_enabled("example", "ssh"));
+        assert!(!features.is_enabled("example", "ipv6"));
+        assert!(!features.is_enabled("other", "tls"));
+        assert!(features.is_enabled("third", "tls"));
+        let features = parse_features(&["tls".to_string(), "other:ssh".to_string()]).unwrap();
+        assert!(features.is_enabled("example", "tls"));
+        assert!(!features.is_enabled("example", "ssh"));
+        assert!(features.is_enabled("other", "ssh"));
+        assert_eq!(parse_features(&[]), None);
+    }
+
+    #[test]
+    fn test_check() {
+        let directory = directory(
+            "check",
+            &[
+                ("example.yang", MODULE),
+                (
+                    "invalid.yang",
+                    "module invalid {\n  namespace urn:invalid;\n  prefix i;\n  \
+                     import example { prefix ex; }\n  leaf x { type unknown; }\n}\n",
+                ),
+            ],
+        );
+        assert_eq!(
+            run_in(&directory, &["check", "example.yang"]),
+            (true, String::new(), String::new())
+        );
+        let path = directory.display().to_string();
+        assert_eq!(
+            run_in(&directory, &["-p", &path, "check", "invalid.yang"]),
+            (
+                false,
+                String::new(),
+                "DIR/invalid.yang:5:17: error: Type unknown not found\n".to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn test_fix() {
+        let misspelled = MODULE
+            .replace("leaf hostname", "lef hostname")
+            .replace("type uint16;\n      must", "type unit16;\n      must");
+        let directory = directory("fix", &[("example.yang", &misspelled)]);
+        assert_eq!(
+            run_in(&directory, &["check", "example.yang"]),
+            (
+                false,
+                String::new(),
+                "DIR/example.yang:8:5: warning: Unexpected keyword lef\n  \
+                 help: Did you mean leaf?\n\
+                 DIR/example.yang:12:12: error: Type unit16 not found\n  \
+                 help: Did you mean uint16?\n"
+                    .to_string()
+            )
+        );
+        assert_eq!(
+            run_in(&directory, &["check", "--fix", "example.yang"]),
+            (
+                true,
+                String::new(),
+                "DIR/example.yang: applied 2 fixes\n".to_string()
+            )
+        );
+        assert_eq!(
+            fs::read_to_string(directory.join("example.yang")).unwrap(),
+            MODULE
+        );
+        assert!(run_in(&directory, &["check", "example.yang"]).0);
+        assert_eq!(
+            apply_fixes(
+                "abc",
+                vec![
+                    TextEdit {
+                        span: (1, 2),
+                        text: "x".to_string()
+                    },
+                    TextEdit {
+                        span: (0, 2),
+                        text: "y".to_string()
+                    },
+                    TextEdit {
+                        span: (3, 3),
+                        text: "d".to_string()
+                    },
+                ]
+            ),
+            ("ycd".to_string(), 2)
+        );
+    }
+
+    #[test]
+    fn test_lint() {
+        let module = "module example {\n  namespace urn:example;\n  prefix ex;\n  \
+                      description \"Example.\";\n  \
+                      // yang-lint: allow(missing-description)\n  \
+                      container system {\n    leaf hostname { type string; }\n  }\n  \
+                      leaf enabled { type boolean; }\n}\n";
+        let directory = directory(
+            "lint",
+            &[
+                ("example.yang", module),
+                (
+                    "lint.json",
+                    r#"{"missing-contact": "allow", "top-level-data-node": "deny"}"#,
+                ),
+                ("invalid.json", r#"{"missing-contact": "off"}"#),
+            ],
+        );
+        assert_eq!(
+            run_in(&directory, &["lint", "example.yang"]),
+            (
+                true,
+                String::new(),
+                "DIR/example.yang:1:8: warning: Module example has no organization \
+                 [missing-organization]\n\
+                 DIR/example.yang:1:8: warning: Module example has no contact \
+                 [missing-contact]\n\
+                 DIR/example.yang:9:8: warning: Leaf enabled has no description \
+                 [missing-description]\n\
+                 DIR/example.yang:9:8: warning: Top-level leaf enabled is not a container \
+                 [top-level-data-node]\n"
+                    .to_string()
+            )
+        );
+        assert_eq!(
+            run_in(
+                &directory,
+                &["lint", "--config", "lint.json", "example.yang"]
+            ),
+            (
+                false,
+                String::new(),
+                "DIR/example.yang:1:8: warning: Module example has no organization \
+                 [missing-organization]\n\
+                 DIR/example.yang:9:8: warning: Leaf enabled has no description \
+                 [missing-description]\n\
+                 DIR/example.yang:9:8: error: Top-level leaf enabled is not a container \
+                 [top-level-data-node]\n"
+                    .to_string()
+            )
+        );
+        assert_eq!(
+            run_in(
+                &directory,
+                &["lint", "--config", "invalid.json", "example.yang"]
+            ),
+            (
+                false,
+                String::new(),
+                "DIR/invalid.json: Invalid level of rule missing-contact, expected allow, \
+                 warn or deny\n"
+                    .to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn test_format() {
+        let unformatted = MODULE.replace("  prefix ex;", "prefix    ex ;");
+        let directory = directory(
+            "format",
+            &[("example.yang", MODULE), ("unformatted.yang", &unformatted)],
+        );
+        assert_eq!(
+            run_in(&directory, &["format", "unformatted.yang"]),
+            (true, MODULE.to_string(), String::new())
+        );
+        assert_eq!(
+            run_in(
+                &directory,
+                &["format", "--check", "example.yang", "unformatted.yang"]
+            ),
+            (
+                false,
+                String::new(),
+                "DIR/unformatted.yang: not formatted\n".to_string()
+            )
+        );
+        assert!(run_in(&directory, &["format", "--in-place", "unformatted.yang"]).0);
+        assert_eq!(
+            fs::read_to_string(directory.join("unformatted.yang")).unwrap(),
+            MODULE
+        );
+    }
+
+    #[test]
+    fn test_tree() {
+        let directory = directory("tree", &[("example.yang", MODULE)]);
+        let (success, tree, _) = run_in(&directory, &["tree", "example.yang"]);
+        assert!(success);
+        assert!(tree.contains("+--rw tls-port?"));
+        let (_, tree, _) = run_in(
+            &directory,
+            &["--features", "example:", "tree", "example.yang"],
+        );
+        assert!(tree.starts_with("module: example\n"));
+        assert!(!tree.contains("tls-port"));
+
+        let obsolete = MODULE.replace("leaf mtu {", "leaf mtu {\n      status obsolete;");
+        fs::write(directory.join("example.yang"), obsolete).unwrap();
+        let (_, tree, _) = run_in(&directory, &["tree", "example.yang"]);
+        assert!(tree.contains("o--rw mtu?"));
+        let (_, tree, _) = run_in(&directory, &["--prune-obsolete", "tree", "example.yang"]);
+        assert!(!tree.contains("mtu"));
+
+        let state = MODULE.replace(
+            "  container system {\n",
+            "  container system {\n    leaf uptime {\n      config false;\n      type uint32;\n    }\n",
+        );
+        fs::write(directory.join("example.yang"), state).unwrap();
+        let (_, tree, _) = run_in(&directory, &["tree", "--config-only", "example.yang"]);
+        assert!(tree.contains("+--rw hostname?"));
+        assert!(!tree.contains("uptime"));
+        let (_, tree, _) = run_in(&directory, &["tree", "--state-only", "example.yang"]);
+        assert_eq!(
+            tree,
+            "module: example\n  +--rw system\n     +--ro uptime?   uint32\n"
+        );
+    }
+
+    #[test]
+    fn test_convert() {
+        let directory = directory("convert", &[("example.yang", MODULE)]);
+        let (success, yin, _) = run_in(&directory, &["convert", "example.yang"]);
+        assert!(success);
+        assert!(yin.contains("<module name=\"example\""));
+
+        assert!(
+            run_in(
+                &directory,
+                &["convert", "example.yang", "-o", "example.yin"]
+            )
+            .0
+        );
+        assert_eq!(
+            run_in(&directory, &["convert", "example.yin"]),
+            (true, MODULE.to_string(), String::new())
+        );
+    }
+
+    #[test]
+    fn test_convert_text() {
+        let directory = directory(
+            "convert-text",
+            &[(
+                "text.yin",
+                "<module name=\"text\" xmlns=\"urn:ietf:params:xml:ns:yang:yin:1\">\n  \
+                 <namespace uri=\"urn:text\"/>\n  <prefix value=\"t\"/>\n  \
+                 <description><text>First line.\nSecond line.</text></description>\n\
+                 </module>\n",
+            )],
+        );
+        assert_eq!(
+            run_in(&directory, &["convert", "text.yin"]),
+            (
+                true,
+                "module text {\n  namespace \"urn:text\";\n  prefix t;\n\n  description\n    \
+                 \"First line.\n     Second line.\";\n}\n"
+                    .to_string(),
+                String::new()
+            )
+        );
+    }
+
+    #[test]
+    fn test_validate() {
+        let directory = directory(
+            "validate",
+            &[
+                ("example.yang", MODULE),
+                (
+                    "other.yang",
+                    "module other {\n  namespace urn:other;\n  prefix o;\n  \
+                     import example { prefix ex; }\n  leaf enabled { type boolean; }\n  \
+                     augment \"/ex:system\" {\n    leaf speed { type uint32; must \". > 0\"; }\n  }\n}\n",
+                ),
+                (
+                    "valid.json",
+                    r#"{ "example:system": { "mtu": 1500, "other:speed": 10 }, "other:enabled": true }"#,
+                ),
+                (
+                    "invalid.json",
+                    r#"{ "example:system": { "mtu": 60, "tls-port": 443 } }"#,
+                ),
+                (
+                    "valid.xml",
+                    r#"<config><system xmlns="urn:example"><hostname>a</hostname><speed xmlns="urn:other">10</speed></system></config>"#,
+                ),
+                (
+                    "speed.json",
+                    r#"{ "example:system": { "other:speed": 0 } }"#,
+                ),
+                (
+                    "augmented.json",
+                    r#"{ "example:system": { "other:speed": 0, "o:speed": 1 } }"#,
+                ),
+                ("data.txt", ""),
+                (
+                    "state.yang",
+                    "module state {\n  namespace urn:state;\n  prefix s;\n  \
+                     container counters {\n    config false;\n    leaf in { type uint32; }\n  }\n}\n",
+                ),
+                ("state.json", r#"{ "state:counters": { "in": 1 } }"#),
+            ],
+        );
+        assert!(run_in(&directory, &["validate", "-m", "state.yang", "state.json"]).0);
+        assert_eq!(
+            run_in(
+                &directory,
+                &[
+                    "validate",
+                    "--config-only",
+                    "-m",
+                    "state.yang",
+                    "state.json"
+                ]
+            ),
+            (
+                false,
+                String::new(),
+                "DIR/state.json: /state:counters: Unknown member state:counters\n".to_string()
+            )
+        );
+        let modules = ["-m", "example.yang", "-m", "other.yang"];
+        let args = |files: &[&'static str]| [&["validate"], &modules[..], files].concat();
+        assert_eq!(
+            run_in(&directory, &args(&["valid.json", "valid.xml"])),
+            (true, String::new(), String::new())
+        );
+        assert_eq!(
+            run_in(&directory, &args(&["invalid.json"])),
+            (
+                false,
+                String::new(),
+                "DIR/invalid.json: /example:system/mtu: Condition . >= 68 is not satisfied \
+                 (error-app-tag must-violation)\n"
+                    .to_string()
+            )
+        );
+        assert_eq!(
+            run_in(&directory, &args(&["speed.json"])).2,
+            "DIR/speed.json: /example:system/other:speed: Condition . > 0 is not satisfied \
+             (error-app-tag must-violation)\n"
+        );
+        assert_eq!(
+            run_in(&directory, &args(&["augmented.json"])).2,
+            "DIR/augmented.json: /example:system/o:speed: Unknown module o\n"
+        );
+        let args = [
+            &["--features", "other:"],
+            &args(&["invalid.json", "data.txt"])[..],
+        ]
+        .concat();
+        assert_eq!(
+            run_in(&directory, &args).2,
+            "DIR/invalid.json: /example:system/mtu: Condition . >= 68 is not satisfied \
+             (error-app-tag must-violation)\n\
+             DIR/data.txt: unknown data format, expected a .json or .xml file\n"
+        );
+        let args = [&["--features", "example:"], &args[2..]].concat();
+        assert_eq!(
+            run_in(&directory, &args).2,
+            "DIR/invalid.json: /example:system/tls-port: Unknown member tls-port\n\
+             DIR/data.txt: unknown data format, expected a .json or .xml file\n"
+        );
+    }
+
+    #[test]
+    fn test_edit() {
+        let directory = directory(
+            "edit",
+            &[
+                ("example.yang", MODULE),
+                ("data.json", r#"{ "example:system": { "mtu": 1500 } }"#),
+                (
+                    "data.xml",
+                    r#"<system xmlns="urn:example"><hostname>a</hostname></system>"#,
+                ),
+            ],
+        );
+        assert_eq!(
+            run_in(
+                &directory,
+                &[
+                    "get",
+                    "-m",
+                    "example.yang",
+                    "data.json",
+                    "/example:system/mtu"
+                ]
+            ),
+            (
+                true,
+                "{\n  \"example:mtu\": 1500\n}\n".to_string(),
+                String::new()
+            )
+        );
+        assert_eq!(
+            run_in(
+                &directory,
+                &[
+                    "get",
+                    "-m",
+                    "example.yang",
+                    "data.xml",
+                    "/example:system/mtu"
+                ]
+            ),
+            (
+                false,
+                String::new(),
+                "DIR/data.xml: /example:system/mtu does not exist\n".to_string()
+            )
+        );
+
+        let edit = |args: &[&str]| {
+            run_in(
+                &directory,
+                &[&["edit", "-m", "example.yang", "data.json"], args].concat(),
+            )
+        };
+        assert_eq!(
+            edit(&[
+                "--replace",
+                "/example:system/mtu=9000",
+                "--create",
+                "/example:system/hostname=a=b",
+                "--delete",
+                "/example:system/mtu",
+            ]),
+            (
+                true,
+                "{\n  \"example:system\": {\n    \"hostname\": \"a=b\"\n  }\n}\n".to_string(),
+                String::new()
+            )
+        );
+        assert_eq!(
+            edit(&[
+                "--create",
+                "/example:system/mtu=1280",
+                "--delete",
+                "/example:system/tls-port"
+            ]),
+            (
+                false,
+                String::new(),
+                "/example:system/mtu=1280: /example:system/mtu already exists\n\
+                 /example:system/tls-port: /example:system/tls-port does not exist\n"
+                    .to_string()
+            )
+        );
+        assert!(edit(&["--replace", "/example:system/mtu=1280", "-o", "edited.json"]).0);
+        assert_eq!(
+            fs::read_to_string(directory.join("edited.json")).unwrap(),
+            "{\n  \"example:system\": {\n    \"mtu\": 1280\n  }\n}\n"
+        );
+        assert_eq!(
+            split_assignment("/ex:a/b[name='x=y']/c=d=e"),
+            ("/ex:a/b[name='x=y']/c", Some("d=e"))
+        );
+        assert_eq!(split_assignment("/ex:a"), ("/ex:a", None));
+    }
+
+    #[test]
+    fn test_compat() {
+        let directory = directory(
+            "compat",
+            &[
+                ("old.yang", MODULE),
+                (
+                    "new.yang",
+                    &MODULE.replace("    leaf hostname {\n      type string;\n    }\n", ""),
+                ),
+            ],
+        );
+        assert_eq!(
+            run_in(&directory, &["compat", "old.yang", "old.yang"]),
+            (true, String::new(), String::new())
+        );
+        let (success, changes, _) = run_in(&directory, &["compat", "old.yang", "new.yang"]);
+        assert!(!success);
+        assert!(changes.starts_with("non-backward-compatible: Node /system/hostname removed\n"));
+    }
+}
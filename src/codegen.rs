@@ -0,0 +1,631 @@
+//! Rust code generation for the data tree of a compiled [`Schema`].
+//!
+//! Containers and list entries become structs, leaves become fields of the Rust
+//! type of their YANG type, enumerations become enums carrying their values and
+//! unions become enums with a variant for each member type. A leafref has the
+//! Rust type of the leaf it refers to, a leafref within a union is a `String`.
+//! The nodes of a `choice` are flattened into the enclosing struct as optional
+//! fields, as at most one of its cases is present.
+
+use crate::errors::ErrorContext;
+use crate::parser::model::OrderedBy;
+use crate::schema::{Schema, SchemaNode, SchemaNodeKind, SchemaType};
+use crate::typing::{resolve_type, Type};
+use std::collections::{HashMap, HashSet};
+
+const KEYWORDS: &[&str] = &[
+    "as", "async", "await", "box", "break", "const", "continue", "dyn", "else", "enum", "extern",
+    "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub",
+    "ref", "return", "static", "struct", "trait", "true", "try", "type", "unsafe", "use", "where",
+    "while", "yield",
+];
+
+/// Converts a YANG identifier like `ipv4-address` or `ifIndex` to a Rust field name.
+pub(crate) fn field_name(name: &str) -> String {
+    let mut field_name = String::with_capacity(name.len());
+    let mut previous: Option<char> = None;
+    for c in name.chars() {
+        if matches!(c, '-' | '.') {
+            field_name.push('_');
+        } else if c.is_ascii_uppercase() {
+            if previous
+                .is_some_and(|previous| previous.is_ascii_lowercase() || previous.is_ascii_digit())
+            {
+                field_name.push('_');
+            }
+            field_name.push(c.to_ascii_lowercase());
+        } else {
+            field_name.push(c);
+        }
+        previous = Some(c);
+    }
+    if matches!(field_name.as_str(), "self" | "super" | "crate") {
+        field_name.push('_');
+    } else if KEYWORDS.contains(&field_name.as_str()) {
+        field_name.insert_str(0, "r#");
+    }
+    field_name
+}
+
+/// Converts a YANG identifier like `address-family` to a Rust type name.
+pub(crate) fn type_name(name: &str) -> String {
+    let mut type_name: String = name
+        .split(['-', '_', '.'])
+        .flat_map(|part| {
+            let mut chars = part.chars();
+            chars
+                .next()
+                .map(|first| first.to_ascii_uppercase())
+                .into_iter()
+                .chain(chars)
+        })
+        .collect();
+    if type_name.starts_with(|c: char| c.is_ascii_digit()) {
+        type_name.insert_str(0, "Value");
+    }
+    type_name
+}
+
+fn push_doc(output: &mut String, indent: &str, description: Option<&str>) {
+    let Some(description) = description else {
+        return;
+    };
+    for line in description.lines() {
+        let line = line.trim_end();
+        if line.is_empty() {
+            output.push_str(&format!("{}///\n", indent));
+        } else {
+            output.push_str(&format!("{}/// {}\n", indent, line));
+        }
+    }
+}
+
+struct Generator<'e> {
+    error_context: &'e mut ErrorContext,
+    error_occured: bool,
+    /// Generated structs and enums, parents before their children.
+    items: Vec<String>,
+    type_names: HashSet<String>,
+    /// Enums generated for typedefs, by typedef name.
+    typedef_enums: HashMap<String, String>,
+    /// Enums generated for enumerations and unions without a typedef, by their
+    /// variants, so a leafref and the leaf it refers to share one.
+    anonymous_enums: HashMap<String, String>,
+    /// The enums generated for unions, which have no total order.
+    union_enums: HashSet<String>,
+    /// Rust types of the generated leafs, by node, as the nodes of submodules and
+    /// imported groupings share the span of their `include` or `import`.
+    leaf_types: HashMap<*const SchemaNode, String>,
+}
+
+impl Generator<'_> {
+    /// Reserves a type name, prefixing it with the name of the parent type if taken.
+    fn reserve_type_name(&mut self, parent: &str, name: &str) -> String {
+        let mut type_name = type_name(name);
+        if self.type_names.contains(&type_name) {
+            type_name = format!("{}{}", parent, type_name);
+        }
+        while self.type_names.contains(&type_name) {
+            type_name.push('_');
+        }
+        self.type_names.insert(type_name.clone());
+        type_name
+    }
+
+    /// The Rust type of a leaf `name` of `schema_type`, generating an enum for an
+    /// enumeration or a union.
+    fn leaf_type(&mut self, parent: &str, name: &str, schema_type: &SchemaType) -> Option<String> {
+        let Ok(resolved) = resolve_type(schema_type, self.error_context) else {
+            self.error_occured = true;
+            return None;
+        };
+        match (&resolved, &schema_type.target) {
+            (Type::Leafref(_), Some(target)) => return self.leaf_type(parent, name, target),
+            (Type::Enumeration(_) | Type::Union(_), _) => {}
+            _ => return Some(resolved.get_rust_type().to_string()),
+        }
+
+        let typedef_name = schema_type.typedefs.first().map(|typedef| &typedef.name);
+        if let Some(enum_name) = typedef_name.and_then(|name| self.typedef_enums.get(name)) {
+            return Some(enum_name.clone());
+        }
+        // Enums of the members go after the union, unless they are generated
+        // already, as they are for a union generated before.
+        let index = self.items.len();
+        self.items.push(String::new());
+        let mut variants = String::new();
+        let derive = match &resolved {
+            Type::Enumeration(enumeration) => {
+                let declared_enums = std::iter::once(&schema_type.r#type)
+                    .chain(schema_type.typedefs.iter().map(|typedef| &typedef.r#type))
+                    .flat_map(|r#type| &r#type.r#enum);
+                for (name, value) in enumeration.items.iter().flatten() {
+                    let description = declared_enums
+                        .clone()
+                        .find(|r#enum| r#enum.name == *name && r#enum.description.is_some())
+                        .and_then(|r#enum| r#enum.description.as_deref());
+                    push_doc(&mut variants, "    ", description);
+                    variants.push_str(&format!("    {} = {},\n", type_name(name), value));
+                }
+                "#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]\n#[repr(i32)]\n"
+            }
+            _ => {
+                let mut variant_names = HashSet::new();
+                for member in &schema_type.members {
+                    let member_name = match member.typedefs.first() {
+                        Some(typedef) => &typedef.name,
+                        None => member.r#type.name.as_deref().unwrap_or_default(),
+                    };
+                    let member_name = member_name.rsplit(':').next().unwrap_or_default();
+                    let rust_type =
+                        self.leaf_type(parent, &format!("{}-{}", name, member_name), member)?;
+                    let mut variant_name = type_name(member_name);
+                    while !variant_names.insert(variant_name.clone()) {
+                        variant_name.push('_');
+                    }
+                    variants.push_str(&format!("    {}({}),\n", variant_name, rust_type));
+                }
+                "#[derive(Debug, Clone, PartialEq)]\n"
+            }
+        };
+
+        if typedef_name.is_none() {
+            if let Some(enum_name) = self.anonymous_enums.get(&variants) {
+                self.items.truncate(index);
+                return Some(enum_name.clone());
+            }
+        }
+        let enum_name = match typedef_name {
+            Some(typedef_name) => self.reserve_type_name(parent, typedef_name),
+            None => self.reserve_type_name(parent, name),
+        };
+        match typedef_name {
+            Some(typedef_name) => self
+                .typedef_enums
+                .insert(typedef_name.clone(), enum_name.clone()),
+            None => self
+                .anonymous_enums
+                .insert(variants.clone(), enum_name.clone()),
+        };
+        if matches!(resolved, Type::Union(_)) {
+            self.union_enums.insert(enum_name.clone());
+        }
+
+        let mut output = String::new();
+        let description = schema_type
+            .typedefs
+            .first()
+            .and_then(|typedef| typedef.description.as_deref());
+        push_doc(&mut output, "", description);
+        output.push_str(derive);
+        output.push_str(&format!("pub enum {} {{\n", enum_name));
+        output.push_str(&variants);
+        output.push_str("}\n");
+        self.items[index] = output;
+        Some(enum_name)
+    }
+
+    /// Generates the fields for `nodes` into `output`. Fields of nodes within a
+    /// choice are always optional.
+    fn push_fields(
+        &mut self,
+        output: &mut String,
+        parent: &str,
+        nodes: &[SchemaNode],
+        keys: &[&str],
+        in_choice: bool,
+    ) {
+        for node in nodes {
+            let field_type = match &node.kind {
+                SchemaNodeKind::Leaf {
+                    r#type, mandatory, ..
+                } => {
+                    let Some(rust_type) = self.leaf_type(parent, &node.name, r#type) else {
+                        continue;
+                    };
+                    self.leaf_types.insert(node, rust_type.clone());
+                    let is_key = keys.contains(&node.name.as_str());
+                    if (*mandatory || is_key) && !in_choice {
+                        rust_type
+                    } else {
+                        format!("Option<{}>", rust_type)
+                    }
+                }
+                SchemaNodeKind::LeafList { r#type, .. } => {
+                    let Some(rust_type) = self.leaf_type(parent, &node.name, r#type) else {
+                        continue;
+                    };
+                    format!("Vec<{}>", rust_type)
+                }
+                SchemaNodeKind::Container { presence } => {
+                    let struct_name = self.push_struct(parent, node, &[]);
+                    if presence.is_some() || in_choice {
+                        format!("Option<{}>", struct_name)
+                    } else {
+                        struct_name
+                    }
+                }
+                SchemaNodeKind::List {
+                    key, ordered_by, ..
+                } => self.list_type(parent, node, key, *ordered_by),
+                SchemaNodeKind::Choice { .. } | SchemaNodeKind::Case => {
+                    self.push_fields(output, parent, &node.children, &[], true);
+                    continue;
+                }
+                _ => continue,
+            };
+            push_doc(output, "    ", node.description.as_deref());
+            output.push_str(&format!(
+                "    pub {}: {},\n",
+                field_name(&node.name),
+                field_type
+            ));
+        }
+    }
+
+    /// Lists with keys become maps from the key, or a tuple of the keys, to the
+    /// entry. Keys without a total order, like decimals and unions, and lists
+    /// ordered by the user, whose order a map would lose, fall back to a `Vec`.
+    fn list_type(
+        &mut self,
+        parent: &str,
+        node: &SchemaNode,
+        key: &[String],
+        ordered_by: OrderedBy,
+    ) -> String {
+        let keys: Vec<&str> = key.iter().map(String::as_str).collect();
+        let entry_name = self.push_struct(parent, node, &keys);
+        if keys.is_empty() {
+            return format!("Vec<{}>", entry_name);
+        }
+
+        let mut key_types = vec![];
+        for key in &keys {
+            let key_type = node
+                .child(key)
+                .and_then(|child| self.leaf_types.get(&(child as *const SchemaNode)));
+            match key_type.map(String::as_str) {
+                Some(key_type) if key_type == "f64" || self.union_enums.contains(key_type) => {
+                    return format!("Vec<{}>", entry_name)
+                }
+                Some(key_type) => key_types.push(key_type.to_string()),
+                None => {
+                    self.error_context.add_error(
+                        node.span,
+                        format!("Key {} of list {} not found", key, node.name),
+                    );
+                    self.error_occured = true;
+                    return format!("Vec<{}>", entry_name);
+                }
+            }
+        }
+        if ordered_by == OrderedBy::User {
+            format!("Vec<{}>", entry_name)
+        } else if key_types.len() == 1 {
+            format!("BTreeMap<{}, {}>", key_types[0], entry_name)
+        } else {
+            format!("BTreeMap<({}), {}>", key_types.join(", "), entry_name)
+        }
+    }
+
+    fn push_struct(&mut self, parent: &str, node: &SchemaNode, keys: &[&str]) -> String {
+        let struct_name = self.reserve_type_name(parent, &node.name);
+        let index = self.items.len();
+        self.items.push(String::new());
+
+        let mut output = String::new();
+        push_doc(&mut output, "", node.description.as_deref());
+        output.push_str("#[derive(Debug, Clone, PartialEq)]\n");
+        output.push_str(&format!("pub struct {} {{\n", struct_name));
+        self.push_fields(&mut output, &struct_name, &node.children, keys, false);
+        output.push_str("}\n");
+        self.items[index] = output;
+        struct_name
+    }
+}
+
+/// Generates Rust source for the data nodes of `schema`, with a struct named
+/// after the module as root.
+pub(crate) fn generate(schema: &Schema, error_context: &mut ErrorContext) -> Result<String, ()> {
+    let mut generator = Generator {
+        error_context,
+        error_occured: false,
+        items: vec![],
+        type_names: HashSet::new(),
+        typedef_enums: HashMap::new(),
+        anonymous_enums: HashMap::new(),
+        union_enums: HashSet::new(),
+        leaf_types: HashMap::new(),
+    };
+
+    let root_name = generator.reserve_type_name("", &schema.name);
+    generator.items.push(String::new());
+    let mut root = String::new();
+    push_doc(&mut root, "", schema.description.as_deref());
+    root.push_str("#[derive(Debug, Clone, PartialEq)]\n");
+    root.push_str(&format!("pub struct {} {{\n", root_name));
+    generator.push_fields(&mut root, &root_name, &schema.data, &[], false);
+    root.push_str("}\n");
+    generator.items[0] = root;
+
+    if generator.error_occured {
+        return Err(());
+    }
+    let mut output = format!("// Generated from YANG module {}.\n\n", schema.name);
+    if generator
+        .items
+        .iter()
+        .any(|item| item.contains("BTreeMap<"))
+    {
+        output.push_str("use std::collections::BTreeMap;\n\n");
+    }
+    output.push_str(&generator.items.join("\n"));
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::model::Module;
+    use crate::parser::model_mapper::Mapper;
+    use crate::parser::parser::parse;
+    use crate::repository::Repository;
+    use crate::schema::compile;
+
+    fn generate_str(input: &str) -> (Result<String, ()>, ErrorContext) {
+        let mut error_context = ErrorContext::new();
+        let module = Module::map(parse(input).unwrap(), &mut error_context).unwrap();
+        match compile(&module, &Repository::default(), &mut error_context) {
+            Ok(schema) => (generate(&schema, &mut error_context), error_context),
+            Err(()) => (Err(()), error_context),
+        }
+    }
+
+    #[test]
+    fn test_names() {
+        assert_eq!(field_name("ipv4-address"), "ipv4_address");
+        assert_eq!(field_name("ifIndex"), "if_index");
+        assert_eq!(field_name("type"), "r#type");
+        assert_eq!(field_name("self"), "self_");
+        assert_eq!(type_name("address-family"), "AddressFamily");
+        assert_eq!(type_name("speed-10m"), "Speed10m");
+        assert_eq!(type_name("10m"), "Value10m");
+    }
+
+    #[test]
+    fn test_generate() {
+        let (output, _) = generate_str(
+            r#"module example {
+                namespace "urn:example";
+                prefix ex;
+                description "Example module.";
+                typedef percent {
+                    type uint8 { range "0..100"; }
+                }
+                typedef speed {
+                    description "Link speed.";
+                    type enumeration {
+                        enum slow { value 10; }
+                        enum fast { description "Really fast."; }
+                    }
+                }
+                container system {
+                    presence "enables the system";
+                    leaf host-name {
+                        type string;
+                        description "Hostname for this system";
+                    }
+                    leaf-list domain-search { type string; }
+                    list user {
+                        key "name";
+                        leaf name { type string; }
+                        leaf uid { type uint32; mandatory true; }
+                        leaf speed { type speed; }
+                    }
+                    list event {
+                        leaf severity {
+                            type enumeration {
+                                enum minor;
+                                enum major { value 5; }
+                            }
+                        }
+                    }
+                    list rule {
+                        key "id";
+                        ordered-by user;
+                        leaf id { type uint8; }
+                    }
+                }
+                container limits {
+                    leaf load { type percent; }
+                    leaf ratio { type decimal64 { fraction-digits 2; } }
+                    leaf type { type speed; }
+                    choice mode {
+                        leaf automatic { type boolean; mandatory true; }
+                        container manual { leaf value { type int16; } }
+                    }
+                }
+            }"#,
+        );
+        assert_eq!(
+            output.unwrap(),
+            "\
+// Generated from YANG module example.
+
+use std::collections::BTreeMap;
+
+/// Example module.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Example {
+    pub system: Option<System>,
+    pub limits: Limits,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct System {
+    /// Hostname for this system
+    pub host_name: Option<String>,
+    pub domain_search: Vec<String>,
+    pub user: BTreeMap<String, User>,
+    pub event: Vec<Event>,
+    pub rule: Vec<Rule>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct User {
+    pub name: String,
+    pub uid: u32,
+    pub speed: Option<Speed>,
+}
+
+/// Link speed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(i32)]
+pub enum Speed {
+    Slow = 10,
+    /// Really fast.
+    Fast = 11,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Event {
+    pub severity: Option<Severity>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(i32)]
+pub enum Severity {
+    Minor = 0,
+    Major = 5,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rule {
+    pub id: u8,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Limits {
+    pub load: Option<u8>,
+    pub ratio: Option<f64>,
+    pub r#type: Option<Speed>,
+    pub automatic: Option<bool>,
+    pub manual: Option<Manual>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Manual {
+    pub value: Option<i16>,
+}
+"
+        );
+    }
+
+    #[test]
+    fn test_unions_and_leafrefs() {
+        let (output, _) = generate_str(
+            r#"module example {
+                namespace "urn:example";
+                prefix ex;
+                typedef port {
+                    description "A port number or any port.";
+                    type union {
+                        type uint16;
+                        type enumeration { enum any; }
+                    }
+                }
+                list interface {
+                    key "name";
+                    leaf name {
+                        type union { type string; type int32; }
+                    }
+                    leaf mode { type enumeration { enum up; enum down; } }
+                    leaf port { type port; }
+                }
+                leaf current {
+                    type leafref { path "/interface/name"; }
+                }
+                leaf-list modes {
+                    type leafref { path "/interface/mode"; }
+                }
+                leaf ports {
+                    type union {
+                        type leafref { path "/interface/port"; }
+                        type string { pattern "[a-z]+"; }
+                        type string;
+                    }
+                }
+            }"#,
+        );
+        assert_eq!(
+            output.unwrap(),
+            "\
+// Generated from YANG module example.
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Example {
+    pub interface: Vec<Interface>,
+    pub current: Option<Name>,
+    pub modes: Vec<Mode>,
+    pub ports: Option<Ports>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Interface {
+    pub name: Name,
+    pub mode: Option<Mode>,
+    pub port: Option<Port>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Name {
+    String(String),
+    Int32(i32),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(i32)]
+pub enum Mode {
+    Up = 0,
+    Down = 1,
+}
+
+/// A port number or any port.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Port {
+    Uint16(u16),
+    Enumeration(PortEnumeration),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(i32)]
+pub enum PortEnumeration {
+    Any = 0,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Ports {
+    Leafref(String),
+    String(String),
+    String_(String),
+}
+"
+        );
+    }
+
+    #[test]
+    fn test_type_error() {
+        let (output, error_context) = generate_str(
+            r#"module example {
+                namespace "urn:example";
+                prefix ex;
+                leaf flags { type bits; }
+            }"#,
+        );
+        assert!(output.is_err());
+        assert_eq!(
+            error_context.messages(),
+            ["Type bits is missing restrictions"]
+        );
+    }
+}
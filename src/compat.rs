@@ -0,0 +1,855 @@
+//! Comparison of two revisions of a module by the update rules of RFC 7950,
+//! section 11.
+//!
+//! ```no_run
+//! let changes = yang_utils::compat::Builder::new(
+//!     "old/acme-system.yang",
+//!     "new/acme-system.yang",
+//! )
+//! .compare()
+//! .unwrap_or_else(|error| panic!("{}", error));
+//! for change in changes {
+//!     println!("{}", change);
+//! }
+//! ```
+
+use crate::bindings::{diagnostics, line_column, Diagnostic, Error};
+use crate::errors::ErrorContext;
+use crate::parser::model::OrderedBy;
+use crate::parser::model_mapper::ArgumentUnmapper;
+use crate::repository::{ModuleKind, Repository};
+use crate::schema::{compile, find_mandatory, Schema, SchemaNode, SchemaNodeKind, SchemaType};
+use crate::typing::{resolve_type, RangeElement, RangeSet, Type};
+use crate::Span;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Whether clients of the old revision keep working with the new one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compatibility {
+    Compatible,
+    NonBackwardCompatible,
+}
+
+impl fmt::Display for Compatibility {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Compatibility::Compatible => "compatible",
+            Compatibility::NonBackwardCompatible => "non-backward-compatible",
+        })
+    }
+}
+
+/// A change between two compiled revisions, with the spans of the changed
+/// definition in each of them.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct SchemaChange {
+    pub(crate) compatibility: Compatibility,
+    pub(crate) message: String,
+    /// Span in the old revision, or of the parent of an added node.
+    pub(crate) old_span: Option<Span>,
+    /// Span in the new revision, or of the parent of a removed node.
+    pub(crate) new_span: Option<Span>,
+}
+
+/// The name of the built-in type `schema_type` derives from, or of the type of
+/// another module it derives from.
+/// The `ordered-by` of a list or leaf-list.
+fn ordered_by(node: &SchemaNode) -> Option<OrderedBy> {
+    match node.kind {
+        SchemaNodeKind::List { ordered_by, .. } | SchemaNodeKind::LeafList { ordered_by, .. } => {
+            Some(ordered_by)
+        }
+        _ => None,
+    }
+}
+
+fn base_type_name(schema_type: &SchemaType) -> &str {
+    schema_type
+        .typedefs
+        .last()
+        .map_or(&schema_type.r#type, |typedef| &typedef.r#type)
+        .name
+        .as_deref()
+        .unwrap_or_default()
+}
+
+/// The patterns of `schema_type` and the typedefs it derives from.
+fn patterns(schema_type: &SchemaType) -> Vec<&str> {
+    std::iter::once(&schema_type.r#type)
+        .chain(schema_type.typedefs.iter().map(|typedef| &typedef.r#type))
+        .flat_map(|r#type| r#type.pattern.iter().map(|pattern| pattern.regex.as_str()))
+        .collect()
+}
+
+/// The bits of `schema_type`, with their positions if given, from the closest type
+/// defining them.
+fn bits(schema_type: &SchemaType) -> Vec<(&str, Option<u32>)> {
+    std::iter::once(&schema_type.r#type)
+        .chain(schema_type.typedefs.iter().map(|typedef| &typedef.r#type))
+        .find(|r#type| !r#type.bit.is_empty())
+        .map(|r#type| {
+            r#type
+                .bit
+                .iter()
+                .map(|bit| (bit.name.as_str(), bit.position))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+struct Comparison {
+    changes: Vec<SchemaChange>,
+}
+
+impl Comparison {
+    fn add(
+        &mut self,
+        compatibility: Compatibility,
+        message: String,
+        old_span: Option<Span>,
+        new_span: Option<Span>,
+    ) {
+        self.changes.push(SchemaChange {
+            compatibility,
+            message,
+            old_span,
+            new_span,
+        });
+    }
+
+    /// Adds a change of a range or length of `path` from `old` to `new`.
+    fn compare_ranges<T: RangeElement>(
+        &mut self,
+        kind: &str,
+        path: &str,
+        spans: (Span, Span),
+        old: &RangeSet<T>,
+        new: &RangeSet<T>,
+    ) {
+        let kept = old.intersection(new);
+        if kept.is_empty() {
+            self.add(
+                Compatibility::NonBackwardCompatible,
+                format!("{} of {} allows none of its former values", kind, path),
+                Some(spans.0),
+                Some(spans.1),
+            );
+        } else if kept != *old {
+            self.add(
+                Compatibility::NonBackwardCompatible,
+                format!("{} of {} narrowed", kind, path),
+                Some(spans.0),
+                Some(spans.1),
+            );
+        } else if old.union(new) != *old {
+            self.add(
+                Compatibility::Compatible,
+                format!("{} of {} expanded", kind, path),
+                Some(spans.0),
+                Some(spans.1),
+            );
+        }
+    }
+
+    fn compare_type(&mut self, old: &SchemaType, new: &SchemaType, path: &str) {
+        let spans = (old.r#type.span, new.r#type.span);
+        let (old_base, new_base) = (base_type_name(old), base_type_name(new));
+        if old_base != new_base {
+            self.add(
+                Compatibility::NonBackwardCompatible,
+                format!("Type of {} changed from {} to {}", path, old_base, new_base),
+                Some(spans.0),
+                Some(spans.1),
+            );
+            return;
+        }
+
+        let resolved = (
+            resolve_type(old, &mut ErrorContext::new()),
+            resolve_type(new, &mut ErrorContext::new()),
+        );
+        match resolved {
+            (Ok(Type::Int(old_type)), Ok(Type::Int(new_type))) => {
+                self.compare_ranges("Range", path, spans, &old_type.range, &new_type.range);
+            }
+            (Ok(Type::Decimal(old_type)), Ok(Type::Decimal(new_type))) => {
+                if old_type.fraction_digits != new_type.fraction_digits {
+                    self.add(
+                        Compatibility::NonBackwardCompatible,
+                        format!("Fraction digits of {} changed", path),
+                        Some(spans.0),
+                        Some(spans.1),
+                    );
+                    return;
+                }
+                if let (Some(old_range), Some(new_range)) = (&old_type.range, &new_type.range) {
+                    self.compare_ranges("Range", path, spans, old_range, new_range);
+                }
+            }
+            (Ok(Type::String(old_type)), Ok(Type::String(new_type))) => {
+                self.compare_ranges("Length", path, spans, &old_type.length, &new_type.length);
+
+                let (old_patterns, new_patterns) = (patterns(old), patterns(new));
+                for pattern in &new_patterns {
+                    if !old_patterns.contains(pattern) {
+                        self.add(
+                            Compatibility::NonBackwardCompatible,
+                            format!("Pattern {} added to {}", pattern, path),
+                            Some(spans.0),
+                            Some(spans.1),
+                        );
+                    }
+                }
+                for pattern in &old_patterns {
+                    if !new_patterns.contains(pattern) {
+                        self.add(
+                            Compatibility::Compatible,
+                            format!("Pattern {} removed from {}", pattern, path),
+                            Some(spans.0),
+                            Some(spans.1),
+                        );
+                    }
+                }
+            }
+            (Ok(Type::Enumeration(old_type)), Ok(Type::Enumeration(new_type))) => {
+                let old_items = old_type.items.unwrap_or_default();
+                let new_items = new_type.items.unwrap_or_default();
+                self.compare_items(
+                    "Enum",
+                    path,
+                    spans,
+                    old_items
+                        .iter()
+                        .map(|(name, value)| (name.as_str(), Some(*value))),
+                    new_items
+                        .iter()
+                        .map(|(name, value)| (name.as_str(), Some(*value))),
+                );
+            }
+            (Ok(Type::Identityref(old_type)), Ok(Type::Identityref(new_type)))
+                if old_type.bases != new_type.bases =>
+            {
+                self.add(
+                    Compatibility::NonBackwardCompatible,
+                    format!(
+                        "Base of {} changed from {} to {}",
+                        path,
+                        old_type.bases.join(", "),
+                        new_type.bases.join(", ")
+                    ),
+                    Some(spans.0),
+                    Some(spans.1),
+                );
+            }
+            _ if old_base == "bits" => {
+                self.compare_items(
+                    "Bit",
+                    path,
+                    spans,
+                    bits(old)
+                        .into_iter()
+                        .map(|(name, position)| (name, position.map(|position| position as i32))),
+                    bits(new)
+                        .into_iter()
+                        .map(|(name, position)| (name, position.map(|position| position as i32))),
+                );
+            }
+            _ => {}
+        }
+    }
+
+    /// Compares the enums or bits of a type, with their values or positions.
+    fn compare_items<'i>(
+        &mut self,
+        kind: &str,
+        path: &str,
+        spans: (Span, Span),
+        old: impl Iterator<Item = (&'i str, Option<i32>)>,
+        new: impl Iterator<Item = (&'i str, Option<i32>)>,
+    ) {
+        let new: Vec<(&str, Option<i32>)> = new.collect();
+        let mut kept = vec![];
+        for (name, value) in old {
+            let change = match new.iter().find(|(new_name, _)| *new_name == name) {
+                None => format!("{} {} of {} removed", kind, name, path),
+                Some((_, new_value)) if *new_value != value => {
+                    format!("{} {} of {} changed its value", kind, name, path)
+                }
+                Some(_) => {
+                    kept.push(name);
+                    continue;
+                }
+            };
+            kept.push(name);
+            self.add(
+                Compatibility::NonBackwardCompatible,
+                change,
+                Some(spans.0),
+                Some(spans.1),
+            );
+        }
+        for (name, _) in new {
+            if !kept.contains(&name) {
+                self.add(
+                    Compatibility::Compatible,
+                    format!("{} {} added to {}", kind, name, path),
+                    Some(spans.0),
+                    Some(spans.1),
+                );
+            }
+        }
+    }
+
+    /// Compares the units of a leaf or leaf-list. Units may only be added.
+    fn compare_units(
+        &mut self,
+        old: &Option<String>,
+        new: &Option<String>,
+        path: &str,
+        spans: (Option<Span>, Option<Span>),
+    ) {
+        let (compatibility, message) = match (old, new) {
+            (None, Some(new)) => (
+                Compatibility::Compatible,
+                format!("Units {} added to {}", new, path),
+            ),
+            (Some(old), None) => (
+                Compatibility::NonBackwardCompatible,
+                format!("Units {} of {} removed", old, path),
+            ),
+            (Some(old), Some(new)) if old != new => (
+                Compatibility::NonBackwardCompatible,
+                format!("Units of {} changed from {} to {}", path, old, new),
+            ),
+            _ => return,
+        };
+        self.add(compatibility, message, spans.0, spans.1);
+    }
+
+    fn compare_node(&mut self, old: &SchemaNode, new: &SchemaNode, path: &str) {
+        let spans = (Some(old.span), Some(new.span));
+        if old.keyword() != new.keyword() {
+            self.add(
+                Compatibility::NonBackwardCompatible,
+                format!(
+                    "Node {} changed from {} to {}",
+                    path,
+                    old.keyword(),
+                    new.keyword()
+                ),
+                spans.0,
+                spans.1,
+            );
+            return;
+        }
+        if let (Some(old_order), Some(new_order)) = (ordered_by(old), ordered_by(new)) {
+            if old_order != new_order {
+                self.add(
+                    Compatibility::NonBackwardCompatible,
+                    format!(
+                        "Order of {} changed from {} to {}",
+                        path,
+                        old_order.unmap_argument(),
+                        new_order.unmap_argument()
+                    ),
+                    spans.0,
+                    spans.1,
+                );
+            }
+        }
+        match (&old.kind, &new.kind) {
+            (
+                SchemaNodeKind::Leaf {
+                    r#type: old_type,
+                    mandatory: old_mandatory,
+                    units: old_units,
+                    ..
+                },
+                SchemaNodeKind::Leaf {
+                    r#type: new_type,
+                    mandatory: new_mandatory,
+                    units: new_units,
+                    ..
+                },
+            ) => {
+                self.compare_type(old_type, new_type, path);
+                self.compare_units(old_units, new_units, path, spans);
+                if !old_mandatory && *new_mandatory {
+                    self.add(
+                        Compatibility::NonBackwardCompatible,
+                        format!("Node {} became mandatory", path),
+                        spans.0,
+                        spans.1,
+                    );
+                }
+            }
+            (
+                SchemaNodeKind::LeafList {
+                    r#type: old_type,
+                    units: old_units,
+                    ..
+                },
+                SchemaNodeKind::LeafList {
+                    r#type: new_type,
+                    units: new_units,
+                    ..
+                },
+            ) => {
+                self.compare_type(old_type, new_type, path);
+                self.compare_units(old_units, new_units, path, spans);
+            }
+            (
+                SchemaNodeKind::List { key: old_key, .. },
+                SchemaNodeKind::List { key: new_key, .. },
+            ) if old_key != new_key => {
+                self.add(
+                    Compatibility::NonBackwardCompatible,
+                    format!(
+                        "Key of list {} changed from {} to {}",
+                        path,
+                        old_key.join(" "),
+                        new_key.join(" ")
+                    ),
+                    spans.0,
+                    spans.1,
+                );
+            }
+            (
+                SchemaNodeKind::Choice {
+                    mandatory: false, ..
+                },
+                SchemaNodeKind::Choice {
+                    mandatory: true, ..
+                },
+            ) => {
+                self.add(
+                    Compatibility::NonBackwardCompatible,
+                    format!("Node {} became mandatory", path),
+                    spans.0,
+                    spans.1,
+                );
+            }
+            _ => {}
+        }
+        self.compare_children(
+            &old.children.iter().collect::<Vec<_>>(),
+            &new.children.iter().collect::<Vec<_>>(),
+            path,
+            spans,
+        );
+    }
+
+    /// Compares the children of a node, given with the spans of the node in both
+    /// revisions.
+    fn compare_children(
+        &mut self,
+        old: &[&SchemaNode],
+        new: &[&SchemaNode],
+        path: &str,
+        spans: (Option<Span>, Option<Span>),
+    ) {
+        for old_node in old {
+            let node_path = format!("{}/{}", path, old_node.name);
+            match new.iter().find(|new_node| new_node.name == old_node.name) {
+                Some(new_node) => self.compare_node(old_node, new_node, &node_path),
+                None => self.add(
+                    Compatibility::NonBackwardCompatible,
+                    format!("Node {} removed", node_path),
+                    Some(old_node.span),
+                    spans.1,
+                ),
+            }
+        }
+        for new_node in new {
+            if old.iter().any(|old_node| old_node.name == new_node.name) {
+                continue;
+            }
+            let node_path = format!("{}/{}", path, new_node.name);
+            // Mandatory nodes of a new case only apply once the case is chosen.
+            let mandatory = !matches!(new_node.kind, SchemaNodeKind::Case)
+                && find_mandatory(new_node).is_some();
+            if mandatory {
+                self.add(
+                    Compatibility::NonBackwardCompatible,
+                    format!("Mandatory node {} added", node_path),
+                    spans.0,
+                    Some(new_node.span),
+                );
+            } else {
+                self.add(
+                    Compatibility::Compatible,
+                    format!("Node {} added", node_path),
+                    spans.0,
+                    Some(new_node.span),
+                );
+            }
+        }
+    }
+}
+
+/// The nodes augmented into other modules by `schema`, grouped by target.
+fn augmented_nodes(schema: &Schema) -> Vec<(&str, Vec<&SchemaNode>)> {
+    let mut targets: Vec<(&str, Vec<&SchemaNode>)> = vec![];
+    for augment in &schema.augments {
+        match targets
+            .iter_mut()
+            .find(|(target, _)| *target == augment.target)
+        {
+            Some((_, nodes)) => nodes.extend(&augment.nodes),
+            None => targets.push((&augment.target, augment.nodes.iter().collect())),
+        }
+    }
+    targets
+}
+
+/// Compares the old revision `old` of a module with the new revision `new`.
+pub(crate) fn compare_schemas(old: &Schema, new: &Schema) -> Vec<SchemaChange> {
+    let mut comparison = Comparison { changes: vec![] };
+    if old.namespace != new.namespace {
+        comparison.add(
+            Compatibility::NonBackwardCompatible,
+            format!(
+                "Namespace changed from {} to {}",
+                old.namespace, new.namespace
+            ),
+            Some(old.span),
+            Some(new.span),
+        );
+    }
+    let spans = (Some(old.span), Some(new.span));
+    for (old_nodes, new_nodes) in [
+        (&old.data, &new.data),
+        (&old.rpcs, &new.rpcs),
+        (&old.notifications, &new.notifications),
+    ] {
+        comparison.compare_children(
+            &old_nodes.iter().collect::<Vec<_>>(),
+            &new_nodes.iter().collect::<Vec<_>>(),
+            "",
+            spans,
+        );
+    }
+
+    let (old_augments, new_augments) = (augmented_nodes(old), augmented_nodes(new));
+    for (target, old_nodes) in &old_augments {
+        let new_nodes = new_augments
+            .iter()
+            .find(|(new_target, _)| new_target == target)
+            .map_or(&[][..], |(_, nodes)| nodes);
+        comparison.compare_children(old_nodes, new_nodes, target, spans);
+    }
+    for (target, new_nodes) in &new_augments {
+        if !old_augments
+            .iter()
+            .any(|(old_target, _)| old_target == target)
+        {
+            comparison.compare_children(&[], new_nodes, target, spans);
+        }
+    }
+    comparison.changes
+}
+
+/// A position in a YANG file, with a 1-based line and column.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Location {
+    pub path: PathBuf,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}:{}", self.path.display(), self.line, self.column)
+    }
+}
+
+/// A change between two revisions, with its location in the old and in the new
+/// revision.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Change {
+    pub compatibility: Compatibility,
+    pub message: String,
+    pub old: Option<Location>,
+    pub new: Option<Location>,
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.compatibility, self.message)?;
+        if let Some(old) = &self.old {
+            write!(f, "\n  old: {}", old)?;
+        }
+        if let Some(new) = &self.new {
+            write!(f, "\n  new: {}", new)?;
+        }
+        Ok(())
+    }
+}
+
+/// A loaded and compiled revision of a module.
+struct Revision {
+    path: PathBuf,
+    source: String,
+    schema: Schema,
+}
+
+impl Revision {
+    fn location(&self, span: Span) -> Location {
+        let (line, column) = line_column(&self.source, span.0);
+        Location {
+            path: self.path.clone(),
+            line,
+            column,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Builder {
+    old: PathBuf,
+    new: PathBuf,
+    search_paths: Vec<PathBuf>,
+}
+
+impl Builder {
+    pub fn new(old: impl Into<PathBuf>, new: impl Into<PathBuf>) -> Self {
+        Self {
+            old: old.into(),
+            new: new.into(),
+            search_paths: vec![],
+        }
+    }
+
+    /// Adds a directory to search imported and included modules of both revisions
+    /// in. The directory of each revision is always searched last.
+    pub fn search_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.search_paths.push(path.into());
+        self
+    }
+
+    fn load(&self, path: &Path) -> Result<Revision, Error> {
+        let mut repository = Repository::new(self.search_paths.clone());
+        let index = repository.load(path)?;
+        let loaded = &repository.modules[index];
+        let ModuleKind::Module(module) = &loaded.kind else {
+            return Err(Error::Yang(vec![Diagnostic {
+                path: loaded.path.clone(),
+                line: 1,
+                column: 1,
+                is_error: true,
+                message: "Only modules can be compared".to_string(),
+                help: None,
+                fixes: vec![],
+                related: vec![],
+            }]));
+        };
+        let mut error_context = ErrorContext::new();
+        let Ok(schema) = compile(module, &repository, &mut error_context) else {
+            return Err(Error::Yang(diagnostics(
+                &loaded.path,
+                &loaded.source,
+                &error_context,
+            )));
+        };
+        Ok(Revision {
+            path: loaded.path.clone(),
+            source: loaded.source.clone(),
+            schema,
+        })
+    }
+
+    /// Compares the old revision with the new one.
+    pub fn compare(&self) -> Result<Vec<Change>, Error> {
+        let old = self.load(&self.old)?;
+        let new = self.load(&self.new)?;
+        Ok(compare_schemas(&old.schema, &new.schema)
+            .into_iter()
+            .map(|change| Change {
+                compatibility: change.compatibility,
+                message: change.message,
+                old: change.old_span.map(|span| old.location(span)),
+                new: change.new_span.map(|span| new.location(span)),
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::model::Module;
+    use crate::parser::model_mapper::Mapper;
+    use crate::parser::parser::parse;
+    use std::fs;
+
+    fn compile_str(input: &str) -> Schema {
+        let mut error_context = ErrorContext::new();
+        let module = Module::map(parse(input).unwrap(), &mut error_context).unwrap();
+        compile(&module, &Repository::default(), &mut error_context).unwrap()
+    }
+
+    fn compare(old: &str, new: &str) -> Vec<(Compatibility, String)> {
+        compare_schemas(&compile_str(old), &compile_str(new))
+            .into_iter()
+            .map(|change| (change.compatibility, change.message))
+            .collect()
+    }
+
+    const OLD: &str = r#"module example {
+        namespace "urn:example";
+        prefix ex;
+        typedef percent { type uint8 { range "0..100"; } }
+        container system {
+            leaf hostname { type string { length "1..64"; } }
+            leaf load { type percent; units percent; }
+            leaf mtu { type uint16 { range "68..1500"; } }
+            leaf retries { type uint8 { range "1..3"; } }
+            leaf mode {
+                type enumeration { enum fast; enum slow; enum off; }
+            }
+            leaf flags { type bits { bit a; bit b; } }
+            leaf port { type uint16; }
+            leaf timeout { type uint32; }
+            list user {
+                key name;
+                leaf name { type string; }
+                leaf id { type uint32; }
+            }
+            choice transport {
+                case tcp { leaf window { type uint16; } }
+            }
+        }
+        rpc reboot;
+    }"#;
+
+    #[test]
+    fn test_unchanged() {
+        assert_eq!(compare(OLD, OLD), []);
+    }
+
+    #[test]
+    fn test_changes() {
+        let new = r#"module example {
+            namespace "urn:example:v2";
+            prefix ex;
+            typedef percent { type uint8 { range "0..50 | 51..100"; } }
+            container system {
+                leaf hostname {
+                    type string { length "1..32"; pattern "[a-z]+"; }
+                }
+                leaf load { type percent; units ratio; }
+                leaf mtu { type uint16 { range "68..9000"; } units bytes; }
+                leaf retries { type uint8 { range "5..10"; } }
+                leaf mode { type enumeration { enum fast; enum slow { value 5; } enum auto; } }
+                leaf flags { type bits { bit a; bit b; bit c; } }
+                leaf port { type string; }
+                list user {
+                    key id;
+                    ordered-by user;
+                    leaf name { type string; }
+                    leaf id { type uint32; }
+                }
+                choice transport {
+                    case tcp { leaf window { type uint16; } }
+                    case udp { leaf size { type uint16; mandatory true; } }
+                }
+                leaf contact { type string; }
+                leaf location { type string; mandatory true; }
+            }
+        }"#;
+        use Compatibility::*;
+        assert_eq!(
+            compare(OLD, new),
+            [
+                (
+                    NonBackwardCompatible,
+                    "Namespace changed from urn:example to urn:example:v2".to_string()
+                ),
+                (
+                    NonBackwardCompatible,
+                    "Length of /system/hostname narrowed".to_string()
+                ),
+                (
+                    NonBackwardCompatible,
+                    "Pattern [a-z]+ added to /system/hostname".to_string()
+                ),
+                (
+                    NonBackwardCompatible,
+                    "Units of /system/load changed from percent to ratio".to_string()
+                ),
+                (Compatible, "Range of /system/mtu expanded".to_string()),
+                (Compatible, "Units bytes added to /system/mtu".to_string()),
+                (
+                    NonBackwardCompatible,
+                    "Range of /system/retries allows none of its former values".to_string()
+                ),
+                (
+                    NonBackwardCompatible,
+                    "Enum slow of /system/mode changed its value".to_string()
+                ),
+                (
+                    NonBackwardCompatible,
+                    "Enum off of /system/mode removed".to_string()
+                ),
+                (Compatible, "Enum auto added to /system/mode".to_string()),
+                (Compatible, "Bit c added to /system/flags".to_string()),
+                (
+                    NonBackwardCompatible,
+                    "Type of /system/port changed from uint16 to string".to_string()
+                ),
+                (
+                    NonBackwardCompatible,
+                    "Node /system/timeout removed".to_string()
+                ),
+                (
+                    NonBackwardCompatible,
+                    "Order of /system/user changed from system to user".to_string()
+                ),
+                (
+                    NonBackwardCompatible,
+                    "Key of list /system/user changed from name to id".to_string()
+                ),
+                (Compatible, "Node /system/transport/udp added".to_string()),
+                (Compatible, "Node /system/contact added".to_string()),
+                (
+                    NonBackwardCompatible,
+                    "Mandatory node /system/location added".to_string()
+                ),
+                (NonBackwardCompatible, "Node /reboot removed".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_builder() {
+        let directory = std::env::temp_dir().join("yang-utils-compat-test");
+        fs::create_dir_all(directory.join("old")).unwrap();
+        fs::create_dir_all(directory.join("new")).unwrap();
+        let old = directory.join("old/example.yang");
+        let new = directory.join("new/example.yang");
+        fs::write(
+            &old,
+            "module example {\n  namespace urn:example;\n  prefix ex;\n  \
+             leaf a { type string; }\n  leaf b { type string; }\n}\n",
+        )
+        .unwrap();
+        fs::write(
+            &new,
+            "module example {\n  namespace urn:example;\n  prefix ex;\n  \
+             leaf a { type string; }\n}\n",
+        )
+        .unwrap();
+
+        let changes = Builder::new(&old, &new).compare().unwrap();
+        assert_eq!(changes.len(), 1);
+        assert_eq!(
+            changes[0].to_string(),
+            format!(
+                "non-backward-compatible: Node /b removed\n  old: {}:5:3\n  new: {}:1:1",
+                old.display(),
+                new.display()
+            )
+        );
+    }
+}
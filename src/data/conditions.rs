@@ -0,0 +1,590 @@
+//! Validation of the `must` and `when` conditions of a data tree, with the context
+//! nodes of RFC 7950, section 6.4.1, of the instances leafrefs require, and of the
+//! `mandatory`, `min-elements`, `max-elements` and `unique` constraints.
+
+use crate::data::path::{InstancePath, PathStep};
+use crate::data::{leaf_type, value_string, DataNode, DataNodeKind, DataTree};
+use crate::parser::model::{MaxElements, Unique};
+use crate::schema::{Schema, SchemaNode, SchemaNodeKind};
+use crate::typing::{Type, Value};
+use crate::xpath::{evaluate_condition, NodeRef};
+use std::collections::HashMap;
+
+/// A condition not satisfied by the node at `path`.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ConditionError {
+    pub(crate) path: String,
+    /// The `error-message` of the failed `must` statement, or a generic message.
+    pub(crate) message: String,
+    /// The `error-app-tag` of the failed `must` statement, or `must-violation`.
+    pub(crate) app_tag: Option<String>,
+}
+
+struct ConditionChecker<'a> {
+    schema: &'a Schema,
+    tree: &'a DataTree,
+    errors: Vec<ConditionError>,
+}
+
+/// The path step of `data_node`, with the key values of a list entry.
+fn path_step(data_node: &DataNode, schema_node: &SchemaNode, parent_module: &str) -> PathStep {
+    let predicates = match (&schema_node.kind, &data_node.kind) {
+        (SchemaNodeKind::List { key, .. }, _) => key
+            .iter()
+            .filter_map(|key| {
+                let value = data_node.child(key)?.value()?;
+                Some((key.to_string(), value_string(value)))
+            })
+            .collect(),
+        (_, DataNodeKind::LeafListEntry(value)) => vec![(".".to_string(), value_string(value))],
+        _ => vec![],
+    };
+    PathStep {
+        module: (data_node.module != parent_module).then(|| data_node.module.clone()),
+        name: data_node.name.clone(),
+        predicates,
+    }
+}
+
+/// The path of the leafref type of `schema_node`, if its instances are required.
+fn required_leafref(schema_node: &SchemaNode) -> Option<&str> {
+    let (SchemaNodeKind::Leaf { r#type, .. } | SchemaNodeKind::LeafList { r#type, .. }) =
+        &schema_node.kind
+    else {
+        return None;
+    };
+    match leaf_type(schema_node) {
+        Some(Type::Leafref(leafref)) if leafref.require_instance => r#type
+            .leafref()?
+            .path
+            .as_ref()
+            .map(|path| path.expression.as_str()),
+        _ => None,
+    }
+}
+
+/// The value of the descendant leaf of `data_node` at the relative schema node
+/// identifier `path`, whose prefixes are ignored.
+fn descendant_value<'d>(data_node: &'d DataNode, path: &str) -> Option<&'d Value> {
+    let mut descendant = data_node;
+    for step in path.split('/') {
+        let name = step.split_once(':').map_or(step, |(_, name)| name);
+        descendant = descendant.child(name)?;
+    }
+    descendant.value()
+}
+
+impl<'a> ConditionChecker<'a> {
+    fn add_error(&mut self, path: &InstancePath, message: String, app_tag: Option<&str>) {
+        self.errors.push(ConditionError {
+            path: path.to_string(),
+            message,
+            app_tag: app_tag.map(str::to_string),
+        });
+    }
+
+    fn node(&self, node: &NodeRef) -> Option<&'a DataNode> {
+        let (first, rest) = node.split_first()?;
+        let mut data_node = &self.tree.nodes[*first];
+        for index in rest {
+            data_node = &data_node.children()[*index];
+        }
+        Some(data_node)
+    }
+
+    /// Evaluates `condition` with `context` as context node, adding an error for
+    /// the node at `path` if it is not satisfied.
+    fn check(
+        &mut self,
+        condition: &str,
+        context: &NodeRef,
+        path: &InstancePath,
+        error: impl FnOnce() -> (String, Option<String>),
+    ) {
+        let (message, app_tag) =
+            match evaluate_condition(condition, self.schema, self.tree, context) {
+                Ok(true) => return,
+                Ok(false) => error(),
+                Err(message) => (message, None),
+            };
+        self.errors.push(ConditionError {
+            path: path.to_string(),
+            message,
+            app_tag,
+        });
+    }
+
+    fn check_when(&mut self, condition: &str, context: &NodeRef, path: &InstancePath) {
+        self.check(condition, context, path, || {
+            (format!("When condition {} is false", condition), None)
+        });
+    }
+
+    fn check_children(
+        &mut self,
+        schema_nodes: &'a [SchemaNode],
+        parent: &NodeRef,
+        parent_path: &InstancePath,
+        parent_module: &str,
+    ) {
+        let children = match self.node(parent) {
+            Some(data_node) => data_node.children(),
+            None => &self.tree.nodes[..],
+        };
+        // Conditions of choices and cases are checked once for all their nodes.
+        let mut checked_cases: Vec<*const SchemaNode> = vec![];
+        let mut counts: HashMap<*const SchemaNode, usize> = HashMap::new();
+        for (index, data_node) in children.iter().enumerate() {
+            let Some((schema_node, cases)) = Schema::find_data_child(schema_nodes, &data_node.name)
+            else {
+                continue;
+            };
+            *counts.entry(schema_node).or_default() += 1;
+            for (_, case) in &cases {
+                *counts.entry(*case).or_default() += 1;
+            }
+            let mut node = parent.clone();
+            node.push(index);
+            let mut path = parent_path.clone();
+            path.steps
+                .push(path_step(data_node, schema_node, parent_module));
+
+            for (choice, case) in cases {
+                for choice_or_case in [choice, case] {
+                    if checked_cases.contains(&(choice_or_case as *const SchemaNode)) {
+                        continue;
+                    }
+                    checked_cases.push(choice_or_case);
+                    for when in &choice_or_case.when {
+                        self.check_when(&when.when.condition, parent, &path);
+                    }
+                }
+            }
+            for when in &schema_node.when {
+                // Conditions of `augment` and `uses` have the parent as context.
+                let context = if when.inherited { parent } else { &node };
+                self.check_when(&when.when.condition, context, &path);
+            }
+            for must in &schema_node.must {
+                self.check(&must.condition, &node, &path, || {
+                    (
+                        must.error_message.clone().unwrap_or_else(|| {
+                            format!("Condition {} is not satisfied", must.condition)
+                        }),
+                        Some(
+                            must.error_app_tag
+                                .clone()
+                                .unwrap_or_else(|| "must-violation".to_string()),
+                        ),
+                    )
+                });
+            }
+            if let Some(leafref_path) = required_leafref(schema_node) {
+                // The value must be the one of a node the path selects.
+                let condition = format!("{} = current()", leafref_path);
+                self.check(&condition, &node, &path, || {
+                    (
+                        format!(
+                            "No instance of {} has the value of the leafref",
+                            leafref_path
+                        ),
+                        Some("instance-required".to_string()),
+                    )
+                });
+            }
+            self.check_children(&schema_node.children, &node, &path, &data_node.module);
+        }
+        self.check_constraints(schema_nodes, children, &counts, parent_path, parent_module);
+    }
+
+    /// Checks the `mandatory`, `min-elements`, `max-elements` and `unique`
+    /// statements of `schema_nodes`, whose instances among `children` are counted
+    /// in `counts`. Mandatory nodes with `when` conditions are not required, as the
+    /// conditions of absent nodes are not evaluated.
+    fn check_constraints(
+        &mut self,
+        schema_nodes: &'a [SchemaNode],
+        children: &'a [DataNode],
+        counts: &HashMap<*const SchemaNode, usize>,
+        parent_path: &InstancePath,
+        parent_module: &str,
+    ) {
+        for schema_node in schema_nodes {
+            let count = counts
+                .get(&(schema_node as *const SchemaNode))
+                .copied()
+                .unwrap_or_default();
+            let is_conditional = !schema_node.when.is_empty();
+            let module = self.schema.node_module(schema_node);
+            let mut path = parent_path.clone();
+            path.steps.push(PathStep {
+                module: (module != parent_module).then(|| module.to_string()),
+                name: schema_node.name.clone(),
+                predicates: vec![],
+            });
+            match &schema_node.kind {
+                SchemaNodeKind::Leaf {
+                    mandatory: true, ..
+                }
+                | SchemaNodeKind::AnyData { mandatory: true }
+                | SchemaNodeKind::AnyXml { mandatory: true }
+                    if count == 0 && !is_conditional =>
+                {
+                    self.add_error(
+                        &path,
+                        format!("Mandatory node {} is missing", schema_node.name),
+                        None,
+                    );
+                }
+                // The descendants of an absent non-presence container are checked
+                // as if it existed.
+                SchemaNodeKind::Container { presence: None } if count == 0 && !is_conditional => {
+                    self.check_constraints(&schema_node.children, &[], counts, &path, module);
+                }
+                SchemaNodeKind::Choice { mandatory, .. } => {
+                    let case = schema_node
+                        .children
+                        .iter()
+                        .find(|case| counts.contains_key(&(*case as *const SchemaNode)));
+                    match case {
+                        Some(case) => self.check_constraints(
+                            &case.children,
+                            children,
+                            counts,
+                            parent_path,
+                            parent_module,
+                        ),
+                        None if *mandatory && !is_conditional => self.add_error(
+                            &path,
+                            format!("Mandatory choice {} has no case", schema_node.name),
+                            Some("missing-choice"),
+                        ),
+                        None => {}
+                    }
+                }
+                SchemaNodeKind::List {
+                    unique,
+                    min_elements,
+                    max_elements,
+                    ..
+                } => {
+                    self.check_elements(schema_node, count, *min_elements, max_elements, &path);
+                    let entries = children
+                        .iter()
+                        .filter(|data_node| data_node.name == schema_node.name);
+                    for unique in unique {
+                        self.check_unique(
+                            schema_node,
+                            entries.clone(),
+                            unique,
+                            parent_path,
+                            parent_module,
+                        );
+                    }
+                }
+                SchemaNodeKind::LeafList {
+                    min_elements,
+                    max_elements,
+                    ..
+                } => self.check_elements(schema_node, count, *min_elements, max_elements, &path),
+                _ => {}
+            }
+        }
+    }
+
+    /// Checks the number of instances `count` of a list or leaf-list.
+    fn check_elements(
+        &mut self,
+        schema_node: &SchemaNode,
+        count: usize,
+        min_elements: u32,
+        max_elements: &MaxElements,
+        path: &InstancePath,
+    ) {
+        if count < min_elements as usize && (count > 0 || schema_node.when.is_empty()) {
+            self.add_error(
+                path,
+                format!(
+                    "{} has {} entries, fewer than {}",
+                    schema_node.name, count, min_elements
+                ),
+                Some("too-few-elements"),
+            );
+        }
+        if let MaxElements::Value(max_elements) = max_elements {
+            if count > *max_elements as usize {
+                self.add_error(
+                    path,
+                    format!(
+                        "{} has {} entries, more than {}",
+                        schema_node.name, count, max_elements
+                    ),
+                    Some("too-many-elements"),
+                );
+            }
+        }
+    }
+
+    /// Checks that no two `entries` of a list have the same values of the leaves
+    /// of `unique`, ignoring entries where one of them is absent.
+    fn check_unique(
+        &mut self,
+        schema_node: &SchemaNode,
+        entries: impl Iterator<Item = &'a DataNode>,
+        unique: &Unique,
+        parent_path: &InstancePath,
+        parent_module: &str,
+    ) {
+        let mut seen: Vec<Vec<String>> = vec![];
+        for entry in entries {
+            let values: Option<Vec<String>> = unique
+                .0
+                .iter()
+                .map(|path| descendant_value(entry, path).map(value_string))
+                .collect();
+            let Some(values) = values else {
+                continue;
+            };
+            if seen.contains(&values) {
+                let mut path = parent_path.clone();
+                path.steps
+                    .push(path_step(entry, schema_node, parent_module));
+                self.add_error(
+                    &path,
+                    format!("Values of {} are not unique", unique.0.join(" ")),
+                    Some("data-not-unique"),
+                );
+            } else {
+                seen.push(values);
+            }
+        }
+    }
+}
+
+/// Checks the `must` and `when` conditions of all nodes of `tree`, that the
+/// values of leafrefs requiring an instance refer to one, and the constraints on
+/// the number and uniqueness of nodes.
+pub(crate) fn check_conditions(tree: &DataTree, schema: &Schema) -> Vec<ConditionError> {
+    let mut checker = ConditionChecker {
+        schema,
+        tree,
+        errors: vec![],
+    };
+    checker.check_children(&schema.data, &vec![], &InstancePath { steps: vec![] }, "");
+    checker.errors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::json::parse_json_modules;
+    use crate::errors::ErrorContext;
+    use crate::parser::model::Module;
+    use crate::parser::model_mapper::Mapper;
+    use crate::parser::parser::parse;
+    use crate::repository::Repository;
+    use crate::schema::compile;
+
+    fn check(data: &str) -> Vec<(String, String, Option<String>)> {
+        let module = r#"module example {
+            yang-version 1.1;
+            namespace "urn:example";
+            prefix ex;
+            grouping limits {
+                leaf max { type uint16; }
+            }
+            container interfaces {
+                list interface {
+                    key "name";
+                    must "mtu >= 68" {
+                        error-message "MTU too small";
+                        error-app-tag "mtu-too-small";
+                    }
+                    leaf name { type string; }
+                    leaf enabled { type boolean; }
+                    leaf mtu { type uint16; }
+                    leaf description {
+                        when "../enabled = 'true'";
+                        type string;
+                    }
+                    uses limits { when "enabled = 'true'"; }
+                    choice address {
+                        when "enabled = 'true'";
+                        leaf dhcp { type empty; }
+                        case static {
+                            leaf ip {
+                                must "starts-with(., '192.')";
+                                type string;
+                            }
+                        }
+                    }
+                }
+            }
+            container system {
+                presence "configured";
+                leaf hostname { type string; mandatory true; }
+                choice transport {
+                    mandatory true;
+                    leaf tcp { type empty; }
+                    leaf udp { type empty; }
+                }
+                container ntp {
+                    leaf-list server { type string; min-elements 1; max-elements 2; }
+                }
+                list user {
+                    key "name";
+                    unique "uid";
+                    leaf name { type string; }
+                    leaf uid { type uint32; }
+                }
+            }
+            container routing {
+                leaf interface {
+                    type leafref { path "/interfaces/interface/name"; }
+                }
+                leaf-list backup {
+                    type leafref {
+                        path "../../interfaces/interface/name";
+                        require-instance false;
+                    }
+                }
+            }
+        }"#;
+        let mut error_context = ErrorContext::new();
+        let module = Module::map(parse(module).unwrap(), &mut error_context).unwrap();
+        let schema = compile(&module, &Repository::default(), &mut error_context).unwrap();
+        let tree = parse_json_modules(data, &[&schema]).unwrap().remove(0);
+        check_conditions(&tree, &schema)
+            .into_iter()
+            .map(|error| (error.path, error.message, error.app_tag))
+            .collect()
+    }
+
+    #[test]
+    fn test_valid() {
+        let data = r#"{
+            "example:interfaces": {
+                "interface": [
+                    {
+                        "name": "eth0",
+                        "enabled": true,
+                        "mtu": 1500,
+                        "description": "uplink",
+                        "max": 10,
+                        "ip": "192.0.2.1"
+                    },
+                    { "name": "eth1", "mtu": 68 }
+                ]
+            },
+            "example:routing": { "interface": "eth1", "backup": ["eth0", "eth9"] }
+        }"#;
+        assert_eq!(check(data), []);
+    }
+
+    #[test]
+    fn test_errors() {
+        let data = r#"{
+            "example:interfaces": {
+                "interface": [
+                    { "name": "eth0", "mtu": 60, "description": "uplink", "max": 10 },
+                    { "name": "eth1", "mtu": 1500, "enabled": true, "ip": "10.0.0.1" },
+                    { "name": "eth2", "mtu": 1500, "dhcp": [null] }
+                ]
+            },
+            "example:routing": { "interface": "eth9" }
+        }"#;
+        let eth0 = "/example:interfaces/interface[name='eth0']";
+        assert_eq!(
+            check(data),
+            [
+                (
+                    eth0.to_string(),
+                    "MTU too small".to_string(),
+                    Some("mtu-too-small".to_string())
+                ),
+                (
+                    format!("{}/description", eth0),
+                    "When condition ../enabled = 'true' is false".to_string(),
+                    None
+                ),
+                (
+                    format!("{}/max", eth0),
+                    "When condition enabled = 'true' is false".to_string(),
+                    None
+                ),
+                (
+                    "/example:interfaces/interface[name='eth1']/ip".to_string(),
+                    "Condition starts-with(., '192.') is not satisfied".to_string(),
+                    Some("must-violation".to_string())
+                ),
+                (
+                    "/example:interfaces/interface[name='eth2']/dhcp".to_string(),
+                    "When condition enabled = 'true' is false".to_string(),
+                    None
+                ),
+                (
+                    "/example:routing/interface".to_string(),
+                    "No instance of /interfaces/interface/name has the value of the leafref"
+                        .to_string(),
+                    Some("instance-required".to_string())
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_constraints() {
+        let data = r#"{
+            "example:system": {
+                "user": [
+                    { "name": "a", "uid": 1 },
+                    { "name": "b", "uid": 1 },
+                    { "name": "c" },
+                    { "name": "d" }
+                ]
+            }
+        }"#;
+        assert_eq!(
+            check(data),
+            [
+                (
+                    "/example:system/hostname".to_string(),
+                    "Mandatory node hostname is missing".to_string(),
+                    None
+                ),
+                (
+                    "/example:system/transport".to_string(),
+                    "Mandatory choice transport has no case".to_string(),
+                    Some("missing-choice".to_string())
+                ),
+                (
+                    "/example:system/ntp/server".to_string(),
+                    "server has 0 entries, fewer than 1".to_string(),
+                    Some("too-few-elements".to_string())
+                ),
+                (
+                    "/example:system/user[name='b']".to_string(),
+                    "Values of uid are not unique".to_string(),
+                    Some("data-not-unique".to_string())
+                ),
+            ]
+        );
+
+        let data = r#"{
+            "example:system": {
+                "hostname": "router",
+                "udp": [null],
+                "ntp": { "server": ["a", "b", "c"] },
+                "user": [{ "name": "a", "uid": 1 }, { "name": "b", "uid": 2 }]
+            }
+        }"#;
+        assert_eq!(
+            check(data),
+            [(
+                "/example:system/ntp/server".to_string(),
+                "server has 3 entries, more than 2".to_string(),
+                Some("too-many-elements".to_string())
+            )]
+        );
+    }
+}
@@ -0,0 +1,483 @@
+//! Schema-aware lookup and editing of data trees by [`InstancePath`].
+
+use crate::data::path::InstancePath;
+use crate::data::{leaf_type, parse_value, DataNode, DataNodeKind, DataTree};
+use crate::schema::{Schema, SchemaNode, SchemaNodeKind};
+use crate::typing::Value;
+
+/// A step of a path bound to its schema node, with the values of its
+/// predicates parsed.
+struct Step<'s> {
+    /// Name of the module defining the node.
+    module: &'s str,
+    schema_node: &'s SchemaNode,
+    cases: Vec<(&'s SchemaNode, &'s SchemaNode)>,
+    /// Key values of a list entry, in the order of the `key` statement.
+    keys: Vec<(&'s SchemaNode, Value)>,
+    /// Value of a leaf-list entry.
+    value: Option<Value>,
+}
+
+impl Step<'_> {
+    fn matches(&self, node: &DataNode) -> bool {
+        node.name == self.schema_node.name
+            && node.module == self.module
+            && self
+                .keys
+                .iter()
+                .all(|(key, value)| node.child(&key.name).and_then(DataNode::value) == Some(value))
+            && self
+                .value
+                .as_ref()
+                .is_none_or(|value| node.value() == Some(value))
+    }
+
+    fn create(&self, value: Option<Value>) -> DataNode {
+        let kind = match (&self.schema_node.kind, value) {
+            (SchemaNodeKind::List { .. }, _) => DataNodeKind::ListEntry(
+                self.keys
+                    .iter()
+                    .map(|(key, value)| DataNode {
+                        module: self.module.to_string(),
+                        name: key.name.clone(),
+                        kind: DataNodeKind::Leaf(value.clone()),
+                    })
+                    .collect(),
+            ),
+            (SchemaNodeKind::Leaf { .. }, Some(value)) => DataNodeKind::Leaf(value),
+            (SchemaNodeKind::LeafList { .. }, _) => {
+                DataNodeKind::LeafListEntry(self.value.clone().unwrap_or(Value::Empty))
+            }
+            _ => DataNodeKind::Container(vec![]),
+        };
+        DataNode {
+            module: self.module.to_string(),
+            name: self.schema_node.name.clone(),
+            kind,
+        }
+    }
+}
+
+/// Binds the steps of `path` to the schema nodes of `schema`.
+fn resolve<'s>(schema: &'s Schema, path: &InstancePath) -> Result<Vec<Step<'s>>, String> {
+    let modules = schema.data_modules();
+    let mut steps: Vec<Step> = vec![];
+    let mut schema_nodes = &schema.data[..];
+    for path_step in &path.steps {
+        let module = match (&path_step.module, steps.last()) {
+            (Some(module), _) => match modules.iter().find(|(name, _)| name == module) {
+                Some((name, _)) => *name,
+                None => return Err(format!("Unknown module {}", module)),
+            },
+            (None, Some(parent)) => parent.module,
+            (None, None) => {
+                return Err(format!(
+                    "Node {} must be qualified with its module name",
+                    path_step.name
+                ))
+            }
+        };
+        let Some((schema_node, cases)) =
+            schema.find_module_child(schema_nodes, module, &path_step.name)
+        else {
+            return Err(format!("Unknown node {}", path_step.name));
+        };
+
+        let mut step = Step {
+            module,
+            schema_node,
+            cases,
+            keys: vec![],
+            value: None,
+        };
+        match &schema_node.kind {
+            SchemaNodeKind::List { key, .. } => {
+                for key in key {
+                    let Some((_, text)) = path_step.predicates.iter().find(|(name, _)| name == key)
+                    else {
+                        return Err(format!("Missing key {} of list {}", key, schema_node.name));
+                    };
+                    let Some(key_node) = schema_node.child(key) else {
+                        return Err(format!("Unknown node {}", key));
+                    };
+                    let value = parse_value(schema, leaf_type(key_node).as_ref(), text)?;
+                    step.keys.push((key_node, value));
+                }
+                if let Some((name, _)) = path_step
+                    .predicates
+                    .iter()
+                    .find(|(name, _)| !step.keys.iter().any(|(key, _)| key.name == *name))
+                {
+                    return Err(format!(
+                        "{} is not a key of list {}",
+                        name, schema_node.name
+                    ));
+                }
+            }
+            SchemaNodeKind::LeafList { .. } => match &path_step.predicates[..] {
+                [] => {}
+                [(dot, text)] if dot == "." => {
+                    step.value = Some(parse_value(schema, leaf_type(schema_node).as_ref(), text)?)
+                }
+                _ => return Err(format!("Invalid predicate on {}", schema_node.name)),
+            },
+            SchemaNodeKind::Container { .. }
+            | SchemaNodeKind::Leaf { .. }
+            | SchemaNodeKind::AnyData { .. }
+            | SchemaNodeKind::AnyXml { .. } => {
+                if !path_step.predicates.is_empty() {
+                    return Err(format!("Invalid predicate on {}", schema_node.name));
+                }
+            }
+            _ => {
+                return Err(format!(
+                    "{} {} is not data",
+                    schema_node.keyword(),
+                    schema_node.name
+                ))
+            }
+        }
+        if steps.len() + 1 < path.steps.len()
+            && !matches!(
+                schema_node.kind,
+                SchemaNodeKind::Container { .. } | SchemaNodeKind::List { .. }
+            )
+        {
+            return Err(format!("{} has no children", schema_node.name));
+        }
+        schema_nodes = &schema_node.children;
+        steps.push(step);
+    }
+    Ok(steps)
+}
+
+fn children_mut(node: &mut DataNode) -> &mut Vec<DataNode> {
+    match &mut node.kind {
+        DataNodeKind::Container(children) | DataNodeKind::ListEntry(children) => children,
+        _ => unreachable!("Only containers and list entries have children"),
+    }
+}
+
+/// Inserts `node` among `siblings`, after the other entries of the same list or
+/// leaf-list. Nodes of other cases of the choices the node is in are removed.
+fn insert_sibling(
+    siblings: &mut Vec<DataNode>,
+    parent_schema_nodes: &[SchemaNode],
+    step: &Step,
+    node: DataNode,
+) {
+    siblings.retain(|sibling| {
+        let Some((_, sibling_cases)) = Schema::find_data_child(parent_schema_nodes, &sibling.name)
+        else {
+            return true;
+        };
+        !sibling_cases.iter().any(|(choice, case)| {
+            step.cases.iter().any(|(other_choice, other_case)| {
+                std::ptr::eq(*choice, *other_choice) && !std::ptr::eq(*case, *other_case)
+            })
+        })
+    });
+    let index = siblings
+        .iter()
+        .rposition(|sibling| sibling.name == node.name)
+        .map_or(siblings.len(), |index| index + 1);
+    siblings.insert(index, node);
+}
+
+impl DataTree {
+    /// Returns the node at `path` with its schema node, or `None` if it does not
+    /// exist.
+    pub(crate) fn get<'s>(
+        &self,
+        schema: &'s Schema,
+        path: &str,
+    ) -> Result<Option<(&DataNode, &'s SchemaNode)>, String> {
+        let steps = resolve(schema, &InstancePath::parse(path)?)?;
+        let mut nodes = &self.nodes[..];
+        let mut found = None;
+        for step in &steps {
+            let Some(node) = nodes.iter().find(|node| step.matches(node)) else {
+                return Ok(None);
+            };
+            nodes = node.children();
+            found = Some((node, step.schema_node));
+        }
+        Ok(found)
+    }
+
+    /// Creates the node at `path` and any missing ancestors. Leaves take their
+    /// value from `value`, list and leaf-list entries from the predicates of
+    /// `path`.
+    pub(crate) fn insert(
+        &mut self,
+        schema: &Schema,
+        path: &str,
+        value: Option<&str>,
+    ) -> Result<(), String> {
+        self.edit(schema, path, value, false)
+    }
+
+    /// Like [`DataTree::insert`], but replaces the node at `path` if it exists.
+    pub(crate) fn replace(
+        &mut self,
+        schema: &Schema,
+        path: &str,
+        value: Option<&str>,
+    ) -> Result<(), String> {
+        self.edit(schema, path, value, true)
+    }
+
+    fn edit(
+        &mut self,
+        schema: &Schema,
+        path: &str,
+        value: Option<&str>,
+        replace: bool,
+    ) -> Result<(), String> {
+        let steps = resolve(schema, &InstancePath::parse(path)?)?;
+        let (last, parents) = steps.split_last().expect("Paths have at least one step");
+        let value = match (&last.schema_node.kind, value) {
+            (SchemaNodeKind::Leaf { .. }, Some(text)) => Some(parse_value(
+                schema,
+                leaf_type(last.schema_node).as_ref(),
+                text,
+            )?),
+            (SchemaNodeKind::Leaf { .. }, None) => {
+                return Err(format!("Missing value of leaf {}", last.schema_node.name))
+            }
+            (SchemaNodeKind::LeafList { .. }, None) if last.value.is_none() => {
+                return Err(format!(
+                    "Missing value of leaf-list {}",
+                    last.schema_node.name
+                ))
+            }
+            (_, None) => None,
+            (_, Some(_)) => {
+                return Err(format!("{} cannot have a value", last.schema_node.name));
+            }
+        };
+        if let Some(parent) = parents.last() {
+            if parent
+                .keys
+                .iter()
+                .any(|(key, _)| std::ptr::eq(*key, last.schema_node))
+            {
+                return Err(format!("Key {} cannot be changed", last.schema_node.name));
+            }
+        }
+
+        let mut nodes = &mut self.nodes;
+        let mut schema_nodes = &schema.data[..];
+        for step in parents {
+            let index = match nodes.iter().position(|node| step.matches(node)) {
+                Some(index) => index,
+                None => {
+                    insert_sibling(nodes, schema_nodes, step, step.create(None));
+                    nodes
+                        .iter()
+                        .position(|node| step.matches(node))
+                        .expect("Node was inserted")
+                }
+            };
+            nodes = children_mut(&mut nodes[index]);
+            schema_nodes = &step.schema_node.children;
+        }
+
+        let node = last.create(value);
+        match nodes.iter().position(|node| last.matches(node)) {
+            Some(_) if !replace => Err(format!("{} already exists", path)),
+            Some(index) => {
+                nodes[index] = node;
+                Ok(())
+            }
+            None => {
+                insert_sibling(nodes, schema_nodes, last, node);
+                Ok(())
+            }
+        }
+    }
+
+    /// Removes the node at `path`, returning it.
+    pub(crate) fn delete(&mut self, schema: &Schema, path: &str) -> Result<DataNode, String> {
+        let steps = resolve(schema, &InstancePath::parse(path)?)?;
+        let (last, parents) = steps.split_last().expect("Paths have at least one step");
+        if let Some(parent) = parents.last() {
+            if parent
+                .keys
+                .iter()
+                .any(|(key, _)| std::ptr::eq(*key, last.schema_node))
+            {
+                return Err(format!("Key {} cannot be deleted", last.schema_node.name));
+            }
+        }
+
+        let mut nodes = &mut self.nodes;
+        for step in parents {
+            let Some(index) = nodes.iter().position(|node| step.matches(node)) else {
+                return Err(format!("{} does not exist", path));
+            };
+            nodes = children_mut(&mut nodes[index]);
+        }
+        match nodes.iter().position(|node| last.matches(node)) {
+            Some(index) => Ok(nodes.remove(index)),
+            None => Err(format!("{} does not exist", path)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::json::parse_json_modules;
+    use crate::errors::ErrorContext;
+    use crate::parser::model::Module;
+    use crate::parser::model_mapper::Mapper;
+    use crate::parser::parser::parse;
+    use crate::repository::Repository;
+    use crate::schema::compile;
+
+    const MODULE: &str = r#"module example {
+        namespace "urn:example";
+        prefix ex;
+        container interfaces {
+            list interface {
+                key "name unit";
+                leaf name { type string; }
+                leaf unit { type uint8; }
+                leaf mtu { type uint16 { range "68..9000"; } }
+                leaf-list tag { type string; }
+                choice address {
+                    leaf ipv4 { type string; }
+                    case v6 { leaf ipv6 { type string; } }
+                }
+            }
+        }
+    }"#;
+
+    fn schema() -> Schema {
+        let mut error_context = ErrorContext::new();
+        let module = Module::map(parse(MODULE).unwrap(), &mut error_context).unwrap();
+        compile(&module, &Repository::default(), &mut error_context).unwrap()
+    }
+
+    #[test]
+    fn test_get() {
+        let schema = schema();
+        let tree = parse_json_modules(
+            r#"{
+                "example:interfaces": {
+                    "interface": [
+                        { "name": "eth0", "unit": 0, "mtu": 1500, "tag": ["a", "b"] },
+                        { "name": "eth0", "unit": 1 }
+                    ]
+                }
+            }"#,
+            &[&schema],
+        )
+        .unwrap()
+        .remove(0);
+        let entry = "/example:interfaces/interface[name='eth0'][unit='0']";
+        assert_eq!(
+            tree.get(&schema, &format!("{}/mtu", entry))
+                .unwrap()
+                .and_then(|(node, _)| node.value()),
+            Some(&Value::Int(1500))
+        );
+        assert!(tree
+            .get(&schema, &format!("{}/tag[.='b']", entry))
+            .unwrap()
+            .is_some());
+        assert_eq!(
+            tree.get(
+                &schema,
+                "/example:interfaces/interface[name='eth1'][unit='0']"
+            )
+            .map(|found| found.is_some()),
+            Ok(false)
+        );
+        assert_eq!(
+            tree.get(&schema, "/example:interfaces/interface[name='eth0']")
+                .map(|_| ()),
+            Err("Missing key unit of list interface".to_string())
+        );
+        assert_eq!(
+            tree.get(
+                &schema,
+                "/example:interfaces/interface[name='eth0'][unit='x']"
+            )
+            .map(|_| ()),
+            Err("Invalid integer x".to_string())
+        );
+        assert_eq!(
+            tree.get(&schema, "/interfaces").map(|_| ()),
+            Err("Node interfaces must be qualified with its module name".to_string())
+        );
+    }
+
+    #[test]
+    fn test_edit() {
+        let schema = schema();
+        let mut tree = DataTree::default();
+        let entry = "/example:interfaces/interface[name='eth0'][unit='0']";
+        tree.insert(&schema, &format!("{}/mtu", entry), Some("1500"))
+            .unwrap();
+        tree.insert(&schema, &format!("{}/tag[.='a']", entry), None)
+            .unwrap();
+        tree.insert(&schema, &format!("{}/ipv4", entry), Some("192.0.2.1"))
+            .unwrap();
+        tree.insert(&schema, &format!("{}/tag[.='b']", entry), None)
+            .unwrap();
+        assert_eq!(
+            tree.insert(&schema, &format!("{}/mtu", entry), Some("9000")),
+            Err(format!("{}/mtu already exists", entry))
+        );
+        assert_eq!(
+            tree.replace(&schema, &format!("{}/mtu", entry), Some("10")),
+            Err("Value 10 is out of range".to_string())
+        );
+        tree.replace(&schema, &format!("{}/mtu", entry), Some("9000"))
+            .unwrap();
+        // Creating a node of another case removes the nodes of the old one.
+        tree.insert(&schema, &format!("{}/ipv6", entry), Some("2001:db8::1"))
+            .unwrap();
+        assert_eq!(
+            tree.replace(&schema, &format!("{}/unit", entry), Some("1")),
+            Err("Key unit cannot be changed".to_string())
+        );
+
+        let expected = parse_json_modules(
+            r#"{
+                "example:interfaces": {
+                    "interface": [
+                        {
+                            "name": "eth0",
+                            "unit": 0,
+                            "mtu": 9000,
+                            "tag": ["a", "b"],
+                            "ipv6": "2001:db8::1"
+                        }
+                    ]
+                }
+            }"#,
+            &[&schema],
+        )
+        .unwrap()
+        .remove(0);
+        assert_eq!(tree, expected);
+
+        let deleted = tree
+            .delete(&schema, &format!("{}/tag[.='a']", entry))
+            .unwrap();
+        assert_eq!(deleted.value(), Some(&Value::String("a".to_string())));
+        assert_eq!(
+            tree.delete(&schema, &format!("{}/name", entry)),
+            Err("Key name cannot be deleted".to_string())
+        );
+        tree.delete(&schema, entry).unwrap();
+        assert_eq!(
+            tree.delete(&schema, entry),
+            Err(format!("{} does not exist", entry))
+        );
+        assert_eq!(tree.nodes[0].children(), []);
+    }
+}
@@ -0,0 +1,658 @@
+//! RFC 7951 JSON encoding of instance data.
+
+use crate::data::{
+    leaf_type, parse_value, top_level_owner, value_string, CaseTracker, DataError, DataNode,
+    DataNodeKind, DataTree, KeyTracker,
+};
+use crate::schema::{Schema, SchemaNode, SchemaNodeKind};
+use crate::typing::{Type, UnionType, Value};
+use serde_json::Map;
+
+/// Escapes a member name as a JSON pointer reference token.
+fn escape_pointer(token: &str) -> String {
+    token.replace('~', "~0").replace('/', "~1")
+}
+
+/// Converts a JSON value to the lexical representation of a value of `r#type`.
+/// 64-bit integers and decimals are encoded as strings, `empty` as `[null]`.
+fn lexical_value(r#type: Option<&Type>, value: &serde_json::Value) -> Result<String, String> {
+    let expected = match r#type {
+        Some(Type::Int(int_type)) if matches!(int_type.rust_type, "i64" | "u64") => "string",
+        Some(Type::Int(_)) => "number",
+        Some(Type::Bool(_)) => "boolean",
+        Some(Type::Empty(_)) => "[null]",
+        _ => "string",
+    };
+    match (expected, value) {
+        ("string", serde_json::Value::String(text)) => Ok(text.clone()),
+        ("number", serde_json::Value::Number(number)) => Ok(number.to_string()),
+        ("boolean", serde_json::Value::Bool(value)) => Ok(value.to_string()),
+        ("[null]", serde_json::Value::Array(values))
+            if values.len() == 1 && values[0].is_null() =>
+        {
+            Ok(String::new())
+        }
+        _ => Err(format!("Expected {}, found {}", expected, value)),
+    }
+}
+
+/// Parses a JSON value of `r#type`, with the first member type of a union whose
+/// encoding and value space accept it.
+fn parse_json_value(
+    schema: &Schema,
+    r#type: Option<&Type>,
+    value: &serde_json::Value,
+) -> Result<Value, String> {
+    match r#type {
+        Some(Type::Union(union)) => union
+            .members
+            .iter()
+            .find_map(|member| parse_json_value(schema, Some(member), value).ok())
+            .ok_or_else(|| {
+                let text = value
+                    .as_str()
+                    .map_or_else(|| value.to_string(), str::to_string);
+                UnionType::mismatch(&text)
+            }),
+        Some(Type::Leafref(leafref)) => parse_json_value(schema, leafref.target.as_deref(), value),
+        _ => lexical_value(r#type, value).and_then(|text| parse_value(schema, r#type, &text)),
+    }
+}
+
+struct JsonParser<'s> {
+    schema: &'s Schema,
+    /// Names and namespaces of the modules with data nodes in the schema.
+    modules: Vec<(&'s str, &'s str)>,
+    errors: Vec<DataError>,
+}
+
+impl<'s> JsonParser<'s> {
+    fn add_error(&mut self, path: &str, message: String) {
+        self.errors.push(DataError {
+            path: path.to_string(),
+            message,
+            app_tag: None,
+        });
+    }
+
+    fn parse_value(
+        &mut self,
+        node: &SchemaNode,
+        value: &serde_json::Value,
+        path: &str,
+    ) -> Option<Value> {
+        let r#type = leaf_type(node);
+        match parse_json_value(self.schema, r#type.as_ref(), value) {
+            Ok(value) => Some(value),
+            Err(message) => {
+                self.add_error(path, message);
+                None
+            }
+        }
+    }
+
+    /// Parses the members of `object`, whose names are qualified by their module
+    /// unless it is `parent_module`, the module of the parent node. Top-level
+    /// members have no parent module.
+    fn parse_object(
+        &mut self,
+        schema_nodes: &'s [SchemaNode],
+        object: &Map<String, serde_json::Value>,
+        path: &str,
+        parent_module: Option<&str>,
+    ) -> Vec<DataNode> {
+        let mut nodes = vec![];
+        let mut case_tracker = CaseTracker::default();
+        for (member, value) in object {
+            let member_path = format!("{}/{}", path, escape_pointer(member));
+            let (module, name) = match (member.split_once(':'), parent_module) {
+                (Some((module, _)), _) if !self.modules.iter().any(|(name, _)| *name == module) => {
+                    self.add_error(&member_path, format!("Unknown module {}", module));
+                    continue;
+                }
+                (Some((module, name)), _) => (module, name),
+                (None, Some(parent_module)) => (parent_module, member.as_str()),
+                (None, None) => {
+                    self.add_error(
+                        &member_path,
+                        format!("Member {} must be qualified with its module name", member),
+                    );
+                    continue;
+                }
+            };
+            let Some((schema_node, cases)) =
+                self.schema.find_module_child(schema_nodes, module, name)
+            else {
+                self.add_error(&member_path, format!("Unknown member {}", member));
+                continue;
+            };
+            if let Err(message) = case_tracker.record(&cases) {
+                self.add_error(&member_path, message);
+            }
+            self.parse_node(schema_node, value, &member_path, &mut nodes);
+        }
+        nodes
+    }
+
+    fn parse_node(
+        &mut self,
+        schema_node: &'s SchemaNode,
+        value: &serde_json::Value,
+        path: &str,
+        nodes: &mut Vec<DataNode>,
+    ) {
+        let module = self.schema.node_module(schema_node);
+        let node = |kind| DataNode {
+            module: module.to_string(),
+            name: schema_node.name.clone(),
+            kind,
+        };
+        match (&schema_node.kind, value) {
+            (SchemaNodeKind::Container { .. }, serde_json::Value::Object(object)) => {
+                let children = self.parse_object(&schema_node.children, object, path, Some(module));
+                nodes.push(node(DataNodeKind::Container(children)));
+            }
+            (SchemaNodeKind::List { .. }, serde_json::Value::Array(entries)) => {
+                let mut key_tracker = KeyTracker::new(schema_node);
+                for (index, entry) in entries.iter().enumerate() {
+                    let entry_path = format!("{}/{}", path, index);
+                    let serde_json::Value::Object(object) = entry else {
+                        self.add_error(&entry_path, "Expected object".to_string());
+                        continue;
+                    };
+                    let children =
+                        self.parse_object(&schema_node.children, object, &entry_path, Some(module));
+                    for message in key_tracker.record(&children) {
+                        self.add_error(&entry_path, message);
+                    }
+                    nodes.push(node(DataNodeKind::ListEntry(children)));
+                }
+            }
+            (SchemaNodeKind::Leaf { .. }, value) => {
+                if let Some(value) = self.parse_value(schema_node, value, path) {
+                    nodes.push(node(DataNodeKind::Leaf(value)));
+                }
+            }
+            (SchemaNodeKind::LeafList { .. }, serde_json::Value::Array(values)) => {
+                for (index, value) in values.iter().enumerate() {
+                    let value_path = format!("{}/{}", path, index);
+                    if let Some(value) = self.parse_value(schema_node, value, &value_path) {
+                        nodes.push(node(DataNodeKind::LeafListEntry(value)));
+                    }
+                }
+            }
+            (SchemaNodeKind::AnyData { .. } | SchemaNodeKind::AnyXml { .. }, value) => {
+                nodes.push(node(DataNodeKind::AnyData(value.to_string())));
+            }
+            (SchemaNodeKind::Container { .. }, _) => {
+                self.add_error(path, "Expected object".to_string());
+            }
+            (SchemaNodeKind::List { .. } | SchemaNodeKind::LeafList { .. }, _) => {
+                self.add_error(path, "Expected array".to_string());
+            }
+            _ => self.add_error(
+                path,
+                format!("{} {} is not data", schema_node.keyword(), schema_node.name),
+            ),
+        }
+    }
+}
+
+/// Parses and validates RFC 7951 JSON instance data of the data nodes of a set of
+/// modules, returning one tree for each schema of `schemas`. Errors carry a JSON
+/// pointer to the offending value.
+pub(crate) fn parse_json_modules(
+    input: &str,
+    schemas: &[&Schema],
+) -> Result<Vec<DataTree>, Vec<DataError>> {
+    let document: serde_json::Value = serde_json::from_str(input).map_err(|error| {
+        vec![DataError {
+            path: String::new(),
+            message: error.to_string(),
+            app_tag: None,
+        }]
+    })?;
+    let serde_json::Value::Object(object) = &document else {
+        return Err(vec![DataError {
+            path: String::new(),
+            message: "Expected object".to_string(),
+            app_tag: None,
+        }]);
+    };
+
+    let mut trees = vec![];
+    let mut errors = vec![];
+    for (index, schema) in schemas.iter().enumerate() {
+        let object: Map<String, serde_json::Value> = object
+            .iter()
+            .filter(|(member, _)| {
+                let module = member.split_once(':').map(|(module, _)| module);
+                top_level_owner(schemas, |schema| Some(schema.name.as_str()) == module) == index
+            })
+            .map(|(member, value)| (member.clone(), value.clone()))
+            .collect();
+        let mut parser = JsonParser {
+            schema,
+            modules: schema.data_modules(),
+            errors: vec![],
+        };
+        let nodes = parser.parse_object(&schema.data, &object, "", None);
+        errors.extend(parser.errors);
+        trees.push(DataTree { nodes });
+    }
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+    Ok(trees)
+}
+
+/// Encodes `value` of a leaf of `r#type`, the inverse of [`lexical_value`].
+fn json_value(r#type: Option<&Type>, value: &Value) -> serde_json::Value {
+    match (r#type, value) {
+        // The value is encoded as the first member type it is a value of.
+        (Some(Type::Union(union)), value) => {
+            let text = value_string(value);
+            let member = union
+                .members
+                .iter()
+                .find(|member| member.parse_value(&text).as_ref() == Ok(value));
+            json_value(member, value)
+        }
+        (Some(Type::Leafref(leafref)), value) => json_value(leafref.target.as_deref(), value),
+        (Some(Type::Int(int_type)), Value::Int(value))
+            if !matches!(int_type.rust_type, "i64" | "u64") =>
+        {
+            serde_json::Value::from(*value as i64)
+        }
+        (_, Value::Bool(value)) => serde_json::Value::Bool(*value),
+        (_, Value::Empty) => serde_json::Value::Array(vec![serde_json::Value::Null]),
+        (_, value) => serde_json::Value::String(value_string(value)),
+    }
+}
+
+fn json_node(node: &DataNode, schema_node: Option<&SchemaNode>) -> serde_json::Value {
+    match &node.kind {
+        DataNodeKind::Container(children) | DataNodeKind::ListEntry(children) => {
+            let mut object = Map::new();
+            let schema_nodes = schema_node.map_or(&[][..], |schema_node| &schema_node.children);
+            json_members(children, schema_nodes, Some(&node.module), &mut object);
+            serde_json::Value::Object(object)
+        }
+        DataNodeKind::Leaf(value) | DataNodeKind::LeafListEntry(value) => {
+            json_value(schema_node.and_then(leaf_type).as_ref(), value)
+        }
+        DataNodeKind::AnyData(content) => serde_json::from_str(content)
+            .unwrap_or_else(|_| serde_json::Value::String(content.clone())),
+    }
+}
+
+/// Adds `nodes` to `object`, qualifying their names with their module if it
+/// differs from `parent_module`. Entries of a list or leaf-list form an array.
+fn json_members(
+    nodes: &[DataNode],
+    schema_nodes: &[SchemaNode],
+    parent_module: Option<&str>,
+    object: &mut Map<String, serde_json::Value>,
+) {
+    for node in nodes {
+        let member = match parent_module {
+            Some(module) if module == node.module => node.name.clone(),
+            _ => format!("{}:{}", node.module, node.name),
+        };
+        let schema_node =
+            Schema::find_data_child(schema_nodes, &node.name).map(|(schema_node, _)| schema_node);
+        let value = json_node(node, schema_node);
+        match node.kind {
+            DataNodeKind::ListEntry(_) | DataNodeKind::LeafListEntry(_) => {
+                if let serde_json::Value::Array(entries) = object
+                    .entry(member)
+                    .or_insert_with(|| serde_json::Value::Array(vec![]))
+                {
+                    entries.push(value);
+                }
+            }
+            _ => {
+                object.insert(member, value);
+            }
+        }
+    }
+}
+
+fn to_string(object: Map<String, serde_json::Value>) -> String {
+    let mut json = serde_json::to_string_pretty(&serde_json::Value::Object(object))
+        .expect("JSON values can be serialized");
+    json.push('\n');
+    json
+}
+
+/// Encodes the trees of `schemas`, as returned by [`parse_json_modules`], in RFC
+/// 7951 JSON.
+pub(crate) fn write_json(trees: &[DataTree], schemas: &[&Schema]) -> String {
+    let mut object = Map::new();
+    for (tree, schema) in trees.iter().zip(schemas) {
+        json_members(&tree.nodes, &schema.data, None, &mut object);
+    }
+    to_string(object)
+}
+
+/// Encodes `node` of `schema_node` as the only member of a JSON object, like a
+/// RESTCONF response does.
+pub(crate) fn write_json_node(node: &DataNode, schema_node: &SchemaNode) -> String {
+    let mut object = Map::new();
+    json_members(
+        std::slice::from_ref(node),
+        std::slice::from_ref(schema_node),
+        None,
+        &mut object,
+    );
+    to_string(object)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::ErrorContext;
+    use crate::parser::model::Module;
+    use crate::parser::model_mapper::Mapper;
+    use crate::parser::parser::parse;
+    use crate::repository::Repository;
+    use crate::schema::compile;
+
+    const MODULE: &str = r#"module example {
+        namespace "urn:example";
+        prefix ex;
+        identity interface-type;
+        identity ethernet { base interface-type; }
+        identity fast-ethernet { base ex:ethernet; }
+        identity other;
+        container interfaces {
+            list interface {
+                key "name";
+                leaf name {
+                    type string { length "1..8"; pattern '[a-z]+[0-9]*'; }
+                }
+                leaf type { type identityref { base interface-type; } }
+                leaf mtu { type uint16 { range "68..9000"; } }
+                leaf in-octets { type uint64; }
+                leaf load { type decimal64 { fraction-digits 2; } }
+                leaf enabled { type boolean; }
+                leaf loopback { type empty; }
+                leaf-list tag { type enumeration { enum red; enum blue; } }
+                leaf limit { type union { type int8; type string { length 1; } } }
+                leaf flags { type bits { bit up; bit running { position 3; } } }
+                leaf secret { type binary { length "1..4"; } }
+                leaf peer { type leafref { path "../name"; } }
+                choice address {
+                    leaf ipv4 { type string; }
+                    case v6 { leaf ipv6 { type string; } }
+                }
+            }
+        }
+    }"#;
+
+    fn validate(input: &str) -> Result<DataTree, Vec<(String, String)>> {
+        let mut error_context = ErrorContext::new();
+        let module = Module::map(parse(MODULE).unwrap(), &mut error_context).unwrap();
+        let schema = compile(&module, &Repository::default(), &mut error_context).unwrap();
+        parse_json_modules(input, &[&schema])
+            .map(|mut trees| trees.remove(0))
+            .map_err(|errors| {
+                errors
+                    .into_iter()
+                    .map(|error| (error.path, error.message))
+                    .collect()
+            })
+    }
+
+    #[test]
+    fn test_valid() {
+        let tree = validate(
+            r#"{
+                "example:interfaces": {
+                    "interface": [
+                        {
+                            "name": "eth0",
+                            "type": "example:fast-ethernet",
+                            "mtu": 1500,
+                            "in-octets": "18446744073709551615",
+                            "load": "+12.5",
+                            "enabled": true,
+                            "loopback": [null],
+                            "tag": ["red", "blue"],
+                            "limit": 5,
+                            "flags": "running up",
+                            "secret": "Zm9v",
+                            "peer": "eth1",
+                            "example:ipv4": "192.0.2.1"
+                        },
+                        { "name": "eth1", "type": "ethernet", "limit": "5" }
+                    ]
+                }
+            }"#,
+        )
+        .unwrap();
+        let interfaces = &tree.nodes[0];
+        assert_eq!(interfaces.name, "interfaces");
+        let entry = &interfaces.children()[0];
+        assert_eq!(
+            entry.kind,
+            DataNodeKind::ListEntry(entry.children().to_vec())
+        );
+        let values: Vec<_> = entry
+            .children()
+            .iter()
+            .map(|child| child.value().cloned().unwrap())
+            .collect();
+        assert_eq!(
+            values,
+            [
+                Value::String("eth0".to_string()),
+                Value::Identityref {
+                    prefix: Some("example".to_string()),
+                    name: "fast-ethernet".to_string()
+                },
+                Value::Int(1500),
+                Value::Int(18446744073709551615),
+                Value::Decimal(12.5),
+                Value::Bool(true),
+                Value::Empty,
+                Value::Enumeration("red".to_string()),
+                Value::Enumeration("blue".to_string()),
+                Value::Int(5),
+                Value::Bits(vec!["up".to_string(), "running".to_string()]),
+                Value::Binary(b"foo".to_vec()),
+                Value::String("eth1".to_string()),
+                Value::String("192.0.2.1".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_write() {
+        let input = r#"{
+            "example:interfaces": {
+                "interface": [
+                    {
+                        "name": "eth0",
+                        "type": "example:fast-ethernet",
+                        "mtu": 1500,
+                        "in-octets": "18446744073709551615",
+                        "load": "12.5",
+                        "enabled": true,
+                        "loopback": [null],
+                        "tag": ["red", "blue"],
+                        "limit": 5,
+                        "flags": "up running",
+                        "secret": "Zm9v",
+                        "ipv4": "192.0.2.1"
+                    },
+                    { "name": "eth1", "limit": "x" }
+                ]
+            }
+        }"#;
+        let mut error_context = ErrorContext::new();
+        let module = Module::map(parse(MODULE).unwrap(), &mut error_context).unwrap();
+        let schema = compile(&module, &Repository::default(), &mut error_context).unwrap();
+        let trees = parse_json_modules(input, &[&schema]).unwrap();
+        let written = write_json(&trees, &[&schema]);
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&written).unwrap(),
+            serde_json::from_str::<serde_json::Value>(input).unwrap()
+        );
+        assert_eq!(parse_json_modules(&written, &[&schema]).unwrap(), trees);
+
+        let mtu = &trees[0].nodes[0].children()[0].children()[2];
+        let (mtu_schema_node, _) =
+            Schema::find_data_child(&schema.data[0].children[0].children, "mtu").unwrap();
+        assert_eq!(
+            write_json_node(mtu, mtu_schema_node),
+            "{\n  \"example:mtu\": 1500\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        let errors = validate(
+            r#"{
+                "interfaces": {},
+                "other-module:interfaces": {},
+                "example:interfaces": {
+                    "interface": [
+                        {
+                            "name": "Eth0",
+                            "type": "other",
+                            "mtu": 10,
+                            "in-octets": 5,
+                            "load": "1.125",
+                            "loopback": null,
+                            "tag": ["green"],
+                            "limit": "toolong",
+                            "flags": "up zzz",
+                            "secret": "Zm9v!",
+                            "peer": "ETH0",
+                            "ipv4": "192.0.2.1",
+                            "ipv6": "2001:db8::1",
+                            "speed": 1
+                        },
+                        { "mtu": 1500 },
+                        { "name": "eth1" },
+                        { "name": "eth1" }
+                    ]
+                }
+            }"#,
+        )
+        .unwrap_err();
+        let entry = "/example:interfaces/interface";
+        assert_eq!(
+            errors,
+            [
+                (
+                    "/interfaces".to_string(),
+                    "Member interfaces must be qualified with its module name".to_string()
+                ),
+                (
+                    "/other-module:interfaces".to_string(),
+                    "Unknown module other-module".to_string()
+                ),
+                (
+                    format!("{}/0/name", entry),
+                    "\"Eth0\" does not match pattern".to_string()
+                ),
+                (
+                    format!("{}/0/type", entry),
+                    "Identity other is not derived from its base".to_string()
+                ),
+                (
+                    format!("{}/0/mtu", entry),
+                    "Value 10 is out of range".to_string()
+                ),
+                (
+                    format!("{}/0/in-octets", entry),
+                    "Expected string, found 5".to_string()
+                ),
+                (
+                    format!("{}/0/load", entry),
+                    "Decimal 1.125 has too many fraction digits".to_string()
+                ),
+                (
+                    format!("{}/0/loopback", entry),
+                    "Expected [null], found null".to_string()
+                ),
+                (
+                    format!("{}/0/tag/0", entry),
+                    "Invalid enum green".to_string()
+                ),
+                (
+                    format!("{}/0/limit", entry),
+                    "\"toolong\" matches no member type of the union".to_string()
+                ),
+                (format!("{}/0/flags", entry), "Invalid bit zzz".to_string()),
+                (
+                    format!("{}/0/secret", entry),
+                    "Invalid base64 value \"Zm9v!\"".to_string()
+                ),
+                (
+                    format!("{}/0/peer", entry),
+                    "\"ETH0\" does not match pattern".to_string()
+                ),
+                (
+                    format!("{}/0/ipv6", entry),
+                    "Cases ipv4 and v6 of choice address are both present".to_string()
+                ),
+                (
+                    format!("{}/0/speed", entry),
+                    "Unknown member speed".to_string()
+                ),
+                (format!("{}/0", entry), "Missing key name".to_string()),
+                (format!("{}/1", entry), "Missing key name".to_string()),
+                (format!("{}/3", entry), "Duplicate list entry".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_syntax_error() {
+        let errors = validate("{").unwrap_err();
+        assert_eq!(errors[0].0, "");
+        assert_eq!(
+            validate("[]").unwrap_err(),
+            [("".to_string(), "Expected object".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_modules() {
+        let mut error_context = ErrorContext::new();
+        let module = Module::map(parse(MODULE).unwrap(), &mut error_context).unwrap();
+        let schema = compile(&module, &Repository::default(), &mut error_context).unwrap();
+        let other = r#"module other {
+            namespace "urn:other";
+            prefix o;
+            leaf hostname { type string; }
+        }"#;
+        let other = Module::map(parse(other).unwrap(), &mut error_context).unwrap();
+        let other = compile(&other, &Repository::default(), &mut error_context).unwrap();
+
+        let input = r#"{
+            "other:hostname": "router",
+            "example:interfaces": { "interface": [{ "name": "eth" }] }
+        }"#;
+        let trees = parse_json_modules(input, &[&schema, &other]).unwrap();
+        assert_eq!(trees[0].nodes[0].name, "interfaces");
+        assert_eq!(trees[1].nodes[0].name, "hostname");
+        assert_eq!(trees[1].nodes[0].module, "other");
+
+        let errors = parse_json_modules(r#"{ "third:x": 1, "other:y": 2 }"#, &[&schema, &other])
+            .unwrap_err()
+            .into_iter()
+            .map(|error| (error.path, error.message))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            errors,
+            [
+                ("/third:x".to_string(), "Unknown module third".to_string()),
+                ("/other:y".to_string(), "Unknown member other:y".to_string()),
+            ]
+        );
+    }
+}
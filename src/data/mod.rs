@@ -0,0 +1,229 @@
+//! Instance data trees validated against a compiled [`Schema`].
+
+use crate::errors::ErrorContext;
+use crate::schema::{Schema, SchemaNode, SchemaNodeKind, SchemaType};
+use crate::typing::{encode_base64, resolve_type, Type, UnionType, Value};
+use std::collections::HashMap;
+
+pub(crate) mod conditions;
+pub(crate) mod edit;
+pub(crate) mod json;
+pub(crate) mod path;
+pub(crate) mod xml;
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum DataNodeKind {
+    Container(Vec<DataNode>),
+    ListEntry(Vec<DataNode>),
+    Leaf(Value),
+    LeafListEntry(Value),
+    /// Content of an anydata or anyxml node in its original encoding.
+    AnyData(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct DataNode {
+    /// Name of the module defining the node.
+    pub(crate) module: String,
+    pub(crate) name: String,
+    pub(crate) kind: DataNodeKind,
+}
+
+impl DataNode {
+    pub(crate) fn children(&self) -> &[DataNode] {
+        match &self.kind {
+            DataNodeKind::Container(children) | DataNodeKind::ListEntry(children) => children,
+            _ => &[],
+        }
+    }
+
+    pub(crate) fn child(&self, name: &str) -> Option<&DataNode> {
+        self.children().iter().find(|child| child.name == name)
+    }
+
+    pub(crate) fn value(&self) -> Option<&Value> {
+        match &self.kind {
+            DataNodeKind::Leaf(value) | DataNodeKind::LeafListEntry(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub(crate) struct DataTree {
+    pub(crate) nodes: Vec<DataNode>,
+}
+
+/// A validation error at `path`, which is in the notation of the input format.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct DataError {
+    pub(crate) path: String,
+    pub(crate) message: String,
+    /// The `error-app-tag` identifying the kind of error, if it has one.
+    pub(crate) app_tag: Option<String>,
+}
+
+/// Resolves the type of a leaf for validating values. Types the typing module
+/// does not support yet are not checked.
+pub(crate) fn resolve_leaf_type(schema_type: &SchemaType) -> Option<Type> {
+    resolve_type(schema_type, &mut ErrorContext::new()).ok()
+}
+
+/// Resolves the type of the values of a leaf or leaf-list `schema_node`.
+pub(crate) fn leaf_type(schema_node: &SchemaNode) -> Option<Type> {
+    match &schema_node.kind {
+        SchemaNodeKind::Leaf { r#type, .. } | SchemaNodeKind::LeafList { r#type, .. } => {
+            resolve_leaf_type(r#type)
+        }
+        _ => None,
+    }
+}
+
+/// The canonical lexical representation of `value`, with identities qualified by
+/// their module name.
+pub(crate) fn value_string(value: &Value) -> String {
+    match value {
+        Value::Int(value) => value.to_string(),
+        Value::Decimal(value) if value.fract() == 0.0 => format!("{:.1}", value),
+        Value::Decimal(value) => value.to_string(),
+        Value::String(value) | Value::Enumeration(value) | Value::InstanceIdentifier(value) => {
+            value.clone()
+        }
+        Value::Bool(value) => value.to_string(),
+        Value::Empty => String::new(),
+        Value::Identityref {
+            prefix: Some(prefix),
+            name,
+        } => format!("{}:{}", prefix, name),
+        Value::Identityref { prefix: None, name } => name.clone(),
+        Value::Bits(bits) => bits.join(" "),
+        Value::Binary(bytes) => encode_base64(bytes),
+    }
+}
+
+/// Parses the lexical representation `text` of a value of `r#type`, with the
+/// first member type of a union accepting it. Identities are looked up with their
+/// prefix being a module name, and only checked if their module is known to the
+/// schema.
+pub(crate) fn parse_value(
+    schema: &Schema,
+    r#type: Option<&Type>,
+    text: &str,
+) -> Result<Value, String> {
+    let Some(r#type) = r#type else {
+        return Ok(Value::String(text.to_string()));
+    };
+    match r#type {
+        Type::Union(union) => {
+            return union
+                .members
+                .iter()
+                .find_map(|member| parse_value(schema, Some(member), text).ok())
+                .ok_or_else(|| UnionType::mismatch(text));
+        }
+        Type::Leafref(leafref) => return parse_value(schema, leafref.target.as_deref(), text),
+        _ => {}
+    }
+    let value = r#type.parse_value(text)?;
+    if let (Type::Identityref(identityref), Value::Identityref { prefix, name }) = (r#type, &value)
+    {
+        let module = prefix.as_deref().unwrap_or(&schema.name);
+        let is_known = module == schema.name
+            || schema
+                .identities
+                .iter()
+                .any(|identity| identity.module == module);
+        let is_derived = identityref.bases.iter().any(|base| {
+            let (base_prefix, base) = match base.split_once(':') {
+                Some((prefix, base)) => (Some(prefix), base),
+                None => (None, base.as_str()),
+            };
+            schema.module_name(base_prefix).is_some_and(|base_module| {
+                schema.is_derived_identity((module, name), (base_module, base))
+            })
+        });
+        if is_known && !is_derived {
+            return Err(format!("Identity {} is not derived from its base", text));
+        }
+    }
+    Ok(value)
+}
+
+/// Index of the schema in `schemas` a top-level node belongs to, the first one
+/// matching `owns`. Nodes of no module of the set are left to the first schema,
+/// whose parser reports them.
+pub(crate) fn top_level_owner(schemas: &[&Schema], owns: impl Fn(&Schema) -> bool) -> usize {
+    schemas
+        .iter()
+        .position(|schema| owns(schema))
+        .unwrap_or_default()
+}
+
+/// Tracks the cases of choices used among siblings, as only one case of a choice
+/// may be present.
+#[derive(Default)]
+pub(crate) struct CaseTracker<'s> {
+    cases: HashMap<*const SchemaNode, &'s SchemaNode>,
+}
+
+impl<'s> CaseTracker<'s> {
+    /// Records the cases a node is in, returning an error if another case of
+    /// one of the choices was used before.
+    pub(crate) fn record(
+        &mut self,
+        cases: &[(&'s SchemaNode, &'s SchemaNode)],
+    ) -> Result<(), String> {
+        for (choice, case) in cases {
+            let used = self
+                .cases
+                .entry(*choice as *const SchemaNode)
+                .or_insert(case);
+            if !std::ptr::eq(*used, *case) {
+                return Err(format!(
+                    "Cases {} and {} of choice {} are both present",
+                    used.name, case.name, choice.name
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Checks the entries of a list for missing keys and duplicate key values.
+pub(crate) struct KeyTracker<'s> {
+    keys: Vec<&'s str>,
+    entries: Vec<Vec<Value>>,
+}
+
+impl<'s> KeyTracker<'s> {
+    pub(crate) fn new(list: &'s SchemaNode) -> Self {
+        let keys = match &list.kind {
+            SchemaNodeKind::List { key, .. } => key.iter().map(String::as_str).collect(),
+            _ => vec![],
+        };
+        Self {
+            keys,
+            entries: vec![],
+        }
+    }
+
+    /// Records the keys of an entry with `children`, returning an error for
+    /// each missing key or if an entry with the same keys was recorded before.
+    pub(crate) fn record(&mut self, children: &[DataNode]) -> Vec<String> {
+        let mut errors = vec![];
+        let mut values = vec![];
+        for key in &self.keys {
+            match children.iter().find(|child| child.name == *key) {
+                Some(child) => values.extend(child.value().cloned()),
+                None => errors.push(format!("Missing key {}", key)),
+            }
+        }
+        if values.len() == self.keys.len() && !self.keys.is_empty() {
+            if self.entries.contains(&values) {
+                errors.push("Duplicate list entry".to_string());
+            }
+            self.entries.push(values);
+        }
+        errors
+    }
+}
@@ -0,0 +1,150 @@
+//! Paths to instances in a data tree, in the encoding of instance identifiers of
+//! RFC 7951, section 6.11, e.g. `/example:interfaces/interface[name='eth0']/mtu`.
+
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct PathStep {
+    /// Module name qualifying the node, if it differs from the parent's.
+    pub(crate) module: Option<String>,
+    pub(crate) name: String,
+    /// Key leaves and their lexical values selecting a list entry, or `.` and
+    /// the value selecting a leaf-list entry.
+    pub(crate) predicates: Vec<(String, String)>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct InstancePath {
+    pub(crate) steps: Vec<PathStep>,
+}
+
+fn is_name_char(char: char) -> bool {
+    char.is_ascii_alphanumeric() || matches!(char, '_' | '-' | '.')
+}
+
+/// Splits a node name of `text` off, returning it and the remaining text.
+fn split_name(text: &str) -> (&str, &str) {
+    let end = text
+        .find(|char: char| !is_name_char(char) && char != ':')
+        .unwrap_or(text.len());
+    text.split_at(end)
+}
+
+impl InstancePath {
+    pub(crate) fn parse(text: &str) -> Result<Self, String> {
+        let invalid = || format!("Invalid path {}", text);
+        let mut steps = vec![];
+        let mut rest = text;
+        while !rest.is_empty() {
+            rest = rest.strip_prefix('/').ok_or_else(invalid)?;
+            let (qualified_name, after_name) = split_name(rest);
+            rest = after_name;
+            let (module, name) = match qualified_name.split_once(':') {
+                Some((module, name)) => (Some(module.to_string()), name),
+                None => (None, qualified_name),
+            };
+            if name.is_empty() || module.as_deref() == Some("") {
+                return Err(invalid());
+            }
+
+            let mut predicates = vec![];
+            while let Some(predicate) = rest.strip_prefix('[') {
+                let (key, after_key) = split_name(predicate.trim_start());
+                let after_equals = after_key
+                    .trim_start()
+                    .strip_prefix('=')
+                    .ok_or_else(invalid)?
+                    .trim_start();
+                let quote = after_equals
+                    .chars()
+                    .next()
+                    .filter(|char| matches!(char, '\'' | '"'))
+                    .ok_or_else(invalid)?;
+                let (value, after_value) =
+                    after_equals[1..].split_once(quote).ok_or_else(invalid)?;
+                rest = after_value
+                    .trim_start()
+                    .strip_prefix(']')
+                    .ok_or_else(invalid)?;
+                // Keys are children of the list, so their module is the list's.
+                let key = key.split_once(':').map_or(key, |(_, key)| key);
+                if key.is_empty() {
+                    return Err(invalid());
+                }
+                predicates.push((key.to_string(), value.to_string()));
+            }
+            steps.push(PathStep {
+                module,
+                name: name.to_string(),
+                predicates,
+            });
+        }
+        if steps.is_empty() {
+            return Err(invalid());
+        }
+        Ok(Self { steps })
+    }
+}
+
+impl fmt::Display for InstancePath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for step in &self.steps {
+            write!(f, "/")?;
+            if let Some(module) = &step.module {
+                write!(f, "{}:", module)?;
+            }
+            write!(f, "{}", step.name)?;
+            for (key, value) in &step.predicates {
+                let quote = if value.contains('\'') { '"' } else { '\'' };
+                write!(f, "[{}={}{}{}]", key, quote, value, quote)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let path =
+            InstancePath::parse("/ex:interfaces/interface[name='eth0'][ unit = \"0\" ]/ip[.='a']")
+                .unwrap();
+        assert_eq!(
+            path.steps,
+            [
+                PathStep {
+                    module: Some("ex".to_string()),
+                    name: "interfaces".to_string(),
+                    predicates: vec![],
+                },
+                PathStep {
+                    module: None,
+                    name: "interface".to_string(),
+                    predicates: vec![
+                        ("name".to_string(), "eth0".to_string()),
+                        ("unit".to_string(), "0".to_string())
+                    ],
+                },
+                PathStep {
+                    module: None,
+                    name: "ip".to_string(),
+                    predicates: vec![(".".to_string(), "a".to_string())],
+                },
+            ]
+        );
+        assert_eq!(
+            path.to_string(),
+            "/ex:interfaces/interface[name='eth0'][unit='0']/ip[.='a']"
+        );
+
+        for invalid in ["", "/", "a", "/a[b]", "/a[b='c'", "/a/", "/:a"] {
+            assert_eq!(
+                InstancePath::parse(invalid),
+                Err(format!("Invalid path {}", invalid))
+            );
+        }
+    }
+}
@@ -0,0 +1,554 @@
+//! XML encoding of instance data, as used by NETCONF (RFC 7950, section 7).
+
+use crate::data::{
+    leaf_type, parse_value, top_level_owner, CaseTracker, DataError, DataNode, DataNodeKind,
+    DataTree, KeyTracker,
+};
+use crate::schema::{Schema, SchemaNode, SchemaNodeKind};
+use crate::typing::{Type, Value};
+use std::collections::HashMap;
+
+/// Text content of an element, or `None` if it has child elements.
+fn text_content(element: roxmltree::Node) -> Option<String> {
+    let mut text = String::new();
+    for child in element.children() {
+        if child.is_element() {
+            return None;
+        }
+        text.push_str(child.text().unwrap_or_default());
+    }
+    Some(text)
+}
+
+/// Whether `char` may start a YANG identifier.
+fn is_identifier_start(char: char) -> bool {
+    char.is_ascii_alphabetic() || char == '_'
+}
+
+fn is_identifier_char(char: char) -> bool {
+    char.is_ascii_alphanumeric() || matches!(char, '_' | '-' | '.')
+}
+
+struct XmlParser<'s, 'input> {
+    schema: &'s Schema,
+    /// Names and namespaces of the modules with data nodes in the schema.
+    modules: Vec<(&'s str, &'s str)>,
+    input: &'input str,
+    errors: Vec<DataError>,
+}
+
+impl<'s> XmlParser<'s, '_> {
+    fn add_error(&mut self, path: &str, message: String) {
+        self.errors.push(DataError {
+            path: path.to_string(),
+            message,
+            app_tag: None,
+        });
+    }
+
+    /// Name of the module with `namespace` among the modules of the schema.
+    fn namespace_module(&self, namespace: &str) -> Option<&'s str> {
+        self.modules
+            .iter()
+            .find(|(_, module_namespace)| *module_namespace == namespace)
+            .map(|(name, _)| *name)
+    }
+
+    /// Maps the XML namespace prefix `prefix` in scope of `element` to the name
+    /// of its module. Namespaces of other modules are not known to the schema,
+    /// so their prefixes are kept.
+    fn module_name(&self, element: roxmltree::Node, prefix: &str) -> Result<String, String> {
+        match element.lookup_namespace_uri(Some(prefix)) {
+            Some(namespace) => Ok(self
+                .namespace_module(namespace)
+                .unwrap_or(prefix)
+                .to_string()),
+            None => Err(format!("Unknown prefix {}", prefix)),
+        }
+    }
+
+    /// Translates the identity `text`, whose prefix is an XML namespace prefix,
+    /// into one qualified by a module name.
+    fn translate_identity(&self, element: roxmltree::Node, text: &str) -> Result<String, String> {
+        match text.split_once(':') {
+            Some((prefix, name)) => Ok(format!("{}:{}", self.module_name(element, prefix)?, name)),
+            // Without a prefix the identity is in the default namespace.
+            None if element.lookup_namespace_uri(None) == Some(self.schema.namespace.as_str()) => {
+                Ok(format!("{}:{}", self.schema.name, text))
+            }
+            None => Ok(text.to_string()),
+        }
+    }
+
+    /// Translates the instance identifier `text`, whose node names are qualified
+    /// by XML namespace prefixes, into the encoding of RFC 7951, section 6.11,
+    /// where only names of nodes defined in another module than their parent
+    /// are qualified by the module name.
+    fn translate_instance_identifier(
+        &self,
+        element: roxmltree::Node,
+        text: &str,
+    ) -> Result<String, String> {
+        let mut translated = String::new();
+        let mut parent_module: Option<String> = None;
+        let mut quote = None;
+        let mut depth = 0;
+        let mut chars = text.char_indices().peekable();
+        while let Some((start, char)) = chars.next() {
+            match (quote, char) {
+                (Some(open), char) if char == open => quote = None,
+                (Some(_), _) => {}
+                (None, '\'' | '"') => quote = Some(char),
+                (None, '[') => depth += 1,
+                (None, ']') => depth -= 1,
+                (None, char) if is_identifier_start(char) => {
+                    let mut end = start + char.len_utf8();
+                    while let Some((index, char)) = chars.peek().copied() {
+                        if !is_identifier_char(char) {
+                            break;
+                        }
+                        end = index + char.len_utf8();
+                        chars.next();
+                    }
+                    if chars.peek().map(|(_, char)| *char) != Some(':') {
+                        translated.push_str(&text[start..end]);
+                        continue;
+                    }
+                    chars.next();
+                    let module = self.module_name(element, &text[start..end])?;
+                    if parent_module.as_ref() != Some(&module) {
+                        translated.push_str(&module);
+                        translated.push(':');
+                    }
+                    if depth == 0 {
+                        parent_module = Some(module);
+                    }
+                    continue;
+                }
+                _ => {}
+            }
+            translated.push(char);
+        }
+        Ok(translated)
+    }
+
+    fn parse_value(
+        &mut self,
+        schema_node: &SchemaNode,
+        element: roxmltree::Node,
+        path: &str,
+    ) -> Option<Value> {
+        let r#type = leaf_type(schema_node);
+        let Some(text) = text_content(element) else {
+            self.add_error(path, "Expected text content".to_string());
+            return None;
+        };
+        // Whitespace is only significant in strings.
+        let text = match r#type {
+            Some(Type::String(_)) | None => text,
+            _ => text.trim().to_string(),
+        };
+        let text = match r#type {
+            Some(Type::Identityref(_)) => self.translate_identity(element, &text),
+            Some(Type::InstanceIdentifier(_)) => self.translate_instance_identifier(element, &text),
+            _ => Ok(text),
+        };
+        match text.and_then(|text| parse_value(self.schema, r#type.as_ref(), &text)) {
+            Ok(value) => Some(value),
+            Err(message) => {
+                self.add_error(path, message);
+                None
+            }
+        }
+    }
+
+    /// Parses the child elements among `children`. Entries of a list or leaf-list
+    /// may be interleaved with other siblings, they are grouped in the data tree in
+    /// the order of their first entry.
+    ///
+    /// Names in error paths are qualified by their module unless it is
+    /// `parent_module`, the module of the parent node, which top-level elements
+    /// have none of.
+    fn parse_children<'a, 'document: 'a>(
+        &mut self,
+        schema_nodes: &'s [SchemaNode],
+        children: impl Iterator<Item = roxmltree::Node<'a, 'document>>,
+        path: &str,
+        parent_module: Option<&str>,
+    ) -> Vec<DataNode> {
+        // Schema node, number of its elements and the nodes parsed from them.
+        let mut groups: Vec<(&'s SchemaNode, usize, Vec<DataNode>)> = vec![];
+        let mut key_trackers: HashMap<*const SchemaNode, KeyTracker> = HashMap::new();
+        let mut case_tracker = CaseTracker::default();
+        for child in children {
+            if child.is_text() && !child.text().unwrap_or_default().trim().is_empty() {
+                self.add_error(path, "Unexpected text content".to_string());
+            }
+            if !child.is_element() {
+                continue;
+            }
+            let name = child.tag_name().name();
+            let namespace = child.tag_name().namespace();
+            let module = namespace.and_then(|namespace| self.namespace_module(namespace));
+            let child_path = match module.unwrap_or(&self.schema.name) {
+                module if Some(module) == parent_module => format!("{}/{}", path, name),
+                module => format!("{}/{}:{}", path, module, name),
+            };
+            let module = match (namespace, module) {
+                (_, Some(module)) => module,
+                (Some(namespace), None) => {
+                    self.add_error(&child_path, format!("Unknown namespace {}", namespace));
+                    continue;
+                }
+                (None, None) => {
+                    self.add_error(
+                        &child_path,
+                        format!("Element {} must be in the namespace of its module", name),
+                    );
+                    continue;
+                }
+            };
+            let Some((schema_node, cases)) =
+                self.schema.find_module_child(schema_nodes, module, name)
+            else {
+                self.add_error(&child_path, format!("Unknown element {}", name));
+                continue;
+            };
+            if let Err(message) = case_tracker.record(&cases) {
+                self.add_error(&child_path, message);
+            }
+
+            let group = match groups
+                .iter()
+                .position(|(node, _, _)| std::ptr::eq(*node, schema_node))
+            {
+                Some(index) => index,
+                None => {
+                    groups.push((schema_node, 0, vec![]));
+                    groups.len() - 1
+                }
+            };
+            let is_multiple = matches!(
+                schema_node.kind,
+                SchemaNodeKind::List { .. } | SchemaNodeKind::LeafList { .. }
+            );
+            let child_path = if is_multiple {
+                format!("{}[{}]", child_path, groups[group].1 + 1)
+            } else if groups[group].1 == 0 {
+                child_path
+            } else {
+                self.add_error(&child_path, format!("Duplicate element {}", name));
+                continue;
+            };
+            groups[group].1 += 1;
+            let node = |kind| DataNode {
+                module: module.to_string(),
+                name: schema_node.name.clone(),
+                kind,
+            };
+            let node = match &schema_node.kind {
+                SchemaNodeKind::Container { .. } => {
+                    let children = self.parse_children(
+                        &schema_node.children,
+                        child.children(),
+                        &child_path,
+                        Some(module),
+                    );
+                    node(DataNodeKind::Container(children))
+                }
+                SchemaNodeKind::List { .. } => {
+                    let children = self.parse_children(
+                        &schema_node.children,
+                        child.children(),
+                        &child_path,
+                        Some(module),
+                    );
+                    let key_tracker = key_trackers
+                        .entry(schema_node as *const SchemaNode)
+                        .or_insert_with(|| KeyTracker::new(schema_node));
+                    for message in key_tracker.record(&children) {
+                        self.add_error(&child_path, message);
+                    }
+                    node(DataNodeKind::ListEntry(children))
+                }
+                SchemaNodeKind::Leaf { .. } => {
+                    let Some(value) = self.parse_value(schema_node, child, &child_path) else {
+                        continue;
+                    };
+                    node(DataNodeKind::Leaf(value))
+                }
+                SchemaNodeKind::LeafList { .. } => {
+                    let Some(value) = self.parse_value(schema_node, child, &child_path) else {
+                        continue;
+                    };
+                    node(DataNodeKind::LeafListEntry(value))
+                }
+                SchemaNodeKind::AnyData { .. } | SchemaNodeKind::AnyXml { .. } => {
+                    node(DataNodeKind::AnyData(self.input[child.range()].to_string()))
+                }
+                _ => {
+                    self.add_error(
+                        &child_path,
+                        format!("{} {} is not data", schema_node.keyword(), schema_node.name),
+                    );
+                    continue;
+                }
+            };
+            groups[group].2.push(node);
+        }
+        groups.into_iter().flat_map(|(_, _, nodes)| nodes).collect()
+    }
+}
+
+/// Parses and validates XML instance data of the data nodes of a set of modules,
+/// returning one tree for each schema of `schemas`. The document element is
+/// either a data node of one of the modules, or a wrapper such as NETCONF's
+/// `<data>` or `<config>` whose children are data nodes. Errors carry an instance
+/// identifier of the offending element, with list and leaf-list entries
+/// identified by their position.
+pub(crate) fn parse_xml_modules(
+    input: &str,
+    schemas: &[&Schema],
+) -> Result<Vec<DataTree>, Vec<DataError>> {
+    let document = roxmltree::Document::parse(input).map_err(|error| {
+        vec![DataError {
+            path: String::new(),
+            message: error.to_string(),
+            app_tag: None,
+        }]
+    })?;
+    let root_element = document.root_element();
+    let parent = if schemas
+        .iter()
+        .any(|schema| root_element.tag_name().namespace() == Some(schema.namespace.as_str()))
+    {
+        document.root()
+    } else {
+        root_element
+    };
+
+    let mut trees = vec![];
+    let mut errors = vec![];
+    for (index, schema) in schemas.iter().enumerate() {
+        let children = parent.children().filter(|child| {
+            let namespace = child.tag_name().namespace();
+            top_level_owner(schemas, |schema| {
+                Some(schema.namespace.as_str()) == namespace
+            }) == index
+        });
+        let mut parser = XmlParser {
+            schema,
+            modules: schema.data_modules(),
+            input,
+            errors: vec![],
+        };
+        let nodes = parser.parse_children(&schema.data, children, "", None);
+        errors.extend(parser.errors);
+        trees.push(DataTree { nodes });
+    }
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+    Ok(trees)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::json::parse_json_modules;
+    use crate::errors::ErrorContext;
+    use crate::parser::model::Module;
+    use crate::parser::model_mapper::Mapper;
+    use crate::parser::parser::parse;
+    use crate::repository::Repository;
+    use crate::schema::compile;
+
+    const MODULE: &str = r#"module example {
+        namespace "urn:example";
+        prefix ex;
+        identity interface-type;
+        identity ethernet { base interface-type; }
+        container interfaces {
+            list interface {
+                key "name";
+                leaf name { type string; }
+                leaf type { type identityref { base interface-type; } }
+                leaf mtu { type uint16 { range "68..9000"; } }
+                leaf loopback { type empty; }
+                leaf-list tag { type string; }
+                leaf parent { type instance-identifier; }
+            }
+            leaf description { type string; }
+        }
+    }"#;
+
+    fn schema() -> Schema {
+        let mut error_context = ErrorContext::new();
+        let module = Module::map(parse(MODULE).unwrap(), &mut error_context).unwrap();
+        compile(&module, &Repository::default(), &mut error_context).unwrap()
+    }
+
+    fn validate(input: &str) -> Result<DataTree, Vec<(String, String)>> {
+        parse_xml_modules(input, &[&schema()])
+            .map(|mut trees| trees.remove(0))
+            .map_err(|errors| {
+                errors
+                    .into_iter()
+                    .map(|error| (error.path, error.message))
+                    .collect()
+            })
+    }
+
+    #[test]
+    fn test_valid() {
+        let tree = validate(
+            r#"<data xmlns="urn:ietf:params:xml:ns:netconf:base:1.0">
+                <interfaces xmlns="urn:example" xmlns:x="urn:example">
+                    <interface>
+                        <name>eth0</name>
+                        <type>x:ethernet</type>
+                        <tag>a</tag>
+                        <mtu> 1500 </mtu>
+                        <tag>b</tag>
+                        <loopback/>
+                    </interface>
+                    <description>uplinks</description>
+                    <interface>
+                        <name>eth0.1</name>
+                        <type>ethernet</type>
+                        <parent>/x:interfaces/x:interface[x:name='eth0']</parent>
+                    </interface>
+                </interfaces>
+            </data>"#,
+        )
+        .unwrap();
+        let json = parse_json_modules(
+            r#"{
+                "example:interfaces": {
+                    "interface": [
+                        {
+                            "name": "eth0",
+                            "type": "example:ethernet",
+                            "tag": ["a", "b"],
+                            "mtu": 1500,
+                            "loopback": [null]
+                        },
+                        {
+                            "name": "eth0.1",
+                            "type": "example:ethernet",
+                            "parent": "/example:interfaces/interface[name='eth0']"
+                        }
+                    ],
+                    "description": "uplinks"
+                }
+            }"#,
+            &[&schema()],
+        )
+        .unwrap()
+        .remove(0);
+        assert_eq!(tree, json);
+    }
+
+    #[test]
+    fn test_document_element() {
+        let tree = validate(r#"<interfaces xmlns="urn:example"/>"#).unwrap();
+        assert_eq!(tree.nodes[0].kind, DataNodeKind::Container(vec![]));
+    }
+
+    #[test]
+    fn test_errors() {
+        let errors = validate(
+            r#"<config xmlns:ex="urn:example">
+                <interfaces/>
+                <interfaces xmlns="urn:other"/>
+                <ex:interfaces>
+                    <ex:interface>
+                        <ex:name>eth0</ex:name>
+                        <ex:type>other:ethernet</ex:type>
+                        <ex:mtu>10</ex:mtu>
+                        <ex:mtu>1500</ex:mtu>
+                        <ex:speed>1</ex:speed>
+                        <ex:parent>/ex:interfaces/ex:interface[</ex:parent>
+                    </ex:interface>
+                    <ex:interface><ex:name>eth0</ex:name></ex:interface>
+                    <ex:interface/>
+                    <ex:description><b/></ex:description>
+                </ex:interfaces>
+            </config>"#,
+        )
+        .unwrap_err();
+        let entry = "/example:interfaces/interface";
+        assert_eq!(
+            errors,
+            [
+                (
+                    "/example:interfaces".to_string(),
+                    "Element interfaces must be in the namespace of its module".to_string()
+                ),
+                (
+                    "/example:interfaces".to_string(),
+                    "Unknown namespace urn:other".to_string()
+                ),
+                (
+                    format!("{}[1]/type", entry),
+                    "Unknown prefix other".to_string()
+                ),
+                (
+                    format!("{}[1]/mtu", entry),
+                    "Value 10 is out of range".to_string()
+                ),
+                (
+                    format!("{}[1]/mtu", entry),
+                    "Duplicate element mtu".to_string()
+                ),
+                (
+                    format!("{}[1]/speed", entry),
+                    "Unknown element speed".to_string()
+                ),
+                (
+                    format!("{}[1]/parent", entry),
+                    "Invalid instance identifier /example:interfaces/interface[".to_string()
+                ),
+                (format!("{}[2]", entry), "Duplicate list entry".to_string()),
+                (format!("{}[3]", entry), "Missing key name".to_string()),
+                (
+                    "/example:interfaces/description".to_string(),
+                    "Expected text content".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_syntax_error() {
+        let errors = validate("<interfaces").unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].0, "");
+    }
+
+    #[test]
+    fn test_modules() {
+        let mut error_context = ErrorContext::new();
+        let other = r#"module other {
+            namespace "urn:other";
+            prefix o;
+            leaf hostname { type string; }
+        }"#;
+        let other = Module::map(parse(other).unwrap(), &mut error_context).unwrap();
+        let other = compile(&other, &Repository::default(), &mut error_context).unwrap();
+
+        let input = r#"<data>
+            <hostname xmlns="urn:other">router</hostname>
+            <interfaces xmlns="urn:example"/>
+            <system xmlns="urn:third"/>
+        </data>"#;
+        let errors = parse_xml_modules(input, &[&schema(), &other]).unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].message, "Unknown namespace urn:third");
+
+        let input = r#"<hostname xmlns="urn:other">router</hostname>"#;
+        let trees = parse_xml_modules(input, &[&schema(), &other]).unwrap();
+        assert_eq!(trees[0].nodes, []);
+        assert_eq!(trees[1].nodes[0].name, "hostname");
+    }
+}
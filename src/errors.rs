@@ -1,9 +1,56 @@
-use std::sync::Arc;
-use miette::{LabeledSpan, MietteDiagnostic, Report, Severity};
+use miette::{LabeledSpan, MietteDiagnostic, Severity};
+
+/// A replacement of `span` of a source by `text`, which fixes a diagnostic.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextEdit {
+    pub span: crate::Span,
+    pub text: String,
+}
+
+/// The distance between `a` and `b` counting insertions, deletions, substitutions
+/// and transpositions of adjacent characters.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    // Rows of the distances of the prefixes of `a` to those of `b`.
+    let mut before_previous: Vec<usize> = vec![0; b.len() + 1];
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for i in 1..=a.len() {
+        let mut current = vec![i; b.len() + 1];
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            current[j] = (previous[j] + 1)
+                .min(current[j - 1] + 1)
+                .min(previous[j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                current[j] = current[j].min(before_previous[j - 2] + 1);
+            }
+        }
+        before_previous = std::mem::replace(&mut previous, current);
+    }
+    previous[b.len()]
+}
+
+/// The candidate closest to the misspelled `name`, if any is close enough to be
+/// meant by it.
+pub(crate) fn closest_match<'c>(
+    name: &str,
+    candidates: impl IntoIterator<Item = &'c str>,
+) -> Option<&'c str> {
+    let max_distance = (name.chars().count() / 3).max(1);
+    candidates
+        .into_iter()
+        .map(|candidate| (edit_distance(name, candidate), candidate))
+        .filter(|(distance, _)| (1..=max_distance).contains(distance))
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
 
 #[derive(Debug, Default)]
 pub struct ErrorContext {
     diagnostics: Vec<MietteDiagnostic>,
+    /// The edits fixing each diagnostic, in the same order.
+    fixes: Vec<Vec<TextEdit>>,
 }
 
 impl ErrorContext {
@@ -23,26 +70,159 @@ impl ErrorContext {
             message,
             url: None,
         });
+        self.fixes.push(vec![]);
     }
 
     pub(crate) fn add_error(&mut self, loc: crate::Span, error: String) {
         self.add_diagnostics(loc, error, Severity::Error);
     }
 
+    /// Adds an error at `loc` about a conflict with the declaration at `previous`,
+    /// which is labeled too.
+    pub(crate) fn add_conflict_error(
+        &mut self,
+        loc: crate::Span,
+        error: String,
+        previous: crate::Span,
+    ) {
+        self.add_error(loc, error);
+        let diagnostic = self.diagnostics.last_mut().unwrap();
+        diagnostic
+            .labels
+            .get_or_insert_with(Vec::new)
+            .push(LabeledSpan::new_with_span(
+                Some("previously defined here".to_string()),
+                previous.0..previous.1,
+            ));
+    }
+
     pub(crate) fn add_warning(&mut self, loc:  crate::Span, warning: String) {
         self.add_diagnostics(loc, warning, Severity::Warning);
     }
 
+    /// Adds `help` to the last diagnostic, together with the edits applying it.
+    pub(crate) fn add_suggestion(&mut self, help: String, fixes: Vec<TextEdit>) {
+        self.diagnostics.last_mut().unwrap().help = Some(help);
+        self.fixes.last_mut().unwrap().extend(fixes);
+    }
+
+    /// Suggests the candidate closest to the misspelled `name` for the last
+    /// diagnostic, replacing `span` by what `replacement` makes of it.
+    pub(crate) fn suggest_closest<'c>(
+        &mut self,
+        name: &str,
+        candidates: impl IntoIterator<Item = &'c str>,
+        span: crate::Span,
+        replacement: impl FnOnce(&str) -> String,
+    ) {
+        if let Some(closest) = closest_match(name, candidates) {
+            let text = replacement(closest);
+            self.add_suggestion(
+                format!("Did you mean {}?", text),
+                vec![TextEdit { span, text }],
+            );
+        }
+    }
+
     pub(crate) fn sort_errors(&mut self) {
+        let mut diagnostics: Vec<(MietteDiagnostic, Vec<TextEdit>)> = self
+            .diagnostics
+            .drain(..)
+            .zip(self.fixes.drain(..))
+            .collect();
+        diagnostics.sort_by_key(|(d, _)| d.labels.as_ref().unwrap()[0].offset());
+        (self.diagnostics, self.fixes) = diagnostics.into_iter().unzip();
+    }
+
+    /// Adds the diagnostics of `other`.
+    pub(crate) fn extend(&mut self, other: &ErrorContext) {
+        self.diagnostics.extend_from_slice(&other.diagnostics);
+        self.fixes.extend_from_slice(&other.fixes);
+    }
+
+    /// Keeps only the diagnostics `keep` returns true for, with their fixes.
+    pub(crate) fn retain(&mut self, mut keep: impl FnMut(&MietteDiagnostic) -> bool) {
+        let (diagnostics, fixes) = self
+            .diagnostics
+            .drain(..)
+            .zip(self.fixes.drain(..))
+            .filter(|(diagnostic, _)| keep(diagnostic))
+            .unzip();
+        (self.diagnostics, self.fixes) = (diagnostics, fixes);
+    }
+
+    /// Moves the labels at or after `from` by `delta`, after an edit of the source.
+    pub(crate) fn shift_spans(&mut self, from: crate::Loc, delta: isize) {
+        for label in self
+            .diagnostics
+            .iter_mut()
+            .flat_map(|diagnostic| diagnostic.labels.iter_mut().flatten())
+        {
+            if label.offset() >= from {
+                let offset = label.offset().wrapping_add_signed(delta);
+                let span = offset..offset + label.len();
+                let text = label.label().map(str::to_string);
+                *label = if label.primary() {
+                    LabeledSpan::new_primary_with_span(text, span)
+                } else {
+                    LabeledSpan::new_with_span(text, span)
+                };
+            }
+        }
+        for fix in self.fixes.iter_mut().flatten() {
+            crate::parser::model_mapper::shift_span(&mut fix.span, from, delta);
+        }
+    }
+
+    pub(crate) fn diagnostics(&self) -> &[MietteDiagnostic] {
+        &self.diagnostics
+    }
+
+    /// The diagnostics together with the edits fixing them.
+    pub(crate) fn diagnostics_with_fixes(
+        &self,
+    ) -> impl Iterator<Item = (&MietteDiagnostic, &[TextEdit])> {
+        self.diagnostics
+            .iter()
+            .zip(self.fixes.iter().map(Vec::as_slice))
+    }
+
+    pub(crate) fn has_errors(&self) -> bool {
         self.diagnostics
-            .sort_by_key(|d| d.labels.as_ref().unwrap()[0].offset());
+            .iter()
+            .any(|d| d.severity == Some(Severity::Error))
     }
 
-    pub fn print(&self, source: &str) {
+    #[cfg(test)]
+    pub(crate) fn messages(&self) -> Vec<&str> {
+        self.diagnostics.iter().map(|d| d.message.as_str()).collect()
+    }
+
+    #[cfg(test)]
+    pub(crate) fn print(&self, source: &str) {
+        use miette::Report;
+        use std::sync::Arc;
+
         let source = Arc::new(source.to_string());
         for diag in &self.diagnostics {
             let report = Report::new(diag.clone()).with_source_code(source.clone());
             println!("{:?}", report);
         }
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_closest_match() {
+        assert_eq!(edit_distance("leaf-lsit", "leaf-list"), 1);
+        assert_eq!(edit_distance("contianer", "container"), 1);
+        assert_eq!(edit_distance("", "abc"), 3);
+        let keywords = ["leaf", "leaf-list", "list", "container"];
+        assert_eq!(closest_match("leaf-lsit", keywords), Some("leaf-list"));
+        assert_eq!(closest_match("lef", keywords), Some("leaf"));
+        assert_eq!(closest_match("leaf", keywords), None);
+        assert_eq!(closest_match("description", keywords), None);
+    }
+}
@@ -0,0 +1,585 @@
+//! Canonical YANG printer.
+//!
+//! Statements are printed with a fixed indentation, arguments are re-quoted and the
+//! substatements are sorted into the order recommended by RFC 8407, section 4.6,
+//! which follows the ABNF of RFC 7950. Text arguments like `description` are
+//! optionally reflowed to fit into the configured line width.
+
+use crate::parser::cst::{self, Cst, CstBody, CstStatement, CstToken, Trivia, TriviaKind};
+use crate::parser::model::Module;
+use crate::parser::model_mapper::Unmapper;
+use crate::parser::parser::{ParseError, Statement};
+use crate::parser::strict::module_section;
+
+#[derive(Debug, Clone)]
+pub struct FormatOptions {
+    /// Number of spaces per indentation level.
+    pub indent: usize,
+    /// Line width text arguments are reflowed to.
+    pub line_width: usize,
+    /// Whether text arguments are reflowed, or printed as they are.
+    pub reflow_text: bool,
+    /// Whether substatements are sorted into the canonical order.
+    pub sort_statements: bool,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        FormatOptions {
+            indent: 2,
+            line_width: 70,
+            reflow_text: true,
+            sort_statements: true,
+        }
+    }
+}
+
+/// Statements whose argument is human readable text.
+const TEXT_KEYWORDS: &[&str] = &[
+    "contact",
+    "description",
+    "error-message",
+    "organization",
+    "reference",
+];
+
+/// Statements whose argument is quoted even if it could be written unquoted.
+const QUOTED_KEYWORDS: &[&str] = &[
+    "augment",
+    "default",
+    "deviation",
+    "key",
+    "length",
+    "must",
+    "namespace",
+    "path",
+    "pattern",
+    "presence",
+    "range",
+    "refine",
+    "unique",
+    "units",
+    "when",
+];
+
+/// Canonical substatement order. Statements not listed here, like data definitions,
+/// typedefs or extension statements, are ranked last and keep their relative order.
+const STATEMENT_ORDER: &[&str] = &[
+    "yang-version",
+    "namespace",
+    "belongs-to",
+    "prefix",
+    "revision-date",
+    "import",
+    "include",
+    "organization",
+    "contact",
+    "argument",
+    "yin-element",
+    "when",
+    "if-feature",
+    "base",
+    "type",
+    "fraction-digits",
+    "range",
+    "length",
+    "pattern",
+    "modifier",
+    "enum",
+    "bit",
+    "path",
+    "require-instance",
+    "value",
+    "position",
+    "units",
+    "must",
+    "error-message",
+    "error-app-tag",
+    "presence",
+    "key",
+    "unique",
+    "default",
+    "config",
+    "mandatory",
+    "min-elements",
+    "max-elements",
+    "ordered-by",
+    "status",
+    "description",
+    "reference",
+    "revision",
+];
+
+pub(crate) fn statement_rank(keyword: &str) -> usize {
+    STATEMENT_ORDER
+        .iter()
+        .position(|k| *k == keyword)
+        .unwrap_or(STATEMENT_ORDER.len())
+}
+
+/// A statement together with the comments attached to it.
+#[derive(Debug, Clone)]
+struct FormatNode {
+    keyword: String,
+    argument: Option<String>,
+    /// Comments on the lines before the statement.
+    leading_comments: Vec<String>,
+    /// Comments between the keyword and the end of the line the statement ends on.
+    trailing_comments: Vec<String>,
+    statements: Vec<FormatNode>,
+    /// Comments in front of the closing brace.
+    closing_comments: Vec<String>,
+    /// Comments after the closing brace.
+    closing_trailing_comments: Vec<String>,
+}
+
+fn comments(trivia: &[Trivia]) -> impl Iterator<Item = String> + '_ {
+    trivia
+        .iter()
+        .filter(|trivia| trivia.kind != TriviaKind::Whitespace)
+        .map(|trivia| trivia.text.clone())
+}
+
+fn token_comments(token: &CstToken) -> impl Iterator<Item = String> + '_ {
+    comments(&token.leading_trivia).chain(comments(&token.trailing_trivia))
+}
+
+impl FormatNode {
+    fn from_statement(statement: &Statement) -> Self {
+        FormatNode {
+            keyword: statement.keyword.clone(),
+            argument: statement.argument.clone(),
+            leading_comments: vec![],
+            trailing_comments: vec![],
+            statements: statement
+                .statements
+                .iter()
+                .map(FormatNode::from_statement)
+                .collect(),
+            closing_comments: vec![],
+            closing_trailing_comments: vec![],
+        }
+    }
+
+    fn from_cst(statement: &CstStatement) -> Self {
+        let mut trailing_comments =
+            comments(&statement.keyword.trailing_trivia).collect::<Vec<_>>();
+        trailing_comments.extend(statement.argument.iter().flat_map(token_comments));
+        let (statements, closing_comments, closing_trailing_comments) = match &statement.body {
+            CstBody::Semicolon(semicolon) => {
+                trailing_comments.extend(token_comments(semicolon));
+                (vec![], vec![], vec![])
+            }
+            CstBody::Block {
+                lbrace,
+                statements,
+                rbrace,
+            } => {
+                trailing_comments.extend(token_comments(lbrace));
+                (
+                    statements.iter().map(FormatNode::from_cst).collect(),
+                    comments(&rbrace.leading_trivia).collect(),
+                    comments(&rbrace.trailing_trivia).collect(),
+                )
+            }
+        };
+        FormatNode {
+            keyword: statement.keyword().to_string(),
+            argument: statement.argument(),
+            leading_comments: comments(&statement.keyword.leading_trivia).collect(),
+            trailing_comments,
+            statements,
+            closing_comments,
+            closing_trailing_comments,
+        }
+    }
+
+    fn sort(&mut self, options: &FormatOptions) {
+        if options.sort_statements {
+            self.statements
+                .sort_by_key(|statement| statement_rank(&statement.keyword));
+        }
+        for statement in &mut self.statements {
+            statement.sort(options);
+        }
+    }
+}
+
+struct Printer<'a> {
+    options: &'a FormatOptions,
+    output: String,
+}
+
+fn is_list_item(line: &str) -> bool {
+    let line = line.trim_start();
+    let digits = line.chars().take_while(|ch| ch.is_ascii_digit()).count();
+    line.starts_with("- ")
+        || line.starts_with("* ")
+        || line.starts_with("o ")
+        || (digits > 0 && line[digits..].starts_with(". "))
+}
+
+/// Reflows the paragraphs of `text` to `width` columns.
+///
+/// Paragraphs are separated by blank lines. Paragraphs containing indented lines or
+/// list items are considered preformatted and are kept unchanged.
+pub(crate) fn reflow(text: &str, width: usize) -> String {
+    let mut paragraphs = vec![];
+    let mut paragraph: Vec<&str> = vec![];
+    for line in text.lines().chain(std::iter::once("")) {
+        if !line.trim().is_empty() {
+            paragraph.push(line);
+            continue;
+        }
+        if paragraph.is_empty() {
+            continue;
+        }
+        let preformatted = paragraph
+            .iter()
+            .any(|line| line.starts_with([' ', '\t']) || is_list_item(line));
+        if preformatted {
+            paragraphs.push(
+                paragraph
+                    .iter()
+                    .map(|line| line.trim_end())
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+            );
+        } else {
+            let mut lines = vec![];
+            let mut current_line = String::new();
+            for word in paragraph.iter().flat_map(|line| line.split_whitespace()) {
+                if !current_line.is_empty() && current_line.len() + 1 + word.len() > width {
+                    lines.push(std::mem::take(&mut current_line));
+                }
+                if !current_line.is_empty() {
+                    current_line.push(' ');
+                }
+                current_line.push_str(word);
+            }
+            lines.push(current_line);
+            paragraphs.push(lines.join("\n"));
+        }
+        paragraph.clear();
+    }
+    paragraphs.join("\n\n")
+}
+
+/// Whether double quoting would alter the value, as leading whitespace of the first
+/// line, trailing whitespace of any line and tabs in front of continuation lines are
+/// stripped from double-quoted strings.
+fn needs_single_quotes(value: &str) -> bool {
+    value.starts_with(char::is_whitespace)
+        || value.split('\n').any(|line| line.ends_with([' ', '\t']))
+        || value.split('\n').skip(1).any(|line| line.starts_with('\t'))
+}
+
+impl Printer<'_> {
+    fn write_indent(&mut self, depth: usize) {
+        self.output
+            .extend(std::iter::repeat_n(' ', depth * self.options.indent));
+    }
+
+    fn current_column(&self) -> usize {
+        self.output.len() - self.output.rfind('\n').map(|pos| pos + 1).unwrap_or(0)
+    }
+
+    /// Writes `value` quoted, starting at the current column.
+    fn write_quoted(&mut self, keyword: &str, value: &str) {
+        let single_quotes_preferred = keyword == "pattern" || needs_single_quotes(value);
+        if single_quotes_preferred && !value.contains('\'') {
+            self.output.push('\'');
+            self.output.push_str(value);
+            self.output.push('\'');
+            return;
+        }
+
+        let quoted = cst::quote_double(value);
+        let continuation_indent = self.current_column() + 1;
+        for (index, line) in quoted.split('\n').enumerate() {
+            if index > 0 {
+                self.output.push('\n');
+                if !line.is_empty() {
+                    self.output
+                        .extend(std::iter::repeat_n(' ', continuation_indent));
+                }
+            }
+            self.output.push_str(line);
+        }
+    }
+
+    fn write_argument(&mut self, keyword: &str, argument: &str, depth: usize) {
+        if !TEXT_KEYWORDS.contains(&keyword) {
+            self.output.push(' ');
+            if cst::is_unquoted_safe(argument) && !QUOTED_KEYWORDS.contains(&keyword) {
+                self.output.push_str(argument);
+            } else {
+                self.write_quoted(keyword, argument);
+            }
+            return;
+        }
+
+        let text_column = (depth + 1) * self.options.indent + 1;
+        let text = if self.options.reflow_text {
+            reflow(
+                argument,
+                self.options
+                    .line_width
+                    .saturating_sub(text_column + 2)
+                    .max(20),
+            )
+        } else {
+            argument.to_string()
+        };
+        let fits_on_line = !text.contains('\n')
+            && self.current_column() + text.len() + 4 <= self.options.line_width;
+        if fits_on_line {
+            self.output.push(' ');
+        } else {
+            self.output.push('\n');
+            self.write_indent(depth + 1);
+        }
+        self.write_quoted(keyword, &text);
+    }
+
+    fn write_comments(&mut self, comments: &[String], depth: usize) {
+        for comment in comments {
+            self.write_indent(depth);
+            self.output.push_str(comment);
+            self.output.push('\n');
+        }
+    }
+
+    fn write_trailing_comments(&mut self, comments: &[String], depth: usize) {
+        let (line_comments, block_comments): (Vec<_>, Vec<_>) = comments
+            .iter()
+            .partition(|comment| comment.starts_with("//"));
+        for comment in block_comments {
+            self.output.push(' ');
+            self.output.push_str(comment);
+        }
+        let mut line_comments = line_comments.into_iter();
+        if let Some(comment) = line_comments.next() {
+            self.output.push(' ');
+            self.output.push_str(comment);
+        }
+        self.output.push('\n');
+        for comment in line_comments {
+            self.write_indent(depth);
+            self.output.push_str(comment);
+            self.output.push('\n');
+        }
+    }
+
+    fn write_node(&mut self, node: &FormatNode, depth: usize) {
+        self.write_comments(&node.leading_comments, depth);
+        self.write_indent(depth);
+        self.output.push_str(&node.keyword);
+        if let Some(argument) = &node.argument {
+            self.write_argument(&node.keyword, argument, depth);
+        }
+
+        if node.statements.is_empty() && node.closing_comments.is_empty() {
+            self.output.push(';');
+            self.write_trailing_comments(&node.trailing_comments, depth);
+            return;
+        }
+
+        self.output.push_str(" {");
+        self.write_trailing_comments(&node.trailing_comments, depth + 1);
+        let is_module = matches!(node.keyword.as_str(), "module" | "submodule");
+        let mut last_section = None;
+        for statement in &node.statements {
+            if is_module {
+                let section = module_section(&statement.keyword);
+                if last_section.is_some_and(|last_section| last_section != section || section == 4)
+                {
+                    self.output.push('\n');
+                }
+                last_section = Some(section);
+            }
+            self.write_node(statement, depth + 1);
+        }
+        self.write_comments(&node.closing_comments, depth + 1);
+        self.write_indent(depth);
+        self.output.push('}');
+        self.write_trailing_comments(&node.closing_trailing_comments, depth);
+    }
+
+    fn print(options: &FormatOptions, node: &FormatNode, trailing_comments: &[String]) -> String {
+        let mut printer = Printer {
+            options,
+            output: String::new(),
+        };
+        printer.write_node(node, 0);
+        printer.write_comments(trailing_comments, 0);
+        printer.output
+    }
+}
+
+fn format_node(
+    mut node: FormatNode,
+    trailing_comments: &[String],
+    options: &FormatOptions,
+) -> String {
+    node.sort(options);
+    Printer::print(options, &node, trailing_comments)
+}
+
+/// Prints `statement` and its substatements as canonical YANG.
+pub(crate) fn format_statement(statement: &Statement, options: &FormatOptions) -> String {
+    format_node(FormatNode::from_statement(statement), &[], options)
+}
+
+/// Prints `module` as canonical YANG.
+pub fn format_module(module: &Module, options: &FormatOptions) -> String {
+    format_statement(&module.unmap("module"), options)
+}
+
+/// Formats YANG source text, keeping its comments.
+pub(crate) fn format_cst(cst: &Cst, options: &FormatOptions) -> String {
+    let trailing_comments = comments(&cst.trailing_trivia).collect::<Vec<_>>();
+    format_node(FormatNode::from_cst(&cst.root), &trailing_comments, options)
+}
+
+pub fn format(input: &str, options: &FormatOptions) -> Result<String, ParseError> {
+    Ok(format_cst(&cst::parse(input)?, options))
+}
+
+/// Returns whether `input` is already formatted, as used by `format --check`.
+pub fn is_formatted(input: &str, options: &FormatOptions) -> Result<bool, ParseError> {
+    Ok(format(input, options)? == input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::ErrorContext;
+    use crate::parser::model_mapper::Mapper;
+    use crate::parser::parser::parse;
+    use std::path::PathBuf;
+
+    fn corpus() -> Vec<(PathBuf, String)> {
+        let corpus_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/corpus");
+        let mut files = std::fs::read_dir(corpus_dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "yang"))
+            .map(|path| {
+                let source = std::fs::read_to_string(&path).unwrap();
+                (path, source)
+            })
+            .collect::<Vec<_>>();
+        files.sort();
+        assert!(!files.is_empty());
+        files
+    }
+
+    /// Normalizes a statement tree the way formatting is allowed to change it: the
+    /// substatement order and the whitespace inside text arguments.
+    fn normalize(statement: &Statement, order_insensitive: bool) -> String {
+        let argument = if TEXT_KEYWORDS.contains(&statement.keyword.as_str()) {
+            statement
+                .argument
+                .as_ref()
+                .map(|argument| argument.split_whitespace().collect::<Vec<_>>().join(" "))
+        } else {
+            statement.argument.clone()
+        };
+        let mut statements = statement.statements.iter().collect::<Vec<_>>();
+        statements.sort_by_key(|statement| statement_rank(&statement.keyword));
+        let mut statements = statements
+            .into_iter()
+            .map(|statement| normalize(statement, order_insensitive))
+            .collect::<Vec<_>>();
+        if order_insensitive {
+            statements.sort();
+        }
+        format!("{} {:?} {:?}", statement.keyword, argument, statements)
+    }
+
+    #[test]
+    fn test_roundtrip_corpus() {
+        for (path, source) in corpus() {
+            let options = FormatOptions::default();
+            let formatted = format(&source, &options).unwrap();
+            let reparsed = parse(&formatted)
+                .unwrap_or_else(|e| panic!("{}: {:?}\n{}", path.display(), e, formatted));
+            assert_eq!(
+                normalize(&reparsed, false),
+                normalize(&parse(&source).unwrap(), false),
+                "{}",
+                path.display()
+            );
+            assert_eq!(format(&formatted, &options).unwrap(), formatted);
+            assert!(is_formatted(&formatted, &options).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_roundtrip_exact_without_reflow() {
+        let options = FormatOptions {
+            indent: 4,
+            reflow_text: false,
+            sort_statements: false,
+            ..FormatOptions::default()
+        };
+        for (_, source) in corpus() {
+            let statement = parse(&source).unwrap();
+            let reparsed = parse(&format_statement(&statement, &options)).unwrap();
+            assert_eq!(normalize(&reparsed, false), normalize(&statement, false));
+            assert_eq!(
+                format_statement(&reparsed, &options),
+                format_statement(&statement, &options)
+            );
+        }
+    }
+
+    #[test]
+    fn test_format() {
+        let input = r#"// header
+module m { prefix "m"; namespace "urn:m";
+  leaf a { description "A leaf"; type string { pattern "[a-z]+"; } } // trailing
+  yang-version 1.1;
+}
+"#;
+        assert_eq!(
+            format(input, &FormatOptions::default()).unwrap(),
+            r#"// header
+module m {
+  yang-version 1.1;
+  namespace "urn:m";
+  prefix m;
+
+  leaf a {
+    type string {
+      pattern '[a-z]+';
+    }
+    description "A leaf";
+  } // trailing
+}
+"#
+        );
+        assert!(!is_formatted(input, &FormatOptions::default()).unwrap());
+    }
+
+    #[test]
+    fn test_reflow() {
+        assert_eq!(
+            reflow("aaa bbb\nccc ddd eee\n\n  - item\n  - item", 11),
+            "aaa bbb ccc\nddd eee\n\n  - item\n  - item"
+        );
+    }
+
+    #[test]
+    fn test_format_module() {
+        let source = &corpus()[0].1;
+        let mut error_context = ErrorContext::new();
+        let module = Module::map(parse(source).unwrap(), &mut error_context).unwrap();
+        let formatted = format_module(&module, &FormatOptions::default());
+        assert_eq!(
+            normalize(&parse(&formatted).unwrap(), true),
+            normalize(&parse(source).unwrap(), true)
+        );
+    }
+}
@@ -0,0 +1,837 @@
+//! Incremental analysis of a module being edited, for editor-sized workloads.
+//!
+//! An edit reparses only the statements of the innermost block it falls into.
+//! Mapping works on the top-level statements of the module: only those an edit
+//! changed are mapped again. Anything else, like an edit of the module header,
+//! falls back to mapping the whole module. The mapped module is then checked the
+//! way loading and compiling it from a file checks it, so the diagnostics are the
+//! same: in strict mode, the module statement is checked again, and once there are
+//! no errors, its dependencies are loaded, keeping those loaded before, and it is
+//! compiled.
+//!
+//! ```
+//! use yang_utils::incremental::IncrementalModule;
+//!
+//! let source = "module example {
+//!   namespace \"urn:example\";
+//!   prefix ex;
+//!   leaf port { type uint8 { range \"1..1024\"; } }
+//! }
+//! ";
+//! let mut module = IncrementalModule::new("example.yang", source.to_string());
+//! assert_eq!(module.diagnostics().len(), 1);
+//! let start = source.find("uint8").unwrap();
+//! module.edit(start..start + 5, "uint16");
+//! assert!(module.diagnostics().is_empty());
+//! ```
+
+use crate::bindings::{diagnostics, Diagnostic};
+use crate::errors::ErrorContext;
+use crate::parser::cst::{self, Cst, CstBody, CstStatement, CstToken, Trivia, TriviaKind};
+use crate::parser::model::{
+    ActionOrRpc, AnyDataOrXml, Augment, Choice, Container, Deviation, Extension, Feature, Grouping,
+    Identity, Import, Include, Leaf, LeafList, List, Module, Notification, Revision, SubModule,
+    Typedef, Uses,
+};
+use crate::parser::model_mapper::{Mapper, ShiftSpans};
+use crate::parser::parser::Statement;
+use crate::parser::strict::{check_strict, remove_reported};
+use crate::repository::{DependencyError, LoadError, ModuleKind, Repository};
+use crate::schema::compile;
+use crate::{Loc, Span};
+use std::ops::Range;
+use std::path::PathBuf;
+
+macro_rules! definition_fields {
+    ($($keyword:literal => $field:ident: $model:ident),* $(,)?) => {
+        /// Whether the top-level statement `keyword` is mapped into a list of the
+        /// module, so it can be mapped on its own.
+        fn is_definition(keyword: &str) -> bool {
+            matches!(keyword, $($keyword)|*)
+        }
+
+        /// Maps the top-level `statement` and inserts it at `index` of its list in
+        /// `module`, if there is a module. Returns whether it could be mapped.
+        fn map_definition(
+            module: Option<&mut ModuleKind>,
+            index: usize,
+            statement: Statement,
+            error_context: &mut ErrorContext,
+        ) -> bool {
+            match statement.keyword.as_str() {
+                $($keyword => match $model::map(statement, error_context) {
+                    Ok(mapped) => {
+                        match module {
+                            Some(ModuleKind::Module(module)) => module.$field.insert(index, mapped),
+                            Some(ModuleKind::SubModule(submodule)) => {
+                                submodule.$field.insert(index, mapped)
+                            }
+                            None => {}
+                        }
+                        true
+                    }
+                    Err(()) => false,
+                },)*
+                keyword => unreachable!("{} is not a definition", keyword),
+            }
+        }
+
+        fn remove_definition(module: &mut ModuleKind, keyword: &str, index: usize) {
+            match keyword {
+                $($keyword => {
+                    match module {
+                        ModuleKind::Module(module) => module.$field.remove(index),
+                        ModuleKind::SubModule(submodule) => submodule.$field.remove(index),
+                    };
+                })*
+                keyword => unreachable!("{} is not a definition", keyword),
+            }
+        }
+    };
+}
+
+definition_fields!(
+    "import" => import: Import,
+    "include" => include: Include,
+    "revision" => revision: Revision,
+    "anydata" => anydata: AnyDataOrXml,
+    "anyxml" => anyxml: AnyDataOrXml,
+    "augment" => augment: Augment,
+    "choice" => choice: Choice,
+    "container" => container: Container,
+    "deviation" => deviation: Deviation,
+    "extension" => extension: Extension,
+    "feature" => feature: Feature,
+    "grouping" => grouping: Grouping,
+    "identity" => identity: Identity,
+    "leaf" => leaf: Leaf,
+    "leaf-list" => leaf_list: LeafList,
+    "list" => list: List,
+    "notification" => notification: Notification,
+    "rpc" => rpc: ActionOrRpc,
+    "typedef" => typedef: Typedef,
+    "uses" => uses: Uses,
+);
+
+fn trivia_len(trivia: &[Trivia]) -> usize {
+    trivia.iter().map(|trivia| trivia.text.len()).sum()
+}
+
+fn last_token(statement: &CstStatement) -> &CstToken {
+    match &statement.body {
+        CstBody::Semicolon(semicolon) => semicolon,
+        CstBody::Block { rbrace, .. } => rbrace,
+    }
+}
+
+/// The span of `statement` including the trivia belonging to it: the lines before
+/// it and the rest of its last line.
+fn extended_span(statement: &CstStatement) -> Span {
+    let last_token = last_token(statement);
+    (
+        statement.keyword.span.0 - trivia_len(&statement.keyword.leading_trivia),
+        last_token.span.1 + trivia_len(&last_token.trailing_trivia),
+    )
+}
+
+/// Whether `range` is within the substatements of `statement` or the lines
+/// before its closing brace, so they can be reparsed on their own. An edit at the
+/// very start could extend the line of the opening brace, and a comment added to
+/// the last line of a block on one line could hide the closing brace, so neither
+/// is.
+fn block_contains(statement: &CstStatement, range: &Range<Loc>) -> bool {
+    let CstBody::Block { lbrace, rbrace, .. } = &statement.body else {
+        return false;
+    };
+    let start = lbrace.span.1 + trivia_len(&lbrace.trailing_trivia);
+    !rbrace.leading_trivia.is_empty() && start < range.start && range.end <= rbrace.span.0
+}
+
+fn shift_token(token: &mut CstToken, from: Loc, delta: isize) {
+    if token.span.0 >= from {
+        token.span = (
+            token.span.0.wrapping_add_signed(delta),
+            token.span.1.wrapping_add_signed(delta),
+        );
+    }
+}
+
+/// Moves the tokens of `statement` at or after `from` by `delta`.
+fn shift_statement(statement: &mut CstStatement, from: Loc, delta: isize) {
+    if last_token(statement).span.1 <= from {
+        return;
+    }
+    shift_token(&mut statement.keyword, from, delta);
+    for token in &mut statement.argument {
+        shift_token(token, from, delta);
+    }
+    match &mut statement.body {
+        CstBody::Semicolon(semicolon) => shift_token(semicolon, from, delta),
+        CstBody::Block {
+            lbrace,
+            statements,
+            rbrace,
+        } => {
+            shift_token(lbrace, from, delta);
+            for statement in statements {
+                shift_statement(statement, from, delta);
+            }
+            shift_token(rbrace, from, delta);
+        }
+    }
+}
+
+/// Puts `trivia` in front of `leading_trivia`, joining whitespace the way the
+/// lexer would.
+fn prepend_trivia(leading_trivia: &mut Vec<Trivia>, mut trivia: Vec<Trivia>) {
+    if let (Some(last), Some(first)) = (trivia.last_mut(), leading_trivia.first()) {
+        if last.kind == TriviaKind::Whitespace && first.kind == TriviaKind::Whitespace {
+            last.text.push_str(&first.text);
+            leading_trivia.remove(0);
+        }
+    }
+    trivia.append(leading_trivia);
+    *leading_trivia = trivia;
+}
+
+/// Top-level statements replaced by an edit: `removed` statements starting at
+/// `index` were replaced by `inserted` ones.
+struct Replaced {
+    index: usize,
+    removed: usize,
+    inserted: usize,
+}
+
+/// Updates `cst` for `range` of the old source having been replaced by
+/// `inserted_len` bytes, which are in `source` now, by reparsing the statements of
+/// the innermost block touched by the edit. Returns `None` if the edit is not in
+/// the block of the module statement or the statements cannot be parsed on their
+/// own, so the whole source must be parsed.
+fn reparse(
+    cst: &mut Cst,
+    source: &str,
+    range: Range<Loc>,
+    inserted_len: usize,
+) -> Option<Replaced> {
+    let delta = inserted_len as isize - range.len() as isize;
+    if !block_contains(&cst.root, &range) {
+        return None;
+    }
+    let mut path = vec![];
+    let mut block = &cst.root;
+    while let Some(index) = block
+        .statements()
+        .iter()
+        .position(|statement| block_contains(statement, &range))
+    {
+        path.push(index);
+        block = &block.statements()[index];
+    }
+
+    // The trivia of the statements and of the closing brace is contiguous within
+    // the block. Statements merely adjacent to the edit are reparsed too, as their
+    // last line might be extended.
+    let touches = |statement: &CstStatement| {
+        let span = extended_span(statement);
+        span.0 <= range.end && range.start <= span.1
+    };
+    let CstBody::Block {
+        statements, rbrace, ..
+    } = &block.body
+    else {
+        unreachable!();
+    };
+    let closing_start = rbrace.span.0 - trivia_len(&rbrace.leading_trivia);
+    let first = statements
+        .iter()
+        .position(touches)
+        .unwrap_or(statements.len());
+    let mut end = statements
+        .iter()
+        .rposition(touches)
+        .map_or(first, |last| last + 1);
+    // A comment added to the end of the last line would hide statements following
+    // on the same line.
+    while statements
+        .get(end)
+        .is_some_and(|next| next.keyword.leading_trivia.is_empty())
+    {
+        end += 1;
+    }
+    let closing = range.end > closing_start;
+    let old_span = (
+        statements
+            .get(first)
+            .map_or(closing_start, |first| extended_span(first).0),
+        match end.checked_sub(1) {
+            _ if closing => rbrace.span.0,
+            Some(last) => extended_span(&statements[last]).1,
+            None => closing_start,
+        },
+    );
+    let new_end = old_span.1.wrapping_add_signed(delta);
+    // The closing brace would end an unquoted string or a line comment at the end.
+    if closing && !source[..new_end].ends_with(char::is_whitespace) {
+        return None;
+    }
+    let line_start = source[..old_span.0]
+        .rfind('\n')
+        .map_or(0, |index| index + 1);
+    let column = source[line_start..old_span.0].chars().count();
+    let (mut inserted, trivia) =
+        cst::parse_statements(&source[old_span.0..new_end], column).ok()?;
+    for statement in &mut inserted {
+        shift_statement(statement, 0, old_span.0 as isize);
+    }
+
+    shift_statement(&mut cst.root, old_span.1, delta);
+    let mut block = &mut cst.root;
+    for index in &path {
+        block = &mut block.statements_mut().unwrap()[*index];
+    }
+    let CstBody::Block {
+        statements, rbrace, ..
+    } = &mut block.body
+    else {
+        unreachable!();
+    };
+    let inserted_count = inserted.len();
+    statements.splice(first..end, inserted);
+    match statements.get_mut(first + inserted_count) {
+        Some(next) => prepend_trivia(&mut next.keyword.leading_trivia, trivia),
+        None if closing => rbrace.leading_trivia = trivia,
+        None => prepend_trivia(&mut rbrace.leading_trivia, trivia),
+    }
+
+    Some(match path.first() {
+        Some(index) => Replaced {
+            index: *index,
+            removed: 1,
+            inserted: 1,
+        },
+        None => Replaced {
+            index: first,
+            removed: end - first,
+            inserted: inserted_count,
+        },
+    })
+}
+
+/// A top-level statement mapped into a list of the module, like a typedef or a
+/// container, which is mapped on its own.
+struct Definition {
+    keyword: String,
+    /// Whether the statement could be mapped, so it is part of the module.
+    mapped: bool,
+    mapping: ErrorContext,
+}
+
+impl Definition {
+    fn new(keyword: String) -> Self {
+        Definition {
+            keyword,
+            mapped: false,
+            mapping: ErrorContext::new(),
+        }
+    }
+}
+
+/// A module being edited, analyzed again incrementally on every edit. See the
+/// [module documentation](self).
+pub struct IncrementalModule {
+    path: PathBuf,
+    source: String,
+    /// `None` while the source has syntax errors.
+    cst: Option<Cst>,
+    /// Errors of parsing the source and of mapping the module statement with the
+    /// substatements that are no definitions.
+    errors: ErrorContext,
+    /// `None` if the module or submodule statement could not be mapped.
+    module: Option<ModuleKind>,
+    /// The top-level statements, `None` for those mapped with the module
+    /// statement.
+    definitions: Vec<Option<Definition>>,
+    /// The modules the module depends on, loaded from its directory and the
+    /// search paths.
+    repository: Repository,
+    /// Errors of checking the module statement in strict mode.
+    strict: ErrorContext,
+    /// Errors of loading the dependencies and compiling the module, see
+    /// [`IncrementalModule::check`].
+    checks: ErrorContext,
+}
+
+impl IncrementalModule {
+    /// Analyzes the module `source`, which is reported as being at `path`.
+    pub fn new(path: impl Into<PathBuf>, source: String) -> Self {
+        Self::with_options(path, source, vec![], false)
+    }
+
+    /// Analyzes the module `source` at `path`, loading the modules it depends on
+    /// from `search_paths` too, in strict mode if `strict` is set.
+    pub fn with_options(
+        path: impl Into<PathBuf>,
+        source: String,
+        search_paths: Vec<PathBuf>,
+        strict: bool,
+    ) -> Self {
+        let mut module = IncrementalModule {
+            path: path.into(),
+            source,
+            cst: None,
+            errors: ErrorContext::new(),
+            module: None,
+            definitions: vec![],
+            repository: Repository::new(search_paths).strict(strict),
+            strict: ErrorContext::new(),
+            checks: ErrorContext::new(),
+        };
+        module.parse();
+        module.check();
+        module
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// The syntax tree, `None` while the source has syntax errors.
+    #[cfg(feature = "lsp")]
+    pub(crate) fn cst(&self) -> Option<&Cst> {
+        self.cst.as_ref()
+    }
+
+    /// The modules loaded as dependencies of the module.
+    #[cfg(feature = "lsp")]
+    pub(crate) fn repository(&self) -> &Repository {
+        &self.repository
+    }
+
+    /// Replaces `range` of the source with `text` and updates the analysis.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `range` is out of bounds or not on character boundaries.
+    pub fn edit(&mut self, range: Range<usize>, text: &str) {
+        self.source.replace_range(range.clone(), text);
+        let delta = text.len() as isize - range.len() as isize;
+        let replaced = match &mut self.cst {
+            Some(cst) => reparse(cst, &self.source, range.clone(), text.len()),
+            None => None,
+        };
+        let Some(replaced) = replaced else {
+            self.parse();
+            self.check();
+            return;
+        };
+
+        // Spans after the edit have moved, the changed statements are mapped again.
+        self.errors.shift_spans(range.end, delta);
+        match &mut self.module {
+            Some(ModuleKind::Module(module)) => module.shift_spans(range.end, delta),
+            Some(ModuleKind::SubModule(submodule)) => submodule.shift_spans(range.end, delta),
+            None => {}
+        }
+        for definition in self.definitions.iter_mut().flatten() {
+            definition.mapping.shift_spans(range.end, delta);
+        }
+        self.update(replaced);
+        self.check();
+    }
+
+    /// The errors and warnings of the module, ordered by their position.
+    pub(crate) fn error_context(&self) -> ErrorContext {
+        let mut mapping = ErrorContext::new();
+        mapping.extend(&self.errors);
+        for definition in self.definitions.iter().flatten() {
+            mapping.extend(&definition.mapping);
+        }
+        remove_reported(&self.strict, &mut mapping);
+        // In the order loading and compiling the module reports them.
+        let mut error_context = ErrorContext::new();
+        error_context.extend(&self.strict);
+        error_context.extend(&mapping);
+        error_context.extend(&self.checks);
+        error_context.sort_errors();
+        error_context
+    }
+
+    /// The errors and warnings of the module, ordered by their position.
+    pub fn diagnostics(&self) -> Vec<Diagnostic> {
+        diagnostics(&self.path, &self.source, &self.error_context())
+    }
+
+    fn parse(&mut self) {
+        self.errors = ErrorContext::new();
+        self.module = None;
+        self.definitions = vec![];
+        match cst::parse(&self.source) {
+            Ok(cst) => {
+                self.cst = Some(cst);
+                self.map();
+            }
+            Err(error) => {
+                self.cst = None;
+                self.errors
+                    .add_error(error.span.unwrap_or((0, 0)), error.message);
+            }
+        }
+    }
+
+    /// Maps the module statement and each definition.
+    fn map(&mut self) {
+        let root = &self.cst.as_ref().unwrap().root;
+        let mut header = root.to_statement();
+        header
+            .statements
+            .retain(|statement| !is_definition(&statement.keyword));
+        self.errors = ErrorContext::new();
+        self.module = if header.keyword == "submodule" {
+            SubModule::map(header, &mut self.errors)
+                .map(ModuleKind::SubModule)
+                .ok()
+        } else {
+            Module::map(header, &mut self.errors)
+                .map(ModuleKind::Module)
+                .ok()
+        };
+        self.definitions = root
+            .statements()
+            .iter()
+            .map(|statement| {
+                is_definition(statement.keyword())
+                    .then(|| Definition::new(statement.keyword().to_string()))
+            })
+            .collect();
+        for index in 0..self.definitions.len() {
+            self.map_definition(index);
+        }
+    }
+
+    /// The index of definition `index` in its list of the module.
+    fn list_index(&self, index: usize, keyword: &str) -> usize {
+        self.definitions[..index]
+            .iter()
+            .flatten()
+            .filter(|definition| definition.mapped && definition.keyword == keyword)
+            .count()
+    }
+
+    fn map_definition(&mut self, index: usize) {
+        let statement = &self.cst.as_ref().unwrap().root.statements()[index];
+        if !is_definition(statement.keyword()) {
+            return;
+        }
+        let list_index = self.list_index(index, statement.keyword());
+        let statement = statement.to_statement();
+        let definition = self.definitions[index].as_mut().unwrap();
+        definition.mapping = ErrorContext::new();
+        definition.mapped = map_definition(
+            self.module.as_mut(),
+            list_index,
+            statement,
+            &mut definition.mapping,
+        );
+    }
+
+    /// Maps the replaced top-level statements again.
+    fn update(&mut self, replaced: Replaced) {
+        let statements = self.cst.as_ref().unwrap().root.statements();
+        let removed = replaced.index..replaced.index + replaced.removed;
+        let inserted = replaced.index..replaced.index + replaced.inserted;
+        if self.definitions[removed.clone()]
+            .iter()
+            .any(Option::is_none)
+            || statements[inserted.clone()]
+                .iter()
+                .any(|statement| !is_definition(statement.keyword()))
+        {
+            self.map();
+            return;
+        }
+
+        for index in removed.clone().rev() {
+            let definition = self.definitions[index].as_ref().unwrap();
+            let list_index = self.list_index(index, &definition.keyword);
+            if let (Some(module), true) = (&mut self.module, definition.mapped) {
+                remove_definition(module, &definition.keyword, list_index);
+            }
+        }
+        let keywords: Vec<String> = statements[inserted.clone()]
+            .iter()
+            .map(|statement| statement.keyword().to_string())
+            .collect();
+        self.definitions.splice(
+            removed,
+            keywords
+                .into_iter()
+                .map(|keyword| Some(Definition::new(keyword))),
+        );
+        for index in inserted {
+            self.map_definition(index);
+        }
+    }
+
+    /// Checks the whole module like loading and compiling it does: in strict mode
+    /// its statement is checked, and once the module has no errors, the modules it
+    /// depends on are loaded and a module is compiled.
+    fn check(&mut self) {
+        self.strict = ErrorContext::new();
+        self.checks = ErrorContext::new();
+        let Some(cst) = &self.cst else {
+            return;
+        };
+        if self.repository.is_strict() {
+            let _ = check_strict(&cst.root.to_statement(), &mut self.strict);
+        }
+        let Some(module) = &self.module else {
+            return;
+        };
+        if self.errors.has_errors()
+            || self.strict.has_errors()
+            || self
+                .definitions
+                .iter()
+                .flatten()
+                .any(|definition| definition.mapping.has_errors())
+        {
+            return;
+        }
+        match self.repository.load_dependencies(&self.path, module) {
+            Ok(()) => {
+                if let ModuleKind::Module(module) = module {
+                    let _ = compile(module, &self.repository, &mut self.checks);
+                }
+            }
+            Err(DependencyError::NotFound(name, span)) => self
+                .checks
+                .add_error(span, format!("Module {} not found", name)),
+            Err(DependencyError::Load(LoadError::Invalid { path, .. })) => self
+                .checks
+                .add_error((0, 0), format!("Module {} is invalid", path.display())),
+            Err(DependencyError::Load(LoadError::Io(path, error))) => self
+                .checks
+                .add_error((0, 0), format!("{}: {}", path.display(), error)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+    use std::fs;
+    use std::path::Path;
+
+    const MODULE: &str = r#"module example {
+  namespace "urn:example";
+  prefix ex;
+
+  typedef percent {
+    type uint8 {
+      range "0..100";
+    }
+  }
+
+  // The system.
+  container system {
+    leaf load { type percent; }
+    container limits {
+      leaf max { type uint8; }
+      leaf min {
+        type uint8;
+        description "A
+                     multi-line text";
+      }
+    }
+  }
+
+  leaf name { type string; } /* trailing */
+  leaf-list ports { type uint16; }
+}
+"#;
+
+    fn messages(module: &IncrementalModule) -> Vec<String> {
+        format_diagnostics(module.diagnostics())
+    }
+
+    fn format_diagnostics(diagnostics: Vec<Diagnostic>) -> Vec<String> {
+        diagnostics
+            .into_iter()
+            .map(|diagnostic| {
+                format!(
+                    "{}:{} {}",
+                    diagnostic.line, diagnostic.column, diagnostic.message
+                )
+            })
+            .collect()
+    }
+
+    /// Applies `text` at the first occurrence of `old` and checks the result against
+    /// a module analyzed from scratch.
+    fn edit(module: &mut IncrementalModule, old: &str, text: &str) {
+        let start = module.source().find(old).unwrap();
+        module.edit(start..start + old.len(), text);
+        assert_same(module);
+    }
+
+    fn assert_same(module: &IncrementalModule) {
+        let expected = IncrementalModule::new(&module.path, module.source.clone());
+        assert_eq!(module.cst, expected.cst);
+        assert_eq!(messages(module), messages(&expected));
+        let spans = |module: &IncrementalModule| {
+            module.module.as_ref().map(|module| {
+                let ModuleKind::Module(module) = module else {
+                    unreachable!();
+                };
+                let leaves: Vec<Span> = module
+                    .leaf
+                    .iter()
+                    .map(|leaf| leaf.r#type.argument_span)
+                    .collect();
+                (module.span, module.typedef.len(), leaves)
+            })
+        };
+        assert_eq!(spans(module), spans(&expected));
+    }
+
+    #[test]
+    fn test_edits() {
+        let mut module = IncrementalModule::new("example.yang", MODULE.to_string());
+        assert!(module.diagnostics().is_empty());
+
+        edit(
+            &mut module,
+            "type uint8; }",
+            "type uint8 { range \"0..300\"; } }",
+        );
+        assert_eq!(
+            messages(&module),
+            ["15:37 Range is larger than range of base type"]
+        );
+        edit(&mut module, "\"0..300\"", "\"0..100\"");
+        assert!(messages(&module).is_empty());
+        edit(&mut module, "range \"0..100\"", "range \"0..1000\"");
+        let range_error = "7:13 Range is larger than range of base type";
+        assert_eq!(messages(&module), [range_error]);
+        edit(&mut module, "typedef percent", "typedef ratio");
+        assert_eq!(
+            messages(&module),
+            [range_error, "13:22 Type percent not found"]
+        );
+        edit(
+            &mut module,
+            "\n  leaf name",
+            "\n  typedef percent { type uint8; }\n  leaf name",
+        );
+        assert_eq!(messages(&module), [range_error]);
+        edit(&mut module, "multi-line", "multi\n   line");
+        edit(&mut module, "leaf max", "leaf-list max");
+        edit(&mut module, "  // The system.\n", "");
+        edit(&mut module, "/* trailing */", "// trailing");
+        edit(&mut module, " { type string; }", ";");
+        // The module is not compiled while it cannot be mapped.
+        assert_eq!(messages(&module), ["25:3 Expected type attribute"]);
+        edit(
+            &mut module,
+            "prefix ex;",
+            "prefix ex; organization \"Example\";",
+        );
+        let end = module.source().len() - 2;
+        module.edit(end..end, "  leaf extra { type percent; }\n");
+        assert_same(&module);
+        edit(&mut module, "leaf-list ports", "leaf-list \"ports");
+        assert_eq!(module.cst, None);
+        edit(&mut module, "\"ports", "ports");
+        assert!(module.cst.is_some());
+    }
+
+    /// The messages of loading and compiling `source` at `path` from scratch.
+    fn compiled(path: &Path, source: &str, strict: bool) -> Vec<String> {
+        let mut repository = Repository::new(vec![]).strict(strict);
+        let mut error_context = ErrorContext::new();
+        match repository.load_source(path, source.to_string()) {
+            Ok(index) => {
+                let loaded = &repository.modules[index];
+                error_context.extend(&loaded.error_context);
+                if let ModuleKind::Module(module) = &loaded.kind {
+                    let _ = compile(module, &repository, &mut error_context);
+                }
+            }
+            Err(LoadError::Invalid {
+                error_context: errors,
+                ..
+            }) => error_context = errors,
+            Err(error) => panic!("{:?}", error),
+        }
+        error_context.sort_errors();
+        format_diagnostics(diagnostics(path, source, &error_context))
+    }
+
+    #[test]
+    fn test_compiled() {
+        let directory = std::env::temp_dir().join("yang-utils-incremental-test");
+        fs::create_dir_all(&directory).unwrap();
+        fs::write(
+            directory.join("types.yang"),
+            "module types { namespace \"urn:types\"; prefix t;\n  \
+             typedef level { type uint8 { range \"0..10\"; } } }\n",
+        )
+        .unwrap();
+        let path = directory.join("example.yang");
+        let source = MODULE.replace("prefix ex;", "prefix ex;\n  import missing { prefix t; }");
+        let mut module = IncrementalModule::with_options(&path, source, vec![], true);
+        let edit = |module: &mut IncrementalModule, old: &str, text: &str| {
+            let start = module.source().find(old).unwrap();
+            module.edit(start..start + old.len(), text);
+            assert_eq!(
+                messages(module),
+                compiled(&module.path, &module.source, true)
+            );
+        };
+
+        assert_eq!(messages(&module), ["4:10 Module missing not found"]);
+        edit(&mut module, "import missing", "import types");
+        assert!(messages(&module).is_empty());
+        edit(
+            &mut module,
+            "leaf max { type uint8; }",
+            "leaf max { type t:level { range \"0..20\"; } }",
+        );
+        assert_eq!(
+            messages(&module),
+            ["16:39 Range is larger than range of base type"]
+        );
+        edit(&mut module, "prefix ex;", "prefix ex; colour red;");
+        assert_eq!(
+            messages(&module),
+            [
+                "3:14 Unknown keyword colour",
+                "4:3 Statement import of the linkage section must come before colour of the body section"
+            ]
+        );
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(200))]
+
+        #[test]
+        fn test_random_edits(
+            edits in prop::collection::vec(
+                (any::<prop::sample::Index>(), 0..8usize, prop::sample::select(vec![
+                    "", " ", "\n", ";", "{", "}", "leaf a { type uint8; }", "\n  leaf b;",
+                    "\"", "// c\n", "/* c */", "typedef percent { type int8; }", "x",
+                ])),
+                1..6,
+            )
+        ) {
+            let mut module = IncrementalModule::new("example.yang", MODULE.to_string());
+            for (start, len, text) in edits {
+                let start = start.index(module.source.len());
+                let end = (start + len).min(module.source.len());
+                module.edit(start..end, text);
+                assert_same(&module);
+            }
+        }
+    }
+}
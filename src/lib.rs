@@ -1,6 +1,26 @@
 mod parser;
 mod typing;
 mod errors;
+#[cfg(feature = "cli")]
+pub mod format;
+mod yin;
+mod schema;
+#[cfg(feature = "cli")]
+mod tree;
+mod codegen;
+mod repository;
+pub mod bindings;
+pub mod compat;
+pub mod incremental;
+#[cfg(feature = "cli")]
+pub mod cli;
+#[cfg(feature = "lsp")]
+mod lsp;
+#[cfg(feature = "cli")]
+mod data;
+mod xpath;
+#[cfg(feature = "cli")]
+mod lint;
 
 pub type Loc = usize;
 pub type Span = (Loc, Loc);
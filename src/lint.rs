@@ -0,0 +1,864 @@
+//! Lints of modules against the guidelines of RFC 8407 for authors of YANG modules.
+//!
+//! Each rule reports at a [`Level`]: not at all, as a warning or as an error. All
+//! rules warn by default, a configuration file maps rule names to other levels:
+//!
+//! ```json
+//! { "line-too-long": "allow", "missing-description": "deny" }
+//! ```
+//!
+//! A comment `// yang-lint: allow(rule, ...)` before a statement suppresses the
+//! rules for the statement and its substatements.
+//!
+//! The rules are:
+//!
+//! - `missing-description`: a definition or data node has no `description`.
+//! - `missing-reference`: a `revision` has no `reference`.
+//! - `missing-organization`, `missing-contact`: the module header lacks the
+//!   `organization` or the `contact` statement.
+//! - `prefix-convention`: the prefix does not abbreviate the module name, like `if`
+//!   does `ietf-interfaces`.
+//! - `top-level-data-node`: a top-level data node is not a container.
+//! - `implicit-enum-value`: an `enum` of an enumeration has no explicit `value`.
+//! - `line-too-long`: a line is longer than 70 characters.
+//! - `deprecated-use`: a current definition uses a deprecated typedef, grouping,
+//!   identity or feature of the module.
+
+use crate::errors::ErrorContext;
+use crate::parser::cst::{self, CstBody, CstStatement, TriviaKind};
+use crate::parser::model::{
+    ActionOrRpc, AnyDataOrXml, Augment, Case, Choice, Container, Deviation, Extension, Feature,
+    Grouping, Identity, InOutput, Leaf, LeafList, List, Module, Notification, Revision, Status,
+    SubModule, Type, Typedef, Uses,
+};
+use crate::repository::ModuleKind;
+use crate::Span;
+use std::collections::HashMap;
+
+const MISSING_DESCRIPTION: &str = "missing-description";
+const MISSING_REFERENCE: &str = "missing-reference";
+const MISSING_ORGANIZATION: &str = "missing-organization";
+const MISSING_CONTACT: &str = "missing-contact";
+const PREFIX_CONVENTION: &str = "prefix-convention";
+const TOP_LEVEL_DATA_NODE: &str = "top-level-data-node";
+const IMPLICIT_ENUM_VALUE: &str = "implicit-enum-value";
+const LINE_TOO_LONG: &str = "line-too-long";
+const DEPRECATED_USE: &str = "deprecated-use";
+
+/// The names of all rules.
+pub(crate) const RULES: [&str; 9] = [
+    MISSING_DESCRIPTION,
+    MISSING_REFERENCE,
+    MISSING_ORGANIZATION,
+    MISSING_CONTACT,
+    PREFIX_CONVENTION,
+    TOP_LEVEL_DATA_NODE,
+    IMPLICIT_ENUM_VALUE,
+    LINE_TOO_LONG,
+    DEPRECATED_USE,
+];
+
+/// The maximum line length of RFC 8407, section 3.
+const MAX_LINE_LENGTH: usize = 70;
+
+/// Marks a comment suppressing rules, followed by their names and `)`.
+const SUPPRESSION_MARKER: &str = "yang-lint: allow(";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Level {
+    Allow,
+    Warn,
+    Deny,
+}
+
+/// The levels of the rules.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct LintConfig {
+    levels: HashMap<&'static str, Level>,
+}
+
+impl LintConfig {
+    /// Parses a configuration from a JSON object mapping rule names to `"allow"`,
+    /// `"warn"` or `"deny"`.
+    pub(crate) fn parse(input: &str) -> Result<LintConfig, String> {
+        let value: serde_json::Value =
+            serde_json::from_str(input).map_err(|error| error.to_string())?;
+        let serde_json::Value::Object(object) = value else {
+            return Err("Expected an object mapping rules to levels".to_string());
+        };
+        let mut levels = HashMap::new();
+        for (name, level) in &object {
+            let Some(rule) = RULES.iter().find(|rule| *rule == name) else {
+                return Err(format!("Unknown rule {}", name));
+            };
+            let level = match level.as_str() {
+                Some("allow") => Level::Allow,
+                Some("warn") => Level::Warn,
+                Some("deny") => Level::Deny,
+                _ => {
+                    return Err(format!(
+                        "Invalid level of rule {}, expected allow, warn or deny",
+                        name
+                    ))
+                }
+            };
+            levels.insert(*rule, level);
+        }
+        Ok(LintConfig { levels })
+    }
+
+    fn level(&self, rule: &str) -> Level {
+        self.levels.get(rule).copied().unwrap_or(Level::Warn)
+    }
+}
+
+/// The rules suppressed by a comment before a statement.
+struct Suppression {
+    keyword_span: Span,
+    /// The end of the statement, after its semicolon or closing brace.
+    end: usize,
+    rules: Vec<String>,
+}
+
+/// The rules named by the suppression in `comment`, if any.
+fn suppressed_rules(comment: &str) -> Vec<String> {
+    let Some((_, rest)) = comment.split_once(SUPPRESSION_MARKER) else {
+        return vec![];
+    };
+    let Some((rules, _)) = rest.split_once(')') else {
+        return vec![];
+    };
+    rules
+        .split(',')
+        .map(str::trim)
+        .filter(|rule| !rule.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+fn collect_suppressions(statement: &CstStatement, suppressions: &mut Vec<Suppression>) {
+    let rules: Vec<String> = statement
+        .keyword
+        .leading_trivia
+        .iter()
+        .filter(|trivia| trivia.kind != TriviaKind::Whitespace)
+        .flat_map(|trivia| suppressed_rules(&trivia.text))
+        .collect();
+    if !rules.is_empty() {
+        let end = match &statement.body {
+            CstBody::Semicolon(semicolon) => semicolon.span.1,
+            CstBody::Block { rbrace, .. } => rbrace.span.1,
+        };
+        suppressions.push(Suppression {
+            keyword_span: statement.keyword.span,
+            end,
+            rules,
+        });
+    }
+    for substatement in statement.statements() {
+        collect_suppressions(substatement, suppressions);
+    }
+}
+
+/// Whether `prefix` abbreviates `module_name`: its characters appear in order in the
+/// name, from the start of the name or of one of its `-` separated parts on.
+fn abbreviates(prefix: &str, module_name: &str) -> bool {
+    let mut starts =
+        std::iter::once(0).chain(module_name.match_indices('-').map(|(index, _)| index + 1));
+    starts.any(|start| {
+        let rest = &module_name[start..];
+        let mut chars = rest.chars();
+        prefix.chars().next() == rest.chars().next()
+            && prefix.chars().all(|ch| chars.any(|other| other == ch))
+    })
+}
+
+fn capitalized(keyword: &str) -> String {
+    let mut chars = keyword.chars();
+    match chars.next() {
+        Some(first) => first.to_ascii_uppercase().to_string() + chars.as_str(),
+        None => String::new(),
+    }
+}
+
+/// The features referred to by an `if-feature` expression.
+fn if_feature_names(expression: &str) -> impl Iterator<Item = &str> {
+    expression
+        .split(|ch: char| ch.is_whitespace() || ch == '(' || ch == ')')
+        .filter(|token| !matches!(*token, "" | "and" | "or" | "not"))
+}
+
+/// What the rules look at of a statement.
+struct Node<'m> {
+    keyword: &'static str,
+    argument: &'m str,
+    span: Span,
+    argument_span: Span,
+    /// Whether the statement has a description, `None` if it needs none.
+    description: Option<bool>,
+    /// Whether the statement has a reference, `None` if it needs none.
+    reference: Option<bool>,
+    organization: Option<bool>,
+    contact: Option<bool>,
+    /// The prefix of a module and the name of the module it stands for.
+    prefix: Option<(&'m str, &'m str)>,
+    status: Option<Status>,
+    if_feature: &'m [String],
+    base: &'m [String],
+    r#type: Option<&'m Type>,
+    /// Whether the argument refers to a grouping.
+    uses: bool,
+    typedef: &'m [Typedef],
+    grouping: &'m [Grouping],
+}
+
+impl<'m> Node<'m> {
+    fn new(keyword: &'static str, argument: &'m str, span: Span, argument_span: Span) -> Self {
+        Node {
+            keyword,
+            argument,
+            span,
+            argument_span,
+            description: None,
+            reference: None,
+            organization: None,
+            contact: None,
+            prefix: None,
+            status: None,
+            if_feature: &[],
+            base: &[],
+            r#type: None,
+            uses: false,
+            typedef: &[],
+            grouping: &[],
+        }
+    }
+}
+
+struct Linter<'m, 'e> {
+    config: &'e LintConfig,
+    error_context: &'e mut ErrorContext,
+    suppressions: Vec<Suppression>,
+    prefix: &'m str,
+    identity: &'m [Identity],
+    feature: &'m [Feature],
+    /// The typedefs and groupings in scope, innermost last.
+    scopes: Vec<(&'m [Typedef], &'m [Grouping])>,
+    /// The effective statuses of the enclosing statements, innermost last. A
+    /// statement without a status of its own has the one of its parent.
+    statuses: Vec<Status>,
+}
+
+impl<'m> Linter<'m, '_> {
+    fn report(&mut self, rule: &'static str, span: Span, message: String) {
+        let suppressed = self.suppressions.iter().any(|suppression| {
+            (suppression.keyword_span.0..suppression.end).contains(&span.0)
+                && suppression
+                    .rules
+                    .iter()
+                    .any(|suppressed| suppressed == rule)
+        });
+        if suppressed {
+            return;
+        }
+        let message = format!("{} [{}]", message, rule);
+        match self.config.level(rule) {
+            Level::Allow => {}
+            Level::Warn => self.error_context.add_warning(span, message),
+            Level::Deny => self.error_context.add_error(span, message),
+        }
+    }
+
+    /// The name of `identifier` within the module, `None` if it refers to an
+    /// imported module.
+    fn local_name<'i>(&self, identifier: &'i str) -> Option<&'i str> {
+        match identifier.split_once(':') {
+            Some((prefix, name)) => (prefix == self.prefix).then_some(name),
+            None => Some(identifier),
+        }
+    }
+
+    fn report_deprecated(&mut self, node: &Node, span: Span, kind: &str, name: &str) {
+        self.report(
+            DEPRECATED_USE,
+            span,
+            format!(
+                "{} {} uses deprecated {} {}",
+                capitalized(node.keyword),
+                node.argument,
+                kind,
+                name
+            ),
+        );
+    }
+
+    fn check_identity(&mut self, node: &Node, span: Span, base: &str) {
+        let Some(name) = self.local_name(base) else {
+            return;
+        };
+        let identity = self.identity.iter().find(|identity| identity.name == name);
+        if identity.is_some_and(|identity| identity.status == Some(Status::Deprecated)) {
+            self.report_deprecated(node, span, "identity", base);
+        }
+    }
+
+    fn check_type(&mut self, node: &Node, r#type: &Type, current: bool) {
+        let name = r#type.name.as_deref().unwrap_or_default();
+        if name == "enumeration" {
+            for r#enum in r#type.r#enum.iter().filter(|r#enum| r#enum.value.is_none()) {
+                self.report(
+                    IMPLICIT_ENUM_VALUE,
+                    r#enum.argument_span,
+                    format!("Enum {} has no explicit value", r#enum.name),
+                );
+            }
+        }
+        if current {
+            let typedef = self.local_name(name).and_then(|name| {
+                self.scopes
+                    .iter()
+                    .rev()
+                    .flat_map(|(typedef, _)| typedef.iter())
+                    .find(|typedef| typedef.name == name)
+            });
+            if typedef.is_some_and(|typedef| typedef.status == Some(Status::Deprecated)) {
+                self.report_deprecated(node, r#type.argument_span, "typedef", name);
+            }
+            if let Some(base) = &r#type.base {
+                self.check_identity(node, r#type.argument_span, base);
+            }
+        }
+        for member in &r#type.r#type {
+            self.check_type(node, member, current);
+        }
+    }
+
+    fn check_references(&mut self, node: &Node<'m>) {
+        if node.uses {
+            let grouping = self.local_name(node.argument).and_then(|name| {
+                self.scopes
+                    .iter()
+                    .rev()
+                    .flat_map(|(_, grouping)| grouping.iter())
+                    .find(|grouping| grouping.name == name)
+            });
+            if grouping.is_some_and(|grouping| grouping.status == Some(Status::Deprecated)) {
+                self.report_deprecated(node, node.argument_span, "grouping", node.argument);
+            }
+        }
+        for base in node.base {
+            self.check_identity(node, node.argument_span, base);
+        }
+        for name in node
+            .if_feature
+            .iter()
+            .flat_map(|expression| if_feature_names(expression))
+        {
+            let feature = self
+                .local_name(name)
+                .and_then(|local| self.feature.iter().find(|feature| feature.name == local));
+            if feature.is_some_and(|feature| feature.status == Some(Status::Deprecated)) {
+                self.report_deprecated(node, node.span, "feature", name);
+            }
+        }
+    }
+
+    fn enter(&mut self, node: Node<'m>) {
+        let top_level = self.scopes.len() == 1;
+        self.scopes.push((node.typedef, node.grouping));
+        let status = node
+            .status
+            .or(self.statuses.last().copied())
+            .unwrap_or(Status::Current);
+        self.statuses.push(status);
+
+        let name = format!("{} {}", capitalized(node.keyword), node.argument);
+        if node.description == Some(false) {
+            self.report(
+                MISSING_DESCRIPTION,
+                node.argument_span,
+                format!("{} has no description", name),
+            );
+        }
+        if node.reference == Some(false) {
+            self.report(
+                MISSING_REFERENCE,
+                node.argument_span,
+                format!("{} has no reference", name),
+            );
+        }
+        if node.organization == Some(false) {
+            self.report(
+                MISSING_ORGANIZATION,
+                node.argument_span,
+                format!("{} has no organization", name),
+            );
+        }
+        if node.contact == Some(false) {
+            self.report(
+                MISSING_CONTACT,
+                node.argument_span,
+                format!("{} has no contact", name),
+            );
+        }
+        if let Some((prefix, module_name)) = node.prefix {
+            if !abbreviates(prefix, module_name) {
+                self.report(
+                    PREFIX_CONVENTION,
+                    node.argument_span,
+                    format!(
+                        "Prefix {} does not abbreviate module name {}",
+                        prefix, module_name
+                    ),
+                );
+            }
+        }
+        if top_level
+            && matches!(
+                node.keyword,
+                "leaf" | "leaf-list" | "list" | "choice" | "anydata" | "anyxml"
+            )
+        {
+            self.report(
+                TOP_LEVEL_DATA_NODE,
+                node.argument_span,
+                format!(
+                    "Top-level {} {} is not a container",
+                    node.keyword, node.argument
+                ),
+            );
+        }
+
+        let current = status == Status::Current;
+        if let Some(r#type) = node.r#type {
+            self.check_type(&node, r#type, current);
+        }
+        if current {
+            self.check_references(&node);
+        }
+    }
+
+    fn leave(&mut self) {
+        self.scopes.pop();
+        self.statuses.pop();
+    }
+}
+
+/// A statement of the model the rules run over.
+trait Lint {
+    fn lint<'m>(&'m self, keyword: &'static str, linter: &mut Linter<'m, '_>);
+}
+
+/// The keyword of the statements mapped to the model field `field`.
+fn keyword(field: &'static str) -> &'static str {
+    match field {
+        "leaf_list" => "leaf-list",
+        field => field,
+    }
+}
+
+macro_rules! lint {
+    ($model:ident($($argument:ident)?) $(, $field:ident)*; [$($child:ident),*]) => {
+        impl Lint for $model {
+            fn lint<'m>(&'m self, keyword: &'static str, linter: &mut Linter<'m, '_>) {
+                let argument = lint!(@argument self $(, $argument)?);
+                let mut node = Node::new(keyword, argument, self.span, self.argument_span);
+                $(lint!(@$field self, node);)*
+                linter.enter(node);
+                $(for child in self.$child.iter() {
+                    child.lint(self::keyword(stringify!($child)), linter);
+                })*
+                linter.leave();
+            }
+        }
+    };
+    (@argument $value:ident) => {
+        ""
+    };
+    (@argument $value:ident, $argument:ident) => {
+        $value.$argument.as_str()
+    };
+    (@description $value:ident, $node:ident) => {
+        $node.description = Some($value.description.is_some());
+    };
+    (@reference $value:ident, $node:ident) => {
+        $node.reference = Some($value.reference.is_some());
+    };
+    (@header $value:ident, $node:ident) => {
+        $node.organization = Some($value.organization.is_some());
+        $node.contact = Some($value.contact.is_some());
+    };
+    (@prefix $value:ident, $node:ident) => {
+        $node.prefix = Some((&$value.prefix, &$value.name));
+    };
+    (@belongs_to $value:ident, $node:ident) => {
+        $node.prefix = Some((&$value.belongs_to.prefix, &$value.belongs_to.module));
+    };
+    (@status $value:ident, $node:ident) => {
+        $node.status = $value.status;
+    };
+    (@if_feature $value:ident, $node:ident) => {
+        $node.if_feature = &$value.if_feature;
+    };
+    (@base $value:ident, $node:ident) => {
+        $node.base = &$value.base;
+    };
+    (@type $value:ident, $node:ident) => {
+        $node.r#type = Some(&$value.r#type);
+    };
+    (@uses $value:ident, $node:ident) => {
+        $node.uses = true;
+    };
+    (@typedef $value:ident, $node:ident) => {
+        $node.typedef = &$value.typedef;
+    };
+    (@grouping $value:ident, $node:ident) => {
+        $node.grouping = &$value.grouping;
+    };
+}
+
+lint!(Module(name), description, header, prefix, typedef, grouping;
+    [revision, extension, feature, identity, typedef, grouping, anydata, anyxml, choice,
+     container, leaf, leaf_list, list, uses, rpc, notification, augment, deviation]);
+lint!(SubModule(name), description, header, belongs_to, typedef, grouping;
+    [revision, extension, feature, identity, typedef, grouping, anydata, anyxml, choice,
+     container, leaf, leaf_list, list, uses, rpc, notification, augment, deviation]);
+lint!(Revision(revision), description, reference; []);
+lint!(Extension(name), description, status; []);
+lint!(Feature(name), description, status, if_feature; []);
+lint!(Identity(name), description, status, if_feature, base; []);
+lint!(Typedef(name), description, status, type; []);
+lint!(Grouping(name), description, status, typedef, grouping;
+    [typedef, grouping, anydata, anyxml, choice, container, leaf, leaf_list, list, uses, action,
+     notification]);
+lint!(Container(name), description, status, if_feature, typedef, grouping;
+    [typedef, grouping, anydata, anyxml, choice, container, leaf, leaf_list, list, uses, action,
+     notification]);
+lint!(List(name), description, status, if_feature, typedef, grouping;
+    [typedef, grouping, anydata, anyxml, choice, container, leaf, leaf_list, list, uses, action,
+     notification]);
+lint!(Leaf(name), description, status, if_feature, type; []);
+lint!(LeafList(name), description, status, if_feature, type; []);
+lint!(AnyDataOrXml(name), description, status, if_feature; []);
+lint!(Choice(name), description, status, if_feature;
+    [anydata, anyxml, case, choice, container, leaf, leaf_list, list]);
+lint!(Case(name), status, if_feature, grouping;
+    [grouping, anydata, anyxml, choice, container, leaf, leaf_list, list, uses]);
+lint!(Uses(name), status, if_feature, uses; [augment]);
+lint!(ActionOrRpc(name), description, status, if_feature, typedef, grouping;
+    [typedef, grouping, input, output]);
+lint!(InOutput(), typedef, grouping;
+    [typedef, grouping, anydata, anyxml, choice, container, leaf, leaf_list, list, uses]);
+lint!(Notification(name), description, status, if_feature, typedef, grouping;
+    [typedef, grouping, anydata, anyxml, choice, container, leaf, leaf_list, list, uses]);
+lint!(Augment(target), description, status, if_feature;
+    [anydata, anyxml, case, choice, container, leaf, leaf_list, list, uses, action,
+     notification]);
+lint!(Deviation(target), description; []);
+
+/// Lints `module` by the levels of `config`. `source` is the text of the module,
+/// only YANG sources are checked for long lines and suppression comments.
+pub(crate) fn lint(
+    module: &ModuleKind,
+    source: &str,
+    config: &LintConfig,
+    error_context: &mut ErrorContext,
+) {
+    let (prefix, identity, feature) = match module {
+        ModuleKind::Module(module) => (&module.prefix, &module.identity, &module.feature),
+        ModuleKind::SubModule(submodule) => (
+            &submodule.belongs_to.prefix,
+            &submodule.identity,
+            &submodule.feature,
+        ),
+    };
+    let mut linter = Linter {
+        config,
+        error_context,
+        suppressions: vec![],
+        prefix,
+        identity,
+        feature,
+        scopes: vec![],
+        statuses: vec![],
+    };
+
+    if let Ok(cst) = cst::parse(source) {
+        collect_suppressions(&cst.root, &mut linter.suppressions);
+        for suppression in &linter.suppressions {
+            for rule in &suppression.rules {
+                if !RULES.contains(&rule.as_str()) {
+                    linter.error_context.add_warning(
+                        suppression.keyword_span,
+                        format!("Unknown lint rule {}", rule),
+                    );
+                }
+            }
+        }
+
+        let mut offset = 0;
+        for line in source.split_inclusive('\n') {
+            let text = line.trim_end_matches(['\r', '\n']);
+            if let Some((index, _)) = text.char_indices().nth(MAX_LINE_LENGTH) {
+                linter.report(
+                    LINE_TOO_LONG,
+                    (offset + index, offset + text.len()),
+                    format!("Line is longer than {} characters", MAX_LINE_LENGTH),
+                );
+            }
+            offset += line.len();
+        }
+    }
+
+    match module {
+        ModuleKind::Module(module) => module.lint("module", &mut linter),
+        ModuleKind::SubModule(submodule) => submodule.lint("submodule", &mut linter),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::model_mapper::Mapper;
+    use crate::parser::parser::parse;
+
+    fn lint_module(input: &str, config: &LintConfig) -> Vec<(String, &'static str)> {
+        let mut error_context = ErrorContext::new();
+        let module = Module::map(parse(input).unwrap(), &mut error_context).unwrap();
+        assert!(!error_context.has_errors());
+        lint(
+            &ModuleKind::Module(module),
+            input,
+            config,
+            &mut error_context,
+        );
+        error_context.sort_errors();
+        error_context
+            .diagnostics()
+            .iter()
+            .map(|diagnostic| {
+                let severity = match diagnostic.severity {
+                    Some(miette::Severity::Error) => "error",
+                    _ => "warning",
+                };
+                (diagnostic.message.clone(), severity)
+            })
+            .collect()
+    }
+
+    const MODULE: &str = r#"module acme-system {
+  namespace "urn:acme:system";
+  prefix sys;
+  organization "ACME";
+  contact "admin@acme.example";
+  description "System configuration.";
+  revision 2024-01-01 {
+    description "Initial revision.";
+    reference "None.";
+  }
+  feature ssh {
+    description "SSH access.";
+  }
+  typedef mode {
+    description "Access mode.";
+    type enumeration {
+      enum read { value 1; }
+      enum write { value 2; }
+    }
+  }
+  container system {
+    description "The system.";
+    leaf mode {
+      if-feature ssh;
+      description "The access mode.";
+      type mode;
+    }
+  }
+}
+"#;
+
+    #[test]
+    fn test_clean() {
+        assert_eq!(lint_module(MODULE, &LintConfig::default()), []);
+    }
+
+    #[test]
+    fn test_rules() {
+        let input = r#"module example {
+  namespace "urn:example";
+  prefix foo;
+  revision 2024-01-01;
+  identity transport {
+    status deprecated;
+    description "A transport.";
+  }
+  feature telnet {
+    status deprecated;
+    description "Telnet access.";
+  }
+  grouping endpoint {
+    status deprecated;
+    description "An endpoint.";
+    leaf address {
+      description "The address.";
+      type string;
+    }
+  }
+  typedef port {
+    status deprecated;
+    description "A port.";
+    type uint16;
+  }
+  leaf enabled {
+    description "Whether enabled.";
+    type boolean;
+  }
+  container server {
+    leaf port {
+      if-feature "telnet or ssh";
+      description "The port, which is chosen by the administrator of the system.";
+      type union {
+        type port;
+        type enumeration {
+          enum default;
+        }
+      }
+    }
+    leaf kind {
+      description "The transport.";
+      type identityref { base transport; }
+    }
+    uses endpoint;
+  }
+  container legacy {
+    status deprecated;
+    description "Deprecated settings.";
+    leaf port {
+      description "The port.";
+      type port;
+    }
+  }
+}
+"#;
+        assert_eq!(
+            lint_module(input, &LintConfig::default()),
+            [
+                (
+                    "Module example has no description [missing-description]".to_string(),
+                    "warning"
+                ),
+                (
+                    "Module example has no organization [missing-organization]".to_string(),
+                    "warning"
+                ),
+                (
+                    "Module example has no contact [missing-contact]".to_string(),
+                    "warning"
+                ),
+                (
+                    "Prefix foo does not abbreviate module name example [prefix-convention]"
+                        .to_string(),
+                    "warning"
+                ),
+                (
+                    "Revision 2024-01-01 has no description [missing-description]".to_string(),
+                    "warning"
+                ),
+                (
+                    "Revision 2024-01-01 has no reference [missing-reference]".to_string(),
+                    "warning"
+                ),
+                (
+                    "Top-level leaf enabled is not a container [top-level-data-node]".to_string(),
+                    "warning"
+                ),
+                (
+                    "Container server has no description [missing-description]".to_string(),
+                    "warning"
+                ),
+                (
+                    "Leaf port uses deprecated feature telnet [deprecated-use]".to_string(),
+                    "warning"
+                ),
+                (
+                    "Line is longer than 70 characters [line-too-long]".to_string(),
+                    "warning"
+                ),
+                (
+                    "Leaf port uses deprecated typedef port [deprecated-use]".to_string(),
+                    "warning"
+                ),
+                (
+                    "Enum default has no explicit value [implicit-enum-value]".to_string(),
+                    "warning"
+                ),
+                (
+                    "Leaf kind uses deprecated identity transport [deprecated-use]".to_string(),
+                    "warning"
+                ),
+                (
+                    "Uses endpoint uses deprecated grouping endpoint [deprecated-use]".to_string(),
+                    "warning"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_config() {
+        let config =
+            LintConfig::parse(r#"{"missing-description": "deny", "missing-contact": "allow"}"#)
+                .unwrap();
+        let input = MODULE
+            .replace("  contact \"admin@acme.example\";\n", "")
+            .replace("    description \"The system.\";\n", "");
+        assert_eq!(
+            lint_module(&input, &config),
+            [(
+                "Container system has no description [missing-description]".to_string(),
+                "error"
+            )]
+        );
+
+        assert_eq!(
+            LintConfig::parse(r#"{"missing-summary": "deny"}"#).unwrap_err(),
+            "Unknown rule missing-summary"
+        );
+        assert_eq!(
+            LintConfig::parse(r#"{"line-too-long": "error"}"#).unwrap_err(),
+            "Invalid level of rule line-too-long, expected allow, warn or deny"
+        );
+        assert!(LintConfig::parse("[]").is_err());
+    }
+
+    #[test]
+    fn test_suppression() {
+        let input = MODULE
+            .replace(
+                "  container system {\n    description \"The system.\";\n",
+                "  // yang-lint: allow(missing-description)\n  container system {\n",
+            )
+            .replace(
+                "    leaf mode {\n      if-feature ssh;\n      description \"The access mode.\";\n",
+                "    /* yang-lint: allow(implicit-enum-value, unknown-rule) */\n    leaf mode {\n",
+            )
+            .replace(
+                "      type mode;\n",
+                "      type enumeration { enum on; }\n",
+            );
+        assert_eq!(
+            lint_module(&input, &LintConfig::default()),
+            [("Unknown lint rule unknown-rule".to_string(), "warning")]
+        );
+    }
+
+    #[test]
+    fn test_abbreviates() {
+        assert!(abbreviates("if", "ietf-interfaces"));
+        assert!(abbreviates("nacm", "ietf-netconf-acm"));
+        assert!(abbreviates("acme", "acme-system"));
+        assert!(abbreviates("sys", "acme-system"));
+        assert!(!abbreviates("foo", "example"));
+        assert!(!abbreviates("xa", "example"));
+    }
+}
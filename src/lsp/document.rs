@@ -0,0 +1,739 @@
+//! Analysis of a YANG document open in an editor: its diagnostics, the definitions
+//! of references, hover texts, keyword completion and its outline.
+
+use crate::errors::ErrorContext;
+use crate::incremental::IncrementalModule;
+use crate::parser::cst::{CstBody, CstStatement};
+use crate::parser::lexer::{Lexer, Tok};
+use crate::parser::model::substatements;
+use crate::parser::parser::{parse, Statement};
+use crate::typing::BUILT_IN_TYPE_NAMES;
+use crate::yin::{parse_yin, YinContext};
+use crate::Span;
+use miette::{LabeledSpan, MietteDiagnostic};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+/// Statements shown in the outline of a document.
+const SYMBOL_KEYWORDS: &[&str] = &[
+    "module",
+    "submodule",
+    "typedef",
+    "grouping",
+    "identity",
+    "feature",
+    "extension",
+    "container",
+    "list",
+    "leaf",
+    "leaf-list",
+    "choice",
+    "case",
+    "anydata",
+    "anyxml",
+    "augment",
+    "rpc",
+    "action",
+    "input",
+    "output",
+    "notification",
+    "deviation",
+];
+
+/// Longest chain of typedefs followed, in case they derive from each other.
+const MAX_TYPE_CHAIN: usize = 32;
+
+/// A module or submodule with its statement tree.
+struct ParsedModule {
+    path: PathBuf,
+    source: String,
+    root: Statement,
+}
+
+/// A definition found for a reference, with the statements enclosing it,
+/// starting with the module statement.
+struct Definition<'d> {
+    module: &'d ParsedModule,
+    ancestors: Vec<&'d Statement>,
+    statement: &'d Statement,
+}
+
+/// A statement shown in the outline of a document.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Symbol {
+    pub(crate) name: String,
+    pub(crate) keyword: String,
+    /// The whole statement.
+    pub(crate) span: Span,
+    /// The argument of the statement, or its keyword if it has none.
+    pub(crate) selection_span: Span,
+    pub(crate) children: Vec<Symbol>,
+}
+
+fn child<'s>(statement: &'s Statement, keyword: &str) -> Option<&'s Statement> {
+    statement
+        .statements
+        .iter()
+        .find(|statement| statement.keyword == keyword)
+}
+
+fn child_argument<'s>(statement: &'s Statement, keyword: &str) -> Option<&'s str> {
+    child(statement, keyword)?.argument.as_deref()
+}
+
+/// The name of the module the definitions of the module or submodule `root`
+/// belong to.
+fn module_name(root: &Statement) -> Option<&str> {
+    match root.keyword.as_str() {
+        "submodule" => child_argument(root, "belongs-to"),
+        _ => root.argument.as_deref(),
+    }
+}
+
+/// The name of the module `prefix` refers to in the module or submodule `root`.
+fn prefix_module<'s>(root: &'s Statement, prefix: &str) -> Option<&'s str> {
+    let own_prefix = match root.keyword.as_str() {
+        "submodule" => {
+            child(root, "belongs-to").and_then(|belongs_to| child_argument(belongs_to, "prefix"))
+        }
+        _ => child_argument(root, "prefix"),
+    };
+    if own_prefix == Some(prefix) {
+        return module_name(root);
+    }
+    root.statements
+        .iter()
+        .filter(|statement| statement.keyword == "import")
+        .find(|import| child_argument(import, "prefix") == Some(prefix))
+        .and_then(|import| import.argument.as_deref())
+}
+
+/// The keyword of the definitions the argument of a `keyword` statement refers to.
+fn referenced_keyword(keyword: &str) -> Option<&'static str> {
+    match keyword {
+        "uses" => Some("grouping"),
+        "type" => Some("typedef"),
+        "base" => Some("identity"),
+        "if-feature" => Some("feature"),
+        _ => None,
+    }
+}
+
+/// The end of `statement`, after its semicolon or closing brace.
+fn statement_end(statement: &CstStatement) -> usize {
+    match &statement.body {
+        CstBody::Semicolon(semicolon) => semicolon.span.1,
+        CstBody::Block { rbrace, .. } => rbrace.span.1,
+    }
+}
+
+/// Indices of the substatements leading from `root` to the innermost statement
+/// containing `offset`, `None` if `root` does not contain it.
+fn statement_path(root: &CstStatement, offset: usize) -> Option<Vec<usize>> {
+    if !(root.keyword.span.0..statement_end(root)).contains(&offset) {
+        return None;
+    }
+    let mut path = vec![];
+    let mut statement = root;
+    while let Some(index) = statement
+        .statements()
+        .iter()
+        .position(|child| (child.keyword.span.0..statement_end(child)).contains(&offset))
+    {
+        path.push(index);
+        statement = &statement.statements()[index];
+    }
+    Some(path)
+}
+
+/// The span of the value of the argument of `statement`, without quotes. Only
+/// arguments of a single string are considered.
+fn argument_value_span(statement: &CstStatement) -> Option<Span> {
+    let [token] = &statement.argument[..] else {
+        return None;
+    };
+    match token.tok {
+        Tok::QString(_) => Some((token.span.0 + 1, token.span.1 - 1)),
+        _ => Some(token.span),
+    }
+}
+
+/// The identifier at `offset` of `text`, with its offset in `text`.
+fn identifier_at(text: &str, offset: usize) -> Option<(usize, &str)> {
+    if !text.is_char_boundary(offset) {
+        return None;
+    }
+    let is_identifier_char =
+        |char: char| char.is_alphanumeric() || matches!(char, '_' | '-' | '.' | ':');
+    let start = text[..offset]
+        .rfind(|char| !is_identifier_char(char))
+        .map_or(0, |index| index + 1);
+    let end = text[offset..]
+        .find(|char| !is_identifier_char(char))
+        .map_or(text.len(), |index| offset + index);
+    (start < end).then(|| (start, &text[start..end]))
+}
+
+fn symbols(statements: &[CstStatement]) -> Vec<Symbol> {
+    statements
+        .iter()
+        .flat_map(|statement| {
+            let children = symbols(statement.statements());
+            if !SYMBOL_KEYWORDS.contains(&statement.keyword()) {
+                return children;
+            }
+            let name = statement
+                .argument()
+                .unwrap_or_else(|| statement.keyword().to_string());
+            let selection_span = match (statement.argument.first(), statement.argument.last()) {
+                (Some(first), Some(last)) => (first.span.0, last.span.1),
+                _ => statement.keyword.span,
+            };
+            vec![Symbol {
+                name,
+                keyword: statement.keyword().to_string(),
+                span: (statement.keyword.span.0, statement_end(statement)),
+                selection_span,
+                children,
+            }]
+        })
+        .collect()
+}
+
+/// A document open in the editor, analyzed together with the modules it imports
+/// and includes.
+pub(crate) struct Document {
+    pub(crate) path: PathBuf,
+    /// The document, analyzed again incrementally on every edit.
+    module: IncrementalModule,
+    /// The document itself if it has no syntax errors, followed by the modules
+    /// loaded with it.
+    modules: Vec<ParsedModule>,
+    /// The number of modules of the repository of `module` parsed into `modules`.
+    loaded: usize,
+    pub(crate) diagnostics: ErrorContext,
+}
+
+impl Document {
+    /// Parses, loads and compiles the document `source` at `path`.
+    pub(crate) fn new(
+        path: PathBuf,
+        source: String,
+        search_paths: &[PathBuf],
+        strict: bool,
+    ) -> Self {
+        let module =
+            IncrementalModule::with_options(path.clone(), source, search_paths.to_vec(), strict);
+        let mut document = Document {
+            path,
+            module,
+            modules: vec![],
+            loaded: 0,
+            diagnostics: ErrorContext::new(),
+        };
+        document.update();
+        document
+    }
+
+    pub(crate) fn source(&self) -> &str {
+        self.module.source()
+    }
+
+    /// Replaces `range` of the source with `text` and analyzes the document again.
+    pub(crate) fn edit(&mut self, range: Range<usize>, text: &str) {
+        self.module.edit(range, text);
+        self.update();
+    }
+
+    /// Updates the statement trees and the diagnostics after the document changed.
+    /// Modules loaded as new dependencies are parsed, the others are kept.
+    fn update(&mut self) {
+        if self
+            .modules
+            .first()
+            .is_some_and(|module| module.path == self.path)
+        {
+            self.modules.remove(0);
+        }
+        let loaded = &self.module.repository().modules;
+        for loaded in &loaded[self.loaded..] {
+            if loaded.path == self.path {
+                continue;
+            }
+            let root = if loaded
+                .path
+                .extension()
+                .is_some_and(|extension| extension == "yin")
+            {
+                parse_yin(&loaded.source, &YinContext::default())
+            } else {
+                parse(&loaded.source)
+            };
+            if let Ok(root) = root {
+                self.modules.push(ParsedModule {
+                    path: loaded.path.clone(),
+                    source: loaded.source.clone(),
+                    root,
+                });
+            }
+        }
+        self.loaded = loaded.len();
+        if let Some(cst) = self.module.cst() {
+            let document = ParsedModule {
+                path: self.path.clone(),
+                source: self.source().to_string(),
+                root: cst.root.to_statement(),
+            };
+            self.modules.insert(0, document);
+        }
+        self.diagnostics = self.module.error_context();
+    }
+
+    /// The spans of the diagnostics, the first label of each.
+    pub(crate) fn diagnostic_span(diagnostic: &MietteDiagnostic) -> Span {
+        diagnostic
+            .labels
+            .as_ref()
+            .and_then(|labels| labels.first())
+            .map_or((0, 0), |label: &LabeledSpan| {
+                (label.offset(), label.offset() + label.len())
+            })
+    }
+
+    /// The statements enclosing `offset`, starting with the module statement, and
+    /// the innermost one in the syntax tree.
+    fn statements_at(&self, offset: usize) -> Option<(Vec<&Statement>, &CstStatement)> {
+        let root = &self.module.cst()?.root;
+        let path = statement_path(root, offset)?;
+        let mut ancestors = vec![&self.modules[0].root];
+        let mut cst_statement = root;
+        for index in path {
+            ancestors.push(&ancestors[ancestors.len() - 1].statements[index]);
+            cst_statement = &cst_statement.statements()[index];
+        }
+        Some((ancestors, cst_statement))
+    }
+
+    /// Finds the `keyword` definition `reference` refers to from within
+    /// `ancestors` of `module`.
+    fn resolve<'d>(
+        &'d self,
+        module: &'d ParsedModule,
+        ancestors: &[&'d Statement],
+        keyword: &str,
+        reference: &str,
+    ) -> Option<Definition<'d>> {
+        let (target, name) = match reference.split_once(':') {
+            Some((prefix, name)) => (prefix_module(&module.root, prefix)?, name),
+            None => (module_name(&module.root)?, reference),
+        };
+        let find = |statement: &'d Statement| {
+            statement.statements.iter().find(|definition| {
+                definition.keyword == keyword && definition.argument.as_deref() == Some(name)
+            })
+        };
+        if Some(target) == module_name(&module.root) {
+            for (depth, ancestor) in ancestors.iter().enumerate().rev() {
+                if let Some(statement) = find(ancestor) {
+                    return Some(Definition {
+                        module,
+                        ancestors: ancestors[..=depth].to_vec(),
+                        statement,
+                    });
+                }
+            }
+        }
+        // Top-level definitions of the module and its submodules.
+        self.modules
+            .iter()
+            .filter(|other| module_name(&other.root) == Some(target))
+            .find_map(|other| {
+                Some(Definition {
+                    module: other,
+                    ancestors: vec![&other.root],
+                    statement: find(&other.root)?,
+                })
+            })
+    }
+
+    fn module(&self, name: &str) -> Option<&ParsedModule> {
+        self.modules.iter().find(|module| {
+            module.root.keyword == "module" && module.root.argument.as_deref() == Some(name)
+        })
+    }
+
+    /// The type `reference` and the types it derives from, ending with a built-in
+    /// type unless a typedef cannot be found.
+    fn type_chain(
+        &self,
+        module: &ParsedModule,
+        ancestors: &[&Statement],
+        reference: &str,
+    ) -> Vec<String> {
+        let mut chain = vec![reference.to_string()];
+        let Some(mut definition) = self.resolve(module, ancestors, "typedef", reference) else {
+            return chain;
+        };
+        while chain.len() < MAX_TYPE_CHAIN {
+            let Some(r#type) = child_argument(definition.statement, "type") else {
+                break;
+            };
+            chain.push(r#type.to_string());
+            if BUILT_IN_TYPE_NAMES.contains(&r#type) {
+                break;
+            }
+            match self.resolve(definition.module, &definition.ancestors, "typedef", r#type) {
+                Some(next) => definition = next,
+                None => break,
+            }
+        }
+        chain
+    }
+
+    /// What the identifier at `offset` refers to: a module for a prefix, a
+    /// definition otherwise. Returns the span of the identifier.
+    fn reference_at(&self, offset: usize) -> Option<(Span, Definition<'_>)> {
+        let (ancestors, cst_statement) = self.statements_at(offset)?;
+        let statement = *ancestors.last()?;
+        let value_span = argument_value_span(cst_statement)?;
+        if !(value_span.0..=value_span.1).contains(&offset) {
+            return None;
+        }
+        let value = &self.source()[value_span.0..value_span.1];
+        let (start, identifier) = identifier_at(value, offset - value_span.0)?;
+        let start = value_span.0 + start;
+        let span = (start, start + identifier.len());
+        let module = &self.modules[0];
+
+        let module_definition = |name: &str| {
+            let other = self.module(name)?;
+            Some(Definition {
+                module: other,
+                ancestors: vec![],
+                statement: &other.root,
+            })
+        };
+        if statement.keyword == "import" {
+            return Some((span, module_definition(identifier)?));
+        }
+        let keyword = referenced_keyword(&statement.keyword)?;
+        match identifier.split_once(':') {
+            Some((prefix, _)) if offset <= start + prefix.len() => {
+                let span = (start, start + prefix.len());
+                Some((
+                    span,
+                    module_definition(prefix_module(&module.root, prefix)?)?,
+                ))
+            }
+            _ => {
+                let definition = self.resolve(
+                    module,
+                    &ancestors[..ancestors.len() - 1],
+                    keyword,
+                    identifier,
+                )?;
+                Some((span, definition))
+            }
+        }
+    }
+
+    /// The path, the source and the span of the name of the definition the
+    /// identifier at `offset` refers to.
+    pub(crate) fn definition(&self, offset: usize) -> Option<(&Path, &str, Span)> {
+        let (_, definition) = self.reference_at(offset)?;
+        Some((
+            &definition.module.path,
+            &definition.module.source,
+            definition.statement.argument_span,
+        ))
+    }
+
+    /// Markdown describing the statement at `offset`, with the span it applies
+    /// to. For a reference this is its definition, for a statement with a type
+    /// the chain of types it derives from.
+    pub(crate) fn hover(&self, offset: usize) -> Option<(Span, String)> {
+        let (span, module, ancestors, statement) = match self.reference_at(offset) {
+            Some((span, definition)) => (
+                span,
+                definition.module,
+                definition.ancestors,
+                definition.statement,
+            ),
+            None => {
+                let (mut ancestors, cst_statement) = self.statements_at(offset)?;
+                let value_span = argument_value_span(cst_statement)?;
+                if !(value_span.0..=value_span.1).contains(&offset) {
+                    return None;
+                }
+                let statement = ancestors.pop()?;
+                if statement.keyword == "type" {
+                    let name = statement.argument.as_deref()?;
+                    if !BUILT_IN_TYPE_NAMES.contains(&name) {
+                        return None;
+                    }
+                    return Some((value_span, format!("Built-in type `{}`", name)));
+                }
+                (value_span, &self.modules[0], ancestors, statement)
+            }
+        };
+
+        let mut sections = vec![format!(
+            "```yang\n{} {}\n```",
+            statement.keyword,
+            statement.argument.as_deref().unwrap_or_default()
+        )];
+        if let Some(r#type) = child_argument(statement, "type") {
+            let chain = if statement.keyword == "typedef" {
+                let name = statement.argument.as_deref().unwrap_or_default();
+                let mut scope = ancestors.clone();
+                scope.push(statement);
+                let mut chain = vec![name.to_string()];
+                chain.extend(self.type_chain(module, &scope, r#type));
+                chain
+            } else {
+                let mut scope = ancestors.clone();
+                scope.push(statement);
+                self.type_chain(module, &scope, r#type)
+            };
+            let chain: Vec<String> = chain.iter().map(|name| format!("`{}`", name)).collect();
+            sections.push(format!("Type: {}", chain.join(" → ")));
+        }
+        if let Some(description) = child_argument(statement, "description") {
+            sections.push(description.to_string());
+        }
+        if sections.len() == 1 && statement.keyword != "module" {
+            return None;
+        }
+        Some((span, sections.join("\n\n")))
+    }
+
+    /// The keywords that may be written at `offset`: the substatements accepted by
+    /// the enclosing statement. The document is tokenized up to `offset` only, so
+    /// this works while the statement being written is incomplete.
+    pub(crate) fn completions(&self, offset: usize) -> &'static [&'static str] {
+        let mut parents: Vec<String> = vec![];
+        let mut keyword: Option<String> = None;
+        let mut tokens = 0;
+        let mut last_end = 0;
+        for (span, tok) in Lexer::new(self.source()) {
+            if span.0 >= offset {
+                break;
+            }
+            let Ok(tok) = tok else {
+                return &[];
+            };
+            match tok {
+                Tok::UString(text) | Tok::QString(text) => {
+                    if tokens == 0 {
+                        keyword = Some(text);
+                    }
+                    tokens += 1;
+                }
+                Tok::Plus => tokens += 1,
+                Tok::Semicolon => tokens = 0,
+                Tok::LBrace => {
+                    parents.push(keyword.take().unwrap_or_default());
+                    tokens = 0;
+                }
+                Tok::RBrace => {
+                    parents.pop();
+                    tokens = 0;
+                }
+                _ => {}
+            }
+            last_end = span.1;
+        }
+        // Only the keyword of a statement is completed, not its argument.
+        if tokens > 1 || (tokens == 1 && last_end != offset) {
+            return &[];
+        }
+        match parents.last() {
+            Some(parent) => substatements(parent).unwrap_or_default(),
+            None => &["module", "submodule"],
+        }
+    }
+
+    /// The outline of the document.
+    pub(crate) fn symbols(&self) -> Vec<Symbol> {
+        match self.module.cst() {
+            Some(cst) => symbols(std::slice::from_ref(&cst.root)),
+            None => vec![],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    const IMPORTED: &str = r#"module types {
+  namespace "urn:types";
+  prefix t;
+  identity transport;
+  typedef port {
+    type uint16;
+    description "A port number.";
+  }
+}
+"#;
+
+    const DOCUMENT: &str = r#"module example {
+  namespace "urn:example";
+  prefix ex;
+  import types { prefix t; }
+  feature tls;
+  identity tcp { base t:transport; }
+  typedef listen-port {
+    type t:port;
+    description "The port to listen on.";
+  }
+  grouping endpoint {
+    leaf port { type listen-port; }
+  }
+  container server {
+    typedef port { type ex:listen-port; }
+    uses endpoint;
+    leaf tls-port { if-feature "ex:tls"; type port; }
+    leaf name { type string; description "Server name."; }
+  }
+}
+"#;
+
+    fn analyze(source: &str) -> Document {
+        let directory = std::env::temp_dir().join("yang-utils-lsp-test");
+        fs::create_dir_all(&directory).unwrap();
+        fs::write(directory.join("types.yang"), IMPORTED).unwrap();
+        Document::new(
+            directory.join("example.yang"),
+            source.to_string(),
+            &[],
+            false,
+        )
+    }
+
+    /// The offset of the first occurrence of `text` in `source`, plus `delta`.
+    fn offset(source: &str, text: &str, delta: usize) -> usize {
+        source.find(text).unwrap() + delta
+    }
+
+    /// The file name and text of the definition at `text` + `delta`.
+    fn definition(document: &Document, text: &str, delta: usize) -> Option<(String, String)> {
+        let (path, source, span) = document.definition(offset(document.source(), text, delta))?;
+        let name = path.file_name().unwrap().to_str().unwrap().to_string();
+        Some((name, source[span.0..span.1].to_string()))
+    }
+
+    #[test]
+    fn test_diagnostics() {
+        assert!(analyze(DOCUMENT).diagnostics.diagnostics().is_empty());
+        let invalid = DOCUMENT.replace("type port;", "type unknown;");
+        let document = analyze(&invalid);
+        assert_eq!(document.diagnostics.messages(), ["Type unknown not found"]);
+        let span = Document::diagnostic_span(&document.diagnostics.diagnostics()[0]);
+        assert_eq!(&invalid[span.0..span.1], "unknown");
+    }
+
+    #[test]
+    fn test_definition() {
+        let document = analyze(DOCUMENT);
+        let at = |text, delta| definition(&document, text, delta);
+        let local = |name: &str| Some(("example.yang".to_string(), name.to_string()));
+        let imported = |name: &str| Some(("types.yang".to_string(), name.to_string()));
+        assert_eq!(at("uses endpoint", 7), local("endpoint"));
+        assert_eq!(at("type listen-port", 6), local("listen-port"));
+        assert_eq!(at("base t:transport", 8), imported("transport"));
+        assert_eq!(at("base t:transport", 5), imported("types"));
+        assert_eq!(at("type t:port", 9), imported("port"));
+        assert_eq!(at("import types", 8), imported("types"));
+        assert_eq!(at("\"ex:tls\"", 5), local("tls"));
+        // The typedef in the container is found before the top-level ones.
+        assert_eq!(
+            document
+                .definition(offset(DOCUMENT, "type port;", 6))
+                .unwrap()
+                .2,
+            (
+                offset(DOCUMENT, "typedef port", 8),
+                offset(DOCUMENT, "typedef port", 12)
+            )
+        );
+        assert_eq!(at("type string", 6), None);
+        assert_eq!(at("container server", 3), None);
+    }
+
+    #[test]
+    fn test_hover() {
+        let document = analyze(DOCUMENT);
+        let hover = |text, delta| {
+            document
+                .hover(offset(DOCUMENT, text, delta))
+                .map(|(_, text)| text)
+        };
+        assert_eq!(
+            hover("type port;", 6).unwrap(),
+            "```yang\ntypedef port\n```\n\n\
+             Type: `port` → `ex:listen-port` → `t:port` → `uint16`"
+        );
+        assert_eq!(
+            hover("leaf name", 6).unwrap(),
+            "```yang\nleaf name\n```\n\nType: `string`\n\nServer name."
+        );
+        assert_eq!(
+            hover("type t:port", 8).unwrap(),
+            "```yang\ntypedef port\n```\n\nType: `port` → `uint16`\n\nA port number."
+        );
+        assert_eq!(hover("type string", 7).unwrap(), "Built-in type `string`");
+        assert_eq!(hover("container server", 12), None);
+    }
+
+    #[test]
+    fn test_completions() {
+        let source = "module example {\n  container server {\n    le\n  }\n}\n";
+        let document = analyze(source);
+        let completions = document.completions(offset(source, "le\n", 2));
+        assert!(completions.contains(&"leaf-list") && completions.contains(&"presence"));
+        assert!(!completions.contains(&"namespace"));
+        assert!(document
+            .completions(offset(source, "container", 0))
+            .contains(&"namespace"));
+        assert!(document.completions(offset(source, "server", 3)).is_empty());
+
+        let source = "module example {\n  leaf name { ty";
+        let completions = analyze(source).completions(source.len());
+        assert!(completions.contains(&"type") && completions.contains(&"mandatory"));
+        assert_eq!(document.completions(0), ["module", "submodule"]);
+    }
+
+    #[test]
+    fn test_symbols() {
+        let symbols = analyze(DOCUMENT).symbols();
+        let names = |symbols: &[Symbol]| {
+            symbols
+                .iter()
+                .map(|symbol| format!("{} {}", symbol.keyword, symbol.name))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(names(&symbols), ["module example"]);
+        assert_eq!(
+            names(&symbols[0].children),
+            [
+                "feature tls",
+                "identity tcp",
+                "typedef listen-port",
+                "grouping endpoint",
+                "container server"
+            ]
+        );
+        let server = &symbols[0].children[4];
+        assert_eq!(
+            names(&server.children),
+            ["typedef port", "leaf tls-port", "leaf name"]
+        );
+        assert_eq!(
+            &DOCUMENT[server.selection_span.0..server.selection_span.1],
+            "server"
+        );
+        assert!(DOCUMENT[server.span.0..server.span.1].ends_with("}\n  }"));
+    }
+}
@@ -0,0 +1,579 @@
+//! Language server for YANG over standard input and output, started by
+//! `yang-utils lsp`.
+//!
+//! Documents are synchronized incrementally: each change is applied to the
+//! document, which reparses and remaps only the statements it touched, see
+//! [`Document`]. Diagnostics with suggested fixes are offered as quick fixes.
+
+mod document;
+
+use document::{Document, Symbol};
+use lsp_server::{Connection, ErrorCode, Message, Notification, Request, Response};
+use lsp_types::notification::{
+    DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument,
+    Notification as NotificationTrait, PublishDiagnostics,
+};
+use lsp_types::request::{
+    CodeActionRequest, Completion, DocumentSymbolRequest, GotoDefinition, HoverRequest,
+    Request as RequestTrait,
+};
+use lsp_types::{
+    CodeAction, CodeActionKind, CodeActionOrCommand, CodeActionParams,
+    CodeActionProviderCapability, CodeActionResponse, CompletionItem, CompletionItemKind,
+    CompletionOptions, CompletionResponse, Diagnostic, DiagnosticRelatedInformation,
+    DiagnosticSeverity, DocumentSymbol, DocumentSymbolResponse, GotoDefinitionResponse, Hover,
+    HoverContents, HoverProviderCapability, Location, MarkupContent, MarkupKind, OneOf, Position,
+    PublishDiagnosticsParams, Range, ServerCapabilities, SymbolKind, TextDocumentPositionParams,
+    TextDocumentSyncCapability, TextDocumentSyncKind, TextEdit, Url, WorkspaceEdit,
+};
+use miette::{MietteDiagnostic, Severity};
+use std::collections::HashMap;
+use std::error::Error;
+use std::path::PathBuf;
+
+type ServerError = Box<dyn Error + Sync + Send>;
+
+/// The LSP position of the byte `offset` in `source`, counting characters in
+/// UTF-16 code units.
+fn position(source: &str, offset: usize) -> Position {
+    let before = &source[..offset.min(source.len())];
+    let line_start = before.rfind('\n').map_or(0, |index| index + 1);
+    Position::new(
+        before.matches('\n').count() as u32,
+        before[line_start..].encode_utf16().count() as u32,
+    )
+}
+
+/// The byte offset of the LSP `position` in `source`.
+fn offset(source: &str, position: Position) -> usize {
+    let mut line_start = 0;
+    for _ in 0..position.line {
+        match source[line_start..].find('\n') {
+            Some(index) => line_start += index + 1,
+            None => return source.len(),
+        }
+    }
+    let line = &source[line_start..];
+    let line = &line[..line.find('\n').unwrap_or(line.len())];
+    let mut units = 0;
+    for (index, char) in line.char_indices() {
+        if units >= position.character {
+            return line_start + index;
+        }
+        units += char.len_utf16() as u32;
+    }
+    line_start + line.len()
+}
+
+fn range(source: &str, span: crate::Span) -> Range {
+    Range::new(position(source, span.0), position(source, span.1))
+}
+
+/// The LSP diagnostic of `diagnostic` in the document `uri`, with its secondary
+/// labels as related information.
+fn diagnostic(uri: &Url, source: &str, diagnostic: &MietteDiagnostic) -> Diagnostic {
+    let severity = match diagnostic.severity {
+        Some(Severity::Warning) => DiagnosticSeverity::WARNING,
+        Some(Severity::Advice) => DiagnosticSeverity::HINT,
+        _ => DiagnosticSeverity::ERROR,
+    };
+    let related: Vec<DiagnosticRelatedInformation> = diagnostic
+        .labels
+        .iter()
+        .flatten()
+        .skip(1)
+        .map(|label| DiagnosticRelatedInformation {
+            location: Location::new(
+                uri.clone(),
+                range(source, (label.offset(), label.offset() + label.len())),
+            ),
+            message: label.label().unwrap_or_default().to_string(),
+        })
+        .collect();
+    Diagnostic {
+        range: range(source, Document::diagnostic_span(diagnostic)),
+        severity: Some(severity),
+        source: Some("yang-utils".to_string()),
+        message: diagnostic.message.clone(),
+        related_information: (!related.is_empty()).then_some(related),
+        ..Default::default()
+    }
+}
+
+fn url_path(url: &Url) -> PathBuf {
+    url.to_file_path()
+        .unwrap_or_else(|()| PathBuf::from(url.path()))
+}
+
+fn symbol_kind(keyword: &str) -> SymbolKind {
+    match keyword {
+        "module" | "submodule" => SymbolKind::MODULE,
+        "typedef" => SymbolKind::TYPE_PARAMETER,
+        "grouping" => SymbolKind::CLASS,
+        "identity" => SymbolKind::ENUM_MEMBER,
+        "feature" => SymbolKind::BOOLEAN,
+        "extension" => SymbolKind::KEY,
+        "container" | "choice" | "case" | "augment" | "deviation" => SymbolKind::STRUCT,
+        "list" | "leaf-list" => SymbolKind::ARRAY,
+        "rpc" | "action" => SymbolKind::FUNCTION,
+        "notification" => SymbolKind::EVENT,
+        _ => SymbolKind::FIELD,
+    }
+}
+
+#[allow(deprecated)]
+fn document_symbol(source: &str, symbol: Symbol) -> DocumentSymbol {
+    DocumentSymbol {
+        name: symbol.name,
+        detail: Some(symbol.keyword.clone()),
+        kind: symbol_kind(&symbol.keyword),
+        tags: None,
+        deprecated: None,
+        range: range(source, symbol.span),
+        selection_range: range(source, symbol.selection_span),
+        children: Some(
+            symbol
+                .children
+                .into_iter()
+                .map(|child| document_symbol(source, child))
+                .collect(),
+        ),
+    }
+}
+
+/// Deserializes the parameters of a request of type `R`, passes them to
+/// `handler` and serializes its result.
+fn handle<R: RequestTrait>(
+    params: serde_json::Value,
+    handler: impl FnOnce(R::Params) -> R::Result,
+) -> serde_json::Result<serde_json::Value> {
+    let params = serde_json::from_value(params)?;
+    serde_json::to_value(handler(params))
+}
+
+struct Server {
+    search_paths: Vec<PathBuf>,
+    strict: bool,
+    documents: HashMap<Url, Document>,
+}
+
+impl Server {
+    fn capabilities() -> ServerCapabilities {
+        ServerCapabilities {
+            text_document_sync: Some(TextDocumentSyncCapability::Kind(
+                TextDocumentSyncKind::INCREMENTAL,
+            )),
+            definition_provider: Some(OneOf::Left(true)),
+            hover_provider: Some(HoverProviderCapability::Simple(true)),
+            completion_provider: Some(CompletionOptions::default()),
+            document_symbol_provider: Some(OneOf::Left(true)),
+            code_action_provider: Some(CodeActionProviderCapability::Simple(true)),
+            ..Default::default()
+        }
+    }
+
+    fn main_loop(&mut self, connection: &Connection) -> Result<(), ServerError> {
+        for message in &connection.receiver {
+            match message {
+                Message::Request(request) => {
+                    if connection.handle_shutdown(&request)? {
+                        return Ok(());
+                    }
+                    let response = self.handle_request(request);
+                    connection.sender.send(Message::Response(response))?;
+                }
+                Message::Notification(notification) => {
+                    if let Some(notification) = self.handle_notification(notification)? {
+                        connection
+                            .sender
+                            .send(Message::Notification(notification))?;
+                    }
+                }
+                Message::Response(_) => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn handle_request(&self, request: Request) -> Response {
+        let result = match request.method.as_str() {
+            GotoDefinition::METHOD => handle::<GotoDefinition>(request.params, |params| {
+                self.definition(params.text_document_position_params)
+            }),
+            HoverRequest::METHOD => handle::<HoverRequest>(request.params, |params| {
+                self.hover(params.text_document_position_params)
+            }),
+            Completion::METHOD => handle::<Completion>(request.params, |params| {
+                self.completion(params.text_document_position)
+            }),
+            DocumentSymbolRequest::METHOD => {
+                handle::<DocumentSymbolRequest>(request.params, |params| {
+                    let document = self.documents.get(&params.text_document.uri)?;
+                    let symbols = document
+                        .symbols()
+                        .into_iter()
+                        .map(|symbol| document_symbol(document.source(), symbol))
+                        .collect();
+                    Some(DocumentSymbolResponse::Nested(symbols))
+                })
+            }
+            CodeActionRequest::METHOD => {
+                handle::<CodeActionRequest>(request.params, |params| self.code_actions(params))
+            }
+            _ => {
+                return Response::new_err(
+                    request.id,
+                    ErrorCode::MethodNotFound as i32,
+                    format!("Unknown method {}", request.method),
+                )
+            }
+        };
+        match result {
+            Ok(result) => Response::new_ok(request.id, result),
+            Err(error) => Response::new_err(
+                request.id,
+                ErrorCode::InvalidParams as i32,
+                error.to_string(),
+            ),
+        }
+    }
+
+    /// Updates the documents, returns the diagnostics to publish.
+    fn handle_notification(
+        &mut self,
+        notification: Notification,
+    ) -> Result<Option<Notification>, ServerError> {
+        let uri = match notification.method.as_str() {
+            DidOpenTextDocument::METHOD => {
+                let params: <DidOpenTextDocument as NotificationTrait>::Params =
+                    serde_json::from_value(notification.params)?;
+                let uri = params.text_document.uri;
+                let document = Document::new(
+                    url_path(&uri),
+                    params.text_document.text,
+                    &self.search_paths,
+                    self.strict,
+                );
+                self.documents.insert(uri.clone(), document);
+                uri
+            }
+            DidChangeTextDocument::METHOD => {
+                let params: <DidChangeTextDocument as NotificationTrait>::Params =
+                    serde_json::from_value(notification.params)?;
+                let uri = params.text_document.uri;
+                let Some(document) = self.documents.get_mut(&uri) else {
+                    return Ok(None);
+                };
+                // Changes without a range replace the whole text.
+                for change in params.content_changes {
+                    let range = match change.range {
+                        Some(range) => {
+                            offset(document.source(), range.start)
+                                ..offset(document.source(), range.end)
+                        }
+                        None => 0..document.source().len(),
+                    };
+                    document.edit(range, &change.text);
+                }
+                uri
+            }
+            DidCloseTextDocument::METHOD => {
+                let params: <DidCloseTextDocument as NotificationTrait>::Params =
+                    serde_json::from_value(notification.params)?;
+                self.documents.remove(&params.text_document.uri);
+                params.text_document.uri
+            }
+            _ => return Ok(None),
+        };
+
+        let diagnostics = match self.documents.get(&uri) {
+            Some(document) => self.diagnostics(&uri, document),
+            None => vec![],
+        };
+        let params = PublishDiagnosticsParams::new(uri, diagnostics, None);
+        Ok(Some(Notification::new(
+            PublishDiagnostics::METHOD.to_string(),
+            params,
+        )))
+    }
+
+    fn diagnostics(&self, uri: &Url, document: &Document) -> Vec<Diagnostic> {
+        document
+            .diagnostics
+            .diagnostics()
+            .iter()
+            .map(|miette_diagnostic| diagnostic(uri, document.source(), miette_diagnostic))
+            .collect()
+    }
+
+    /// Quick fixes for the diagnostics in the range of the request.
+    fn code_actions(&self, params: CodeActionParams) -> Option<CodeActionResponse> {
+        let document = self.documents.get(&params.text_document.uri)?;
+        let start = offset(document.source(), params.range.start);
+        let end = offset(document.source(), params.range.end);
+        let actions = document
+            .diagnostics
+            .diagnostics_with_fixes()
+            .filter(|(miette_diagnostic, fixes)| {
+                let span = Document::diagnostic_span(miette_diagnostic);
+                !fixes.is_empty() && span.0 <= end && start <= span.1
+            })
+            .map(|(miette_diagnostic, fixes)| {
+                let edits = fixes
+                    .iter()
+                    .map(|fix| TextEdit::new(range(document.source(), fix.span), fix.text.clone()))
+                    .collect();
+                CodeActionOrCommand::CodeAction(CodeAction {
+                    title: miette_diagnostic
+                        .help
+                        .clone()
+                        .unwrap_or_else(|| "Fix".to_string()),
+                    kind: Some(CodeActionKind::QUICKFIX),
+                    diagnostics: Some(vec![diagnostic(
+                        &params.text_document.uri,
+                        document.source(),
+                        miette_diagnostic,
+                    )]),
+                    edit: Some(WorkspaceEdit::new(HashMap::from([(
+                        params.text_document.uri.clone(),
+                        edits,
+                    )]))),
+                    is_preferred: Some(true),
+                    ..Default::default()
+                })
+            })
+            .collect();
+        Some(actions)
+    }
+
+    /// The document and the offset of a request.
+    fn document_offset(&self, params: &TextDocumentPositionParams) -> Option<(&Document, usize)> {
+        let document = self.documents.get(&params.text_document.uri)?;
+        Some((document, offset(document.source(), params.position)))
+    }
+
+    fn definition(&self, params: TextDocumentPositionParams) -> Option<GotoDefinitionResponse> {
+        let (document, offset) = self.document_offset(&params)?;
+        let (path, source, span) = document.definition(offset)?;
+        let uri = if path == document.path {
+            params.text_document.uri
+        } else {
+            Url::from_file_path(path).ok()?
+        };
+        Some(GotoDefinitionResponse::Scalar(Location::new(
+            uri,
+            range(source, span),
+        )))
+    }
+
+    fn hover(&self, params: TextDocumentPositionParams) -> Option<Hover> {
+        let (document, offset) = self.document_offset(&params)?;
+        let (span, text) = document.hover(offset)?;
+        Some(Hover {
+            contents: HoverContents::Markup(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value: text,
+            }),
+            range: Some(range(document.source(), span)),
+        })
+    }
+
+    fn completion(&self, params: TextDocumentPositionParams) -> Option<CompletionResponse> {
+        let (document, offset) = self.document_offset(&params)?;
+        let items = document
+            .completions(offset)
+            .iter()
+            .map(|keyword| CompletionItem {
+                label: keyword.to_string(),
+                kind: Some(CompletionItemKind::KEYWORD),
+                ..Default::default()
+            })
+            .collect();
+        Some(CompletionResponse::Array(items))
+    }
+}
+
+/// Runs the language server over standard input and output until the client
+/// shuts it down. Imports and includes are searched in `search_paths` and next to
+/// the document.
+pub(crate) fn run(search_paths: Vec<PathBuf>, strict: bool) -> Result<(), ServerError> {
+    let (connection, io_threads) = Connection::stdio();
+    let capabilities = serde_json::to_value(Server::capabilities())?;
+    connection.initialize(capabilities)?;
+    let mut server = Server {
+        search_paths,
+        strict,
+        documents: HashMap::new(),
+    };
+    server.main_loop(&connection)?;
+    drop(connection);
+    io_threads.join()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::ErrorContext;
+    use lsp_server::RequestId;
+    use serde_json::json;
+    use std::thread;
+
+    #[test]
+    fn test_positions() {
+        let source = "a\nä😀b\n";
+        assert_eq!(position(source, 0), Position::new(0, 0));
+        assert_eq!(position(source, 2), Position::new(1, 0));
+        let b = source.find('b').unwrap();
+        assert_eq!(position(source, b), Position::new(1, 3));
+        assert_eq!(offset(source, Position::new(1, 3)), b);
+        assert_eq!(offset(source, Position::new(1, 10)), b + 1);
+        assert_eq!(offset(source, Position::new(5, 0)), source.len());
+    }
+
+    #[test]
+    fn test_related_information() {
+        let uri = Url::parse("file:///example.yang").unwrap();
+        let source = "typedef a;\ntypedef a;\n";
+        let mut error_context = ErrorContext::new();
+        error_context.add_conflict_error(
+            (19, 20),
+            "Typedef a is already defined".to_string(),
+            (8, 9),
+        );
+        let diagnostic = diagnostic(&uri, source, &error_context.diagnostics()[0]);
+        assert_eq!(
+            diagnostic.range,
+            Range::new(Position::new(1, 8), Position::new(1, 9))
+        );
+        assert_eq!(
+            diagnostic.related_information,
+            Some(vec![DiagnosticRelatedInformation {
+                location: Location::new(uri, Range::new(Position::new(0, 8), Position::new(0, 9))),
+                message: "previously defined here".to_string(),
+            }])
+        );
+    }
+
+    #[test]
+    fn test_server() {
+        let (server_connection, client) = Connection::memory();
+        let server = thread::spawn(move || {
+            let mut server = Server {
+                search_paths: vec![],
+                strict: false,
+                documents: HashMap::new(),
+            };
+            server.main_loop(&server_connection).unwrap();
+        });
+        let uri = "file:///yang-utils-lsp-test/example.yang";
+        let source = "module example {\n  namespace \"urn:example\";\n  prefix ex;\n  \
+                      grouping g { leaf a { type string; } }\n  uses g;\n  leaf b { type unknown; }\n}\n";
+
+        let notify = |method: &str, params| {
+            client
+                .sender
+                .send(Message::Notification(Notification::new(
+                    method.to_string(),
+                    params,
+                )))
+                .unwrap();
+        };
+        let request = |id: i32, method: &str, params| {
+            client
+                .sender
+                .send(Message::Request(Request::new(
+                    RequestId::from(id),
+                    method.to_string(),
+                    params,
+                )))
+                .unwrap();
+            match client.receiver.recv().unwrap() {
+                Message::Response(response) => response.result.unwrap(),
+                message => panic!("Unexpected message {:?}", message),
+            }
+        };
+
+        notify(
+            DidOpenTextDocument::METHOD,
+            json!({"textDocument": {"uri": uri, "languageId": "yang", "version": 1, "text": source}}),
+        );
+        let Message::Notification(diagnostics) = client.receiver.recv().unwrap() else {
+            panic!("Expected diagnostics");
+        };
+        assert_eq!(diagnostics.method, PublishDiagnostics::METHOD);
+        assert_eq!(
+            diagnostics.params["diagnostics"][0]["message"],
+            "Type unknown not found"
+        );
+        assert_eq!(
+            diagnostics.params["diagnostics"][0]["range"],
+            json!({"start": {"line": 5, "character": 16}, "end": {"line": 5, "character": 23}})
+        );
+
+        let position =
+            json!({"textDocument": {"uri": uri}, "position": {"line": 4, "character": 7}});
+        assert_eq!(
+            request(1, GotoDefinition::METHOD, position.clone()),
+            json!({"uri": uri, "range": {"start": {"line": 3, "character": 11}, "end": {"line": 3, "character": 12}}})
+        );
+        let symbols = request(
+            2,
+            DocumentSymbolRequest::METHOD,
+            json!({"textDocument": {"uri": uri}}),
+        );
+        assert_eq!(symbols[0]["name"], "example");
+        assert_eq!(symbols[0]["children"][0]["name"], "g");
+        let completions = request(
+            3,
+            Completion::METHOD,
+            json!({"textDocument": {"uri": uri}, "position": {"line": 4, "character": 2}}),
+        );
+        assert!(completions
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|item| item["label"] == "container"));
+
+        let actions = request(
+            4,
+            CodeActionRequest::METHOD,
+            json!({
+                "textDocument": {"uri": uri},
+                "range": {"start": {"line": 5, "character": 0}, "end": {"line": 6, "character": 0}},
+                "context": {"diagnostics": []}
+            }),
+        );
+        assert_eq!(actions.as_array().unwrap().len(), 0);
+
+        notify(
+            DidChangeTextDocument::METHOD,
+            json!({
+                "textDocument": {"uri": uri, "version": 2},
+                "contentChanges": [
+                    {"range": {"start": {"line": 5, "character": 16}, "end": {"line": 5, "character": 23}}, "text": "strnig"}
+                ]
+            }),
+        );
+        client.receiver.recv().unwrap();
+        let actions = request(
+            5,
+            CodeActionRequest::METHOD,
+            json!({
+                "textDocument": {"uri": uri},
+                "range": {"start": {"line": 5, "character": 0}, "end": {"line": 6, "character": 0}},
+                "context": {"diagnostics": []}
+            }),
+        );
+        assert_eq!(actions[0]["title"], "Did you mean string?");
+        assert_eq!(actions[0]["kind"], "quickfix");
+        assert_eq!(
+            actions[0]["edit"]["changes"][uri],
+            json!([{"range": {"start": {"line": 5, "character": 16}, "end": {"line": 5, "character": 22}}, "newText": "string"}])
+        );
+
+        request(6, "shutdown", serde_json::Value::Null);
+        notify("exit", serde_json::Value::Null);
+        server.join().unwrap();
+    }
+}
@@ -0,0 +1,446 @@
+//! Lossless concrete syntax tree.
+//!
+//! Unlike [`Statement`], the CST keeps every byte of the input: comments, whitespace,
+//! the quoting style of each argument and the individual pieces of `+` concatenations.
+//! Printing a [`Cst`] with [`ToString`] reproduces the parsed input exactly, which
+//! allows refactoring tools to edit a module and write it back without losing the
+//! formatting chosen by its authors.
+
+use crate::parser::lexer::{Lexer, Tok};
+use crate::parser::parser::{ParseError, Statement};
+use crate::Span;
+use std::fmt;
+use std::iter::Peekable;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum TriviaKind {
+    Whitespace,
+    LineComment,
+    BlockComment,
+}
+
+/// Whitespace or a comment preceding a token.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Trivia {
+    pub(crate) kind: TriviaKind,
+    pub(crate) text: String,
+}
+
+/// A significant token together with the trivia around it.
+///
+/// Trivia on the same line after a token, like a trailing comment, belongs to that
+/// token; everything from the next line break on leads the following token.
+///
+/// `span` refers to the original input and is `(0, 0)` for tokens created by edits.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct CstToken {
+    pub(crate) leading_trivia: Vec<Trivia>,
+    pub(crate) span: Span,
+    pub(crate) text: String,
+    pub(crate) tok: Tok,
+    pub(crate) trailing_trivia: Vec<Trivia>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum CstBody {
+    Semicolon(CstToken),
+    Block {
+        lbrace: CstToken,
+        statements: Vec<CstStatement>,
+        rbrace: CstToken,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct CstStatement {
+    pub(crate) keyword: CstToken,
+    /// The argument strings and the `+` tokens joining them, in source order.
+    pub(crate) argument: Vec<CstToken>,
+    pub(crate) body: CstBody,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Cst {
+    pub(crate) root: CstStatement,
+    /// Trivia after the closing token of the root statement.
+    pub(crate) trailing_trivia: Vec<Trivia>,
+}
+
+struct TokenSource<'input> {
+    input: &'input str,
+    lexer: Peekable<Lexer<'input>>,
+    /// Trivia following a line break which belongs to the next token.
+    pending_trivia: Vec<Trivia>,
+}
+
+impl<'input> TokenSource<'input> {
+    fn new(input: &'input str, column: usize) -> Self {
+        TokenSource {
+            input,
+            lexer: Lexer::with_trivia(input)
+                .starting_at_column(column)
+                .peekable(),
+            pending_trivia: vec![],
+        }
+    }
+
+    fn next_trivia_or_tok(&mut self) -> Result<Option<(Span, Tok, String)>, ParseError> {
+        match self.lexer.next() {
+            None => Ok(None),
+            Some((span, Err(err))) => Err(ParseError {
+                span: Some(span),
+                message: format!("{:?}", err),
+            }),
+            Some((span, Ok(tok))) => Ok(Some((span, tok, self.input[span.0..span.1].to_string()))),
+        }
+    }
+
+    fn trivia_kind(tok: &Tok) -> Option<TriviaKind> {
+        match tok {
+            Tok::Whitespace => Some(TriviaKind::Whitespace),
+            Tok::LineComment => Some(TriviaKind::LineComment),
+            Tok::BlockComment => Some(TriviaKind::BlockComment),
+            _ => None,
+        }
+    }
+
+    /// Collects the trivia up to the end of the current line.
+    fn take_trailing_trivia(&mut self) -> Result<Vec<Trivia>, ParseError> {
+        let mut trailing_trivia = vec![];
+        while let Some((_, Ok(tok))) = self.lexer.peek() {
+            let Some(kind) = Self::trivia_kind(tok) else {
+                break;
+            };
+            let (_, _, text) = self.next_trivia_or_tok()?.unwrap();
+            if let Some(newline_pos) = text.find('\n').filter(|_| kind == TriviaKind::Whitespace) {
+                if newline_pos > 0 {
+                    trailing_trivia.push(Trivia {
+                        kind,
+                        text: text[..newline_pos].to_string(),
+                    });
+                }
+                self.pending_trivia.push(Trivia {
+                    kind,
+                    text: text[newline_pos..].to_string(),
+                });
+                break;
+            }
+            trailing_trivia.push(Trivia { kind, text });
+        }
+        Ok(trailing_trivia)
+    }
+
+    fn next_trivia_and_tok(&mut self) -> Result<(Vec<Trivia>, Option<CstToken>), ParseError> {
+        let mut leading_trivia = std::mem::take(&mut self.pending_trivia);
+        while let Some((span, tok, text)) = self.next_trivia_or_tok()? {
+            if let Some(kind) = Self::trivia_kind(&tok) {
+                leading_trivia.push(Trivia { kind, text });
+                continue;
+            }
+            let trailing_trivia = self.take_trailing_trivia()?;
+            return Ok((
+                vec![],
+                Some(CstToken {
+                    leading_trivia,
+                    span,
+                    text,
+                    tok,
+                    trailing_trivia,
+                }),
+            ));
+        }
+        Ok((leading_trivia, None))
+    }
+
+    fn next_tok_or_err(&mut self) -> Result<CstToken, ParseError> {
+        self.next_trivia_and_tok()?.1.ok_or_else(|| ParseError {
+            span: None,
+            message: "Expected token, found end of input".to_string(),
+        })
+    }
+}
+
+pub(crate) fn parse(input: &str) -> Result<Cst, ParseError> {
+    let mut source = TokenSource::new(input, 0);
+    let first_tok = source.next_tok_or_err()?;
+    let root = parse_statement(&mut source, first_tok)?;
+    let (trailing_trivia, tok) = source.next_trivia_and_tok()?;
+    if let Some(tok) = tok {
+        return Err(ParseError {
+            span: Some(tok.span),
+            message: format!("Expected end of input, found {:?}", tok.tok),
+        });
+    }
+
+    Ok(Cst {
+        root,
+        trailing_trivia,
+    })
+}
+
+/// Parses a sequence of statements, like a part of a block, that starts at `column`
+/// of a line. Returns the trivia after the last statement too.
+pub(crate) fn parse_statements(
+    input: &str,
+    column: usize,
+) -> Result<(Vec<CstStatement>, Vec<Trivia>), ParseError> {
+    let mut source = TokenSource::new(input, column);
+    let mut statements = vec![];
+    loop {
+        let (trailing_trivia, tok) = source.next_trivia_and_tok()?;
+        let Some(tok) = tok else {
+            return Ok((statements, trailing_trivia));
+        };
+        statements.push(parse_statement(&mut source, tok)?);
+    }
+}
+
+fn parse_statement(
+    source: &mut TokenSource,
+    keyword: CstToken,
+) -> Result<CstStatement, ParseError> {
+    if !matches!(keyword.tok, Tok::UString(_)) {
+        return Err(ParseError {
+            span: Some(keyword.span),
+            message: "Expected keyword, found something else".to_string(),
+        });
+    }
+
+    let mut argument = vec![];
+    let mut argument_or_body = source.next_tok_or_err()?;
+    match argument_or_body.tok {
+        Tok::UString(_) => {
+            argument.push(argument_or_body);
+            argument_or_body = source.next_tok_or_err()?;
+        }
+        Tok::QString(_) => {
+            argument.push(argument_or_body);
+            loop {
+                argument_or_body = source.next_tok_or_err()?;
+                if let Tok::Plus = argument_or_body.tok {
+                    let next_tok = source.next_tok_or_err()?;
+                    if let Tok::QString(_) = next_tok.tok {
+                        argument.push(argument_or_body);
+                        argument.push(next_tok);
+                    } else {
+                        return Err(ParseError {
+                            span: Some(next_tok.span),
+                            message: "Expected string, found something else".to_string(),
+                        });
+                    }
+                } else {
+                    break;
+                }
+            }
+        }
+        _ => {}
+    }
+
+    let body = match argument_or_body.tok {
+        Tok::Semicolon => CstBody::Semicolon(argument_or_body),
+        Tok::LBrace => {
+            let mut statements = vec![];
+            let rbrace = loop {
+                let next_tok = source.next_tok_or_err()?;
+                if let Tok::RBrace = next_tok.tok {
+                    break next_tok;
+                }
+                statements.push(parse_statement(source, next_tok)?);
+            };
+            CstBody::Block {
+                lbrace: argument_or_body,
+                statements,
+                rbrace,
+            }
+        }
+        _ => {
+            return Err(ParseError {
+                span: Some(argument_or_body.span),
+                message: "Expected semicolon or left brace, found something else".to_string(),
+            })
+        }
+    };
+
+    Ok(CstStatement {
+        keyword,
+        argument,
+        body,
+    })
+}
+
+#[cfg(feature = "cli")]
+pub(crate) fn is_unquoted_safe(value: &str) -> bool {
+    let mut chars = value.chars();
+    matches!(chars.next(), Some(ch) if ch.is_ascii_alphanumeric() || ch == '_')
+        && chars.all(|ch| ch.is_ascii_alphanumeric() || matches!(ch, '_' | '.' | ':' | '-'))
+}
+
+#[cfg(feature = "cli")]
+pub(crate) fn quote_double(value: &str) -> String {
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+    for ch in value.chars() {
+        match ch {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\t' => quoted.push_str("\\t"),
+            ch => quoted.push(ch),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+impl CstStatement {
+    pub(crate) fn keyword(&self) -> &str {
+        match &self.keyword.tok {
+            Tok::UString(keyword) => keyword,
+            _ => unreachable!("keyword token is always an unquoted string"),
+        }
+    }
+
+    /// The argument value after unquoting and joining all concatenated pieces.
+    pub(crate) fn argument(&self) -> Option<String> {
+        if self.argument.is_empty() {
+            return None;
+        }
+        Some(
+            self.argument
+                .iter()
+                .filter_map(|token| match &token.tok {
+                    Tok::UString(value) | Tok::QString(value) => Some(value.as_str()),
+                    _ => None,
+                })
+                .collect(),
+        )
+    }
+
+    pub(crate) fn statements(&self) -> &[CstStatement] {
+        match &self.body {
+            CstBody::Semicolon(_) => &[],
+            CstBody::Block { statements, .. } => statements,
+        }
+    }
+
+    pub(crate) fn statements_mut(&mut self) -> Option<&mut Vec<CstStatement>> {
+        match &mut self.body {
+            CstBody::Semicolon(_) => None,
+            CstBody::Block { statements, .. } => Some(statements),
+        }
+    }
+
+    /// Lowers the statement into the [`Statement`] tree used by the model mapper.
+    pub(crate) fn to_statement(&self) -> Statement {
+        let argument_span = match (self.argument.first(), &self.body) {
+            (Some(first), _) => first.span,
+            (None, CstBody::Semicolon(semicolon)) => semicolon.span,
+            (None, CstBody::Block { lbrace, .. }) => lbrace.span,
+        };
+        Statement {
+            keyword_span: self.keyword.span,
+            keyword: self.keyword().to_string(),
+            argument_span,
+            argument: self.argument(),
+            statements: self
+                .statements()
+                .iter()
+                .map(|statement| statement.to_statement())
+                .collect(),
+        }
+    }
+
+    fn write_tokens(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.keyword)?;
+        for token in &self.argument {
+            write!(f, "{}", token)?;
+        }
+        match &self.body {
+            CstBody::Semicolon(semicolon) => write!(f, "{}", semicolon),
+            CstBody::Block {
+                lbrace,
+                statements,
+                rbrace,
+            } => {
+                write!(f, "{}", lbrace)?;
+                for statement in statements {
+                    statement.write_tokens(f)?;
+                }
+                write!(f, "{}", rbrace)
+            }
+        }
+    }
+}
+
+impl fmt::Display for Trivia {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+impl fmt::Display for CstToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for trivia in &self.leading_trivia {
+            write!(f, "{}", trivia)?;
+        }
+        f.write_str(&self.text)?;
+        for trivia in &self.trailing_trivia {
+            write!(f, "{}", trivia)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for CstStatement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write_tokens(f)
+    }
+}
+
+impl fmt::Display for Cst {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.root)?;
+        for trivia in &self.trailing_trivia {
+            write!(f, "{}", trivia)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INPUT: &str = r#"// Leading comment
+module acme-system {
+    namespace "http://acme.example.com/system";
+    prefix 'acme';   /* trailing
+                        block comment */
+
+    revision 2007-06-09 {
+        description
+            "Initial" +
+              ' revision.';
+    }
+
+    leaf host-name {
+        type string; // line comment
+    }
+}
+"#;
+
+    #[test]
+    fn test_roundtrip() {
+        let cst = parse(INPUT).unwrap();
+        assert_eq!(cst.to_string(), INPUT);
+    }
+
+    #[test]
+    fn test_lowering_matches_statement() {
+        let statement = parse(INPUT).unwrap().root.to_statement();
+        assert_eq!(statement.keyword, "module");
+        assert_eq!(statement.argument.as_deref(), Some("acme-system"));
+        assert_eq!(statement.statements.len(), 4);
+        let description = &statement.statements[2].statements[0];
+        assert_eq!(description.argument.as_deref(), Some("Initial revision."));
+    }
+}
@@ -12,6 +12,9 @@ pub enum Tok {
     Semicolon,
     LBrace,
     RBrace,
+    Whitespace,
+    LineComment,
+    BlockComment,
 }
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
@@ -24,6 +27,7 @@ pub enum LexicalError {
 pub struct Lexer<'input> {
     chars: Peekable<CharIndices<'input>>,
     line_loc: usize,
+    preserve_trivia: bool,
 }
 
 #[derive(Copy, Clone)]
@@ -46,7 +50,27 @@ impl LocatedChar {
 impl<'input> Lexer<'input> {
     pub fn new(input: &'input str) -> Self {
         let chars = input.char_indices().peekable();
-        Lexer { chars, line_loc: 0 }
+        Lexer {
+            chars,
+            line_loc: 0,
+            preserve_trivia: false,
+        }
+    }
+
+    /// Creates a lexer which also emits whitespace and comments as tokens, so the
+    /// input can be reconstructed from the token spans.
+    pub fn with_trivia(input: &'input str) -> Self {
+        Lexer {
+            preserve_trivia: true,
+            ..Lexer::new(input)
+        }
+    }
+
+    /// Sets the column the input starts at, for input taken from the middle of a
+    /// line. Continuation lines of quoted strings are unindented relative to it.
+    pub(crate) fn starting_at_column(mut self, column: usize) -> Self {
+        self.line_loc = column;
+        self
     }
 
     fn pop(&mut self) -> Option<LocatedChar> {
@@ -69,12 +93,40 @@ impl<'input> Lexer<'input> {
         self.chars.peek().map(|(loc, ch)| LocatedChar(*loc, *ch))
     }
 
-    fn consume_linecomment(&mut self) {
-        while let Some(ch) = self.pop() {
+    fn consume_linecomment(&mut self) -> Loc {
+        let mut end_loc = None;
+        while let Some(ch) = self.peek() {
             if ch.1 == '\n' {
                 break;
             }
+            self.pop();
+            end_loc = Some(ch.0 + ch.1.len_utf8());
         }
+        end_loc.unwrap_or(0)
+    }
+
+    fn consume_blockcomment(&mut self, starting_pos: Loc) -> SpannedTok {
+        let mut last_was_star = false;
+        while let Some(ch) = self.pop() {
+            if last_was_star && ch.ch() == '/' {
+                return ((starting_pos, ch.loc() + 1), Ok(Tok::BlockComment));
+            }
+            last_was_star = ch.ch() == '*';
+        }
+        let end_loc = self.peek().map(|ch| ch.loc()).unwrap_or(starting_pos + 2);
+        ((starting_pos, end_loc), Err(LexicalError::UnexpectedEOF))
+    }
+
+    fn consume_whitespace(&mut self, first_char: LocatedChar) -> SpannedTok {
+        let mut end_loc = first_char.loc() + first_char.ch().len_utf8();
+        while let Some(ch) = self.peek() {
+            if !ch.ch().is_whitespace() {
+                break;
+            }
+            self.pop();
+            end_loc = ch.loc() + ch.ch().len_utf8();
+        }
+        ((first_char.loc(), end_loc), Ok(Tok::Whitespace))
     }
 
     fn consume_escaped_char(&mut self) -> Result<char, LexicalError> {
@@ -136,11 +188,18 @@ impl<'input> Lexer<'input> {
                     );
                 }
                 '"' if is_double_quoted => {
+                    let line_count = string_content.split(|c| *c == '\n').count();
                     let trimmed_string_content = string_content
                         .split(|c| *c == '\n')
-                        .map(|s| {
+                        .enumerate()
+                        .map(|(line, s)| {
                             let s = String::from_iter(s);
-                            let s = s.trim_end();
+                            // Only whitespace in front of a line break is removed, and
+                            // only continuation lines are unindented.
+                            let s = if line + 1 < line_count { s.trim_end() } else { &s };
+                            if line == 0 {
+                                return s.to_string();
+                            }
                             let mut pos = 0;
                             let s = s.trim_start_matches(|c| {
                                 if pos >= first_indent {
@@ -188,14 +247,26 @@ impl<'input> Iterator for Lexer<'input> {
                 ch if ch.is_ascii_alphanumeric() || ch == '_' => {
                     return Some(self.consume_unquoted_string(next_char));
                 }
-                ch if ch.is_whitespace() => continue,
+                ch if ch.is_whitespace() => {
+                    let tok = self.consume_whitespace(next_char);
+                    if self.preserve_trivia {
+                        return Some(tok);
+                    }
+                    continue;
+                }
                 '/' => {
                     let loc = next_char.loc();
-                    let next_char = self.pop();
-                    if next_char.map(|c| c.ch()) != Some('/') {
-                        return Some(((loc, loc + 2), Err(LexicalError::IllegalToken)));
+                    let tok = match self.pop().map(|c| c.ch()) {
+                        Some('/') => {
+                            let end_loc = self.consume_linecomment().max(loc + 2);
+                            ((loc, end_loc), Ok(Tok::LineComment))
+                        }
+                        Some('*') => self.consume_blockcomment(loc),
+                        _ => return Some(((loc, loc + 2), Err(LexicalError::IllegalToken))),
+                    };
+                    if self.preserve_trivia || tok.1.is_err() {
+                        return Some(tok);
                     }
-                    self.consume_linecomment();
                     continue;
                 }
                 '"' => return Some(self.consume_quoted_string(next_char.loc(), true)),
@@ -245,6 +316,42 @@ mod tests {
         assert_tokens(input, vec![], Some(LexicalError::IllegalToken));
     }
 
+    #[test]
+    fn test_trivia() {
+        let input = "a /* b */ // c\n;";
+        let mut lexer = Lexer::with_trivia(input);
+        let toks = lexer
+            .by_ref()
+            .map(|(span, tok)| (span, tok.unwrap()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            toks,
+            vec![
+                ((0, 1), Tok::UString("a".to_string())),
+                ((1, 2), Tok::Whitespace),
+                ((2, 9), Tok::BlockComment),
+                ((9, 10), Tok::Whitespace),
+                ((10, 14), Tok::LineComment),
+                ((14, 15), Tok::Whitespace),
+                ((15, 16), Tok::Semicolon),
+            ]
+        );
+
+        assert_tokens(
+            input,
+            vec![
+                (0, Tok::UString("a".to_string()), 1),
+                (15, Tok::Semicolon, 16),
+            ],
+            None,
+        );
+        assert_tokens(
+            "a /* b",
+            vec![(0, Tok::UString("a".to_string()), 1)],
+            Some(LexicalError::UnexpectedEOF),
+        );
+    }
+
     #[test]
     fn test_valid_token() {
         let input = r#"
@@ -259,6 +366,7 @@ leaf host-name {
     test-singlequote
         'This is a single"
            \" quote string';
+    test-whitespace " leading and trailing ";
 }
         "#;
         assert_tokens(
@@ -293,7 +401,14 @@ leaf host-name {
                     260,
                 ),
                 (260, Tok::Semicolon, 261),
-                (262, Tok::RBrace, 263),
+                (266, Tok::UString("test-whitespace".to_string()), 281),
+                (
+                    282,
+                    Tok::QString(" leading and trailing ".to_string()),
+                    306,
+                ),
+                (306, Tok::Semicolon, 307),
+                (308, Tok::RBrace, 309),
             ],
             None,
         );
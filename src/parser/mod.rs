@@ -1,4 +1,6 @@
-mod lexer;
-mod parser;
+pub(crate) mod cst;
+pub(crate) mod lexer;
+pub(crate) mod parser;
 pub(crate) mod model;
-mod model_mapper;
\ No newline at end of file
+pub(crate) mod model_mapper;
+pub(crate) mod strict;
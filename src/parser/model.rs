@@ -1,10 +1,12 @@
-use crate::parser::model_mapper::{model, ArgumentMapper, Mapper};
+use crate::parser::model_mapper::{model, ArgumentMapper, ArgumentUnmapper, Mapper};
 use crate::parser::parser::Statement;
+use std::fmt;
 use std::str::FromStr;
 use crate::errors::ErrorContext;
 use crate::Span;
 
-#[derive(Debug)]
+/// The status of a definition, ordered from the most to the least current.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub enum Status {
     Current,
     Deprecated,
@@ -32,6 +34,35 @@ impl Mapper<Status> for Status {
     }
 }
 
+impl ArgumentUnmapper for Status {
+    fn unmap_argument(&self) -> String {
+        match self {
+            Status::Current => "current",
+            Status::Deprecated => "deprecated",
+            Status::Obsolete => "obsolete",
+        }
+        .to_string()
+    }
+}
+
+impl ArgumentUnmapper for bool {
+    fn unmap_argument(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl ArgumentUnmapper for u32 {
+    fn unmap_argument(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl ArgumentUnmapper for i32 {
+    fn unmap_argument(&self) -> String {
+        self.to_string()
+    }
+}
+
 impl Mapper<bool> for bool {
     fn map(statement: Statement, error_context: &mut ErrorContext) -> Result<bool, ()> {
         let argument = match statement.argument.as_ref() {
@@ -152,6 +183,142 @@ model! {
     }
 }
 
+/// The `key` of a list: the names of its key leaves, in order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Key(pub Vec<String>);
+
+impl Mapper<Key> for Key {
+    fn map(statement: Statement, error_context: &mut ErrorContext) -> Result<Key, ()> {
+        let argument = match statement.argument.as_ref() {
+            Some(argument) => argument,
+            None => {
+                error_context.add_error(statement.argument_span, "Expected key".to_string());
+                return Err(());
+            }
+        };
+        let names: Vec<String> = argument.split_whitespace().map(str::to_string).collect();
+        if names.is_empty() {
+            error_context.add_error(statement.argument_span, "Invalid key".to_string());
+            return Err(());
+        }
+        Ok(Key(names))
+    }
+}
+
+impl ArgumentUnmapper for Key {
+    fn unmap_argument(&self) -> String {
+        self.0.join(" ")
+    }
+}
+
+/// A `unique` constraint of a list: the descendant paths of the leaves whose
+/// values must be unique in combination among the entries.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Unique(pub Vec<String>);
+
+impl Mapper<Unique> for Unique {
+    fn map(statement: Statement, error_context: &mut ErrorContext) -> Result<Unique, ()> {
+        let argument = match statement.argument.as_ref() {
+            Some(argument) => argument,
+            None => {
+                error_context.add_error(statement.argument_span, "Expected unique".to_string());
+                return Err(());
+            }
+        };
+        let paths: Vec<String> = argument.split_whitespace().map(str::to_string).collect();
+        if paths.is_empty() {
+            error_context.add_error(statement.argument_span, "Invalid unique".to_string());
+            return Err(());
+        }
+        Ok(Unique(paths))
+    }
+}
+
+impl ArgumentUnmapper for Unique {
+    fn unmap_argument(&self) -> String {
+        self.0.join(" ")
+    }
+}
+
+/// The `max-elements` of a list or leaf-list.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MaxElements {
+    #[default]
+    Unbounded,
+    Value(u32),
+}
+
+impl Mapper<MaxElements> for MaxElements {
+    fn map(statement: Statement, error_context: &mut ErrorContext) -> Result<MaxElements, ()> {
+        let argument = match statement.argument.as_ref() {
+            Some(argument) => argument,
+            None => {
+                error_context
+                    .add_error(statement.argument_span, "Expected max-elements".to_string());
+                return Err(());
+            }
+        };
+        match argument.as_str() {
+            "unbounded" => Ok(MaxElements::Unbounded),
+            _ => match argument.parse() {
+                Ok(value) if value > 0 => Ok(MaxElements::Value(value)),
+                _ => {
+                    error_context
+                        .add_error(statement.argument_span, "Invalid max-elements".to_string());
+                    Err(())
+                }
+            },
+        }
+    }
+}
+
+impl ArgumentUnmapper for MaxElements {
+    fn unmap_argument(&self) -> String {
+        match self {
+            MaxElements::Unbounded => "unbounded".to_string(),
+            MaxElements::Value(value) => value.to_string(),
+        }
+    }
+}
+
+/// The `ordered-by` of a list or leaf-list.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OrderedBy {
+    #[default]
+    System,
+    User,
+}
+
+impl Mapper<OrderedBy> for OrderedBy {
+    fn map(statement: Statement, error_context: &mut ErrorContext) -> Result<OrderedBy, ()> {
+        let argument = match statement.argument.as_ref() {
+            Some(argument) => argument,
+            None => {
+                error_context.add_error(statement.argument_span, "Expected ordered-by".to_string());
+                return Err(());
+            }
+        };
+        match argument.as_str() {
+            "system" => Ok(OrderedBy::System),
+            "user" => Ok(OrderedBy::User),
+            _ => {
+                error_context.add_error(statement.argument_span, "Invalid ordered-by".to_string());
+                Err(())
+            }
+        }
+    }
+}
+
+impl ArgumentUnmapper for OrderedBy {
+    fn unmap_argument(&self) -> String {
+        match self {
+            OrderedBy::System => "system",
+            OrderedBy::User => "user",
+        }
+        .to_string()
+    }
+}
+
 #[derive(Debug, Copy, Clone)]
 pub enum LengthBoundary {
     Min,
@@ -174,6 +341,16 @@ impl FromStr for LengthBoundary {
     }
 }
 
+impl fmt::Display for LengthBoundary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LengthBoundary::Min => f.write_str("min"),
+            LengthBoundary::Max => f.write_str("max"),
+            LengthBoundary::Value(value) => write!(f, "{}", value),
+        }
+    }
+}
+
 #[derive(Debug, Copy, Clone)]
 pub enum RangeBoundary {
     Min,
@@ -198,13 +375,24 @@ impl FromStr for RangeBoundary {
     }
 }
 
-#[derive(Debug)]
+impl fmt::Display for RangeBoundary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RangeBoundary::Min => f.write_str("min"),
+            RangeBoundary::Max => f.write_str("max"),
+            RangeBoundary::Integer(value) => write!(f, "{}", value),
+            RangeBoundary::Decimal(value) => write!(f, "{:?}", value),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct LengthRangePatternPart<T> {
     pub lower_boundary: T,
     pub upper_boundary: Option<T>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct LengthRangePattern<T: FromStr<Err = String>>(pub Vec<LengthRangePatternPart<T>>);
 
 impl<T: FromStr<Err = String>> ArgumentMapper<LengthRangePattern<T>> for LengthRangePattern<T> {
@@ -256,6 +444,19 @@ impl<T: FromStr<Err = String>> ArgumentMapper<LengthRangePattern<T>> for LengthR
     }
 }
 
+impl<T: FromStr<Err = String> + fmt::Display> ArgumentUnmapper for LengthRangePattern<T> {
+    fn unmap_argument(&self) -> String {
+        self.0
+            .iter()
+            .map(|part| match &part.upper_boundary {
+                Some(upper_boundary) => format!("{}..{}", part.lower_boundary, upper_boundary),
+                None => part.lower_boundary.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join(" | ")
+    }
+}
+
 model! {
     "length", Length,
     length_expression: One<LengthRangePattern<LengthBoundary>>,
@@ -290,6 +491,12 @@ model! {
     }
 }
 
+model! {
+    "path", LeafrefPath,
+    expression: One<String>,
+    {}
+}
+
 model! {
     "type", Type,
     name: Option<String>,
@@ -300,20 +507,40 @@ model! {
         fraction_digits: Option<u32> => "fraction-digits",
         length: Option<Length>,
         range: Option<Range>,
-        path: Option<String>,
+        path: Option<LeafrefPath>,
         pattern: Vec<Pattern>,
         require_instance: Option<bool> => "require-instance",
         r#type: Vec<Type> => "type"
     }
 }
 
+model! {
+    "must", Must,
+    condition: One<String>,
+    {
+        description: Option<String>,
+        error_app_tag: Option<String> => "error-app-tag",
+        error_message: Option<String> => "error-message",
+        reference: Option<String>
+    }
+}
+
+model! {
+    "when", When,
+    condition: One<String>,
+    {
+        description: Option<String>,
+        reference: Option<String>
+    }
+}
+
 model! {
     "identity", Identity,
      name: One<String>,
     {
         base: Vec<String>,
         description: Option<String>,
-        if_feature: Vec<String>,
+        if_feature: Vec<String> => "if-feature",
         reference: Option<String>,
         status: Option<Status>
     }
@@ -327,7 +554,7 @@ model! {
         description: Option<String>,
         reference: Option<String>,
         status: Option<Status>,
-        r#type: One<Type>,
+        r#type: One<Type> => "type",
         units: Option<String>
     }
 }
@@ -362,12 +589,12 @@ model!(
         default: Option<String>,
         description: Option<String>,
         if_feature: Vec<String> => "if-feature",
-        must: Vec<String>,
+        must: Vec<Must>,
         reference: Option<String>,
         status: Option<Status>,
         r#type: One<Type> => "type",
         units: Option<String>,
-        when: Option<String>,
+        when: Option<When>,
         mandatory: Option<bool>
     }
 );
@@ -380,14 +607,15 @@ model!(
         default: Vec<String>,
         description: Option<String>,
         if_feature: Vec<String> => "if-feature",
-        must: Vec<String>,
+        must: Vec<Must>,
         reference: Option<String>,
         status: Option<Status>,
         r#type: One<Type> => "type",
         units: Option<String>,
-        when: Option<String>,
-        max_elements: Option<String>,
-        min_elements: Option<String>
+        when: Option<When>,
+        max_elements: Option<MaxElements> => "max-elements",
+        min_elements: Option<u32> => "min-elements",
+        ordered_by: Option<OrderedBy> => "ordered-by"
     }
 );
 
@@ -399,10 +627,10 @@ model!(
         description: Option<String>,
         if_feature: Vec<String> => "if-feature",
         mandatory: Option<bool>,
-        must: Vec<String>,
+        must: Vec<Must>,
         reference: Option<String>,
         status: Option<Status>,
-        when: Option<String>
+        when: Option<When>
     }
 );
 
@@ -417,7 +645,7 @@ model!(
         leaf: Vec<Leaf>,
         leaf_list: Vec<LeafList> => "leaf-list",
         list: Vec<List>,
-        must: Vec<String>,
+        must: Vec<Must>,
         typedef: Vec<Typedef>,
         uses: Vec<Uses>
     }
@@ -441,6 +669,23 @@ model!(
 model!(
     "refine", Refine,
     target: One<String>,
+    {
+        config: Option<bool>,
+        default: Vec<String>,
+        description: Option<String>,
+        if_feature: Vec<String> => "if-feature",
+        mandatory: Option<bool>,
+        max_elements: Option<MaxElements> => "max-elements",
+        min_elements: Option<u32> => "min-elements",
+        must: Vec<Must>,
+        presence: Option<String>,
+        reference: Option<String>
+    }
+);
+
+model!(
+    "augment", Augment,
+    target: One<String>,
     {
         action: Vec<ActionOrRpc>,
         anydata: Vec<AnyDataOrXml>,
@@ -457,19 +702,7 @@ model!(
         reference: Option<String>,
         status: Option<Status>,
         uses: Vec<Uses>,
-        when: Option<String>
-    }
-);
-
-model!(
-    "augment", Augment,
-    target: One<String>,
-    {
-        description: Option<String>,
-        if_feature: Vec<String> => "if-feature",
-        reference: Option<String>,
-        status: Option<Status>,
-        when: Option<String>
+        when: Option<When>
     }
 );
 
@@ -483,7 +716,7 @@ model!(
         reference: Option<String>,
         refine: Vec<Refine>,
         status: Option<Status>,
-        when: Option<String>
+        when: Option<When>
     }
 );
 
@@ -503,13 +736,13 @@ model!(
         leaf: Vec<Leaf>,
         leaf_list: Vec<LeafList> => "leaf-list",
         list: Vec<List>,
-        must: Vec<String>,
+        must: Vec<Must>,
         notification: Vec<Notification>,
         reference: Option<String>,
         status: Option<Status>,
         typedef: Vec<Typedef>,
         uses: Vec<Uses>,
-        when: Option<String>,
+        when: Option<When>,
         presence: Option<String>,
     }
 );
@@ -530,18 +763,18 @@ model!(
         leaf: Vec<Leaf>,
         leaf_list: Vec<LeafList> => "leaf-list",
         list: Vec<List>,
-        must: Vec<String>,
+        must: Vec<Must>,
         notification: Vec<Notification>,
         reference: Option<String>,
         status: Option<Status>,
         typedef: Vec<Typedef>,
         uses: Vec<Uses>,
-        when: Option<String>,
-        key: Option<String>,
-        max_elements: Option<String> => "max-elements",
-        min_elements: Option<String> => "min-elements",
-        ordered_by: Option<String>,
-        unique: Vec<String>
+        when: Option<When>,
+        key: Option<Key>,
+        max_elements: Option<MaxElements> => "max-elements",
+        min_elements: Option<u32> => "min-elements",
+        ordered_by: Option<OrderedBy> => "ordered-by",
+        unique: Vec<Unique>
     }
 );
 
@@ -559,7 +792,7 @@ model!(
         leaf: Vec<Leaf>,
         leaf_list: Vec<LeafList> => "leaf-list",
         list: Vec<List>,
-        must: Vec<String>,
+        must: Vec<Must>,
         reference: Option<String>,
         status: Option<Status>,
         typedef: Vec<Typedef>,
@@ -569,6 +802,7 @@ model!(
 
 model!(
     "choice", Choice,
+    name: One<String>,
     {
         anydata: Vec<AnyDataOrXml>,
         anyxml: Vec<AnyDataOrXml>,
@@ -578,19 +812,20 @@ model!(
         container: Vec<Container>,
         default: Option<String>,
         description: Option<String>,
-        if_feature: Vec<String>,
+        if_feature: Vec<String> => "if-feature",
         leaf: Vec<Leaf>,
-        leaf_list: Vec<LeafList>,
+        leaf_list: Vec<LeafList> => "leaf-list",
         list: Vec<List>,
         mandatory: Option<bool>,
         reference: Option<String>,
         status: Option<Status>,
-        when: Option<String>
+        when: Option<When>
     }
 );
 
 model!(
     "case", Case,
+    name: One<String>,
     {
         anydata: Vec<AnyDataOrXml>,
         anyxml: Vec<AnyDataOrXml>,
@@ -605,7 +840,7 @@ model!(
         reference: Option<String>,
         status: Option<Status>,
         uses: Vec<Uses>,
-        when: Option<String>
+        when: Option<When>
     }
 );
 
@@ -619,7 +854,7 @@ model!(
     }
 );
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum DeviateAspect {
     NotSupported,
     Add,
@@ -646,6 +881,18 @@ impl ArgumentMapper<DeviateAspect> for DeviateAspect {
     }
 }
 
+impl ArgumentUnmapper for DeviateAspect {
+    fn unmap_argument(&self) -> String {
+        match self {
+            DeviateAspect::NotSupported => "not-supported",
+            DeviateAspect::Add => "add",
+            DeviateAspect::Replace => "replace",
+            DeviateAspect::Delete => "delete",
+        }
+        .to_string()
+    }
+}
+
 model! {
     "deviate", Deviate,
     aspect: One<DeviateAspect>,
@@ -653,15 +900,45 @@ model! {
         config: Option<bool>,
         default: Vec<String>,
         mandatory: Option<bool>,
-        max_elements: Option<String> => "max-elements",
-        min_elements: Option<String> => "min-elements",
-        must: Vec<String>,
+        max_elements: Option<MaxElements> => "max-elements",
+        min_elements: Option<u32> => "min-elements",
+        must: Vec<Must>,
         r#type: Option<Type> => "type",
-        unique: Vec<bool>,
+        unique: Vec<Unique>,
         units: Option<String>
     }
 }
 
+model!(
+    "feature", Feature,
+    name: One<String>,
+    {
+        description: Option<String>,
+        if_feature: Vec<String> => "if-feature",
+        reference: Option<String>,
+        status: Option<Status>
+    }
+);
+
+model!(
+    "argument", Argument,
+    name: One<String>,
+    {
+        yin_element: Option<bool> => "yin-element"
+    }
+);
+
+model!(
+    "extension", Extension,
+    name: One<String>,
+    {
+        argument: Option<Argument>,
+        description: Option<String>,
+        reference: Option<String>,
+        status: Option<Status>
+    }
+);
+
 model!(
     "module", Module,
     name: One<String>,
@@ -686,6 +963,8 @@ model!(
         choice: Vec<Choice>,
         container: Vec<Container>,
         deviation: Vec<Deviation>,
+        extension: Vec<Extension>,
+        feature: Vec<Feature>,
         grouping: Vec<Grouping>,
         identity: Vec<Identity>,
         leaf: Vec<Leaf>,
@@ -720,6 +999,8 @@ model!(
         choice: Vec<Choice>,
         container: Vec<Container>,
         deviation: Vec<Deviation>,
+        extension: Vec<Extension>,
+        feature: Vec<Feature>,
         grouping: Vec<Grouping>,
         identity: Vec<Identity>,
         leaf: Vec<Leaf>,
@@ -732,6 +1013,27 @@ model!(
     }
 );
 
+/// Keywords of the substatements accepted by the statement `keyword`, as mapped by
+/// its model struct. `None` for statements without substatements of their own.
+pub(crate) fn substatements(keyword: &str) -> Option<&'static [&'static str]> {
+    macro_rules! find {
+        ($($model:ident),*) => {
+            $(
+                if $model::is_keyword(keyword) {
+                    return Some($model::SUBSTATEMENTS);
+                }
+            )*
+        };
+    }
+    find!(
+        Import, Include, Revision, BelongsTo, Bit, Enum, Length, Pattern, Range, LeafrefPath, Type,
+        Must, When, Identity, Typedef, Grouping, Leaf, LeafList, AnyDataOrXml, InOutput,
+        ActionOrRpc, Refine, Augment, Uses, Container, List, Notification, Choice, Case, Deviation,
+        Deviate, Feature, Argument, Extension, Module, SubModule
+    );
+    None
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -795,4 +1097,56 @@ mod tests {
         error_context.print(input);
         print!("{:?}", module.unwrap());
     }
+
+    #[test]
+    fn test_must_and_when() {
+        let input = r#"module example {
+            namespace "urn:example";
+            prefix ex;
+            container interface {
+                must "mtu >= 68" {
+                    description "Minimum MTU of IPv4";
+                    error-message "MTU too small";
+                    error-app-tag "mtu-too-small";
+                    reference "RFC 791";
+                }
+                must "name";
+                when "../enabled = 'true'" {
+                    description "Only enabled interfaces";
+                }
+            }
+        }"#;
+        let mut error_context = ErrorContext::new();
+        let module = Module::map(parse(input).unwrap(), &mut error_context).unwrap();
+        assert_eq!(error_context.messages(), Vec::<&str>::new());
+        let container = &module.container[0];
+
+        let must = &container.must[0];
+        assert_eq!(must.condition, "mtu >= 68");
+        assert_eq!(must.description.as_deref(), Some("Minimum MTU of IPv4"));
+        assert_eq!(must.error_message.as_deref(), Some("MTU too small"));
+        assert_eq!(must.error_app_tag.as_deref(), Some("mtu-too-small"));
+        assert_eq!(must.reference.as_deref(), Some("RFC 791"));
+        assert_eq!(
+            &input[must.argument_span.0..must.argument_span.1],
+            "\"mtu >= 68\""
+        );
+        assert_eq!(container.must[1].error_message, None);
+
+        let when = container.when.as_ref().unwrap();
+        assert_eq!(when.condition, "../enabled = 'true'");
+        assert_eq!(when.description.as_deref(), Some("Only enabled interfaces"));
+        assert_eq!(when.reference, None);
+    }
+
+    #[test]
+    fn test_substatements() {
+        let leaf = substatements("leaf").unwrap();
+        assert!(leaf.contains(&"type") && leaf.contains(&"if-feature"));
+        assert!(!leaf.contains(&"leaf"));
+        assert!(substatements("container").unwrap().contains(&"leaf-list"));
+        assert!(substatements("anyxml").unwrap().contains(&"mandatory"));
+        assert!(substatements("rpc").unwrap().contains(&"input"));
+        assert_eq!(substatements("ex:annotation"), None);
+    }
 }
@@ -1,6 +1,6 @@
 use crate::errors::ErrorContext;
 use crate::parser::parser::Statement;
-use crate::Span;
+use crate::{Loc, Span};
 
 pub(crate) type Result<T> = std::result::Result<T, ()>;
 
@@ -16,6 +16,64 @@ pub(crate) trait ArgumentMapper<T> {
     ) -> Result<T>;
 }
 
+/// Converts a model value back into the [`Statement`] it was mapped from, so it can
+/// be printed as YANG again.
+#[cfg(feature = "cli")]
+pub(crate) trait Unmapper {
+    fn unmap(&self, keyword: &str) -> Statement;
+}
+
+pub(crate) trait ArgumentUnmapper {
+    fn unmap_argument(&self) -> String;
+}
+
+/// Moves the spans of a model value at or after `from` by `delta`, after an edit
+/// of the source it was mapped from.
+pub(crate) trait ShiftSpans {
+    fn shift_spans(&mut self, from: Loc, delta: isize);
+}
+
+pub(crate) fn shift_span(span: &mut Span, from: Loc, delta: isize) {
+    if span.0 >= from {
+        *span = (
+            span.0.wrapping_add_signed(delta),
+            span.1.wrapping_add_signed(delta),
+        );
+    }
+}
+
+#[cfg(feature = "cli")]
+pub(crate) fn unmapped_statement(
+    keyword: &str,
+    argument: Option<String>,
+    statements: Vec<Statement>,
+) -> Statement {
+    Statement {
+        keyword_span: (0, 0),
+        keyword: keyword.to_string(),
+        argument_span: (0, 0),
+        argument,
+        statements,
+    }
+}
+
+impl ArgumentUnmapper for String {
+    fn unmap_argument(&self) -> String {
+        self.clone()
+    }
+}
+
+#[cfg(feature = "cli")]
+impl<T: ArgumentUnmapper> Unmapper for T {
+    fn unmap(&self, keyword: &str) -> Statement {
+        unmapped_statement(keyword, Some(self.unmap_argument()), vec![])
+    }
+}
+
+impl<T: ArgumentUnmapper> ShiftSpans for T {
+    fn shift_spans(&mut self, _from: Loc, _delta: isize) {}
+}
+
 impl ArgumentMapper<String> for String {
     fn map_argument(
         argument: String,
@@ -50,6 +108,22 @@ macro_rules! prioritize_name {
     };
 }
 
+#[cfg(feature = "cli")]
+macro_rules! unmap_argument {
+    ($self:ident) => {
+        None
+    };
+    ($self:ident, $argument_ident:ident, one $argument_type:ty) => {
+        Some($crate::parser::model_mapper::ArgumentUnmapper::unmap_argument(&$self.$argument_ident))
+    };
+    ($self:ident, $argument_ident:ident, optional $argument_type:ty) => {
+        $self
+            .$argument_ident
+            .as_ref()
+            .map($crate::parser::model_mapper::ArgumentUnmapper::unmap_argument)
+    };
+}
+
 macro_rules! model {
     ($keyword:pat, $struc:ident,
     $($argument_ident:ident : $(One<$argument_type_one:ty>)? $(Option<$argument_type_optional:ty>)?,)?
@@ -60,8 +134,13 @@ macro_rules! model {
         $(,)?
     }
     ) => {
-         #[derive(Debug)]
+         // Some fields are only read when printing the model back as YANG.
+         #[derive(Debug, Clone)]
+         #[cfg_attr(not(feature = "cli"), allow(dead_code))]
          pub struct $struc {
+            /// Span of the keyword of the statement this was mapped from.
+            pub span: $crate::Span,
+            pub argument_span: $crate::Span,
             $(
                 pub $argument_ident: $($argument_type_one)? $(Option<$argument_type_optional>)?,
             )?
@@ -77,6 +156,17 @@ macro_rules! model {
                 )?
             )*
          }
+        impl $struc {
+            /// Keywords of the substatements the statement accepts.
+            pub(crate) const SUBSTATEMENTS: &'static [&'static str] = &[
+                $($crate::parser::model_mapper::prioritize_name!($($attribute_name,)? $attribute_ident)),*
+            ];
+
+            /// Whether `keyword` is a keyword of the statement.
+            pub(crate) fn is_keyword(keyword: &str) -> bool {
+                matches!(keyword, $keyword)
+            }
+        }
         impl $crate::parser::model_mapper::Mapper<$struc> for $struc {
             fn map(statement: $crate::parser::parser::Statement, error_context: &mut ErrorContext) -> $crate::parser::model_mapper::Result<$struc> {
                 if !matches!(statement.keyword.as_str(), $keyword) {
@@ -175,6 +265,14 @@ macro_rules! model {
                     )*
                         _ => {
                             error_context.add_warning(statement.keyword_span, format!("Unexpected keyword {}", statement.keyword));
+                            if !statement.keyword.contains(':') {
+                                error_context.suggest_closest(
+                                    &statement.keyword,
+                                    $struc::SUBSTATEMENTS.iter().copied(),
+                                    statement.keyword_span,
+                                    str::to_string,
+                                );
+                            }
                         }
                     }
                 }
@@ -208,6 +306,8 @@ macro_rules! model {
 
 
                 let inst = $struc {
+                    span: statement.keyword_span,
+                    argument_span: statement.argument_span,
                     $(
                         $argument_ident,
                     )?
@@ -219,8 +319,73 @@ macro_rules! model {
                 Ok(inst)
             }
         }
+
+        #[cfg(feature = "cli")]
+        impl $crate::parser::model_mapper::Unmapper for $struc {
+            fn unmap(&self, keyword: &str) -> $crate::parser::parser::Statement {
+                let argument = $crate::parser::model_mapper::unmap_argument!(
+                    self
+                    $(
+                        , $argument_ident
+                        $(, one $argument_type_one)?
+                        $(, optional $argument_type_optional)?
+                    )?
+                );
+
+                #[allow(unused_mut)]
+                let mut statements = Vec::new();
+                $(
+                    let _att_name = $crate::parser::model_mapper::prioritize_name!($($attribute_name,)? $attribute_ident);
+                    $(
+                        let _dummy: Option<$attribute_type_one>;
+                        statements.push($crate::parser::model_mapper::Unmapper::unmap(&self.$attribute_ident, _att_name));
+                    )?
+                    $(
+                        let _dummy: Option<$attribute_type_optional>;
+                        if let Some(att) = self.$attribute_ident.as_ref() {
+                            statements.push($crate::parser::model_mapper::Unmapper::unmap(att, _att_name));
+                        }
+                    )?
+                    $(
+                        let _dummy: Option<$attribute_type_multiple>;
+                        for att in self.$attribute_ident.iter() {
+                            statements.push($crate::parser::model_mapper::Unmapper::unmap(att, _att_name));
+                        }
+                    )?
+                )*
+
+                $crate::parser::model_mapper::unmapped_statement(keyword, argument, statements)
+            }
+        }
+
+        impl $crate::parser::model_mapper::ShiftSpans for $struc {
+            fn shift_spans(&mut self, from: $crate::Loc, delta: isize) {
+                $crate::parser::model_mapper::shift_span(&mut self.span, from, delta);
+                $crate::parser::model_mapper::shift_span(&mut self.argument_span, from, delta);
+                $(
+                    $(
+                        let _dummy: Option<$attribute_type_one>;
+                        $crate::parser::model_mapper::ShiftSpans::shift_spans(&mut self.$attribute_ident, from, delta);
+                    )?
+                    $(
+                        let _dummy: Option<$attribute_type_optional>;
+                        if let Some(att) = self.$attribute_ident.as_mut() {
+                            $crate::parser::model_mapper::ShiftSpans::shift_spans(att, from, delta);
+                        }
+                    )?
+                    $(
+                        let _dummy: Option<$attribute_type_multiple>;
+                        for att in self.$attribute_ident.iter_mut() {
+                            $crate::parser::model_mapper::ShiftSpans::shift_spans(att, from, delta);
+                        }
+                    )?
+                )*
+            }
+        }
     };
 }
 
 pub(crate) use model;
 pub(crate) use prioritize_name;
+#[cfg(feature = "cli")]
+pub(crate) use unmap_argument;
@@ -0,0 +1,282 @@
+//! Strict checks of a module against the YANG grammar of RFC 7950, section 14, that
+//! the [`model`](crate::parser::model) mapper leaves out: the order of the module
+//! sections, the header arguments and unknown keywords.
+
+use crate::errors::ErrorContext;
+use crate::parser::model::substatements;
+use crate::parser::parser::Statement;
+use crate::yin::is_yang_keyword;
+use miette::{MietteDiagnostic, Severity};
+use regex::Regex;
+
+const SECTION_NAMES: [&str; 5] = ["header", "linkage", "meta", "revision", "body"];
+
+/// The section of a module or submodule body the statement `keyword` belongs to,
+/// an index of [`SECTION_NAMES`]. Formatting separates the sections by blank lines.
+pub(crate) fn module_section(keyword: &str) -> usize {
+    match keyword {
+        "yang-version" | "namespace" | "belongs-to" | "prefix" => 0,
+        "import" | "include" => 1,
+        "organization" | "contact" | "description" | "reference" => 2,
+        "revision" => 3,
+        _ => 4,
+    }
+}
+
+/// Whether `date` is a valid `YYYY-MM-DD` date.
+fn is_valid_date(date: &str) -> bool {
+    let parts: Vec<&str> = date.split('-').collect();
+    let [year, month, day] = parts[..] else {
+        return false;
+    };
+    if year.len() != 4 || month.len() != 2 || day.len() != 2 {
+        return false;
+    }
+    let (Ok(year), Ok(month), Ok(day)) = (
+        year.parse::<u32>(),
+        month.parse::<u32>(),
+        day.parse::<u32>(),
+    ) else {
+        return false;
+    };
+    let leap_year = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+    let days = match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        2 if leap_year => 29,
+        2 => 28,
+        _ => return false,
+    };
+    (1..=days).contains(&day)
+}
+
+/// Whether `uri` is an absolute URI as of RFC 3986, section 3.
+fn is_valid_uri(uri: &str) -> bool {
+    let regex = Regex::new(
+        r"^[A-Za-z][A-Za-z0-9+.-]*:(?:[A-Za-z0-9\-._~:/?#\[\]@!$&'()*+,;=]|%[0-9A-Fa-f]{2})*$",
+    )
+    .unwrap();
+    regex.is_match(uri)
+}
+
+struct StrictChecker<'e> {
+    error_context: &'e mut ErrorContext,
+    error_occured: bool,
+}
+
+impl StrictChecker<'_> {
+    fn add_error(&mut self, span: crate::Span, message: String) {
+        self.error_context.add_error(span, message);
+        self.error_occured = true;
+    }
+
+    /// Checks that the statements of a module or submodule are ordered by section.
+    fn check_sections(&mut self, module: &Statement) {
+        let mut last: Option<(usize, &Statement)> = None;
+        let mut last_revision: Option<&str> = None;
+        for statement in &module.statements {
+            let section = module_section(&statement.keyword);
+            match last {
+                Some((last_section, last_statement)) if section < last_section => {
+                    self.add_error(
+                        statement.keyword_span,
+                        format!(
+                            "Statement {} of the {} section must come before {} of the {} section",
+                            statement.keyword,
+                            SECTION_NAMES[section],
+                            last_statement.keyword,
+                            SECTION_NAMES[last_section]
+                        ),
+                    );
+                }
+                Some((last_section, _)) if section == last_section => {}
+                _ => last = Some((section, statement)),
+            }
+
+            let argument = statement.argument.as_deref().unwrap_or_default();
+            match statement.keyword.as_str() {
+                "yang-version" if !matches!(argument, "1" | "1.1") => self.add_error(
+                    statement.argument_span,
+                    format!("Invalid yang-version {}, expected 1 or 1.1", argument),
+                ),
+                "namespace" if !is_valid_uri(argument) => self.add_error(
+                    statement.argument_span,
+                    format!("Invalid namespace URI {}", argument),
+                ),
+                "revision" if is_valid_date(argument) => {
+                    match last_revision {
+                        Some(last_revision) if argument == last_revision => self.add_error(
+                            statement.argument_span,
+                            format!("Revision {} is given twice", argument),
+                        ),
+                        Some(last_revision) if argument > last_revision => self.add_error(
+                            statement.argument_span,
+                            format!(
+                                "Revision {} must come before revision {}",
+                                argument, last_revision
+                            ),
+                        ),
+                        _ => {}
+                    }
+                    last_revision = Some(argument);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Checks `statement`, a substatement of a statement accepting the `allowed`
+    /// keywords.
+    fn check_statement(&mut self, statement: &Statement, allowed: &[&str]) {
+        if statement.keyword.contains(':') {
+            // The substatements of extensions are not defined by YANG.
+            return;
+        }
+        if !is_yang_keyword(&statement.keyword) {
+            self.add_error(
+                statement.keyword_span,
+                format!("Unknown keyword {}", statement.keyword),
+            );
+            self.error_context.suggest_closest(
+                &statement.keyword,
+                allowed.iter().copied(),
+                statement.keyword_span,
+                str::to_string,
+            );
+            return;
+        }
+        if matches!(statement.keyword.as_str(), "revision" | "revision-date") {
+            let argument = statement.argument.as_deref().unwrap_or_default();
+            if !is_valid_date(argument) {
+                self.add_error(
+                    statement.argument_span,
+                    format!(
+                        "Invalid {} {}, expected YYYY-MM-DD",
+                        statement.keyword, argument
+                    ),
+                );
+            }
+        }
+        let allowed = substatements(&statement.keyword).unwrap_or_default();
+        for substatement in &statement.statements {
+            self.check_statement(substatement, allowed);
+        }
+    }
+}
+
+/// Checks the module or submodule `statement` in strict mode.
+pub(crate) fn check_strict(
+    statement: &Statement,
+    error_context: &mut ErrorContext,
+) -> Result<(), ()> {
+    let mut checker = StrictChecker {
+        error_context,
+        error_occured: false,
+    };
+    checker.check_sections(statement);
+    checker.check_statement(statement, &["module", "submodule"]);
+    if checker.error_occured {
+        return Err(());
+    }
+    Ok(())
+}
+
+/// Removes the warnings of mapping a module in `error_context` about keywords the
+/// strict checks reported in `strict` already, which share their span.
+pub(crate) fn remove_reported(strict: &ErrorContext, error_context: &mut ErrorContext) {
+    let span = |diagnostic: &MietteDiagnostic| {
+        let label = diagnostic.labels.as_ref()?.first()?;
+        Some((label.offset(), label.len()))
+    };
+    let reported: Vec<_> = strict
+        .diagnostics()
+        .iter()
+        .filter(|diagnostic| diagnostic.severity == Some(Severity::Error))
+        .filter_map(span)
+        .collect();
+    error_context.retain(|diagnostic| {
+        diagnostic.severity != Some(Severity::Warning)
+            || !span(diagnostic).is_some_and(|span| reported.contains(&span))
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parser::parse;
+
+    fn check(input: &str) -> Vec<String> {
+        let mut error_context = ErrorContext::new();
+        let result = check_strict(&parse(input).unwrap(), &mut error_context);
+        assert_eq!(result.is_err(), error_context.has_errors());
+        error_context.sort_errors();
+        error_context
+            .messages()
+            .into_iter()
+            .map(str::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn test_valid() {
+        let input = r#"module example {
+            yang-version 1.1;
+            namespace "urn:ietf:params:xml:ns:yang:example";
+            prefix ex;
+            import other { prefix o; revision-date 2020-02-29; }
+            organization "Example";
+            description "An example module.";
+            revision 2024-01-31;
+            revision 2023-12-01 { description "Initial revision."; }
+            o:annotation value { anything goes; }
+            container system { leaf name { type string; } }
+        }"#;
+        assert_eq!(check(input), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_errors() {
+        let input = r#"module example {
+            yang-version 2;
+            prefix ex;
+            organization "Example";
+            namespace "not a uri";
+            revision 2023-12-01;
+            revision 2024-01-31;
+            revision 2024-01-31;
+            revision 2023-02-29;
+            container system {
+                leaf name { type string; colour blue; }
+            }
+            import other { prefix o; revision-date 2020-13-01; }
+        }"#;
+        assert_eq!(
+            check(input),
+            [
+                "Invalid yang-version 2, expected 1 or 1.1",
+                "Statement namespace of the header section must come before organization of the meta section",
+                "Invalid namespace URI not a uri",
+                "Revision 2024-01-31 must come before revision 2023-12-01",
+                "Revision 2024-01-31 is given twice",
+                "Invalid revision 2023-02-29, expected YYYY-MM-DD",
+                "Unknown keyword colour",
+                "Statement import of the linkage section must come before container of the body section",
+                "Invalid revision-date 2020-13-01, expected YYYY-MM-DD",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_suggestions() {
+        let input = r#"module example {
+            namespace "urn:example";
+            prefix ex;
+            leaf name { type string { lenght "1..64"; } }
+        }"#;
+        let mut error_context = ErrorContext::new();
+        assert!(check_strict(&parse(input).unwrap(), &mut error_context).is_err());
+        let diagnostic = &error_context.diagnostics()[0];
+        assert_eq!(diagnostic.message, "Unknown keyword lenght");
+        assert_eq!(diagnostic.help.as_deref(), Some("Did you mean length?"));
+    }
+}
@@ -0,0 +1,446 @@
+//! Loading of modules together with the modules they import and the submodules
+//! they include, from a list of search paths.
+
+use crate::errors::ErrorContext;
+use crate::parser::model::{Import, Module, SubModule};
+use crate::parser::model_mapper::Mapper;
+use crate::parser::parser::{parse, ParseError, Statement};
+use crate::parser::strict::{check_strict, remove_reported};
+use crate::yin::{parse_yin, YinContext};
+use crate::Span;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone)]
+pub(crate) enum ModuleKind {
+    Module(Module),
+    SubModule(SubModule),
+}
+
+#[derive(Debug)]
+pub(crate) struct LoadedModule {
+    pub(crate) name: String,
+    pub(crate) path: PathBuf,
+    pub(crate) source: String,
+    pub(crate) kind: ModuleKind,
+    /// Warnings reported while loading the module.
+    pub(crate) error_context: ErrorContext,
+}
+
+#[derive(Debug)]
+pub(crate) enum LoadError {
+    Io(PathBuf, io::Error),
+    /// The module at the path is invalid, the error context holds its errors.
+    Invalid {
+        path: PathBuf,
+        source: String,
+        error_context: ErrorContext,
+    },
+}
+
+/// Why the dependencies of a module could not be loaded.
+#[derive(Debug)]
+pub(crate) enum DependencyError {
+    /// No file was found for the module imported or the submodule included at
+    /// the span.
+    NotFound(String, Span),
+    Load(LoadError),
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct Repository {
+    search_paths: Vec<PathBuf>,
+    /// Whether modules are checked in strict mode, see [`check_strict`].
+    strict: bool,
+    pub(crate) modules: Vec<LoadedModule>,
+}
+
+fn parse_file(path: &Path, source: &str) -> Result<Statement, ParseError> {
+    if path.extension().is_some_and(|extension| extension == "yin") {
+        parse_yin(source, &YinContext::default())
+    } else {
+        parse(source)
+    }
+}
+
+/// Finds `name.yang`, `name.yin` or `name@revision.yang` in `directory`, preferring
+/// `revision` if given and the latest revision otherwise.
+fn find_in_directory(directory: &Path, name: &str, revision: Option<&str>) -> Option<PathBuf> {
+    if let Some(revision) = revision {
+        for extension in ["yang", "yin"] {
+            let path = directory.join(format!("{}@{}.{}", name, revision, extension));
+            if path.is_file() {
+                return Some(path);
+            }
+        }
+    }
+    for extension in ["yang", "yin"] {
+        let path = directory.join(format!("{}.{}", name, extension));
+        if path.is_file() {
+            return Some(path);
+        }
+    }
+
+    let prefix = format!("{}@", name);
+    let mut revisions: Vec<PathBuf> = fs::read_dir(directory)
+        .ok()?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.file_name()
+                .and_then(|file_name| file_name.to_str())
+                .is_some_and(|file_name| {
+                    file_name.starts_with(&prefix)
+                        && (file_name.ends_with(".yang") || file_name.ends_with(".yin"))
+                })
+        })
+        .collect();
+    revisions.sort();
+    revisions.pop()
+}
+
+impl Repository {
+    pub(crate) fn new(search_paths: Vec<PathBuf>) -> Self {
+        Self {
+            search_paths,
+            strict: false,
+            modules: vec![],
+        }
+    }
+
+    /// Enables or disables strict mode, which reports violations of the statement
+    /// order and header rules of the YANG grammar and unknown keywords as errors.
+    pub(crate) fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    pub(crate) fn is_strict(&self) -> bool {
+        self.strict
+    }
+
+    pub(crate) fn get(&self, name: &str) -> Option<&LoadedModule> {
+        self.modules.iter().find(|module| module.name == name)
+    }
+
+    /// Paths of all loaded modules.
+    pub(crate) fn paths(&self) -> impl Iterator<Item = &Path> {
+        self.modules.iter().map(|module| module.path.as_path())
+    }
+
+    /// Loads the module at `path` and, transitively, the modules it imports and
+    /// the submodules it includes. Returns the index of the module. If loading
+    /// fails, none of the modules loaded with it are kept.
+    pub(crate) fn load(&mut self, path: &Path) -> Result<usize, LoadError> {
+        if let Some(index) = self.modules.iter().position(|module| module.path == path) {
+            return Ok(index);
+        }
+        let source =
+            fs::read_to_string(path).map_err(|error| LoadError::Io(path.to_path_buf(), error))?;
+        self.load_source(path, source)
+    }
+
+    /// Loads the module at `path` from `source` instead of reading the file, like
+    /// an editor buffer with unsaved changes. Its dependencies are read from disk.
+    pub(crate) fn load_source(&mut self, path: &Path, source: String) -> Result<usize, LoadError> {
+        let mut error_context = ErrorContext::new();
+        let invalid = |source: String, error_context: ErrorContext| LoadError::Invalid {
+            path: path.to_path_buf(),
+            source,
+            error_context,
+        };
+
+        let statement = match parse_file(path, &source) {
+            Ok(statement) => statement,
+            Err(error) => {
+                error_context.add_error(error.span.unwrap_or((0, 0)), error.message);
+                return Err(invalid(source, error_context));
+            }
+        };
+        // Strict errors are reported together with the mapping errors, instead of
+        // the mapping warnings about the same keywords.
+        let mut strict_context = ErrorContext::new();
+        if self.strict {
+            let _ = check_strict(&statement, &mut strict_context);
+        }
+        let kind = if statement.keyword == "submodule" {
+            SubModule::map(statement, &mut error_context).map(ModuleKind::SubModule)
+        } else {
+            Module::map(statement, &mut error_context).map(ModuleKind::Module)
+        };
+        remove_reported(&strict_context, &mut error_context);
+        strict_context.extend(&error_context);
+        error_context = strict_context;
+        let kind = match kind {
+            Ok(kind) if !error_context.has_errors() => kind,
+            _ => return Err(invalid(source, error_context)),
+        };
+
+        let name = match &kind {
+            ModuleKind::Module(module) => module.name.clone(),
+            ModuleKind::SubModule(submodule) => submodule.name.clone(),
+        };
+        // The module is registered before its dependencies, so cyclic references to
+        // it are found, and removed together with them on an error.
+        let index = self.modules.len();
+        self.modules.push(LoadedModule {
+            name,
+            path: path.to_path_buf(),
+            source,
+            kind,
+            error_context,
+        });
+
+        let kind = self.modules[index].kind.clone();
+        match self.load_dependencies(path, &kind) {
+            Ok(()) => Ok(index),
+            Err(DependencyError::Load(error)) => {
+                self.modules.truncate(index);
+                Err(error)
+            }
+            Err(DependencyError::NotFound(name, span)) => {
+                let module = self
+                    .modules
+                    .drain(index..)
+                    .next()
+                    .expect("The module is registered");
+                let mut error_context = module.error_context;
+                error_context.add_error(span, format!("Module {} not found", name));
+                Err(LoadError::Invalid {
+                    path: module.path,
+                    source: module.source,
+                    error_context,
+                })
+            }
+        }
+    }
+
+    /// Loads the modules the module or submodule `kind` at `path` imports and the
+    /// submodules it includes, which are searched next to it too. Dependencies
+    /// loaded before the error are kept.
+    pub(crate) fn load_dependencies(
+        &mut self,
+        path: &Path,
+        kind: &ModuleKind,
+    ) -> Result<(), DependencyError> {
+        let (import, include) = match kind {
+            ModuleKind::Module(module) => (&module.import, &module.include),
+            ModuleKind::SubModule(submodule) => (&submodule.import, &submodule.include),
+        };
+        let dependencies = import
+            .iter()
+            .map(|import| (&import.module, &import.revision_date, import.argument_span))
+            .chain(include.iter().map(|include| {
+                (
+                    &include.submodule,
+                    &include.revision_date,
+                    include.argument_span,
+                )
+            }));
+        for (name, revision, span) in dependencies {
+            if self.get(name).is_some() {
+                continue;
+            }
+            let found = self
+                .search_paths
+                .iter()
+                .map(PathBuf::as_path)
+                .chain(path.parent())
+                .find_map(|directory| find_in_directory(directory, name, revision.as_deref()));
+            let Some(dependency_path) = found else {
+                return Err(DependencyError::NotFound(name.clone(), span));
+            };
+            self.load(&dependency_path).map_err(DependencyError::Load)?;
+        }
+        Ok(())
+    }
+
+    /// The loaded module or submodule `name`, with all its statements located at
+    /// `span`, so definitions of another file can be used in diagnostics of a
+    /// module that refers to them.
+    fn relocated(&self, name: &str, span: Span) -> Option<ModuleKind> {
+        fn relocate(statement: &mut Statement, span: Span) {
+            statement.keyword_span = span;
+            statement.argument_span = span;
+            for statement in &mut statement.statements {
+                relocate(statement, span);
+            }
+        }
+
+        let module = self.get(name)?;
+        let mut statement = parse_file(&module.path, &module.source).ok()?;
+        relocate(&mut statement, span);
+        // The module was loaded without errors, so mapping it again succeeds.
+        let mut error_context = ErrorContext::new();
+        if statement.keyword == "submodule" {
+            SubModule::map(statement, &mut error_context)
+                .ok()
+                .map(ModuleKind::SubModule)
+        } else {
+            Module::map(statement, &mut error_context)
+                .ok()
+                .map(ModuleKind::Module)
+        }
+    }
+
+    /// `module` with the definitions of the submodules it includes, transitively,
+    /// added. They are located at the `include` statements of `module`, or at
+    /// `span` if given.
+    pub(crate) fn with_submodules(&self, mut module: Module, span: Option<Span>) -> Module {
+        let mut included = HashSet::new();
+        let mut pending: VecDeque<(String, Span)> = module
+            .include
+            .iter()
+            .map(|include| {
+                (
+                    include.submodule.clone(),
+                    span.unwrap_or(include.argument_span),
+                )
+            })
+            .collect();
+        while let Some((name, include_span)) = pending.pop_front() {
+            if !included.insert(name.clone()) {
+                continue;
+            }
+            let Some(ModuleKind::SubModule(submodule)) = self.relocated(&name, include_span) else {
+                continue;
+            };
+            pending.extend(
+                submodule
+                    .include
+                    .iter()
+                    .map(|include| (include.submodule.clone(), include_span)),
+            );
+            merge_submodule(&mut module, submodule);
+        }
+        module
+    }
+
+    /// The modules `module` imports, transitively, by name, each with its
+    /// submodules. Their definitions are located at the `import` statement of
+    /// `module` they are reached through.
+    pub(crate) fn imported_modules(&self, module: &Module) -> HashMap<String, Module> {
+        let mut modules = HashMap::new();
+        let mut pending: VecDeque<(String, Span)> = module
+            .import
+            .iter()
+            .map(|import| (import.module.clone(), import.argument_span))
+            .collect();
+        while let Some((name, span)) = pending.pop_front() {
+            if name == module.name || modules.contains_key(&name) {
+                continue;
+            }
+            let Some(ModuleKind::Module(imported)) = self.relocated(&name, span) else {
+                continue;
+            };
+            let imported = self.with_submodules(imported, Some(span));
+            pending.extend(
+                imported
+                    .import
+                    .iter()
+                    .map(|import| (import.module.clone(), span)),
+            );
+            modules.insert(name, imported);
+        }
+        modules
+    }
+}
+
+/// Adds the definitions and imports of `submodule` to `module`. The prefix the
+/// submodule uses for the module it belongs to is imported as the module itself.
+fn merge_submodule(module: &mut Module, submodule: SubModule) {
+    macro_rules! extend {
+        ($($field:ident),*) => {
+            $(module.$field.extend(submodule.$field);)*
+        };
+    }
+
+    if submodule.belongs_to.prefix != module.prefix {
+        module.import.push(Import {
+            span: submodule.belongs_to.span,
+            argument_span: submodule.belongs_to.argument_span,
+            module: module.name.clone(),
+            prefix: submodule.belongs_to.prefix.clone(),
+            revision_date: None,
+            description: None,
+            reference: None,
+        });
+    }
+    extend!(
+        import,
+        anydata,
+        anyxml,
+        augment,
+        choice,
+        container,
+        deviation,
+        extension,
+        feature,
+        grouping,
+        identity,
+        leaf,
+        leaf_list,
+        list,
+        notification,
+        rpc,
+        typedef,
+        uses
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn corpus() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/corpus")
+    }
+
+    #[test]
+    fn test_load() {
+        let directory = std::env::temp_dir().join("yang-utils-repository-test");
+        fs::create_dir_all(&directory).unwrap();
+        fs::write(
+            directory.join("importing.yang"),
+            "module importing { namespace urn:importing; prefix i; \
+             import acme-system { prefix acme; } }",
+        )
+        .unwrap();
+
+        let mut repository = Repository::new(vec![corpus()]);
+        let index = repository.load(&directory.join("importing.yang")).unwrap();
+        assert_eq!(repository.modules[index].name, "importing");
+        assert!(repository.get("acme-system").is_some());
+        assert_eq!(repository.paths().count(), 2);
+    }
+
+    #[test]
+    fn test_missing_import() {
+        let directory = std::env::temp_dir().join("yang-utils-repository-test-missing");
+        fs::create_dir_all(&directory).unwrap();
+        fs::write(
+            directory.join("importing.yang"),
+            "module importing { namespace urn:importing; prefix i; \
+             import acme-system { prefix acme; } import missing { prefix m; } }",
+        )
+        .unwrap();
+        fs::write(
+            directory.join("indirect.yang"),
+            "module indirect { namespace urn:indirect; prefix n; \
+             import importing { prefix i; } }",
+        )
+        .unwrap();
+
+        let mut repository = Repository::new(vec![corpus()]);
+        let Err(LoadError::Invalid { error_context, .. }) =
+            repository.load(&directory.join("importing.yang"))
+        else {
+            panic!("expected missing module error");
+        };
+        assert_eq!(error_context.messages(), ["Module missing not found"]);
+        assert!(repository.modules.is_empty());
+        assert!(repository.load(&directory.join("indirect.yang")).is_err());
+        assert!(repository.modules.is_empty());
+    }
+}
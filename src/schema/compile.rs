@@ -0,0 +1,2257 @@
+use crate::errors::ErrorContext;
+use crate::parser::model::{
+    ActionOrRpc, AnyDataOrXml, Augment, Case, Choice, Container, Grouping, InOutput, Leaf,
+    LeafList, List, Module, Notification, Refine, Status, Type, Typedef, Uses,
+};
+use crate::parser::model_mapper::ArgumentUnmapper;
+use crate::repository::Repository;
+use crate::schema::namespace::check_namespaces;
+use crate::schema::{
+    evaluate_if_feature, find_mandatory, find_node, find_node_mut, parse_schema_path, Schema,
+    SchemaAugment, SchemaIdentity, SchemaNode, SchemaNodeKind, SchemaType, SchemaWhen,
+};
+use crate::typing::{check_type, BUILT_IN_TYPE_NAMES};
+use crate::xpath::check_schema;
+use crate::{Loc, Span};
+use std::collections::HashMap;
+
+/// The data definition statements and scoped definitions of a statement.
+#[derive(Default)]
+struct DataDefinitions<'a> {
+    action: &'a [ActionOrRpc],
+    anydata: &'a [AnyDataOrXml],
+    anyxml: &'a [AnyDataOrXml],
+    case: &'a [Case],
+    choice: &'a [Choice],
+    container: &'a [Container],
+    leaf: &'a [Leaf],
+    leaf_list: &'a [LeafList],
+    list: &'a [List],
+    notification: &'a [Notification],
+    uses: &'a [Uses],
+    grouping: &'a [Grouping],
+    typedef: &'a [Typedef],
+}
+
+macro_rules! data_definitions {
+    ($model:expr, [$($field:ident),*]) => {
+        DataDefinitions {
+            $($field: &$model.$field,)*
+            ..DataDefinitions::default()
+        }
+    };
+}
+
+/// Groupings and typedefs visible at a point of the module, innermost first.
+#[derive(Clone, Copy)]
+pub(crate) struct Scope<'a> {
+    pub(super) parent: Option<&'a Scope<'a>>,
+    pub(super) grouping: &'a [Grouping],
+    pub(super) typedef: &'a [Typedef],
+}
+
+impl<'a> Scope<'a> {
+    /// Finds the grouping `name` and the scope it is defined in.
+    pub(super) fn find_grouping(&'a self, name: &str) -> Option<(&'a Grouping, &'a Scope<'a>)> {
+        match self.grouping.iter().find(|grouping| grouping.name == name) {
+            Some(grouping) => Some((grouping, self)),
+            None => self.parent?.find_grouping(name),
+        }
+    }
+
+    /// Finds the typedef `name` and the scope it is defined in.
+    pub(super) fn find_typedef(&'a self, name: &str) -> Option<(&'a Typedef, &'a Scope<'a>)> {
+        match self.typedef.iter().find(|typedef| typedef.name == name) {
+            Some(typedef) => Some((typedef, self)),
+            None => self.parent?.find_typedef(name),
+        }
+    }
+
+    /// Names of the groupings visible in the scope.
+    pub(super) fn grouping_names(&self) -> Vec<&'a str> {
+        let mut names: Vec<&str> = self
+            .grouping
+            .iter()
+            .map(|grouping| grouping.name.as_str())
+            .collect();
+        names.extend(self.parent.map(Scope::grouping_names).unwrap_or_default());
+        names
+    }
+
+    /// Names of the typedefs visible in the scope.
+    pub(super) fn typedef_names(&self) -> Vec<&'a str> {
+        let mut names: Vec<&str> = self
+            .typedef
+            .iter()
+            .map(|typedef| typedef.name.as_str())
+            .collect();
+        names.extend(self.parent.map(Scope::typedef_names).unwrap_or_default());
+        names
+    }
+}
+
+/// Suggests the candidate closest to the unknown `identifier` for the last
+/// diagnostic, keeping the prefix of the identifier.
+pub(super) fn suggest_identifier(
+    identifier: &str,
+    span: Span,
+    candidates: Vec<&str>,
+    error_context: &mut ErrorContext,
+) {
+    let (prefix, name) = match identifier.split_once(':') {
+        Some((prefix, name)) => (Some(prefix), name),
+        None => (None, identifier),
+    };
+    error_context.suggest_closest(name, candidates, span, |closest| match prefix {
+        Some(prefix) => format!("{}:{}", prefix, closest),
+        None => closest.to_string(),
+    });
+}
+
+/// Suggests the typedef visible in `scope` or the built-in type closest to the
+/// unknown type `identifier`.
+pub(super) fn suggest_type(
+    identifier: &str,
+    span: Span,
+    scope: &Scope,
+    error_context: &mut ErrorContext,
+) {
+    let mut candidates = scope.typedef_names();
+    if !identifier.contains(':') {
+        candidates.extend(BUILT_IN_TYPE_NAMES.iter().copied());
+    }
+    suggest_identifier(identifier, span, candidates, error_context);
+}
+
+/// The module the definitions being compiled are in, whose prefixes their
+/// references are resolved with: the compiled module or one it imports.
+#[derive(Clone)]
+struct ModuleContext {
+    name: String,
+    prefix: String,
+    /// Names of the imported modules by their prefix.
+    imports: HashMap<String, String>,
+}
+
+impl ModuleContext {
+    fn new(module: &Module) -> Self {
+        Self {
+            name: module.name.clone(),
+            prefix: module.prefix.clone(),
+            imports: module
+                .import
+                .iter()
+                .map(|import| (import.prefix.clone(), import.module.clone()))
+                .collect(),
+        }
+    }
+
+    /// The name of the module `prefix` refers to, if any.
+    fn module_name(&self, prefix: &str) -> Option<&str> {
+        if prefix == self.prefix {
+            Some(&self.name)
+        } else {
+            self.imports.get(prefix).map(String::as_str)
+        }
+    }
+}
+
+/// A module imported by the compiled module, with the submodules it includes.
+struct ImportedModule<'m> {
+    context: ModuleContext,
+    /// Its top-level groupings and typedefs.
+    scope: Scope<'m>,
+}
+
+struct Compiler<'e> {
+    /// Name of the compiled module.
+    module_name: String,
+    context: ModuleContext,
+    /// The modules imported by the compiled module, directly or not, by name.
+    imported: &'e HashMap<String, ImportedModule<'e>>,
+    error_context: &'e mut ErrorContext,
+    error_occured: bool,
+    /// Groupings currently being expanded, to detect recursive `uses`.
+    expanding: Vec<*const Grouping>,
+    /// Whether the module is of YANG version 1.1 rather than 1.0.
+    yang_1_1: bool,
+    /// The effective status of the definition being compiled: its own status, or the
+    /// one of an enclosing definition if that is less current.
+    status: Status,
+    identity_status: HashMap<String, Status>,
+    feature_status: HashMap<String, Status>,
+    /// The effective status of the definitions enclosing each grouping of the
+    /// scopes entered, which the grouping is compiled with.
+    grouping_status: HashMap<*const Grouping, Status>,
+    /// The nodes of the groupings compiled, which each `uses` copies.
+    groupings: HashMap<*const Grouping, Vec<SchemaNode>>,
+    /// Typedefs whose union members are being compiled, to stop at recursive unions.
+    deriving: Vec<*const Typedef>,
+}
+
+fn node(name: &str, kind: SchemaNodeKind, span: Span) -> SchemaNode {
+    SchemaNode {
+        name: name.to_string(),
+        kind,
+        config: None,
+        effective_config: None,
+        status: Status::Current,
+        if_feature: vec![],
+        when: vec![],
+        must: vec![],
+        description: None,
+        span,
+        module: None,
+        children: vec![],
+    }
+}
+
+/// Wraps a data node placed directly in a choice into its implicit case of the
+/// same name, as in RFC 7950, section 7.9.2.
+fn shorthand_case(child: SchemaNode) -> SchemaNode {
+    match child.kind {
+        SchemaNodeKind::Case => child,
+        _ => {
+            let mut case = node(&child.name, SchemaNodeKind::Case, child.span);
+            case.children.push(child);
+            case
+        }
+    }
+}
+
+/// Adds the nodes of an `augment` to its target.
+pub(super) fn augment_target(target: &mut SchemaNode, nodes: Vec<SchemaNode>) {
+    match target.kind {
+        SchemaNodeKind::Choice { .. } => target
+            .children
+            .extend(nodes.into_iter().map(shorthand_case)),
+        _ => target.children.extend(nodes),
+    }
+}
+
+/// Collects the nodes sharing the identifier namespace of the children of a
+/// node, looking through choices and cases.
+fn namespace_nodes<'n>(nodes: &'n [SchemaNode], collected: &mut Vec<&'n SchemaNode>) {
+    for node in nodes {
+        if !matches!(node.kind, SchemaNodeKind::Case) {
+            collected.push(node);
+        }
+        if matches!(
+            node.kind,
+            SchemaNodeKind::Choice { .. } | SchemaNodeKind::Case
+        ) {
+            namespace_nodes(&node.children, collected);
+        }
+    }
+}
+
+impl<'e> Compiler<'e> {
+    fn add_error(&mut self, span: Span, message: String) {
+        self.error_context.add_error(span, message);
+        self.error_occured = true;
+    }
+
+    fn add_conflict_error(&mut self, span: Span, message: String, previous: Span) {
+        self.error_context
+            .add_conflict_error(span, message, previous);
+        self.error_occured = true;
+    }
+
+    /// Strips the prefix of an identifier referring to a definition of this module.
+    fn local_name<'n>(&mut self, identifier: &'n str, span: Span) -> Option<&'n str> {
+        match identifier.split_once(':') {
+            None => Some(identifier),
+            Some((prefix, name)) if prefix == self.context.prefix => Some(name),
+            Some((prefix, _)) => {
+                self.add_error(
+                    span,
+                    format!(
+                        "Definitions of module with prefix {} are not available",
+                        prefix
+                    ),
+                );
+                None
+            }
+        }
+    }
+
+    /// The name of `identifier` if it refers to a definition of the compiled module.
+    fn own_name<'n>(&self, identifier: &'n str) -> Option<&'n str> {
+        if self.context.name != self.module_name {
+            return None;
+        }
+        match identifier.split_once(':') {
+            None => Some(identifier),
+            Some((prefix, name)) => (prefix == self.context.prefix).then_some(name),
+        }
+    }
+
+    /// Resolves the prefix of `identifier` in the current module context to the
+    /// name it refers to and the imported module it is defined in, `None` for the
+    /// module of the context. Fails with the prefix if it refers to no module
+    /// available.
+    fn resolve<'n>(
+        &self,
+        identifier: &'n str,
+    ) -> Result<(Option<&'e ImportedModule<'e>>, &'n str), &'n str> {
+        let Some((prefix, name)) = identifier.split_once(':') else {
+            return Ok((None, identifier));
+        };
+        match self.context.module_name(prefix) {
+            Some(module) if module == self.context.name => Ok((None, name)),
+            Some(module) => match self.imported.get(module) {
+                Some(imported) => Ok((Some(imported), name)),
+                None => Err(prefix),
+            },
+            None => Err(prefix),
+        }
+    }
+
+    /// Diagnoses a reference at `span` from a definition of status `referrer` to the
+    /// `kind` definition `name` of status `status`. As of RFC 7950, section 7.21.2,
+    /// a current definition MUST NOT reference a deprecated or obsolete one, nor a
+    /// deprecated definition an obsolete one, so either is an error.
+    fn check_reference_status(
+        &mut self,
+        referrer: Status,
+        kind: &str,
+        name: &str,
+        status: Status,
+        span: Span,
+    ) {
+        if status <= referrer {
+            return;
+        }
+        self.add_error(
+            span,
+            format!(
+                "{} {} is {}, but referenced by a {} definition",
+                kind,
+                name,
+                status.unmap_argument(),
+                referrer.unmap_argument()
+            ),
+        );
+    }
+
+    fn check_identity_status(&mut self, identity: &str, span: Span) {
+        let status = self
+            .own_name(identity)
+            .and_then(|name| self.identity_status.get(name));
+        if let Some(&status) = status {
+            self.check_reference_status(self.status, "Identity", identity, status, span);
+        }
+    }
+
+    /// Enters a definition with the `status` and `if-feature` statements given,
+    /// checking the status of the features it refers to. Returns the effective
+    /// status of the enclosing definition, to be restored when leaving it.
+    fn enter_definition(
+        &mut self,
+        status: Option<Status>,
+        if_feature: &[String],
+        span: Span,
+    ) -> Status {
+        let enclosing = self.status;
+        self.status = self.status.max(status.unwrap_or(Status::Current));
+        let features = if_feature.iter().flat_map(|expression| {
+            expression
+                .split(|c: char| c.is_whitespace() || c == '(' || c == ')')
+                .filter(|token| !matches!(*token, "" | "and" | "or" | "not"))
+        });
+        for feature in features {
+            let status = self
+                .own_name(feature)
+                .and_then(|name| self.feature_status.get(name));
+            if let Some(&status) = status {
+                self.check_reference_status(self.status, "Feature", feature, status, span);
+            }
+        }
+        enclosing
+    }
+
+    /// Checks the status of the typedefs and identities `r#type` and the member
+    /// types of a union refer to.
+    fn check_type_status(&mut self, r#type: &Type, scope: &Scope) {
+        let identifier = r#type.name.as_deref().unwrap_or_default();
+        let typedef = self
+            .own_name(identifier)
+            .and_then(|name| scope.find_typedef(name));
+        if let Some((typedef, _)) = typedef {
+            let status = typedef.status.unwrap_or(Status::Current);
+            self.check_reference_status(
+                self.status,
+                "Typedef",
+                identifier,
+                status,
+                r#type.argument_span,
+            );
+        }
+        if let Some(base) = &r#type.base {
+            self.check_identity_status(base, r#type.argument_span);
+        }
+        for member in &r#type.r#type {
+            self.check_type_status(member, scope);
+        }
+    }
+
+    /// Collects the typedefs `r#type` derives from, following them into imported
+    /// modules, with the scope and module context of the last one. Types of modules
+    /// that are not available are left unresolved. Only the reference of `r#type`
+    /// itself is diagnosed, if `report` is set, those of the typedefs are where
+    /// they are defined, and the chain ends before a typedef is repeated.
+    fn typedef_chain<'s>(
+        &mut self,
+        r#type: &'s Type,
+        scope: &'s Scope<'s>,
+        report: bool,
+    ) -> (Vec<&'s Typedef>, &'s Scope<'s>, ModuleContext)
+    where
+        'e: 's,
+    {
+        let context = self.context.clone();
+        let mut typedefs: Vec<&Typedef> = vec![];
+        let mut current = r#type;
+        let mut scope = scope;
+        loop {
+            let name = current.name.as_deref().unwrap_or_default();
+            if BUILT_IN_TYPE_NAMES.contains(&name) {
+                break;
+            }
+            let Ok((imported, name)) = self.resolve(name) else {
+                break;
+            };
+            if let Some(imported) = imported {
+                self.context = imported.context.clone();
+                scope = &imported.scope;
+            }
+            let Some((typedef, typedef_scope)) = scope.find_typedef(name) else {
+                if report && typedefs.is_empty() {
+                    self.add_error(current.argument_span, format!("Type {} not found", name));
+                    let identifier = current.name.as_deref().unwrap_or_default();
+                    suggest_type(identifier, current.argument_span, scope, self.error_context);
+                }
+                break;
+            };
+            if typedefs.iter().any(|seen| std::ptr::eq(*seen, typedef)) {
+                break;
+            }
+            typedefs.push(typedef);
+            current = &typedef.r#type;
+            scope = typedef_scope;
+        }
+        let base_context = std::mem::replace(&mut self.context, context);
+        (typedefs, scope, base_context)
+    }
+
+    /// The schema type of `r#type`, with the member types of the union it derives
+    /// from compiled in the scope of the union. References of members of typedefs
+    /// are diagnosed where the typedefs are defined.
+    fn schema_type(&mut self, r#type: &Type, scope: &Scope, report: bool) -> SchemaType {
+        let (typedefs, base_scope, base_context) = self.typedef_chain(r#type, scope, report);
+        let base = typedefs.last().map_or(r#type, |typedef| &typedef.r#type);
+        let mut members = vec![];
+        // A union whose members derive from its own typedef has no members.
+        if !typedefs
+            .iter()
+            .any(|typedef| self.deriving.contains(&(*typedef as *const Typedef)))
+        {
+            let enclosing = self.deriving.len();
+            self.deriving
+                .extend(typedefs.iter().map(|typedef| *typedef as *const Typedef));
+            let context = std::mem::replace(&mut self.context, base_context);
+            let report = report && typedefs.is_empty();
+            for member in &base.r#type {
+                members.push(self.schema_type(member, base_scope, report));
+            }
+            self.context = context;
+            self.deriving.truncate(enclosing);
+        }
+        SchemaType {
+            r#type: r#type.clone(),
+            typedefs: typedefs.into_iter().cloned().collect(),
+            members,
+            target: None,
+        }
+    }
+
+    /// Checks the restrictions of `schema_type` itself, see [`check_type`].
+    fn check_restrictions(&mut self, schema_type: &SchemaType) {
+        if check_type(schema_type, self.error_context).is_err() {
+            self.error_occured = true;
+        }
+    }
+
+    fn compile_type(&mut self, r#type: &Type, scope: &Scope) -> SchemaType {
+        self.check_type_status(r#type, scope);
+        let schema_type = self.schema_type(r#type, scope, true);
+        self.check_restrictions(&schema_type);
+        schema_type
+    }
+
+    /// Compiles the typedefs and groupings of `scope` itself where they are defined,
+    /// so their diagnostics, like those about the status of their references, are
+    /// reported once, whether and wherever they are used.
+    fn compile_definitions(&mut self, scope: &Scope) {
+        for typedef in scope.typedef {
+            let enclosing = self.enter_definition(typedef.status, &[], typedef.span);
+            self.check_type_status(&typedef.r#type, scope);
+            let (chain, _, _) = self.typedef_chain(&typedef.r#type, scope, false);
+            let schema_type = self.schema_type(&typedef.r#type, scope, true);
+            if chain.iter().any(|derived| std::ptr::eq(*derived, typedef)) {
+                self.add_error(
+                    typedef.r#type.argument_span,
+                    format!("Typedef {} derives from itself", typedef.name),
+                );
+            } else {
+                self.check_restrictions(&schema_type);
+            }
+            self.status = enclosing;
+        }
+        for grouping in scope.grouping {
+            self.grouping_status.insert(grouping, self.status);
+        }
+        for grouping in scope.grouping {
+            self.compile_grouping(grouping, scope);
+        }
+    }
+
+    /// The nodes of `grouping`, defined in `scope`, compiled the first time they
+    /// are needed with the status of the definitions enclosing the grouping.
+    fn compile_grouping(&mut self, grouping: &Grouping, scope: &Scope) -> Vec<SchemaNode> {
+        if let Some(nodes) = self.groupings.get(&(grouping as *const Grouping)) {
+            return nodes.clone();
+        }
+        let enclosing = self.grouping_status.get(&(grouping as *const Grouping));
+        let status = std::mem::replace(
+            &mut self.status,
+            enclosing.copied().unwrap_or(Status::Current),
+        );
+        self.enter_definition(grouping.status, &[], grouping.span);
+        self.expanding.push(grouping);
+        let nodes = self.compile_data_definitions(
+            data_definitions!(
+                grouping,
+                [
+                    action,
+                    anydata,
+                    anyxml,
+                    choice,
+                    container,
+                    leaf,
+                    leaf_list,
+                    list,
+                    notification,
+                    uses,
+                    grouping,
+                    typedef
+                ]
+            ),
+            scope,
+        );
+        self.expanding.pop();
+        self.status = status;
+        self.groupings.insert(grouping, nodes.clone());
+        nodes
+    }
+
+    fn compile_data_definitions(
+        &mut self,
+        definitions: DataDefinitions,
+        scope: &Scope,
+    ) -> Vec<SchemaNode> {
+        let scope = Scope {
+            parent: Some(scope),
+            grouping: definitions.grouping,
+            typedef: definitions.typedef,
+        };
+        self.compile_definitions(&scope);
+        let mut nodes: Vec<(Loc, Vec<SchemaNode>)> = vec![];
+        for container in definitions.container {
+            nodes.push((
+                container.span.0,
+                vec![self.compile_container(container, &scope)],
+            ));
+        }
+        for leaf in definitions.leaf {
+            nodes.push((leaf.span.0, vec![self.compile_leaf(leaf, &scope)]));
+        }
+        for leaf_list in definitions.leaf_list {
+            nodes.push((
+                leaf_list.span.0,
+                vec![self.compile_leaf_list(leaf_list, &scope)],
+            ));
+        }
+        for list in definitions.list {
+            nodes.push((list.span.0, vec![self.compile_list(list, &scope)]));
+        }
+        for choice in definitions.choice {
+            nodes.push((choice.span.0, vec![self.compile_choice(choice, &scope)]));
+        }
+        for case in definitions.case {
+            nodes.push((case.span.0, vec![self.compile_case(case, &scope)]));
+        }
+        for anydata in definitions.anydata {
+            let kind = SchemaNodeKind::AnyData {
+                mandatory: anydata.mandatory.unwrap_or(false),
+            };
+            nodes.push((anydata.span.0, vec![self.compile_anydata(anydata, kind)]));
+        }
+        for anyxml in definitions.anyxml {
+            let kind = SchemaNodeKind::AnyXml {
+                mandatory: anyxml.mandatory.unwrap_or(false),
+            };
+            nodes.push((anyxml.span.0, vec![self.compile_anydata(anyxml, kind)]));
+        }
+        for action in definitions.action {
+            let node = self.compile_action_or_rpc(action, SchemaNodeKind::Action, &scope);
+            nodes.push((action.span.0, vec![node]));
+        }
+        for notification in definitions.notification {
+            nodes.push((
+                notification.span.0,
+                vec![self.compile_notification(notification, &scope)],
+            ));
+        }
+        for uses in definitions.uses {
+            nodes.push((uses.span.0, self.expand_uses(uses, &scope)));
+        }
+        nodes.sort_by_key(|(loc, _)| *loc);
+        nodes.into_iter().flat_map(|(_, nodes)| nodes).collect()
+    }
+
+    fn compile_container(&mut self, container: &Container, scope: &Scope) -> SchemaNode {
+        let enclosing =
+            self.enter_definition(container.status, &container.if_feature, container.span);
+        let mut node = node(
+            &container.name,
+            SchemaNodeKind::Container {
+                presence: container.presence.clone(),
+            },
+            container.span,
+        );
+        node.config = container.config;
+        node.status = self.status;
+        node.if_feature = container.if_feature.clone();
+        node.when = container
+            .when
+            .iter()
+            .cloned()
+            .map(SchemaWhen::own)
+            .collect();
+        node.must = container.must.clone();
+        node.description = container.description.clone();
+        node.children = self.compile_data_definitions(
+            data_definitions!(
+                container,
+                [
+                    action,
+                    anydata,
+                    anyxml,
+                    choice,
+                    container,
+                    leaf,
+                    leaf_list,
+                    list,
+                    notification,
+                    uses,
+                    grouping,
+                    typedef
+                ]
+            ),
+            scope,
+        );
+        self.status = enclosing;
+        node
+    }
+
+    fn compile_leaf(&mut self, leaf: &Leaf, scope: &Scope) -> SchemaNode {
+        let enclosing = self.enter_definition(leaf.status, &leaf.if_feature, leaf.span);
+        let mut node = node(
+            &leaf.name,
+            SchemaNodeKind::Leaf {
+                r#type: self.compile_type(&leaf.r#type, scope),
+                default: leaf.default.clone(),
+                mandatory: leaf.mandatory.unwrap_or(false),
+                units: leaf.units.clone(),
+            },
+            leaf.span,
+        );
+        node.config = leaf.config;
+        node.status = self.status;
+        node.if_feature = leaf.if_feature.clone();
+        node.when = leaf.when.iter().cloned().map(SchemaWhen::own).collect();
+        node.must = leaf.must.clone();
+        node.description = leaf.description.clone();
+        self.status = enclosing;
+        node
+    }
+
+    fn compile_leaf_list(&mut self, leaf_list: &LeafList, scope: &Scope) -> SchemaNode {
+        let enclosing =
+            self.enter_definition(leaf_list.status, &leaf_list.if_feature, leaf_list.span);
+        let mut node = node(
+            &leaf_list.name,
+            SchemaNodeKind::LeafList {
+                r#type: self.compile_type(&leaf_list.r#type, scope),
+                default: leaf_list.default.clone(),
+                units: leaf_list.units.clone(),
+                min_elements: leaf_list.min_elements.unwrap_or_default(),
+                max_elements: leaf_list.max_elements.unwrap_or_default(),
+                ordered_by: leaf_list.ordered_by.unwrap_or_default(),
+            },
+            leaf_list.span,
+        );
+        node.config = leaf_list.config;
+        node.status = self.status;
+        node.if_feature = leaf_list.if_feature.clone();
+        node.when = leaf_list
+            .when
+            .iter()
+            .cloned()
+            .map(SchemaWhen::own)
+            .collect();
+        node.must = leaf_list.must.clone();
+        node.description = leaf_list.description.clone();
+        self.status = enclosing;
+        node
+    }
+
+    fn compile_list(&mut self, list: &List, scope: &Scope) -> SchemaNode {
+        let enclosing = self.enter_definition(list.status, &list.if_feature, list.span);
+        let key = list
+            .key
+            .iter()
+            .flat_map(|key| &key.0)
+            .filter_map(|key| self.local_name(key, list.argument_span))
+            .map(str::to_string)
+            .collect();
+        let mut node = node(
+            &list.name,
+            SchemaNodeKind::List {
+                key,
+                unique: list.unique.clone(),
+                min_elements: list.min_elements.unwrap_or_default(),
+                max_elements: list.max_elements.unwrap_or_default(),
+                ordered_by: list.ordered_by.unwrap_or_default(),
+            },
+            list.span,
+        );
+        node.config = list.config;
+        node.status = self.status;
+        node.if_feature = list.if_feature.clone();
+        node.when = list.when.iter().cloned().map(SchemaWhen::own).collect();
+        node.must = list.must.clone();
+        node.description = list.description.clone();
+        node.children = self.compile_data_definitions(
+            data_definitions!(
+                list,
+                [
+                    action,
+                    anydata,
+                    anyxml,
+                    choice,
+                    container,
+                    leaf,
+                    leaf_list,
+                    list,
+                    notification,
+                    uses,
+                    grouping,
+                    typedef
+                ]
+            ),
+            scope,
+        );
+        self.status = enclosing;
+        node
+    }
+
+    fn compile_choice(&mut self, choice: &Choice, scope: &Scope) -> SchemaNode {
+        let enclosing = self.enter_definition(choice.status, &choice.if_feature, choice.span);
+        let mut node = node(
+            &choice.name,
+            SchemaNodeKind::Choice {
+                default: choice.default.clone(),
+                mandatory: choice.mandatory.unwrap_or(false),
+            },
+            choice.span,
+        );
+        node.config = choice.config;
+        node.status = self.status;
+        node.if_feature = choice.if_feature.clone();
+        node.when = choice.when.iter().cloned().map(SchemaWhen::own).collect();
+        node.description = choice.description.clone();
+        node.children = self
+            .compile_data_definitions(
+                data_definitions!(
+                    choice,
+                    [anydata, anyxml, case, choice, container, leaf, leaf_list, list]
+                ),
+                scope,
+            )
+            .into_iter()
+            .map(shorthand_case)
+            .collect();
+        self.status = enclosing;
+        node
+    }
+
+    fn compile_case(&mut self, case: &Case, scope: &Scope) -> SchemaNode {
+        let enclosing = self.enter_definition(case.status, &case.if_feature, case.span);
+        let mut node = node(&case.name, SchemaNodeKind::Case, case.span);
+        node.status = self.status;
+        node.if_feature = case.if_feature.clone();
+        node.when = case.when.iter().cloned().map(SchemaWhen::own).collect();
+        node.description = case.description.clone();
+        node.children = self.compile_data_definitions(
+            data_definitions!(
+                case,
+                [anydata, anyxml, choice, container, leaf, leaf_list, list, uses, grouping]
+            ),
+            scope,
+        );
+        self.status = enclosing;
+        node
+    }
+
+    fn compile_anydata(&mut self, anydata: &AnyDataOrXml, kind: SchemaNodeKind) -> SchemaNode {
+        let enclosing = self.enter_definition(anydata.status, &anydata.if_feature, anydata.span);
+        let mut node = node(&anydata.name, kind, anydata.span);
+        node.config = anydata.config;
+        node.status = self.status;
+        node.if_feature = anydata.if_feature.clone();
+        node.when = anydata.when.iter().cloned().map(SchemaWhen::own).collect();
+        node.must = anydata.must.clone();
+        node.description = anydata.description.clone();
+        self.status = enclosing;
+        node
+    }
+
+    fn compile_in_output(
+        &mut self,
+        in_output: Option<&InOutput>,
+        name: &str,
+        kind: SchemaNodeKind,
+        span: Span,
+        scope: &Scope,
+    ) -> SchemaNode {
+        let Some(in_output) = in_output else {
+            return node(name, kind, span);
+        };
+        let mut node = node(name, kind, in_output.span);
+        node.must = in_output.must.clone();
+        node.children = self.compile_data_definitions(
+            data_definitions!(
+                in_output,
+                [
+                    anydata, anyxml, choice, container, leaf, leaf_list, list, uses, grouping,
+                    typedef
+                ]
+            ),
+            scope,
+        );
+        node
+    }
+
+    fn compile_action_or_rpc(
+        &mut self,
+        action: &ActionOrRpc,
+        kind: SchemaNodeKind,
+        scope: &Scope,
+    ) -> SchemaNode {
+        let enclosing = self.enter_definition(action.status, &action.if_feature, action.span);
+        let mut node = node(&action.name, kind, action.span);
+        node.status = self.status;
+        node.if_feature = action.if_feature.clone();
+        node.description = action.description.clone();
+        let scope = Scope {
+            parent: Some(scope),
+            grouping: &action.grouping,
+            typedef: &action.typedef,
+        };
+        self.compile_definitions(&scope);
+        node.children = vec![
+            self.compile_in_output(
+                action.input.as_ref(),
+                "input",
+                SchemaNodeKind::Input,
+                action.span,
+                &scope,
+            ),
+            self.compile_in_output(
+                action.output.as_ref(),
+                "output",
+                SchemaNodeKind::Output,
+                action.span,
+                &scope,
+            ),
+        ];
+        self.status = enclosing;
+        node
+    }
+
+    fn compile_notification(&mut self, notification: &Notification, scope: &Scope) -> SchemaNode {
+        let enclosing = self.enter_definition(
+            notification.status,
+            &notification.if_feature,
+            notification.span,
+        );
+        let mut node = node(
+            &notification.name,
+            SchemaNodeKind::Notification,
+            notification.span,
+        );
+        node.status = self.status;
+        node.if_feature = notification.if_feature.clone();
+        node.must = notification.must.clone();
+        node.description = notification.description.clone();
+        node.children = self.compile_data_definitions(
+            data_definitions!(
+                notification,
+                [
+                    anydata, anyxml, choice, container, leaf, leaf_list, list, uses, grouping,
+                    typedef
+                ]
+            ),
+            scope,
+        );
+        self.status = enclosing;
+        node
+    }
+
+    fn compile_augment_nodes(&mut self, augment: &Augment, scope: &Scope) -> Vec<SchemaNode> {
+        let enclosing = self.enter_definition(augment.status, &augment.if_feature, augment.span);
+        let mut nodes = self.compile_data_definitions(
+            data_definitions!(
+                augment,
+                [
+                    action,
+                    anydata,
+                    anyxml,
+                    case,
+                    choice,
+                    container,
+                    leaf,
+                    leaf_list,
+                    list,
+                    notification,
+                    uses
+                ]
+            ),
+            scope,
+        );
+        for node in &mut nodes {
+            node.if_feature.extend(augment.if_feature.iter().cloned());
+            node.when
+                .extend(augment.when.iter().cloned().map(SchemaWhen::inherited));
+        }
+        self.status = enclosing;
+        nodes
+    }
+
+    /// Resolves a schema node identifier to local node names, reporting an error if
+    /// it refers to another module.
+    fn local_path<'p>(&mut self, path: &'p str, span: Span) -> Option<Vec<&'p str>> {
+        let mut names = vec![];
+        for (prefix, name) in parse_schema_path(path) {
+            if prefix.is_some_and(|prefix| prefix != self.context.prefix) {
+                self.add_error(span, format!("Target {} is not in this module", path));
+                return None;
+            }
+            names.push(name);
+        }
+        Some(names)
+    }
+
+    /// Sets the effective config of `node` and its descendants, given the
+    /// effective config of the parent. `operation` is the rpc, action or
+    /// notification the node is in, if any.
+    fn resolve_config(
+        &mut self,
+        node: &mut SchemaNode,
+        parent_config: Option<bool>,
+        operation: Option<&str>,
+    ) {
+        let operation = match node.kind {
+            SchemaNodeKind::Rpc | SchemaNodeKind::Action | SchemaNodeKind::Notification => {
+                Some(format!("{} {}", node.keyword(), node.name))
+            }
+            _ => operation.map(str::to_string),
+        };
+        match (&operation, parent_config, node.config) {
+            (Some(operation), _, Some(_)) => self.add_error(
+                node.span,
+                format!(
+                    "Config of {} is not allowed within {}",
+                    node.name, operation
+                ),
+            ),
+            (None, Some(false), Some(true)) => self.add_error(
+                node.span,
+                format!(
+                    "Node {} cannot be config true under config false",
+                    node.name
+                ),
+            ),
+            _ => {}
+        }
+        node.effective_config = match operation {
+            Some(_) => None,
+            None => parent_config.map(|parent_config| node.config.unwrap_or(parent_config)),
+        };
+        for child in &mut node.children {
+            self.resolve_config(child, node.effective_config, operation.as_deref());
+        }
+    }
+
+    /// Checks the keys and unique constraints of the lists at and below `node`.
+    fn check_lists(&mut self, node: &SchemaNode) {
+        if let SchemaNodeKind::List { key, unique, .. } = &node.kind {
+            for (index, name) in key.iter().enumerate() {
+                if key[..index].contains(name) {
+                    self.add_error(
+                        node.span,
+                        format!("Key {} of list {} is given twice", name, node.name),
+                    );
+                    continue;
+                }
+                let Some(key_node) = node.child(name) else {
+                    self.add_error(
+                        node.span,
+                        format!("Key {} of list {} not found", name, node.name),
+                    );
+                    continue;
+                };
+                let SchemaNodeKind::Leaf { r#type, .. } = &key_node.kind else {
+                    self.add_error(
+                        key_node.span,
+                        format!("Key {} of list {} is not a leaf", name, node.name),
+                    );
+                    continue;
+                };
+                let base_type = r#type
+                    .typedefs
+                    .last()
+                    .map_or(&r#type.r#type, |typedef| &typedef.r#type);
+                if !self.yang_1_1 && base_type.name.as_deref() == Some("empty") {
+                    self.add_error(
+                        key_node.span,
+                        format!(
+                            "Key {} of list {} cannot be of type empty in YANG 1.0",
+                            name, node.name
+                        ),
+                    );
+                }
+                if key_node.effective_config != node.effective_config {
+                    self.add_error(
+                        key_node.span,
+                        format!(
+                            "Key {} of list {} must have the same config as the list",
+                            name, node.name
+                        ),
+                    );
+                }
+            }
+            for path in unique.iter().flat_map(|unique| &unique.0) {
+                let Some(names) = self.local_path(path, node.span) else {
+                    continue;
+                };
+                match find_node(&node.children, &names) {
+                    Some(SchemaNode {
+                        kind: SchemaNodeKind::Leaf { .. },
+                        ..
+                    }) => {}
+                    Some(_) => self.add_error(
+                        node.span,
+                        format!("Unique {} of list {} is not a leaf", path, node.name),
+                    ),
+                    None => self.add_error(
+                        node.span,
+                        format!("Unique {} of list {} not found", path, node.name),
+                    ),
+                }
+            }
+        }
+        for child in &node.children {
+            self.check_lists(child);
+        }
+    }
+
+    /// Checks that the identifiers of `nodes`, with the nodes of their choices and
+    /// cases, are unique, and checks the choices among them. Continues with the
+    /// children of each node.
+    fn check_namespace<'n>(&mut self, nodes: impl IntoIterator<Item = &'n SchemaNode>) {
+        let mut collected = vec![];
+        for node in nodes {
+            namespace_nodes(std::slice::from_ref(node), &mut collected);
+        }
+        for (index, node) in collected.iter().enumerate() {
+            if let Some(previous) = collected[..index]
+                .iter()
+                .find(|other| other.name == node.name)
+            {
+                self.add_conflict_error(
+                    node.span,
+                    format!("Identifier {} is already defined", node.name),
+                    previous.span,
+                );
+            }
+            match node.kind {
+                SchemaNodeKind::Choice { .. } => self.check_choice(node),
+                _ => self.check_namespace(&node.children),
+            }
+        }
+    }
+
+    fn check_choice(&mut self, choice: &SchemaNode) {
+        for (index, case) in choice.children.iter().enumerate() {
+            if let Some(previous) = choice.children[..index]
+                .iter()
+                .find(|other| other.name == case.name)
+            {
+                self.add_conflict_error(
+                    case.span,
+                    format!(
+                        "Case {} of choice {} is already defined",
+                        case.name, choice.name
+                    ),
+                    previous.span,
+                );
+            }
+        }
+        let SchemaNodeKind::Choice {
+            default: Some(default),
+            mandatory,
+        } = &choice.kind
+        else {
+            return;
+        };
+        if *mandatory {
+            self.add_error(
+                choice.span,
+                format!(
+                    "Mandatory choice {} cannot have a default case",
+                    choice.name
+                ),
+            );
+        }
+        let Some(name) = self.local_name(default, choice.span) else {
+            return;
+        };
+        let Some(case) = choice.child(name) else {
+            self.add_error(
+                choice.span,
+                format!(
+                    "Default case {} of choice {} not found",
+                    default, choice.name
+                ),
+            );
+            return;
+        };
+        if let Some(mandatory) = find_mandatory(case) {
+            self.add_error(
+                mandatory.span,
+                format!(
+                    "Default case {} of choice {} contains mandatory node {}",
+                    default, choice.name, mandatory.name
+                ),
+            );
+        }
+    }
+
+    fn check_if_features(&mut self, node: &SchemaNode) {
+        for expression in &node.if_feature {
+            if evaluate_if_feature(expression, &|_| true).is_none() {
+                self.add_error(
+                    node.span,
+                    format!("Invalid if-feature expression {}", expression),
+                );
+            }
+        }
+        for child in &node.children {
+            self.check_if_features(child);
+        }
+    }
+
+    fn apply_refine(&mut self, nodes: &mut [SchemaNode], refine: &Refine) {
+        let Some(path) = self.local_path(&refine.target, refine.argument_span) else {
+            return;
+        };
+        let Some(target) = find_node_mut(nodes, &path) else {
+            self.add_error(
+                refine.argument_span,
+                format!("Refine target {} not found", refine.target),
+            );
+            return;
+        };
+
+        target.if_feature.extend(refine.if_feature.iter().cloned());
+        target.must.extend(refine.must.iter().cloned());
+        if refine.description.is_some() {
+            target.description = refine.description.clone();
+        }
+        if refine.config.is_some() {
+            target.config = refine.config;
+        }
+        match &mut target.kind {
+            SchemaNodeKind::Container { presence } if refine.presence.is_some() => {
+                *presence = refine.presence.clone();
+            }
+            SchemaNodeKind::Leaf {
+                default, mandatory, ..
+            } => {
+                if let Some(refined_default) = refine.default.first() {
+                    *default = Some(refined_default.clone());
+                }
+                if let Some(refined_mandatory) = refine.mandatory {
+                    *mandatory = refined_mandatory;
+                }
+            }
+            SchemaNodeKind::Choice {
+                default, mandatory, ..
+            } => {
+                if let Some(refined_default) = refine.default.first() {
+                    *default = Some(refined_default.clone());
+                }
+                if let Some(refined_mandatory) = refine.mandatory {
+                    *mandatory = refined_mandatory;
+                }
+            }
+            SchemaNodeKind::AnyData { mandatory } | SchemaNodeKind::AnyXml { mandatory } => {
+                if let Some(refined_mandatory) = refine.mandatory {
+                    *mandatory = refined_mandatory;
+                }
+            }
+            SchemaNodeKind::LeafList {
+                default,
+                min_elements,
+                max_elements,
+                ..
+            } => {
+                if !refine.default.is_empty() {
+                    *default = refine.default.clone();
+                }
+                if let Some(refined_min_elements) = refine.min_elements {
+                    *min_elements = refined_min_elements;
+                }
+                if let Some(refined_max_elements) = refine.max_elements {
+                    *max_elements = refined_max_elements;
+                }
+            }
+            SchemaNodeKind::List {
+                min_elements,
+                max_elements,
+                ..
+            } => {
+                if let Some(refined_min_elements) = refine.min_elements {
+                    *min_elements = refined_min_elements;
+                }
+                if let Some(refined_max_elements) = refine.max_elements {
+                    *max_elements = refined_max_elements;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn expand_uses(&mut self, uses: &Uses, scope: &Scope) -> Vec<SchemaNode> {
+        let (imported, name) = match self.resolve(&uses.name) {
+            Ok(resolved) => resolved,
+            Err(prefix) => {
+                self.add_error(
+                    uses.argument_span,
+                    format!(
+                        "Definitions of module with prefix {} are not available",
+                        prefix
+                    ),
+                );
+                return vec![];
+            }
+        };
+        let lookup_scope = imported.map_or(scope, |imported| &imported.scope);
+        let Some((grouping, grouping_scope)) = lookup_scope.find_grouping(name) else {
+            self.add_error(
+                uses.argument_span,
+                format!("Grouping {} not found", uses.name),
+            );
+            let candidates = lookup_scope.grouping_names();
+            suggest_identifier(
+                &uses.name,
+                uses.argument_span,
+                candidates,
+                self.error_context,
+            );
+            return vec![];
+        };
+        if self.expanding.contains(&(grouping as *const Grouping)) {
+            self.add_error(
+                uses.argument_span,
+                format!("Grouping {} uses itself", uses.name),
+            );
+            return vec![];
+        }
+
+        let enclosing = self.enter_definition(uses.status, &uses.if_feature, uses.span);
+        let grouping_status = grouping.status.unwrap_or(Status::Current);
+        self.check_reference_status(
+            self.status,
+            "Grouping",
+            &uses.name,
+            grouping_status,
+            uses.argument_span,
+        );
+        // The grouping is compiled in the module it is defined in.
+        let context = match imported {
+            Some(imported) => std::mem::replace(&mut self.context, imported.context.clone()),
+            None => self.context.clone(),
+        };
+        let mut nodes = self.compile_grouping(grouping, grouping_scope);
+        self.context = context;
+
+        for node in &mut nodes {
+            resolve_status(node, self.status);
+        }
+        for node in &mut nodes {
+            node.if_feature.extend(uses.if_feature.iter().cloned());
+            node.when
+                .extend(uses.when.iter().cloned().map(SchemaWhen::inherited));
+        }
+        for refine in &uses.refine {
+            self.apply_refine(&mut nodes, refine);
+        }
+        for augment in &uses.augment {
+            let augment_nodes = self.compile_augment_nodes(augment, scope);
+            let Some(path) = self.local_path(&augment.target, augment.argument_span) else {
+                continue;
+            };
+            match find_node_mut(&mut nodes, &path) {
+                Some(target) => augment_target(target, augment_nodes),
+                None => self.add_error(
+                    augment.argument_span,
+                    format!("Augment target {} not found", augment.target),
+                ),
+            }
+        }
+        self.status = enclosing;
+        nodes
+    }
+}
+
+/// Raises the status of `node` and its descendants to at least `parent`, the
+/// effective status of the parent, like for nodes augmented into a deprecated one.
+fn resolve_status(node: &mut SchemaNode, parent: Status) {
+    node.status = node.status.max(parent);
+    for child in &mut node.children {
+        resolve_status(child, node.status);
+    }
+}
+
+/// The identities of `module`, with their bases qualified by the name of the
+/// module they are defined in.
+fn schema_identities<'m>(
+    module: &'m Module,
+    context: &'m ModuleContext,
+) -> impl Iterator<Item = SchemaIdentity> + 'm {
+    module.identity.iter().map(|identity| SchemaIdentity {
+        module: context.name.clone(),
+        name: identity.name.clone(),
+        bases: identity
+            .base
+            .iter()
+            .map(|base| match base.split_once(':') {
+                Some((prefix, name)) => (
+                    context.module_name(prefix).unwrap_or(prefix).to_string(),
+                    name.to_string(),
+                ),
+                None => (context.name.clone(), base.clone()),
+            })
+            .collect(),
+    })
+}
+
+/// Compiles `module` into its resolved schema tree, with the submodules it
+/// includes. References to the modules it imports are resolved with the modules
+/// loaded in `repository`.
+pub(crate) fn compile(
+    module: &Module,
+    repository: &Repository,
+    error_context: &mut ErrorContext,
+) -> Result<Schema, ()> {
+    let module = &repository.with_submodules(module.clone(), None);
+    let imported_modules = repository.imported_modules(module);
+    let imported: HashMap<String, ImportedModule> = imported_modules
+        .iter()
+        .map(|(name, module)| {
+            let imported = ImportedModule {
+                context: ModuleContext::new(module),
+                scope: Scope {
+                    parent: None,
+                    grouping: &module.grouping,
+                    typedef: &module.typedef,
+                },
+            };
+            (name.clone(), imported)
+        })
+        .collect();
+    let context = ModuleContext::new(module);
+    let mut compiler = Compiler {
+        module_name: module.name.clone(),
+        context: context.clone(),
+        imported: &imported,
+        error_context,
+        error_occured: false,
+        expanding: vec![],
+        yang_1_1: module.yang_version.as_deref() == Some("1.1"),
+        status: Status::Current,
+        identity_status: module
+            .identity
+            .iter()
+            .map(|identity| {
+                (
+                    identity.name.clone(),
+                    identity.status.unwrap_or(Status::Current),
+                )
+            })
+            .collect(),
+        feature_status: module
+            .feature
+            .iter()
+            .map(|feature| {
+                (
+                    feature.name.clone(),
+                    feature.status.unwrap_or(Status::Current),
+                )
+            })
+            .collect(),
+        grouping_status: HashMap::new(),
+        groupings: HashMap::new(),
+        deriving: vec![],
+    };
+    if check_namespaces(module, compiler.error_context).is_err() {
+        compiler.error_occured = true;
+    }
+    for feature in &module.feature {
+        let enclosing =
+            compiler.enter_definition(feature.status, &feature.if_feature, feature.span);
+        compiler.status = enclosing;
+    }
+    for identity in &module.identity {
+        let enclosing =
+            compiler.enter_definition(identity.status, &identity.if_feature, identity.span);
+        for base in &identity.base {
+            compiler.check_identity_status(base, identity.argument_span);
+        }
+        compiler.status = enclosing;
+    }
+    let scope = Scope {
+        parent: None,
+        grouping: &[],
+        typedef: &[],
+    };
+
+    let data = compiler.compile_data_definitions(
+        data_definitions!(
+            module,
+            [anydata, anyxml, choice, container, leaf, leaf_list, list, uses, grouping, typedef]
+        ),
+        &scope,
+    );
+    let module_scope = Scope {
+        parent: None,
+        grouping: &module.grouping,
+        typedef: &module.typedef,
+    };
+    let rpcs = module
+        .rpc
+        .iter()
+        .map(|rpc| compiler.compile_action_or_rpc(rpc, SchemaNodeKind::Rpc, &module_scope))
+        .collect();
+    let notifications = module
+        .notification
+        .iter()
+        .map(|notification| compiler.compile_notification(notification, &module_scope))
+        .collect();
+
+    let mut schema = Schema {
+        name: module.name.clone(),
+        prefix: module.prefix.clone(),
+        imports: module
+            .import
+            .iter()
+            .map(|import| (import.prefix.clone(), import.module.clone()))
+            .collect(),
+        namespace: module.namespace.clone(),
+        span: module.span,
+        description: module.description.clone(),
+        identities: schema_identities(module, &context)
+            .chain(
+                imported_modules
+                    .iter()
+                    .flat_map(|(name, module)| schema_identities(module, &imported[name].context)),
+            )
+            .collect(),
+        data,
+        rpcs,
+        notifications,
+        augments: vec![],
+    };
+
+    for augment in &module.augment {
+        let nodes = compiler.compile_augment_nodes(augment, &module_scope);
+        let steps = parse_schema_path(&augment.target);
+        let is_local = steps
+            .first()
+            .is_some_and(|(prefix, _)| prefix.is_none_or(|prefix| prefix == module.prefix));
+        if !is_local {
+            schema.augments.push(SchemaAugment {
+                target: augment.target.clone(),
+                nodes,
+            });
+            continue;
+        }
+
+        let Some(path) = compiler.local_path(&augment.target, augment.argument_span) else {
+            continue;
+        };
+        let target = find_node_mut(&mut schema.data, &path)
+            .or_else(|| find_node_mut(&mut schema.rpcs, &path))
+            .or_else(|| find_node_mut(&mut schema.notifications, &path));
+        match target {
+            Some(target) => augment_target(target, nodes),
+            None => compiler.add_error(
+                augment.argument_span,
+                format!("Augment target {} not found", augment.target),
+            ),
+        }
+    }
+
+    let augmented = schema.augments.iter().flat_map(|augment| &augment.nodes);
+    for node in schema
+        .data
+        .iter()
+        .chain(&schema.rpcs)
+        .chain(&schema.notifications)
+        .chain(augmented)
+    {
+        compiler.check_if_features(node);
+    }
+    for node in &mut schema.data {
+        compiler.resolve_config(node, Some(true), None);
+    }
+    for node in schema
+        .augments
+        .iter_mut()
+        .flat_map(|augment| &mut augment.nodes)
+    {
+        // The config of nodes of other modules is not known, so it is assumed true.
+        compiler.resolve_config(node, Some(true), None);
+    }
+    for node in schema.rpcs.iter_mut().chain(&mut schema.notifications) {
+        compiler.resolve_config(node, None, None);
+    }
+    for node in schema
+        .data
+        .iter_mut()
+        .chain(&mut schema.rpcs)
+        .chain(&mut schema.notifications)
+    {
+        resolve_status(node, Status::Current);
+    }
+    let augmented = schema.augments.iter().flat_map(|augment| &augment.nodes);
+    for node in schema
+        .data
+        .iter()
+        .chain(&schema.rpcs)
+        .chain(&schema.notifications)
+        .chain(augmented)
+    {
+        compiler.check_lists(node);
+    }
+    compiler.check_namespace(
+        schema
+            .data
+            .iter()
+            .chain(&schema.rpcs)
+            .chain(&schema.notifications),
+    );
+    for augment in &schema.augments {
+        compiler.check_namespace(&augment.nodes);
+    }
+    if check_schema(&mut schema, module, compiler.error_context).is_err() {
+        compiler.error_occured = true;
+    }
+
+    if compiler.error_occured {
+        return Err(());
+    }
+    Ok(schema)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::model::{MaxElements, OrderedBy};
+    use crate::parser::model_mapper::Mapper;
+    use crate::parser::parser::parse;
+    use crate::repository::ModuleKind;
+    use crate::schema::find_node;
+    use std::fs;
+
+    fn compile_str(input: &str) -> (Result<Schema, ()>, ErrorContext) {
+        let mut error_context = ErrorContext::new();
+        let module = Module::map(parse(input).unwrap(), &mut error_context).unwrap();
+        (
+            compile(&module, &Repository::default(), &mut error_context),
+            error_context,
+        )
+    }
+
+    /// Writes `files`, by file name, to a directory of the `test` and compiles the
+    /// first one with the modules it loads.
+    fn compile_files(test: &str, files: &[(&str, &str)]) -> (Result<Schema, ()>, ErrorContext) {
+        let directory = std::env::temp_dir().join(format!("yang-utils-compile-test-{}", test));
+        fs::create_dir_all(&directory).unwrap();
+        for (name, source) in files {
+            fs::write(directory.join(name), source).unwrap();
+        }
+        let mut repository = Repository::new(vec![]);
+        let index = repository.load(&directory.join(files[0].0)).unwrap();
+        let ModuleKind::Module(module) = &repository.modules[index].kind else {
+            panic!("expected a module");
+        };
+        let mut error_context = ErrorContext::new();
+        (
+            compile(module, &repository, &mut error_context),
+            error_context,
+        )
+    }
+
+    fn typedef_names(node: &SchemaNode) -> Vec<&str> {
+        let (SchemaNodeKind::Leaf { r#type, .. } | SchemaNodeKind::LeafList { r#type, .. }) =
+            &node.kind
+        else {
+            panic!("expected a leaf or leaf-list");
+        };
+        r#type
+            .typedefs
+            .iter()
+            .map(|typedef| typedef.name.as_str())
+            .collect()
+    }
+
+    fn names(nodes: &[SchemaNode]) -> Vec<&str> {
+        nodes.iter().map(|node| node.name.as_str()).collect()
+    }
+
+    #[test]
+    fn test_uses_and_document_order() {
+        let (schema, _) = compile_str(
+            r#"module m {
+                namespace "urn:m";
+                prefix m;
+                grouping g {
+                    leaf b { type string; }
+                    container c { leaf d { type string; } }
+                }
+                container top {
+                    leaf a { type string; }
+                    uses m:g {
+                        if-feature f;
+                        refine "c/d" { mandatory true; description "refined"; }
+                        augment c { leaf e { type string; } }
+                    }
+                    leaf z { type string; }
+                }
+            }"#,
+        );
+        let schema = schema.unwrap();
+        assert_eq!(names(&schema.data[0].children), ["a", "b", "c", "z"]);
+        let d = find_node(&schema.data, &["top", "c", "d"]).unwrap();
+        assert!(matches!(
+            d.kind,
+            SchemaNodeKind::Leaf {
+                mandatory: true,
+                ..
+            }
+        ));
+        assert_eq!(d.description.as_deref(), Some("refined"));
+        let c = find_node(&schema.data, &["top", "c"]).unwrap();
+        assert_eq!(c.if_feature, ["f"]);
+        assert_eq!(names(&c.children), ["d", "e"]);
+    }
+
+    #[test]
+    fn test_augment_and_rpcs() {
+        let (schema, _) = compile_str(
+            r#"module m {
+                namespace "urn:m";
+                prefix m;
+                import other { prefix o; }
+                container top;
+                augment "/m:top" { leaf a { type string; } }
+                augment "/o:remote" { leaf b { type string; } }
+                augment "/m:op/m:input" { leaf c { type string; } }
+                rpc op;
+            }"#,
+        );
+        let schema = schema.unwrap();
+        assert_eq!(names(&schema.data[0].children), ["a"]);
+        assert_eq!(schema.augments[0].target, "/o:remote");
+        assert_eq!(names(&schema.rpcs[0].children), ["input", "output"]);
+        assert_eq!(names(&schema.rpcs[0].children[0].children), ["c"]);
+    }
+
+    #[test]
+    fn test_imported_definitions() {
+        let (schema, error_context) = compile_files(
+            "imported",
+            &[
+                (
+                    "main.yang",
+                    r#"module main {
+                        namespace "urn:main";
+                        prefix m;
+                        import types { prefix l; }
+                        identity cat { base l:animal; }
+                        container server {
+                            uses l:endpoint;
+                            leaf backup-load { type l:percent { range "0..50"; } }
+                            leaf pet { type identityref { base l:animal; } }
+                        }
+                    }"#,
+                ),
+                (
+                    "types.yang",
+                    r#"module types {
+                        namespace "urn:types";
+                        prefix t;
+                        import units { prefix u; }
+                        identity animal;
+                        typedef percent { type u:fraction { range "0..100"; } }
+                        grouping endpoint {
+                            leaf address { type string; }
+                            leaf load { type t:percent; }
+                        }
+                    }"#,
+                ),
+                (
+                    "units.yang",
+                    r#"module units {
+                        namespace "urn:units";
+                        prefix u;
+                        typedef fraction { type uint8; }
+                    }"#,
+                ),
+            ],
+        );
+        assert!(error_context.messages().is_empty());
+        let schema = schema.unwrap();
+        let server = &schema.data[0];
+        assert_eq!(
+            names(&server.children),
+            ["address", "load", "backup-load", "pet"]
+        );
+        assert_eq!(typedef_names(&server.children[1]), ["percent", "fraction"]);
+        assert_eq!(typedef_names(&server.children[2]), ["percent", "fraction"]);
+        #[cfg(feature = "cli")]
+        assert!(schema.is_derived_identity(("main", "cat"), ("types", "animal")));
+
+        let (_, error_context) = compile_str(
+            r#"module m {
+                namespace "urn:m";
+                prefix m;
+                import types { prefix l; }
+                container server { uses l:endpoint; }
+            }"#,
+        );
+        assert_eq!(
+            error_context.messages(),
+            ["Definitions of module with prefix l are not available"]
+        );
+    }
+
+    #[test]
+    fn test_submodules() {
+        let (schema, error_context) = compile_files(
+            "submodules",
+            &[
+                (
+                    "main.yang",
+                    r#"module main {
+                        yang-version 1.1;
+                        namespace "urn:main";
+                        prefix m;
+                        include sub;
+                        container top {
+                            uses shared;
+                            leaf limit { type ratio; }
+                        }
+                    }"#,
+                ),
+                (
+                    "sub.yang",
+                    r#"submodule sub {
+                        yang-version 1.1;
+                        belongs-to main { prefix s; }
+                        typedef ratio { type decimal64 { fraction-digits 2; } }
+                        grouping shared { leaf level { type s:ratio; } }
+                        container settings { leaf threshold { type ratio; } }
+                    }"#,
+                ),
+            ],
+        );
+        assert!(error_context.messages().is_empty());
+        let schema = schema.unwrap();
+        assert_eq!(names(&schema.data), ["settings", "top"]);
+        let top = &schema.data[1];
+        assert_eq!(names(&top.children), ["level", "limit"]);
+        assert_eq!(typedef_names(&top.children[0]), ["ratio"]);
+        assert_eq!(typedef_names(&top.children[1]), ["ratio"]);
+    }
+
+    #[test]
+    fn test_errors() {
+        let (schema, error_context) = compile_str(
+            r#"module m {
+                namespace "urn:m";
+                prefix m;
+                typedef loop { type loop; }
+                typedef either { type union { type int8; type missing; } }
+                grouping rec { uses rec; }
+                grouping unused { leaf z { type nosuch; } }
+                container top {
+                    uses unknown;
+                    uses rec;
+                    leaf x { type either; }
+                    leaf y { type either; }
+                }
+                augment "/m:missing" { leaf a { type string; } }
+            }"#,
+        );
+        assert!(schema.is_err());
+        assert_eq!(
+            error_context.messages(),
+            [
+                "Typedef loop derives from itself",
+                "Type missing not found",
+                "Grouping rec uses itself",
+                "Type nosuch not found",
+                "Grouping unknown not found",
+                "Augment target /m:missing not found"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_type_restrictions() {
+        let (schema, error_context) = compile_str(
+            r#"module m {
+                namespace "urn:m";
+                prefix m;
+                typedef percent { type uint8 { range "0..100"; } }
+                typedef wide { type percent { range "0..200"; } }
+                typedef flags { type bits; }
+                container top {
+                    leaf a { type wide; }
+                    leaf b { type wide; }
+                    leaf c { type percent { range "0..300"; } }
+                    leaf d { type flags; }
+                    leaf-list e { type string { length "1..64"; } }
+                }
+            }"#,
+        );
+        assert!(schema.is_err());
+        // Errors of typedefs are reported once, where they are defined.
+        assert_eq!(
+            error_context.messages(),
+            [
+                "Range is larger than range of base type",
+                "Type bits is missing restrictions",
+                "Range is larger than range of base type"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_suggestions() {
+        let (_, error_context) = compile_str(
+            r#"module m {
+                namespace "urn:m";
+                prefix m;
+                typedef percent { type uint8; }
+                grouping address { leaf ip { type string; } }
+                container top {
+                    uses m:adress;
+                    leaf a { type m:percnet; }
+                    leaf b { type unit8; }
+                }
+            }"#,
+        );
+        let suggestions: Vec<(&str, &str)> = error_context
+            .diagnostics_with_fixes()
+            .map(|(diagnostic, fixes)| {
+                (diagnostic.help.as_deref().unwrap(), fixes[0].text.as_str())
+            })
+            .collect();
+        assert_eq!(
+            suggestions,
+            [
+                ("Did you mean m:percent?", "m:percent"),
+                ("Did you mean uint8?", "uint8"),
+                ("Did you mean m:address?", "m:address")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lists() {
+        let (schema, _) = compile_str(
+            r#"module m {
+                namespace "urn:m";
+                prefix m;
+                list entry {
+                    key "m:name kind";
+                    unique "address/ip port";
+                    min-elements 1;
+                    max-elements 10;
+                    ordered-by user;
+                    leaf name { type string; }
+                    leaf kind { type string; }
+                    container address { leaf ip { type string; } }
+                    leaf port { type uint16; }
+                    leaf-list tag { type string; max-elements unbounded; }
+                }
+            }"#,
+        );
+        let schema = schema.unwrap();
+        let SchemaNodeKind::List {
+            key,
+            unique,
+            min_elements,
+            max_elements,
+            ordered_by,
+        } = &schema.data[0].kind
+        else {
+            panic!("Expected list");
+        };
+        assert_eq!(key, &["name", "kind"]);
+        assert_eq!(unique[0].0, ["address/ip", "port"]);
+        assert_eq!(*min_elements, 1);
+        assert_eq!(*max_elements, MaxElements::Value(10));
+        assert_eq!(*ordered_by, OrderedBy::User);
+        let tag = find_node(&schema.data, &["entry", "tag"]).unwrap();
+        assert!(matches!(
+            tag.kind,
+            SchemaNodeKind::LeafList {
+                min_elements: 0,
+                max_elements: MaxElements::Unbounded,
+                ordered_by: OrderedBy::System,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_list_errors() {
+        let (schema, error_context) = compile_str(
+            r#"module m {
+                namespace "urn:m";
+                prefix m;
+                container top {
+                    list a {
+                        key "name missing sub flag";
+                        unique "sub name/x";
+                        unique "nothing";
+                        leaf name { config false; type string; }
+                        container sub;
+                        leaf flag { type empty; }
+                    }
+                }
+            }"#,
+        );
+        assert!(schema.is_err());
+        assert_eq!(
+            error_context.messages(),
+            [
+                "Key name of list a must have the same config as the list",
+                "Key missing of list a not found",
+                "Key sub of list a is not a leaf",
+                "Key flag of list a cannot be of type empty in YANG 1.0",
+                "Unique sub of list a is not a leaf",
+                "Unique name/x of list a not found",
+                "Unique nothing of list a not found"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_invalid_list_properties() {
+        let mut error_context = ErrorContext::new();
+        let module = Module::map(
+            parse(
+                r#"module m {
+                    namespace "urn:m";
+                    prefix m;
+                    list a { max-elements 0; ordered-by nobody; leaf b { type string; } }
+                }"#,
+            )
+            .unwrap(),
+            &mut error_context,
+        );
+        assert!(module.is_err());
+        assert_eq!(
+            error_context.messages(),
+            ["Invalid max-elements", "Invalid ordered-by"]
+        );
+    }
+
+    #[test]
+    fn test_config() {
+        let (schema, _) = compile_str(
+            r#"module m {
+                namespace "urn:m";
+                prefix m;
+                container top {
+                    leaf a { type string; }
+                    container state {
+                        config false;
+                        leaf b { type string; }
+                    }
+                }
+                rpc op { input { leaf c { type string; } } }
+            }"#,
+        );
+        let schema = schema.unwrap();
+        let effective_config =
+            |path: &[&str]| find_node(&schema.data, path).unwrap().effective_config;
+        assert_eq!(effective_config(&["top", "a"]), Some(true));
+        assert_eq!(effective_config(&["top", "state"]), Some(false));
+        assert_eq!(effective_config(&["top", "state", "b"]), Some(false));
+        let c = find_node(&schema.rpcs, &["op", "input", "c"]).unwrap();
+        assert_eq!(c.effective_config, None);
+    }
+
+    #[test]
+    fn test_config_errors() {
+        let (schema, error_context) = compile_str(
+            r#"module m {
+                namespace "urn:m";
+                prefix m;
+                container top {
+                    config false;
+                    leaf a { config true; type string; }
+                    list b {
+                        key "name";
+                        leaf name { config true; type string; }
+                    }
+                    action reset { input { leaf force { config true; type boolean; } } }
+                }
+                rpc op { output { container c { config false; } } }
+                notification event { leaf d { config false; type string; } }
+            }"#,
+        );
+        assert!(schema.is_err());
+        assert_eq!(
+            error_context.messages(),
+            [
+                "Node a cannot be config true under config false",
+                "Node name cannot be config true under config false",
+                "Config of force is not allowed within action reset",
+                "Config of c is not allowed within rpc op",
+                "Config of d is not allowed within notification event",
+                "Key name of list b must have the same config as the list"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_choices() {
+        let (schema, _) = compile_str(
+            r#"module m {
+                namespace "urn:m";
+                prefix m;
+                container top {
+                    choice transport {
+                        default tcp;
+                        leaf tcp { type empty; }
+                        case udp { leaf port { type uint16; mandatory true; } }
+                    }
+                }
+                augment "/m:top/m:transport" { container sctp; }
+            }"#,
+        );
+        let schema = schema.unwrap();
+        let transport = find_node(&schema.data, &["top", "transport"]).unwrap();
+        assert_eq!(names(&transport.children), ["tcp", "udp", "sctp"]);
+        for case in &transport.children {
+            assert!(matches!(case.kind, SchemaNodeKind::Case));
+        }
+        let tcp = find_node(&schema.data, &["top", "transport", "tcp", "tcp"]).unwrap();
+        assert!(matches!(tcp.kind, SchemaNodeKind::Leaf { .. }));
+        assert_eq!(names(&transport.children[2].children), ["sctp"]);
+    }
+
+    #[test]
+    fn test_choice_errors() {
+        let input = r#"module m {
+                namespace "urn:m";
+                prefix m;
+                container top {
+                    leaf port { type uint16; }
+                    choice transport {
+                        default udp;
+                        case tcp { leaf port { type uint16; } }
+                        case udp {
+                            container options { leaf size { type uint16; mandatory true; } }
+                        }
+                        case tcp { leaf window { type uint16; } }
+                    }
+                    choice missing { default other; leaf a { type string; } }
+                    choice both { mandatory true; default b; leaf b { type string; } }
+                }
+            }"#;
+        let (schema, error_context) = compile_str(input);
+        assert!(schema.is_err());
+        assert_eq!(
+            error_context.messages(),
+            [
+                "Case tcp of choice transport is already defined",
+                "Default case udp of choice transport contains mandatory node size",
+                "Identifier port is already defined",
+                "Default case other of choice missing not found",
+                "Mandatory choice both cannot have a default case"
+            ]
+        );
+        // Both declarations of port are labeled, the second one first.
+        let offsets: Vec<usize> = error_context.diagnostics()[2]
+            .labels
+            .iter()
+            .flatten()
+            .map(|label| label.offset())
+            .collect();
+        let first = input.find("leaf port").unwrap();
+        let second = input.rfind("leaf port").unwrap();
+        assert_eq!(offsets, [second, first]);
+    }
+
+    #[test]
+    fn test_status() {
+        let (schema, error_context) = compile_str(
+            r#"module m {
+                namespace "urn:m";
+                prefix m;
+                typedef legacy-port { status deprecated; type uint16; }
+                grouping old-endpoint {
+                    status deprecated;
+                    leaf address { type legacy-port; }
+                }
+                container top {
+                    leaf a { type string; }
+                    uses old-endpoint { status deprecated; }
+                    container legacy {
+                        status deprecated;
+                        leaf c { type legacy-port; }
+                        leaf d { status obsolete; type string; }
+                    }
+                }
+                augment "/m:top/m:legacy" { leaf e { type string; } }
+            }"#,
+        );
+        let schema = schema.unwrap();
+        assert!(error_context.messages().is_empty());
+        let status = |path: &[&str]| find_node(&schema.data, path).unwrap().status;
+        assert_eq!(status(&["top", "a"]), Status::Current);
+        assert_eq!(status(&["top", "address"]), Status::Deprecated);
+        assert_eq!(status(&["top", "legacy", "c"]), Status::Deprecated);
+        assert_eq!(status(&["top", "legacy", "d"]), Status::Obsolete);
+        assert_eq!(status(&["top", "legacy", "e"]), Status::Deprecated);
+    }
+
+    #[test]
+    fn test_deprecated_references() {
+        let (schema, error_context) = compile_str(
+            r#"module m {
+                namespace "urn:m";
+                prefix m;
+                feature legacy-auth { status deprecated; }
+                typedef legacy-port { status deprecated; type uint16; }
+                typedef port { type legacy-port; }
+                grouping old-endpoint {
+                    status deprecated;
+                    leaf address { type legacy-port; }
+                }
+                container top {
+                    leaf a { if-feature legacy-auth; type port; }
+                    leaf b { type union { type string; type m:legacy-port; } }
+                    uses old-endpoint;
+                }
+            }"#,
+        );
+        assert!(schema.is_err());
+        assert_eq!(
+            error_context.messages(),
+            [
+                "Typedef legacy-port is deprecated, but referenced by a current definition",
+                "Feature legacy-auth is deprecated, but referenced by a current definition",
+                "Typedef m:legacy-port is deprecated, but referenced by a current definition",
+                "Grouping old-endpoint is deprecated, but referenced by a current definition"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unused_definitions_status() {
+        let (schema, error_context) = compile_str(
+            r#"module m {
+                namespace "urn:m";
+                prefix m;
+                typedef old-port { status obsolete; type uint16; }
+                typedef legacy-port { status deprecated; type old-port; }
+                grouping legacy-endpoint {
+                    status deprecated;
+                    leaf port { type old-port; }
+                }
+                container top {
+                    grouping endpoint { leaf port { type legacy-port; } }
+                }
+            }"#,
+        );
+        assert!(schema.is_err());
+        assert_eq!(
+            error_context.messages(),
+            [
+                "Typedef old-port is obsolete, but referenced by a deprecated definition",
+                "Typedef old-port is obsolete, but referenced by a deprecated definition",
+                "Typedef legacy-port is deprecated, but referenced by a current definition"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_obsolete_references() {
+        let (schema, error_context) = compile_str(
+            r#"module m {
+                namespace "urn:m";
+                prefix m;
+                identity transport { status obsolete; }
+                identity tcp { base transport; }
+                typedef old-port { status obsolete; type uint16; }
+                container top {
+                    status deprecated;
+                    leaf kind { type identityref { base transport; } }
+                    leaf port { type old-port; }
+                }
+            }"#,
+        );
+        assert!(schema.is_err());
+        assert_eq!(
+            error_context.messages(),
+            [
+                "Identity transport is obsolete, but referenced by a current definition",
+                "Identity transport is obsolete, but referenced by a deprecated definition",
+                "Typedef old-port is obsolete, but referenced by a deprecated definition"
+            ]
+        );
+    }
+}
@@ -0,0 +1,704 @@
+//! Resolved schema tree of a module.
+//!
+//! The schema is compiled from the [`model`] structs: groupings are expanded at their
+//! `uses`, refinements and augmentations are applied, and the data definitions of
+//! each statement are put back into document order.
+
+use crate::parser::model;
+use crate::parser::model::{MaxElements, OrderedBy, Status};
+use crate::Span;
+use std::collections::HashMap;
+
+mod compile;
+mod namespace;
+
+#[cfg(feature = "cli")]
+use compile::augment_target;
+pub(crate) use compile::compile;
+
+/// A `type` statement with the typedefs it derives from, the one it names first.
+#[derive(Debug, Clone)]
+pub(crate) struct SchemaType {
+    pub(crate) r#type: model::Type,
+    pub(crate) typedefs: Vec<model::Typedef>,
+    /// The member types of the union it derives from, in the scope they are
+    /// defined in.
+    pub(crate) members: Vec<SchemaType>,
+    /// For a leafref, the type of the leaf its path refers to, if it is known.
+    pub(crate) target: Option<Box<SchemaType>>,
+}
+
+impl SchemaType {
+    /// The type statement with the path of the leafref it derives from, if any.
+    pub(crate) fn leafref(&self) -> Option<&model::Type> {
+        std::iter::once(&self.r#type)
+            .chain(self.typedefs.iter().map(|typedef| &typedef.r#type))
+            .find(|r#type| r#type.path.is_some())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) enum SchemaNodeKind {
+    Container {
+        presence: Option<String>,
+    },
+    Leaf {
+        r#type: SchemaType,
+        default: Option<String>,
+        mandatory: bool,
+        units: Option<String>,
+    },
+    LeafList {
+        r#type: SchemaType,
+        default: Vec<String>,
+        units: Option<String>,
+        min_elements: u32,
+        max_elements: MaxElements,
+        ordered_by: OrderedBy,
+    },
+    List {
+        /// Names of the key leaves, without prefixes.
+        key: Vec<String>,
+        unique: Vec<model::Unique>,
+        min_elements: u32,
+        max_elements: MaxElements,
+        ordered_by: OrderedBy,
+    },
+    Choice {
+        default: Option<String>,
+        mandatory: bool,
+    },
+    Case,
+    AnyData {
+        mandatory: bool,
+    },
+    AnyXml {
+        mandatory: bool,
+    },
+    Rpc,
+    Action,
+    Input,
+    Output,
+    Notification,
+}
+
+/// A `when` condition of a node.
+#[derive(Debug, Clone)]
+pub(crate) struct SchemaWhen {
+    pub(crate) when: model::When,
+    /// Whether the condition is of the `augment` or `uses` statement the node was
+    /// brought in by, so its context node is the closest ancestor data node
+    /// rather than the node itself.
+    pub(crate) inherited: bool,
+}
+
+impl SchemaWhen {
+    pub(crate) fn own(when: model::When) -> Self {
+        Self {
+            when,
+            inherited: false,
+        }
+    }
+
+    pub(crate) fn inherited(when: model::When) -> Self {
+        Self {
+            when,
+            inherited: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct SchemaNode {
+    pub(crate) name: String,
+    pub(crate) kind: SchemaNodeKind,
+    /// The `config` statement of the node itself, if any.
+    pub(crate) config: Option<bool>,
+    /// The config of the node, its own or inherited from its parent. `None` for
+    /// rpcs, actions, notifications and their descendants, which are not data.
+    pub(crate) effective_config: Option<bool>,
+    /// The status of the node, its own or the one of the parent or of the `uses`,
+    /// `augment` and grouping it was brought in by, whichever is least current.
+    pub(crate) status: Status,
+    /// `if-feature` statements of the node and of the `uses` and `augment`
+    /// statements it was brought in by.
+    pub(crate) if_feature: Vec<String>,
+    pub(crate) when: Vec<SchemaWhen>,
+    pub(crate) must: Vec<model::Must>,
+    pub(crate) description: Option<String>,
+    pub(crate) span: Span,
+    /// Name and namespace of the module defining the node, if another module
+    /// augmented it into the schema by [`merge_augments`].
+    #[cfg_attr(not(feature = "cli"), allow(dead_code))]
+    pub(crate) module: Option<(String, String)>,
+    pub(crate) children: Vec<SchemaNode>,
+}
+
+impl SchemaNode {
+    pub(crate) fn keyword(&self) -> &'static str {
+        match self.kind {
+            SchemaNodeKind::Container { .. } => "container",
+            SchemaNodeKind::Leaf { .. } => "leaf",
+            SchemaNodeKind::LeafList { .. } => "leaf-list",
+            SchemaNodeKind::List { .. } => "list",
+            SchemaNodeKind::Choice { .. } => "choice",
+            SchemaNodeKind::Case => "case",
+            SchemaNodeKind::AnyData { .. } => "anydata",
+            SchemaNodeKind::AnyXml { .. } => "anyxml",
+            SchemaNodeKind::Rpc => "rpc",
+            SchemaNodeKind::Action => "action",
+            SchemaNodeKind::Input => "input",
+            SchemaNodeKind::Output => "output",
+            SchemaNodeKind::Notification => "notification",
+        }
+    }
+
+    pub(crate) fn child(&self, name: &str) -> Option<&SchemaNode> {
+        self.children.iter().find(|child| child.name == name)
+    }
+}
+
+/// An `augment` of a node defined in another module.
+#[derive(Debug, Clone)]
+pub(crate) struct SchemaAugment {
+    pub(crate) target: String,
+    pub(crate) nodes: Vec<SchemaNode>,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(not(feature = "cli"), allow(dead_code))]
+pub(crate) struct SchemaIdentity {
+    /// Name of the module defining the identity.
+    pub(crate) module: String,
+    pub(crate) name: String,
+    /// The identities it is derived from, by module name and name.
+    pub(crate) bases: Vec<(String, String)>,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct Schema {
+    pub(crate) name: String,
+    pub(crate) prefix: String,
+    /// Names of the imported modules by their prefix.
+    pub(crate) imports: HashMap<String, String>,
+    pub(crate) namespace: String,
+    /// Span of the `module` statement.
+    pub(crate) span: Span,
+    pub(crate) description: Option<String>,
+    /// The identities of the module and of the modules it imports.
+    #[cfg_attr(not(feature = "cli"), allow(dead_code))]
+    pub(crate) identities: Vec<SchemaIdentity>,
+    pub(crate) data: Vec<SchemaNode>,
+    pub(crate) rpcs: Vec<SchemaNode>,
+    pub(crate) notifications: Vec<SchemaNode>,
+    pub(crate) augments: Vec<SchemaAugment>,
+}
+
+#[cfg(feature = "cli")]
+impl Schema {
+    /// The name of the module `prefix` refers to, this module for no prefix.
+    pub(crate) fn module_name<'s>(&'s self, prefix: Option<&'s str>) -> Option<&'s str> {
+        match prefix {
+            None => Some(&self.name),
+            Some(prefix) if prefix == self.prefix => Some(&self.name),
+            Some(prefix) => self.imports.get(prefix).map(String::as_str),
+        }
+    }
+
+    /// Whether the identity `identity` is derived from `base`, both given by module
+    /// name and name.
+    pub(crate) fn is_derived_identity(&self, identity: (&str, &str), base: (&str, &str)) -> bool {
+        let mut pending = vec![identity];
+        let mut visited = vec![];
+        while let Some((module, name)) = pending.pop() {
+            let Some(identity) = self
+                .identities
+                .iter()
+                .find(|identity| identity.module == module && identity.name == name)
+            else {
+                continue;
+            };
+            for (base_module, base_name) in &identity.bases {
+                let identity_base = (base_module.as_str(), base_name.as_str());
+                if identity_base == base {
+                    return true;
+                }
+                if !visited.contains(&identity_base) {
+                    visited.push(identity_base);
+                    pending.push(identity_base);
+                }
+            }
+        }
+        false
+    }
+
+    /// Finds the data node `name` among `nodes`, looking through choices and cases.
+    /// Returns the node and the choices and cases it is in.
+    pub(crate) fn find_data_child<'s>(
+        nodes: &'s [SchemaNode],
+        name: &str,
+    ) -> Option<(&'s SchemaNode, Vec<(&'s SchemaNode, &'s SchemaNode)>)> {
+        Self::find_data_child_by(nodes, &|node| node.name == name)
+    }
+
+    /// Finds the data node `name` of `module` among `nodes` like
+    /// [`Schema::find_data_child`], as nodes augmented by different modules may
+    /// share a name.
+    pub(crate) fn find_module_child<'s>(
+        &self,
+        nodes: &'s [SchemaNode],
+        module: &str,
+        name: &str,
+    ) -> Option<(&'s SchemaNode, Vec<(&'s SchemaNode, &'s SchemaNode)>)> {
+        Self::find_data_child_by(nodes, &|node| {
+            node.name == name && self.node_module(node) == module
+        })
+    }
+
+    fn find_data_child_by<'s>(
+        nodes: &'s [SchemaNode],
+        matches: &dyn Fn(&SchemaNode) -> bool,
+    ) -> Option<(&'s SchemaNode, Vec<(&'s SchemaNode, &'s SchemaNode)>)> {
+        for node in nodes {
+            match node.kind {
+                SchemaNodeKind::Choice { .. } => {
+                    for case in &node.children {
+                        if let Some((found, mut cases)) =
+                            Self::find_data_child_by(&case.children, matches)
+                        {
+                            cases.insert(0, (node, case));
+                            return Some((found, cases));
+                        }
+                    }
+                }
+                _ if matches(node) => return Some((node, vec![])),
+                _ => {}
+            }
+        }
+        None
+    }
+
+    /// Name of the module defining `node`.
+    pub(crate) fn node_module<'a>(&'a self, node: &'a SchemaNode) -> &'a str {
+        node.module.as_ref().map_or(&self.name, |(name, _)| name)
+    }
+
+    /// Names and namespaces of the modules with data nodes in this schema, its own
+    /// module first and then the ones augmenting it.
+    pub(crate) fn data_modules(&self) -> Vec<(&str, &str)> {
+        fn collect<'a>(nodes: &'a [SchemaNode], modules: &mut Vec<(&'a str, &'a str)>) {
+            for node in nodes {
+                if let Some((name, namespace)) = &node.module {
+                    if !modules.iter().any(|(known, _)| known == name) {
+                        modules.push((name, namespace));
+                    }
+                }
+                collect(&node.children, modules);
+            }
+        }
+        let mut modules = vec![(self.name.as_str(), self.namespace.as_str())];
+        collect(&self.data, &mut modules);
+        modules
+    }
+}
+
+/// Keeps the nodes of `nodes` and their descendants whose effective config is
+/// `config`. Ancestors of kept nodes are kept too, with the keys of lists, so
+/// state data can be placed in its configuration list entries.
+#[cfg(feature = "cli")]
+fn filter_config(nodes: &[SchemaNode], config: bool) -> Vec<SchemaNode> {
+    nodes
+        .iter()
+        .filter_map(|node| {
+            let mut children = filter_config(&node.children, config);
+            let has_children = !children.is_empty();
+            if let SchemaNodeKind::List { key, .. } = &node.kind {
+                if has_children && node.effective_config != Some(config) {
+                    let keys: Vec<SchemaNode> = node
+                        .children
+                        .iter()
+                        .filter(|child| key.contains(&child.name))
+                        .filter(|child| children.iter().all(|kept| kept.name != child.name))
+                        .cloned()
+                        .collect();
+                    children.splice(0..0, keys);
+                }
+            }
+            let is_kept = match node.kind {
+                SchemaNodeKind::Choice { .. } | SchemaNodeKind::Case => has_children,
+                _ => node.effective_config == Some(config) || has_children,
+            };
+            is_kept.then(|| SchemaNode {
+                children,
+                ..node.clone()
+            })
+        })
+        .collect()
+}
+
+#[cfg(feature = "cli")]
+impl Schema {
+    /// The schema of the configuration data, without state data, rpcs and
+    /// notifications.
+    pub(crate) fn config_view(&self) -> Schema {
+        self.view(true)
+    }
+
+    /// The schema of the state data, with the configuration nodes containing it.
+    pub(crate) fn state_view(&self) -> Schema {
+        self.view(false)
+    }
+
+    fn view(&self, config: bool) -> Schema {
+        Schema {
+            data: filter_config(&self.data, config),
+            rpcs: vec![],
+            notifications: vec![],
+            augments: self
+                .augments
+                .iter()
+                .map(|augment| SchemaAugment {
+                    nodes: filter_config(&augment.nodes, config),
+                    ..augment.clone()
+                })
+                .filter(|augment| !augment.nodes.is_empty())
+                .collect(),
+            ..self.clone()
+        }
+    }
+}
+
+/// Splits a schema node identifier like `/a:b/c` into `(prefix, name)` steps.
+pub(crate) fn parse_schema_path(path: &str) -> Vec<(Option<&str>, &str)> {
+    path.split('/')
+        .map(str::trim)
+        .filter(|step| !step.is_empty())
+        .map(|step| match step.split_once(':') {
+            Some((prefix, name)) => (Some(prefix), name),
+            None => (None, step),
+        })
+        .collect()
+}
+
+/// Finds the node at `path` below `nodes`, with `path` given as node names.
+pub(crate) fn find_node<'n>(nodes: &'n [SchemaNode], path: &[&str]) -> Option<&'n SchemaNode> {
+    let (first, rest) = path.split_first()?;
+    let node = nodes.iter().find(|node| node.name == *first)?;
+    if rest.is_empty() {
+        return Some(node);
+    }
+    find_node(&node.children, rest)
+}
+
+pub(crate) fn find_node_mut<'n>(
+    nodes: &'n mut [SchemaNode],
+    path: &[&str],
+) -> Option<&'n mut SchemaNode> {
+    let (first, rest) = path.split_first()?;
+    let node = nodes.iter_mut().find(|node| node.name == *first)?;
+    if rest.is_empty() {
+        return Some(node);
+    }
+    find_node_mut(&mut node.children, rest)
+}
+
+fn evaluate_or<'t>(
+    tokens: &mut std::iter::Peekable<impl Iterator<Item = &'t str>>,
+    is_enabled: &dyn Fn(&str) -> bool,
+) -> Option<bool> {
+    let mut value = evaluate_and(tokens, is_enabled)?;
+    while tokens.next_if_eq(&"or").is_some() {
+        value |= evaluate_and(tokens, is_enabled)?;
+    }
+    Some(value)
+}
+
+fn evaluate_and<'t>(
+    tokens: &mut std::iter::Peekable<impl Iterator<Item = &'t str>>,
+    is_enabled: &dyn Fn(&str) -> bool,
+) -> Option<bool> {
+    let mut value = evaluate_factor(tokens, is_enabled)?;
+    while tokens.next_if_eq(&"and").is_some() {
+        value &= evaluate_factor(tokens, is_enabled)?;
+    }
+    Some(value)
+}
+
+fn evaluate_factor<'t>(
+    tokens: &mut std::iter::Peekable<impl Iterator<Item = &'t str>>,
+    is_enabled: &dyn Fn(&str) -> bool,
+) -> Option<bool> {
+    match tokens.next()? {
+        "not" => evaluate_factor(tokens, is_enabled).map(|value| !value),
+        "(" => {
+            let value = evaluate_or(tokens, is_enabled)?;
+            tokens.next_if_eq(&")")?;
+            Some(value)
+        }
+        ")" | "and" | "or" => None,
+        feature => Some(is_enabled(feature)),
+    }
+}
+
+/// Evaluates an `if-feature` expression like `a and (b or not c)`, returning `None`
+/// if it is malformed.
+pub(crate) fn evaluate_if_feature(
+    expression: &str,
+    is_enabled: &dyn Fn(&str) -> bool,
+) -> Option<bool> {
+    let spaced = expression.replace('(', " ( ").replace(')', " ) ");
+    let mut tokens = spaced.split_whitespace().peekable();
+    let value = evaluate_or(&mut tokens, is_enabled)?;
+    tokens.next().is_none().then_some(value)
+}
+
+/// Removes the nodes below `nodes` whose `if-feature` statements are not satisfied.
+pub(crate) fn prune_features(nodes: &mut Vec<SchemaNode>, is_enabled: &dyn Fn(&str) -> bool) {
+    nodes.retain(|node| {
+        node.if_feature
+            .iter()
+            .all(|expression| evaluate_if_feature(expression, is_enabled) == Some(true))
+    });
+    for node in nodes {
+        prune_features(&mut node.children, is_enabled);
+    }
+}
+
+/// Removes the data nodes, RPCs, notifications and augmentations of `schema` that
+/// depend on a feature that is not enabled. `is_enabled` is called with the name of the module
+/// defining a feature and the name of the feature.
+pub(crate) fn enable_features(schema: &mut Schema, is_enabled: &dyn Fn(&str, &str) -> bool) {
+    let is_enabled = |feature: &str| {
+        let (module, name) = match feature.split_once(':') {
+            Some((prefix, name)) if prefix != schema.prefix => (
+                schema.imports.get(prefix).map_or(prefix, String::as_str),
+                name,
+            ),
+            Some((_, name)) => (schema.name.as_str(), name),
+            None => (schema.name.as_str(), feature),
+        };
+        is_enabled(module, name)
+    };
+    prune_features(&mut schema.data, &is_enabled);
+    prune_features(&mut schema.rpcs, &is_enabled);
+    prune_features(&mut schema.notifications, &is_enabled);
+    for augment in &mut schema.augments {
+        prune_features(&mut augment.nodes, &is_enabled);
+    }
+    schema.augments.retain(|augment| !augment.nodes.is_empty());
+}
+
+fn prune_obsolete_nodes(nodes: &mut Vec<SchemaNode>) {
+    nodes.retain(|node| node.status != Status::Obsolete);
+    for node in nodes {
+        prune_obsolete_nodes(&mut node.children);
+    }
+}
+
+/// Removes the data nodes, RPCs, notifications and augmentations of `schema` whose
+/// effective status is obsolete.
+pub(crate) fn prune_obsolete(schema: &mut Schema) {
+    prune_obsolete_nodes(&mut schema.data);
+    prune_obsolete_nodes(&mut schema.rpcs);
+    prune_obsolete_nodes(&mut schema.notifications);
+    for augment in &mut schema.augments {
+        prune_obsolete_nodes(&mut augment.nodes);
+    }
+    schema.augments.retain(|augment| !augment.nodes.is_empty());
+}
+
+#[cfg(feature = "cli")]
+fn mark_module(node: &mut SchemaNode, module: &(String, String)) {
+    node.module.get_or_insert_with(|| module.clone());
+    for child in &mut node.children {
+        mark_module(child, module);
+    }
+}
+
+/// Sets the effective config of an augmented node, resolved as if its parent
+/// were configuration, to the one inherited from `parent_config`.
+#[cfg(feature = "cli")]
+fn inherit_config(node: &mut SchemaNode, parent_config: Option<bool>) {
+    if parent_config != Some(true) {
+        node.effective_config = parent_config;
+    }
+    for child in &mut node.children {
+        inherit_config(child, node.effective_config);
+    }
+}
+
+/// Merges the augmentations of other modules among `schemas` into their target
+/// nodes, marking the augmented nodes with their module, so instance data can be
+/// validated against the schema of the augmented module. Augmentations whose
+/// target is not among `schemas` are left out.
+#[cfg(feature = "cli")]
+pub(crate) fn merge_augments(schemas: &mut [Schema]) {
+    let mut merged = vec![];
+    for schema in schemas.iter() {
+        let module = (schema.name.clone(), schema.namespace.clone());
+        for augment in &schema.augments {
+            let steps = parse_schema_path(&augment.target);
+            let Some((Some(prefix), _)) = steps.first() else {
+                continue;
+            };
+            let Some(target_module) = schema.imports.get(*prefix) else {
+                continue;
+            };
+            let path: Vec<String> = steps.iter().map(|(_, name)| name.to_string()).collect();
+            let mut nodes = augment.nodes.clone();
+            for node in &mut nodes {
+                mark_module(node, &module);
+            }
+            merged.push((target_module.clone(), path, nodes));
+        }
+    }
+    for (target_module, path, mut nodes) in merged {
+        let Some(schema) = schemas
+            .iter_mut()
+            .find(|schema| schema.name == target_module)
+        else {
+            continue;
+        };
+        let path: Vec<&str> = path.iter().map(String::as_str).collect();
+        let target = find_node_mut(&mut schema.data, &path)
+            .or_else(|| find_node_mut(&mut schema.rpcs, &path))
+            .or_else(|| find_node_mut(&mut schema.notifications, &path));
+        if let Some(target) = target {
+            for node in &mut nodes {
+                inherit_config(node, target.effective_config);
+            }
+            augment_target(target, nodes);
+        }
+    }
+}
+
+/// The first mandatory node at or below `node`, as defined in RFC 7950,
+/// section 3.
+pub(crate) fn find_mandatory(node: &SchemaNode) -> Option<&SchemaNode> {
+    let is_mandatory = match &node.kind {
+        SchemaNodeKind::Leaf { mandatory, .. }
+        | SchemaNodeKind::Choice { mandatory, .. }
+        | SchemaNodeKind::AnyData { mandatory }
+        | SchemaNodeKind::AnyXml { mandatory } => *mandatory,
+        SchemaNodeKind::List { min_elements, .. }
+        | SchemaNodeKind::LeafList { min_elements, .. } => *min_elements > 0,
+        SchemaNodeKind::Container { presence: None } | SchemaNodeKind::Case => {
+            return node.children.iter().find_map(find_mandatory);
+        }
+        _ => false,
+    };
+    is_mandatory.then_some(node)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::ErrorContext;
+    use crate::parser::model::Module;
+    use crate::parser::model_mapper::Mapper;
+    use crate::parser::parser::parse;
+    use crate::repository::Repository;
+
+    #[test]
+    fn test_evaluate_if_feature() {
+        let is_enabled = |feature: &str| matches!(feature, "a" | "p:b");
+        assert_eq!(evaluate_if_feature("a", &is_enabled), Some(true));
+        assert_eq!(evaluate_if_feature("a and c", &is_enabled), Some(false));
+        assert_eq!(evaluate_if_feature("c or p:b", &is_enabled), Some(true));
+        assert_eq!(
+            evaluate_if_feature("not (c or a)", &is_enabled),
+            Some(false)
+        );
+        assert_eq!(evaluate_if_feature("a and not c", &is_enabled), Some(true));
+        assert_eq!(evaluate_if_feature("a and", &is_enabled), None);
+        assert_eq!(evaluate_if_feature("(a", &is_enabled), None);
+        assert_eq!(evaluate_if_feature("a b", &is_enabled), None);
+    }
+
+    #[test]
+    #[cfg(feature = "cli")]
+    fn test_views() {
+        let input = r#"module m {
+            namespace "urn:m";
+            prefix m;
+            container interfaces {
+                list interface {
+                    key "name";
+                    leaf name { type string; }
+                    leaf mtu { type uint16; }
+                    container statistics {
+                        config false;
+                        leaf in-octets { type uint64; }
+                    }
+                }
+                leaf count { config false; type uint32; }
+            }
+            container system { leaf host-name { type string; } }
+            rpc restart;
+        }"#;
+        let mut error_context = ErrorContext::new();
+        let module = Module::map(parse(input).unwrap(), &mut error_context).unwrap();
+        let schema = compile(&module, &Repository::default(), &mut error_context).unwrap();
+        let names = |nodes: &[SchemaNode]| -> Vec<String> {
+            nodes.iter().map(|node| node.name.clone()).collect()
+        };
+
+        let config = schema.config_view();
+        assert_eq!(names(&config.data), ["interfaces", "system"]);
+        assert_eq!(names(&config.data[0].children), ["interface"]);
+        assert_eq!(names(&config.data[0].children[0].children), ["name", "mtu"]);
+        assert!(config.rpcs.is_empty());
+
+        let state = schema.state_view();
+        assert_eq!(names(&state.data), ["interfaces"]);
+        assert_eq!(names(&state.data[0].children), ["interface", "count"]);
+        assert_eq!(
+            names(&state.data[0].children[0].children),
+            ["name", "statistics"]
+        );
+    }
+
+    #[test]
+    fn test_enable_features() {
+        let input = r#"module m {
+            namespace "urn:m";
+            prefix m;
+            import other { prefix o; }
+            feature fast;
+            container system {
+                leaf speed { if-feature "m:fast"; type uint32; }
+                leaf mode { if-feature "o:modes"; type string; }
+            }
+            augment "/o:interfaces" {
+                if-feature fast;
+                leaf speed { type uint32; }
+            }
+            augment "/o:routes" {
+                leaf metric { if-feature "o:metrics"; type uint32; }
+            }
+        }"#;
+        let mut error_context = ErrorContext::new();
+        let module = Module::map(parse(input).unwrap(), &mut error_context).unwrap();
+        let schema = compile(&module, &Repository::default(), &mut error_context).unwrap();
+        let names = |nodes: &[SchemaNode]| -> Vec<String> {
+            nodes.iter().map(|node| node.name.clone()).collect()
+        };
+
+        let mut enabled = schema.clone();
+        enable_features(&mut enabled, &|module, feature| {
+            (module, feature) == ("other", "modes")
+        });
+        assert_eq!(names(&enabled.data[0].children), ["mode"]);
+        assert!(enabled.augments.is_empty());
+
+        let mut enabled = schema.clone();
+        enable_features(&mut enabled, &|module, _| module == "m");
+        assert_eq!(names(&enabled.data[0].children), ["speed"]);
+        let targets: Vec<&str> = enabled
+            .augments
+            .iter()
+            .map(|augment| augment.target.as_str())
+            .collect();
+        assert_eq!(targets, ["/o:interfaces"]);
+    }
+}
@@ -0,0 +1,308 @@
+//! Checks of the identifier namespaces of RFC 7950, section 6.2.1, for the
+//! definitions that are not part of the schema tree.
+//!
+//! Typedefs and groupings are scoped to the statement defining them and may not be
+//! redefined in a nested scope, while identities, features and extensions share one
+//! namespace for the whole module.
+
+use super::compile::Scope;
+use crate::errors::ErrorContext;
+use crate::parser::model::{
+    ActionOrRpc, Augment, Case, Choice, Container, Grouping, InOutput, List, Module, Notification,
+    Typedef, Uses,
+};
+use crate::typing::BUILT_IN_TYPE_NAMES;
+use crate::Span;
+
+/// A statement that may define typedefs and groupings, or contain one that does.
+trait Definitions {
+    fn grouping(&self) -> &[Grouping] {
+        &[]
+    }
+
+    fn typedef(&self) -> &[Typedef] {
+        &[]
+    }
+
+    /// The substatements that may define typedefs and groupings themselves.
+    fn nested(&self) -> Vec<&dyn Definitions>;
+}
+
+macro_rules! definitions {
+    ($model:ty $(, $definition:ident)*; [$($nested:ident),*]) => {
+        impl Definitions for $model {
+            $(definitions!(@$definition);)*
+
+            fn nested(&self) -> Vec<&dyn Definitions> {
+                let mut nested: Vec<&dyn Definitions> = vec![];
+                $(nested.extend(self.$nested.iter().map(|node| node as &dyn Definitions));)*
+                nested
+            }
+        }
+    };
+    (@grouping) => {
+        fn grouping(&self) -> &[Grouping] {
+            &self.grouping
+        }
+    };
+    (@typedef) => {
+        fn typedef(&self) -> &[Typedef] {
+            &self.typedef
+        }
+    };
+}
+
+definitions!(Module, grouping, typedef;
+    [augment, choice, container, grouping, list, notification, rpc, uses]);
+definitions!(Container, grouping, typedef;
+    [action, choice, container, grouping, list, notification, uses]);
+definitions!(List, grouping, typedef;
+    [action, choice, container, grouping, list, notification, uses]);
+definitions!(Grouping, grouping, typedef;
+    [action, choice, container, grouping, list, notification, uses]);
+definitions!(Notification, grouping, typedef; [choice, container, grouping, list, uses]);
+definitions!(ActionOrRpc, grouping, typedef; [grouping, input, output]);
+definitions!(InOutput, grouping, typedef; [choice, container, grouping, list, uses]);
+definitions!(Case, grouping; [choice, container, grouping, list, uses]);
+definitions!(Choice; [case, choice, container, list]);
+definitions!(Augment; [action, case, choice, container, list, notification, uses]);
+definitions!(Uses; [augment]);
+
+struct NamespaceChecker<'e> {
+    error_context: &'e mut ErrorContext,
+    error_occured: bool,
+}
+
+impl NamespaceChecker<'_> {
+    fn add_conflict_error(&mut self, span: Span, message: String, previous: Span) {
+        self.error_context
+            .add_conflict_error(span, message, previous);
+        self.error_occured = true;
+    }
+
+    /// Checks that the `kind` definitions named by `name` are unique within their
+    /// list and not defined by an enclosing scope, as found by `enclosing`.
+    fn check_unique<T>(
+        &mut self,
+        kind: &str,
+        definitions: &[T],
+        name: impl Fn(&T) -> (&str, Span),
+        enclosing: impl Fn(&str) -> Option<Span>,
+    ) {
+        for (index, definition) in definitions.iter().enumerate() {
+            let (identifier, span) = name(definition);
+            if let Some(previous) = definitions[..index]
+                .iter()
+                .map(&name)
+                .find(|(other, _)| *other == identifier)
+            {
+                self.add_conflict_error(
+                    span,
+                    format!("{} {} is already defined", kind, identifier),
+                    previous.1,
+                );
+            } else if let Some(previous) = enclosing(identifier) {
+                self.add_conflict_error(
+                    span,
+                    format!(
+                        "{} {} is already defined in an enclosing scope",
+                        kind, identifier
+                    ),
+                    previous,
+                );
+            }
+        }
+    }
+
+    fn check_definitions(&mut self, definitions: &dyn Definitions, enclosing: Option<&Scope>) {
+        self.check_unique(
+            "Typedef",
+            definitions.typedef(),
+            |typedef| (&typedef.name, typedef.argument_span),
+            |name| Some(enclosing?.find_typedef(name)?.0.argument_span),
+        );
+        for typedef in definitions.typedef() {
+            if BUILT_IN_TYPE_NAMES.contains(&typedef.name.as_str()) {
+                self.error_context.add_error(
+                    typedef.argument_span,
+                    format!("Typedef {} shadows a built-in type", typedef.name),
+                );
+                self.error_occured = true;
+            }
+        }
+        self.check_unique(
+            "Grouping",
+            definitions.grouping(),
+            |grouping| (&grouping.name, grouping.argument_span),
+            |name| Some(enclosing?.find_grouping(name)?.0.argument_span),
+        );
+
+        let scope = Scope {
+            parent: enclosing,
+            grouping: definitions.grouping(),
+            typedef: definitions.typedef(),
+        };
+        for nested in definitions.nested() {
+            self.check_definitions(nested, Some(&scope));
+        }
+    }
+}
+
+/// Checks the namespaces of the typedefs, groupings, identities, features and
+/// extensions of `module`.
+pub(crate) fn check_namespaces(
+    module: &Module,
+    error_context: &mut ErrorContext,
+) -> Result<(), ()> {
+    let mut checker = NamespaceChecker {
+        error_context,
+        error_occured: false,
+    };
+    checker.check_definitions(module, None);
+    checker.check_unique(
+        "Identity",
+        &module.identity,
+        |identity| (&identity.name, identity.argument_span),
+        |_| None,
+    );
+    checker.check_unique(
+        "Feature",
+        &module.feature,
+        |feature| (&feature.name, feature.argument_span),
+        |_| None,
+    );
+    checker.check_unique(
+        "Extension",
+        &module.extension,
+        |extension| (&extension.name, extension.argument_span),
+        |_| None,
+    );
+    if checker.error_occured {
+        return Err(());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::model_mapper::Mapper;
+    use crate::parser::parser::parse;
+
+    fn check(input: &str) -> Vec<(String, Vec<&str>)> {
+        let mut error_context = ErrorContext::new();
+        let module = Module::map(parse(input).unwrap(), &mut error_context).unwrap();
+        let result = check_namespaces(&module, &mut error_context);
+        assert_eq!(result.is_err(), error_context.has_errors());
+        error_context.sort_errors();
+        error_context
+            .diagnostics()
+            .iter()
+            .map(|diagnostic| {
+                let labels = diagnostic
+                    .labels
+                    .iter()
+                    .flatten()
+                    .map(|label| &input[label.offset()..label.offset() + label.len()])
+                    .collect();
+                (diagnostic.message.clone(), labels)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_valid() {
+        let input = r#"module example {
+            namespace "urn:example";
+            prefix ex;
+            typedef percent { type uint8; }
+            grouping endpoint {
+                typedef port { type uint16; }
+                leaf port { type port; }
+            }
+            container server {
+                typedef port { type uint32; }
+                grouping limits { leaf max { type uint16; } }
+                uses endpoint;
+            }
+            container client {
+                grouping limits { leaf max { type uint32; } }
+            }
+            identity transport;
+            feature tls;
+            extension annotation;
+        }"#;
+        assert_eq!(check(input), []);
+    }
+
+    #[test]
+    fn test_errors() {
+        let input = r#"module example {
+            namespace "urn:example";
+            prefix ex;
+            typedef percent { type uint8; }
+            typedef percent { type uint16; }
+            typedef string { type uint8; }
+            grouping endpoint {
+                leaf port { type uint16; }
+            }
+            container server {
+                grouping endpoint { leaf address { type string; } }
+                list session {
+                    key id;
+                    typedef percent { type uint32; }
+                    leaf id { type uint32; }
+                }
+            }
+            rpc reset {
+                input {
+                    grouping options { leaf force { type boolean; } }
+                    grouping options { leaf delay { type uint32; } }
+                }
+            }
+            identity transport;
+            identity transport;
+            feature tls;
+            feature tls;
+            extension annotation;
+            extension annotation;
+        }"#;
+        assert_eq!(
+            check(input),
+            [
+                (
+                    "Typedef percent is already defined".to_string(),
+                    vec!["percent", "percent"]
+                ),
+                (
+                    "Typedef string shadows a built-in type".to_string(),
+                    vec!["string"]
+                ),
+                (
+                    "Grouping endpoint is already defined in an enclosing scope".to_string(),
+                    vec!["endpoint", "endpoint"]
+                ),
+                (
+                    "Typedef percent is already defined in an enclosing scope".to_string(),
+                    vec!["percent", "percent"]
+                ),
+                (
+                    "Grouping options is already defined".to_string(),
+                    vec!["options", "options"]
+                ),
+                (
+                    "Identity transport is already defined".to_string(),
+                    vec!["transport", "transport"]
+                ),
+                (
+                    "Feature tls is already defined".to_string(),
+                    vec!["tls", "tls"]
+                ),
+                (
+                    "Extension annotation is already defined".to_string(),
+                    vec!["annotation", "annotation"]
+                ),
+            ]
+        );
+    }
+}
@@ -0,0 +1,431 @@
+//! RFC 8340 tree diagrams of a compiled [`Schema`].
+//!
+//! The layout follows `pyang -f tree`: each node is printed as
+//! `<status>--<flags> <name><opts>   <type> <if-features>`, with the names of
+//! siblings aligned so that their types line up.
+
+use crate::parser::model::Status;
+use crate::schema::{parse_schema_path, Schema, SchemaNode, SchemaNodeKind};
+
+#[derive(Debug, Clone, Default)]
+pub(crate) struct TreeOptions {
+    /// Maximum number of levels printed below a section, deeper levels are
+    /// replaced by `...`.
+    pub(crate) depth: Option<usize>,
+    /// Schema node identifier of the subtree to print, like `/system/login`.
+    pub(crate) path: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Mode {
+    Data,
+    Rpc,
+    Input,
+    Output,
+    Notification,
+}
+
+#[derive(Clone, Copy)]
+struct Context<'a> {
+    mode: Mode,
+    /// Key leafs of the parent list.
+    keys: &'a [&'a str],
+    /// Remaining steps of the selected subtree.
+    path: &'a [&'a str],
+    depth: Option<usize>,
+}
+
+/// Narrows `nodes` down to the next step of `path`.
+fn select<'n, 'p>(
+    nodes: &'n [SchemaNode],
+    path: &'p [&'p str],
+) -> (Vec<&'n SchemaNode>, &'p [&'p str]) {
+    match path.split_first() {
+        Some((first, rest)) => (
+            nodes.iter().filter(|node| node.name == *first).collect(),
+            rest,
+        ),
+        None => (nodes.iter().collect(), path),
+    }
+}
+
+/// Width of the widest name of `nodes`, choices and cases count with the
+/// indentation of their children.
+fn name_width<'n>(nodes: impl IntoIterator<Item = &'n SchemaNode>) -> usize {
+    nodes
+        .into_iter()
+        .map(|node| match node.kind {
+            SchemaNodeKind::Choice { .. } | SchemaNodeKind::Case => 3 + name_width(&node.children),
+            _ => node.name.len(),
+        })
+        .max()
+        .unwrap_or(0)
+}
+
+fn type_name(node: &SchemaNode) -> String {
+    match &node.kind {
+        SchemaNodeKind::Leaf { r#type, .. } | SchemaNodeKind::LeafList { r#type, .. } => {
+            let name = r#type.r#type.name.as_deref().unwrap_or_default();
+            match &r#type.r#type.path {
+                Some(path) if name == "leafref" => format!("-> {}", path.expression),
+                _ => name.to_string(),
+            }
+        }
+        SchemaNodeKind::AnyData { .. } => "<anydata>".to_string(),
+        SchemaNodeKind::AnyXml { .. } => "<anyxml>".to_string(),
+        _ => String::new(),
+    }
+}
+
+struct TreePrinter {
+    output: String,
+}
+
+impl TreePrinter {
+    fn print_children(
+        &mut self,
+        children: &[SchemaNode],
+        prefix: &str,
+        context: Context,
+        width: Option<usize>,
+    ) {
+        if context.depth == Some(0) {
+            if !children.is_empty() {
+                self.output.push_str(prefix);
+                self.output.push_str("     ...\n");
+            }
+            return;
+        }
+
+        let (children, path) = select(children, context.path);
+        let width = width.unwrap_or_else(|| name_width(children.iter().copied()));
+        // An empty output is not printed, so an input before it is the last child.
+        let empty_output_follows = children.last().is_some_and(|child| {
+            matches!(child.kind, SchemaNodeKind::Output) && child.children.is_empty()
+        });
+        for (index, child) in children.iter().enumerate() {
+            let mode = match child.kind {
+                SchemaNodeKind::Input | SchemaNodeKind::Output if child.children.is_empty() => {
+                    continue;
+                }
+                SchemaNodeKind::Input => Mode::Input,
+                SchemaNodeKind::Output => Mode::Output,
+                _ => context.mode,
+            };
+            let child_prefix = if index == children.len() - 1 || empty_output_follows {
+                format!("{}   ", prefix)
+            } else {
+                format!("{}  |", prefix)
+            };
+            self.print_node(
+                child,
+                &child_prefix,
+                Context {
+                    mode,
+                    path,
+                    ..context
+                },
+                width,
+            );
+        }
+    }
+
+    fn print_node(&mut self, node: &SchemaNode, prefix: &str, context: Context, width: usize) {
+        let flags = match (&node.kind, context.mode, node.effective_config) {
+            (_, Mode::Input, _) => "-w",
+            (SchemaNodeKind::Rpc | SchemaNodeKind::Action, _, _) => "-x",
+            (SchemaNodeKind::Notification, _, _) => "-n",
+            (_, _, Some(true)) => "rw",
+            (_, _, Some(false)) | (_, Mode::Output | Mode::Notification, None) => "ro",
+            _ => "",
+        };
+        let status = match node.status {
+            Status::Current => '+',
+            Status::Deprecated => 'x',
+            Status::Obsolete => 'o',
+        };
+
+        let mut line = format!("{}{}--", &prefix[..prefix.len() - 1], status);
+        let name = &node.name;
+        match &node.kind {
+            SchemaNodeKind::List { key, .. } => {
+                line.push_str(&format!("{} {}*", flags, name));
+                if !key.is_empty() {
+                    line.push_str(&format!(" [{}]", key.join(" ")));
+                }
+            }
+            SchemaNodeKind::Container { presence } => {
+                let opts = if presence.is_some() { "!" } else { "" };
+                line.push_str(&format!("{} {}{}", flags, name, opts));
+            }
+            SchemaNodeKind::Choice { mandatory, .. } => {
+                let opts = if *mandatory { "" } else { "?" };
+                line.push_str(&format!("{} ({}){}", flags, name, opts));
+            }
+            SchemaNodeKind::Case => line.push_str(&format!(":({})", name)),
+            SchemaNodeKind::Leaf { mandatory, .. } => {
+                let is_key = context.keys.contains(&name.as_str());
+                let opts = if *mandatory || is_key { "" } else { "?" };
+                Self::push_typed(&mut line, flags, &format!("{}{}", name, opts), node, width);
+            }
+            SchemaNodeKind::AnyData { mandatory } | SchemaNodeKind::AnyXml { mandatory } => {
+                let opts = if *mandatory { "" } else { "?" };
+                Self::push_typed(&mut line, flags, &format!("{}{}", name, opts), node, width);
+            }
+            SchemaNodeKind::LeafList { .. } => {
+                Self::push_typed(&mut line, flags, &format!("{}*", name), node, width);
+            }
+            _ => line.push_str(&format!("{} {}", flags, name)),
+        }
+        if !node.if_feature.is_empty() {
+            line.push_str(&format!(" {{{}}}?", node.if_feature.join(",")));
+        }
+        self.output.push_str(&line);
+        self.output.push('\n');
+
+        let keys: Vec<&str> = match &node.kind {
+            SchemaNodeKind::List { key, .. } => key.iter().map(String::as_str).collect(),
+            _ => vec![],
+        };
+        let mode = match node.kind {
+            SchemaNodeKind::Rpc | SchemaNodeKind::Action => Mode::Rpc,
+            SchemaNodeKind::Notification => Mode::Notification,
+            _ => context.mode,
+        };
+        let child_width = match node.kind {
+            SchemaNodeKind::Choice { .. } | SchemaNodeKind::Case => (width > 3).then(|| width - 3),
+            _ => None,
+        };
+        self.print_children(
+            &node.children,
+            prefix,
+            Context {
+                mode,
+                keys: &keys,
+                path: context.path,
+                depth: context.depth.map(|depth| depth - 1),
+            },
+            child_width,
+        );
+    }
+
+    fn push_typed(line: &mut String, flags: &str, name: &str, node: &SchemaNode, width: usize) {
+        let type_name = type_name(node);
+        if type_name.is_empty() {
+            line.push_str(&format!("{} {}", flags, name));
+        } else {
+            line.push_str(&format!(
+                "{} {:<width$}   {}",
+                flags,
+                name,
+                type_name,
+                width = width + 1
+            ));
+        }
+    }
+}
+
+/// Renders the tree diagram of `schema`.
+pub(crate) fn render_tree(schema: &Schema, options: &TreeOptions) -> String {
+    let path: Vec<&str> = options
+        .path
+        .as_deref()
+        .map(|path| {
+            parse_schema_path(path)
+                .into_iter()
+                .map(|(_, name)| name)
+                .collect()
+        })
+        .unwrap_or_default();
+    let context = Context {
+        mode: Mode::Data,
+        keys: &[],
+        path: &path,
+        depth: options.depth,
+    };
+    let is_selected = |nodes: &[SchemaNode]| !select(nodes, &path).0.is_empty();
+
+    let mut printer = TreePrinter {
+        output: format!("module: {}\n", schema.name),
+    };
+    printer.print_children(&schema.data, "", context, None);
+    for augment in &schema.augments {
+        if is_selected(&augment.nodes) {
+            printer
+                .output
+                .push_str(&format!("\n  augment {}:\n", augment.target));
+            printer.print_children(&augment.nodes, "  ", context, None);
+        }
+    }
+    if is_selected(&schema.rpcs) {
+        printer.output.push_str("\n  rpcs:\n");
+        let context = Context {
+            mode: Mode::Rpc,
+            ..context
+        };
+        printer.print_children(&schema.rpcs, "  ", context, None);
+    }
+    if is_selected(&schema.notifications) {
+        printer.output.push_str("\n  notifications:\n");
+        let context = Context {
+            mode: Mode::Notification,
+            ..context
+        };
+        printer.print_children(&schema.notifications, "  ", context, None);
+    }
+    printer.output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::ErrorContext;
+    use crate::parser::model::Module;
+    use crate::parser::model_mapper::Mapper;
+    use crate::parser::parser::parse;
+    use crate::repository::Repository;
+    use crate::schema::compile;
+
+    fn tree(input: &str, options: &TreeOptions) -> String {
+        let mut error_context = ErrorContext::new();
+        let module = Module::map(parse(input).unwrap(), &mut error_context).unwrap();
+        let schema = compile(&module, &Repository::default(), &mut error_context).unwrap();
+        render_tree(&schema, options)
+    }
+
+    fn corpus(name: &str) -> String {
+        std::fs::read_to_string(format!(
+            "{}/tests/corpus/{}",
+            env!("CARGO_MANIFEST_DIR"),
+            name
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_tree() {
+        assert_eq!(
+            tree(&corpus("acme-system.yang"), &TreeOptions::default()),
+            "\
+module: acme-system
+  +--rw system
+     +--rw host-name?       string
+     +--rw domain-search*   string
+     +--rw login
+        +--rw message?   string
+        +--rw user* [name]
+           +--rw name         string
+           +--rw full-name?   string
+           +--rw class?       string
+"
+        );
+    }
+
+    #[test]
+    fn test_tree_flags() {
+        let input = r#"module example {
+            namespace "urn:example";
+            prefix ex;
+            feature extended;
+            feature fast;
+            container settings {
+                presence "enables settings";
+                leaf mode {
+                    type leafref { path "../../modes/name"; }
+                    mandatory true;
+                }
+                anydata extra { if-feature "extended"; }
+                choice transport {
+                    mandatory true;
+                    case tcp { leaf port { type uint16; } }
+                    case udp { leaf datagram-port { type uint16; } }
+                }
+                action restart {
+                    input { leaf delay { type uint32; } }
+                }
+            }
+            list modes {
+                key name;
+                config false;
+                leaf name { type string; }
+                leaf description { type string; status deprecated; }
+            }
+            augment "/ex:settings" {
+                if-feature "extended and fast";
+                leaf tuning { type string; }
+            }
+            augment "/other:remote" {
+                container local { leaf enabled { type boolean; } }
+            }
+            rpc ping;
+        }"#;
+        assert_eq!(
+            tree(input, &TreeOptions::default()),
+            "\
+module: example
+  +--rw settings!
+  |  +--rw mode                   -> ../../modes/name
+  |  +--rw extra?                 <anydata> {extended}?
+  |  +--rw (transport)
+  |  |  +--:(tcp)
+  |  |  |  +--rw port?            uint16
+  |  |  +--:(udp)
+  |  |     +--rw datagram-port?   uint16
+  |  +---x restart
+  |  |  +---w input
+  |  |     +---w delay?   uint32
+  |  +--rw tuning?                string {extended and fast}?
+  +--ro modes* [name]
+     +--ro name           string
+     x--ro description?   string
+
+  augment /other:remote:
+    +--rw local
+       +--rw enabled?   boolean
+
+  rpcs:
+    +---x ping
+"
+        );
+    }
+
+    #[test]
+    fn test_tree_depth_and_path() {
+        let input = corpus("acme-system.yang");
+        assert_eq!(
+            tree(
+                &input,
+                &TreeOptions {
+                    depth: Some(2),
+                    path: None
+                }
+            ),
+            "\
+module: acme-system
+  +--rw system
+     +--rw host-name?       string
+     +--rw domain-search*   string
+     +--rw login
+           ...
+"
+        );
+        assert_eq!(
+            tree(
+                &input,
+                &TreeOptions {
+                    depth: None,
+                    path: Some("/acme:system/login/user".to_string())
+                }
+            ),
+            "\
+module: acme-system
+  +--rw system
+     +--rw login
+        +--rw user* [name]
+           +--rw name         string
+           +--rw full-name?   string
+           +--rw class?       string
+"
+        );
+    }
+}
@@ -0,0 +1,143 @@
+use crate::errors::ErrorContext;
+use crate::parser::model::Type;
+use crate::typing::range::{derive_range, RangeSet};
+use crate::typing::{collect_unused_fields, TypeTrait};
+#[cfg(feature = "cli")]
+use crate::typing::Value;
+
+#[cfg(feature = "cli")]
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// The base64 encoding of `bytes`, as in RFC 4648, section 4.
+#[cfg(feature = "cli")]
+pub(crate) fn encode_base64(bytes: &[u8]) -> String {
+    let mut text = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let group = chunk.iter().enumerate().fold(0u32, |group, (index, byte)| {
+            group | (*byte as u32) << (16 - 8 * index)
+        });
+        for index in 0..4 {
+            if index <= chunk.len() {
+                text.push(BASE64_ALPHABET[(group >> (18 - 6 * index) & 0x3f) as usize] as char);
+            } else {
+                text.push('=');
+            }
+        }
+    }
+    text
+}
+
+/// Decodes the base64 text `text`, ignoring whitespace, or `None` if it is not
+/// valid base64.
+#[cfg(feature = "cli")]
+fn decode_base64(text: &str) -> Option<Vec<u8>> {
+    let digits: Vec<u8> = text
+        .bytes()
+        .filter(|byte| !byte.is_ascii_whitespace())
+        .collect();
+    if !digits.len().is_multiple_of(4) {
+        return None;
+    }
+    let padding = digits
+        .iter()
+        .rev()
+        .take_while(|digit| **digit == b'=')
+        .count();
+    if padding > 2 {
+        return None;
+    }
+    let mut bytes = Vec::with_capacity(digits.len() / 4 * 3);
+    for (chunk_index, chunk) in digits.chunks(4).enumerate() {
+        let is_last = chunk_index == digits.len() / 4 - 1;
+        let mut group = 0u32;
+        for (index, digit) in chunk.iter().enumerate() {
+            let value = match digit {
+                b'=' if is_last && index >= 4 - padding => 0,
+                _ => BASE64_ALPHABET.iter().position(|c| c == digit)? as u32,
+            };
+            group |= value << (18 - 6 * index);
+        }
+        let length = if is_last { 3 - padding } else { 3 };
+        bytes.extend((0..length).map(|index| (group >> (16 - 8 * index)) as u8));
+    }
+    Some(bytes)
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct BinaryType {
+    /// The allowed number of octets.
+    pub(crate) length: RangeSet<i128>,
+}
+
+impl TypeTrait for BinaryType {
+    fn get_rust_type(&self) -> &'static str {
+        "Vec<u8>"
+    }
+
+    fn derive(&self, model: &Type, error_context: &mut ErrorContext) -> Result<Self, ()> {
+        let unused_fields = collect_unused_fields!(
+            model,
+            [
+                base,
+                bit,
+                r#enum,
+                fraction_digits,
+                range,
+                path,
+                pattern,
+                require_instance,
+                r#type
+            ]
+        );
+
+        if !unused_fields.is_empty() {
+            error_context.add_warning((0, 0), format!("Unused fields: {:?}", unused_fields));
+        }
+
+        let length = derive_range(
+            &self.length,
+            model
+                .length
+                .as_ref()
+                .map(|length| (&length.length_expression, length.argument_span)),
+            0,
+            error_context,
+        )?;
+        Ok(BinaryType { length })
+    }
+
+    #[cfg(feature = "cli")]
+    fn parse_value(&self, text: &str) -> Result<Value, String> {
+        let Some(bytes) = decode_base64(text) else {
+            return Err(format!("Invalid base64 value {:?}", text));
+        };
+        if !self.length.contains(bytes.len() as i128) {
+            return Err(format!("Length of {:?} is out of range", text));
+        }
+        Ok(Value::Binary(bytes))
+    }
+}
+
+#[cfg(all(test, feature = "cli"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_base64() {
+        for (bytes, text) in [
+            (&b""[..], ""),
+            (b"f", "Zg=="),
+            (b"fo", "Zm8="),
+            (b"foo", "Zm9v"),
+            (b"foobar", "Zm9vYmFy"),
+        ] {
+            assert_eq!(encode_base64(bytes), text);
+            assert_eq!(decode_base64(text).as_deref(), Some(bytes));
+        }
+        assert_eq!(decode_base64("Zm9v\nYmFy").as_deref(), Some(&b"foobar"[..]));
+        for text in ["Zm9", "Zm9v!A==", "Z===", "Zg==Zm8=", "=Zg="] {
+            assert_eq!(decode_base64(text), None, "{}", text);
+        }
+    }
+}
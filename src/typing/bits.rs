@@ -0,0 +1,115 @@
+use crate::errors::ErrorContext;
+use crate::parser::model::Type;
+use crate::typing::{collect_unused_fields, TypeTrait};
+#[cfg(feature = "cli")]
+use crate::typing::Value;
+use std::collections::HashSet;
+
+#[derive(Debug, Clone)]
+pub(crate) struct BitsType {
+    pub(crate) items: Option<Vec<(String, u32)>>,
+}
+
+impl TypeTrait for BitsType {
+    fn get_rust_type(&self) -> &'static str {
+        "Vec<String>"
+    }
+
+    fn derive(&self, model: &Type, error_context: &mut ErrorContext) -> Result<Self, ()> {
+        let unused_fields = collect_unused_fields!(
+            model,
+            [
+                base,
+                r#enum,
+                fraction_digits,
+                length,
+                range,
+                path,
+                pattern,
+                require_instance,
+                r#type
+            ]
+        );
+
+        if !unused_fields.is_empty() {
+            error_context.add_warning((0, 0), format!("Unused fields: {:?}", unused_fields));
+        }
+
+        // Without bits of its own, the type stays the base type, and is abstract if
+        // that is `bits` itself.
+        if model.bit.is_empty() {
+            return Ok(self.clone());
+        }
+        let is_subtype = !self.is_abstract_type();
+
+        if HashSet::<&str>::from_iter(model.bit.iter().map(|bit| bit.name.as_str())).len()
+            != model.bit.len()
+        {
+            error_context.add_error((0, 0), "Duplicate bit names".to_string());
+            return Err(());
+        }
+        let mut items: Vec<(String, u32)> = Vec::with_capacity(model.bit.len());
+        if !is_subtype {
+            let mut next_position = 0;
+            for bit in model.bit.iter() {
+                let position = bit.position.unwrap_or(next_position);
+                if items.iter().any(|(_, p)| *p == position) {
+                    error_context.add_error(
+                        (0, 0),
+                        format!("Duplicate position {} for bit {}", position, bit.name),
+                    );
+                    return Err(());
+                }
+                next_position = next_position.max(position.saturating_add(1));
+                items.push((bit.name.clone(), position));
+            }
+        } else {
+            for bit in model.bit.iter() {
+                let Some((_, base_position)) =
+                    self.items
+                        .as_deref()
+                        .unwrap_or(&[])
+                        .iter()
+                        .find(|(name, position)| {
+                            name == &bit.name
+                                && bit
+                                    .position
+                                    .map(|base_position| base_position == *position)
+                                    .unwrap_or(true)
+                        })
+                else {
+                    error_context.add_error((0, 0), format!("Bit not in base bits {}", bit.name));
+                    return Err(());
+                };
+                items.push((bit.name.clone(), *base_position));
+            }
+        }
+
+        Ok(BitsType { items: Some(items) })
+    }
+
+    fn is_abstract_type(&self) -> bool {
+        self.items.is_none()
+    }
+
+    /// Parses a space-separated set of bit names, returned in the order of their
+    /// positions.
+    #[cfg(feature = "cli")]
+    fn parse_value(&self, text: &str) -> Result<Value, String> {
+        let items = self.items.as_deref().unwrap_or(&[]);
+        let mut bits: Vec<&(String, u32)> = vec![];
+        for name in text.split_whitespace() {
+            let Some(item) = items.iter().find(|(item, _)| item == name) else {
+                return Err(format!("Invalid bit {}", name));
+            };
+            if bits.iter().any(|(bit, _)| bit == name) {
+                return Err(format!("Duplicate bit {}", name));
+            }
+            bits.push(item);
+        }
+        bits.sort_by_key(|(_, position)| *position);
+        Ok(Value::Bits(
+            bits.into_iter().map(|(name, _)| name.clone()).collect(),
+        ))
+    }
+}
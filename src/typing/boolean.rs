@@ -1,7 +1,10 @@
 use crate::errors::ErrorContext;
 use crate::parser::model::Type;
 use crate::typing::{collect_unused_fields, TypeTrait};
+#[cfg(feature = "cli")]
+use crate::typing::Value;
 
+#[derive(Debug, Clone)]
 pub(crate) struct BoolType {}
 
 impl TypeTrait for BoolType {
@@ -31,4 +34,13 @@ impl TypeTrait for BoolType {
         }
         Ok(Self {})
     }
+
+    #[cfg(feature = "cli")]
+    fn parse_value(&self, text: &str) -> Result<Value, String> {
+        match text {
+            "true" => Ok(Value::Bool(true)),
+            "false" => Ok(Value::Bool(false)),
+            _ => Err(format!("Invalid boolean {}", text)),
+        }
+    }
 }
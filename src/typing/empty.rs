@@ -0,0 +1,45 @@
+use crate::errors::ErrorContext;
+use crate::parser::model::Type;
+use crate::typing::{collect_unused_fields, TypeTrait};
+#[cfg(feature = "cli")]
+use crate::typing::Value;
+
+#[derive(Debug, Clone)]
+pub(crate) struct EmptyType {}
+
+impl TypeTrait for EmptyType {
+    fn get_rust_type(&self) -> &'static str {
+        "()"
+    }
+
+    fn derive(&self, model: &Type, error_context: &mut ErrorContext) -> Result<Self, ()> {
+        let unused_fields = collect_unused_fields!(
+            model,
+            [
+                base,
+                bit,
+                r#enum,
+                fraction_digits,
+                length,
+                range,
+                path,
+                pattern,
+                require_instance,
+                r#type
+            ]
+        );
+
+        if !unused_fields.is_empty() {
+            error_context.add_warning((0, 0), format!("Unused fields: {:?}", unused_fields));
+        }
+        Ok(Self {})
+    }
+
+    #[cfg(feature = "cli")]
+    fn parse_value(&self, text: &str) -> Result<Value, String> {
+        if !text.is_empty() {
+            return Err(format!("Unexpected value {} for empty type", text));
+        }
+        Ok(Value::Empty)
+    }
+}
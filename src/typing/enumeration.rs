@@ -1,9 +1,12 @@
 use crate::errors::ErrorContext;
 use crate::parser::model::Type;
 use crate::typing::{collect_unused_fields, TypeTrait};
+#[cfg(feature = "cli")]
+use crate::typing::Value;
 use std::cmp::max;
 use std::collections::HashSet;
 
+#[derive(Debug, Clone)]
 pub(crate) struct EnumerationType {
     pub(crate) items: Option<Vec<(String, i32)>>,
 }
@@ -34,6 +37,9 @@ impl TypeTrait for EnumerationType {
         }
 
         let is_subtype = !self.is_abstract_type();
+        if is_subtype && model.r#enum.is_empty() {
+            return Ok(self.clone());
+        }
 
         if HashSet::<&str>::from_iter(model.r#enum.iter().map(|item| item.name.as_str())).len()
             != model.r#enum.len()
@@ -63,10 +69,9 @@ impl TypeTrait for EnumerationType {
             }
         } else {
             for r#enum in model.r#enum.iter() {
-                if !self
+                let Some((_, base_value)) = self
                     .items
-                    .as_ref()
-                    .map(|items| items.as_slice())
+                    .as_deref()
                     .unwrap_or(&[])
                     .iter()
                     .find(|(name, value)| {
@@ -76,13 +81,12 @@ impl TypeTrait for EnumerationType {
                                 .map(|base_value| base_value == *value)
                                 .unwrap_or(true)
                     })
-                    .is_some()
-                {
+                else {
                     error_context
                         .add_error((0, 0), format!("Enum not in base enum {}", r#enum.name));
                     return Err(());
-                }
-                items.push((r#enum.name.clone(), 0));
+                };
+                items.push((r#enum.name.clone(), *base_value));
             }
         };
 
@@ -92,4 +96,12 @@ impl TypeTrait for EnumerationType {
     fn is_abstract_type(&self) -> bool {
         self.items.is_none()
     }
+
+    #[cfg(feature = "cli")]
+    fn parse_value(&self, text: &str) -> Result<Value, String> {
+        if !self.items.iter().flatten().any(|(name, _)| name == text) {
+            return Err(format!("Invalid enum {}", text));
+        }
+        Ok(Value::Enumeration(text.to_string()))
+    }
 }
@@ -0,0 +1,72 @@
+use crate::errors::ErrorContext;
+use crate::parser::model::Type;
+use crate::typing::{collect_unused_fields, TypeTrait};
+#[cfg(feature = "cli")]
+use crate::typing::Value;
+
+#[derive(Debug, Clone)]
+pub(crate) struct IdentityrefType {
+    pub(crate) bases: Vec<String>,
+}
+
+impl TypeTrait for IdentityrefType {
+    fn get_rust_type(&self) -> &'static str {
+        "String"
+    }
+
+    fn derive(&self, model: &Type, error_context: &mut ErrorContext) -> Result<Self, ()> {
+        let unused_fields = collect_unused_fields!(
+            model,
+            [
+                bit,
+                r#enum,
+                fraction_digits,
+                length,
+                range,
+                path,
+                pattern,
+                require_instance,
+                r#type
+            ]
+        );
+
+        if !unused_fields.is_empty() {
+            error_context.add_warning((0, 0), format!("Unused fields: {:?}", unused_fields));
+        }
+
+        match &model.base {
+            Some(_) if !self.is_abstract_type() => {
+                error_context.add_error(
+                    model.argument_span,
+                    "Base of a derived identityref cannot be changed".to_string(),
+                );
+                Err(())
+            }
+            Some(base) => Ok(Self {
+                bases: vec![base.clone()],
+            }),
+            None => Ok(self.clone()),
+        }
+    }
+
+    fn is_abstract_type(&self) -> bool {
+        self.bases.is_empty()
+    }
+
+    /// Splits `text` into its prefix and identity, deriving from the bases is
+    /// checked against the identities of the schema.
+    #[cfg(feature = "cli")]
+    fn parse_value(&self, text: &str) -> Result<Value, String> {
+        let (prefix, name) = match text.split_once(':') {
+            Some((prefix, name)) => (Some(prefix.to_string()), name),
+            None => (None, text),
+        };
+        if name.is_empty() || prefix.as_deref() == Some("") {
+            return Err(format!("Invalid identity {}", text));
+        }
+        Ok(Value::Identityref {
+            prefix,
+            name: name.to_string(),
+        })
+    }
+}
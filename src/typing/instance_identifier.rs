@@ -0,0 +1,77 @@
+use crate::errors::ErrorContext;
+use crate::parser::model::Type;
+use crate::typing::{collect_unused_fields, TypeTrait};
+#[cfg(feature = "cli")]
+use crate::typing::Value;
+
+#[derive(Debug, Clone)]
+pub(crate) struct InstanceIdentifierType {
+    pub(crate) require_instance: bool,
+}
+
+impl TypeTrait for InstanceIdentifierType {
+    fn get_rust_type(&self) -> &'static str {
+        "String"
+    }
+
+    fn derive(&self, model: &Type, error_context: &mut ErrorContext) -> Result<Self, ()> {
+        let unused_fields = collect_unused_fields!(
+            model,
+            [
+                base,
+                bit,
+                r#enum,
+                fraction_digits,
+                length,
+                range,
+                path,
+                pattern,
+                r#type
+            ]
+        );
+
+        if !unused_fields.is_empty() {
+            error_context.add_warning((0, 0), format!("Unused fields: {:?}", unused_fields));
+        }
+        Ok(Self {
+            require_instance: model.require_instance.unwrap_or(self.require_instance),
+        })
+    }
+
+    /// Checks that `text` is an absolute path of qualified node names, with
+    /// the prefixes being module names. Whether the instance exists is not
+    /// checked.
+    #[cfg(feature = "cli")]
+    fn parse_value(&self, text: &str) -> Result<Value, String> {
+        let invalid = || format!("Invalid instance identifier {}", text);
+        if !text.starts_with('/') {
+            return Err(invalid());
+        }
+        let mut quote = None;
+        let mut depth = 0;
+        let mut step_start = true;
+        for char in text[1..].chars() {
+            match (quote, char) {
+                (Some(open), char) if char == open => quote = None,
+                (Some(_), _) => {}
+                (None, '\'' | '"') => quote = Some(char),
+                (None, '[') => depth += 1,
+                (None, ']') if depth > 0 => depth -= 1,
+                (None, ']') => return Err(invalid()),
+                (None, '/') if depth == 0 => {
+                    if step_start {
+                        return Err(invalid());
+                    }
+                    step_start = true;
+                    continue;
+                }
+                _ => {}
+            }
+            step_start = false;
+        }
+        if step_start || quote.is_some() || depth > 0 {
+            return Err(invalid());
+        }
+        Ok(Value::InstanceIdentifier(text.to_string()))
+    }
+}
@@ -0,0 +1,54 @@
+use crate::errors::ErrorContext;
+use crate::parser::model;
+use crate::typing::{collect_unused_fields, Type, TypeTrait};
+#[cfg(feature = "cli")]
+use crate::typing::Value;
+
+#[derive(Clone)]
+pub(crate) struct LeafrefType {
+    pub(crate) require_instance: bool,
+    /// The type of the leaf the path refers to, if it is known.
+    pub(crate) target: Option<Box<Type>>,
+}
+
+impl TypeTrait for LeafrefType {
+    fn get_rust_type(&self) -> &'static str {
+        self.target
+            .as_ref()
+            .map_or("String", |target| target.get_rust_type())
+    }
+
+    fn derive(&self, model: &model::Type, error_context: &mut ErrorContext) -> Result<Self, ()> {
+        let unused_fields = collect_unused_fields!(
+            model,
+            [
+                base,
+                bit,
+                r#enum,
+                fraction_digits,
+                length,
+                range,
+                pattern,
+                r#type
+            ]
+        );
+
+        if !unused_fields.is_empty() {
+            error_context.add_warning((0, 0), format!("Unused fields: {:?}", unused_fields));
+        }
+        Ok(Self {
+            require_instance: model.require_instance.unwrap_or(self.require_instance),
+            target: self.target.clone(),
+        })
+    }
+
+    /// Parses `text` as a value of the target type, or as a string if that is
+    /// not known. Whether the instance exists is not checked.
+    #[cfg(feature = "cli")]
+    fn parse_value(&self, text: &str) -> Result<Value, String> {
+        match &self.target {
+            Some(target) => target.parse_value(text),
+            None => Ok(Value::String(text.to_string())),
+        }
+    }
+}
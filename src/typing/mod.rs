@@ -1,11 +1,17 @@
 use crate::errors::ErrorContext;
 use crate::parser::model;
-use crate::typing::range::IntRangePart;
 use phf::phf_map;
 use std::slice::Iter;
 use crate::typing::number::{DecimalType, IntType};
 use crate::typing::string::StringType;
 use crate::typing::enumeration::EnumerationType;
+use crate::typing::empty::EmptyType;
+use crate::typing::identityref::IdentityrefType;
+use crate::typing::instance_identifier::InstanceIdentifierType;
+use crate::typing::bits::BitsType;
+use crate::typing::binary::BinaryType;
+use crate::typing::leafref::LeafrefType;
+use crate::schema::SchemaType;
 
 
 mod number;
@@ -13,6 +19,18 @@ mod range;
 mod string;
 mod boolean;
 mod enumeration;
+mod empty;
+mod identityref;
+mod instance_identifier;
+mod bits;
+mod binary;
+mod union;
+mod leafref;
+
+pub(crate) use range::{RangeElement, RangeSet};
+#[cfg(feature = "cli")]
+pub(crate) use binary::encode_base64;
+pub(crate) use union::UnionType;
 
 #[derive(Debug, Clone)]
 pub(crate) enum SingleItemOrVec<T> {
@@ -58,12 +76,33 @@ impl<'a, T> Iterator for SingleItemOrVecIter<'a, T> {
     }
 }
 
+/// A value checked against a type.
+#[cfg(feature = "cli")]
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Value {
+    Int(i128),
+    Decimal(f64),
+    String(String),
+    Bool(bool),
+    Enumeration(String),
+    Empty,
+    Identityref { prefix: Option<String>, name: String },
+    /// Path with node names qualified by module names, as in RFC 7951.
+    InstanceIdentifier(String),
+    /// Names of the bits set, in the order of their positions.
+    Bits(Vec<String>),
+    Binary(Vec<u8>),
+}
+
 trait TypeTrait: Sized {
     fn get_rust_type(&self) -> &'static str;
     fn derive(&self, model: &model::Type, error_context: &mut ErrorContext) -> Result<Self, ()>;
     fn is_abstract_type(&self) -> bool {
         false
     }
+    /// Parses the lexical representation `text` of a value of this type.
+    #[cfg(feature = "cli")]
+    fn parse_value(&self, text: &str) -> Result<Value, String>;
 }
 
 macro_rules! collect_unused_fields {
@@ -71,7 +110,7 @@ macro_rules! collect_unused_fields {
         {
             let mut unused_fields = vec![];
             $(
-                if !$model.$field.iter().next().is_some() {
+                if $model.$field.iter().next().is_some() {
                     unused_fields.push(stringify!($field));
                 }
             )*
@@ -83,45 +122,241 @@ macro_rules! collect_unused_fields {
 pub(crate) use collect_unused_fields;
 use crate::typing::boolean::BoolType;
 
-enum Type {
+#[derive(Clone)]
+pub(crate) enum Type {
     Int(IntType),
     Decimal(DecimalType),
     String(StringType),
     Bool(BoolType),
     Enumeration(EnumerationType),
+    Empty(EmptyType),
+    Identityref(IdentityrefType),
+    InstanceIdentifier(InstanceIdentifierType),
+    Bits(BitsType),
+    Binary(BinaryType),
+    Union(UnionType),
+    Leafref(LeafrefType),
+}
+
+impl Type {
+    pub(crate) fn get_rust_type(&self) -> &'static str {
+        match self {
+            Type::Int(int_type) => int_type.get_rust_type(),
+            Type::Decimal(decimal_type) => decimal_type.get_rust_type(),
+            Type::String(string_type) => string_type.get_rust_type(),
+            Type::Bool(bool_type) => bool_type.get_rust_type(),
+            Type::Enumeration(enumeration_type) => enumeration_type.get_rust_type(),
+            Type::Empty(empty_type) => empty_type.get_rust_type(),
+            Type::Identityref(identityref_type) => identityref_type.get_rust_type(),
+            Type::InstanceIdentifier(instance_identifier_type) => {
+                instance_identifier_type.get_rust_type()
+            }
+            Type::Bits(bits_type) => bits_type.get_rust_type(),
+            Type::Binary(binary_type) => binary_type.get_rust_type(),
+            Type::Union(union_type) => union_type.get_rust_type(),
+            Type::Leafref(leafref_type) => leafref_type.get_rust_type(),
+        }
+    }
+
+    fn derive(&self, model: &model::Type, error_context: &mut ErrorContext) -> Result<Self, ()> {
+        Ok(match self {
+            Type::Int(int_type) => Type::Int(int_type.derive(model, error_context)?),
+            Type::Decimal(decimal_type) => Type::Decimal(decimal_type.derive(model, error_context)?),
+            Type::String(string_type) => Type::String(string_type.derive(model, error_context)?),
+            Type::Bool(bool_type) => Type::Bool(bool_type.derive(model, error_context)?),
+            Type::Enumeration(enumeration_type) => {
+                Type::Enumeration(enumeration_type.derive(model, error_context)?)
+            }
+            Type::Empty(empty_type) => Type::Empty(empty_type.derive(model, error_context)?),
+            Type::Identityref(identityref_type) => {
+                Type::Identityref(identityref_type.derive(model, error_context)?)
+            }
+            Type::InstanceIdentifier(instance_identifier_type) => {
+                Type::InstanceIdentifier(instance_identifier_type.derive(model, error_context)?)
+            }
+            Type::Bits(bits_type) => Type::Bits(bits_type.derive(model, error_context)?),
+            Type::Binary(binary_type) => Type::Binary(binary_type.derive(model, error_context)?),
+            Type::Union(union_type) => Type::Union(union_type.derive(model, error_context)?),
+            Type::Leafref(leafref_type) => Type::Leafref(leafref_type.derive(model, error_context)?),
+        })
+    }
+
+    fn is_abstract_type(&self) -> bool {
+        match self {
+            Type::Int(int_type) => int_type.is_abstract_type(),
+            Type::Decimal(decimal_type) => decimal_type.is_abstract_type(),
+            Type::String(string_type) => string_type.is_abstract_type(),
+            Type::Bool(bool_type) => bool_type.is_abstract_type(),
+            Type::Enumeration(enumeration_type) => enumeration_type.is_abstract_type(),
+            Type::Empty(empty_type) => empty_type.is_abstract_type(),
+            Type::Identityref(identityref_type) => identityref_type.is_abstract_type(),
+            Type::InstanceIdentifier(instance_identifier_type) => {
+                instance_identifier_type.is_abstract_type()
+            }
+            Type::Bits(bits_type) => bits_type.is_abstract_type(),
+            Type::Binary(binary_type) => binary_type.is_abstract_type(),
+            Type::Union(union_type) => union_type.is_abstract_type(),
+            Type::Leafref(leafref_type) => leafref_type.is_abstract_type(),
+        }
+    }
+
+    #[cfg(feature = "cli")]
+    pub(crate) fn parse_value(&self, text: &str) -> Result<Value, String> {
+        match self {
+            Type::Int(int_type) => int_type.parse_value(text),
+            Type::Decimal(decimal_type) => decimal_type.parse_value(text),
+            Type::String(string_type) => string_type.parse_value(text),
+            Type::Bool(bool_type) => bool_type.parse_value(text),
+            Type::Enumeration(enumeration_type) => enumeration_type.parse_value(text),
+            Type::Empty(empty_type) => empty_type.parse_value(text),
+            Type::Identityref(identityref_type) => identityref_type.parse_value(text),
+            Type::InstanceIdentifier(instance_identifier_type) => {
+                instance_identifier_type.parse_value(text)
+            }
+            Type::Bits(bits_type) => bits_type.parse_value(text),
+            Type::Binary(binary_type) => binary_type.parse_value(text),
+            Type::Union(union_type) => union_type.parse_value(text),
+            Type::Leafref(leafref_type) => leafref_type.parse_value(text),
+        }
+    }
+}
+
+/// The built-in types of RFC 7950, section 4.2.4.
+pub(crate) static BUILT_IN_TYPE_NAMES: &[&str] = &[
+    "binary",
+    "bits",
+    "boolean",
+    "decimal64",
+    "empty",
+    "enumeration",
+    "identityref",
+    "instance-identifier",
+    "int8",
+    "int16",
+    "int32",
+    "int64",
+    "leafref",
+    "string",
+    "uint8",
+    "uint16",
+    "uint32",
+    "uint64",
+    "union",
+];
+
+/// Resolves `schema_type` by deriving its built-in type along its typedefs.
+pub(crate) fn resolve_type(
+    schema_type: &SchemaType,
+    error_context: &mut ErrorContext,
+) -> Result<Type, ()> {
+    resolve(schema_type, false, error_context)
+}
+
+/// Resolves `schema_type` like [`resolve_type`], but only reports the errors of its
+/// own restrictions. Those of the typedefs it derives from are reported where the
+/// typedefs are defined, and unresolved typedefs where they are referred to.
+pub(crate) fn check_type(
+    schema_type: &SchemaType,
+    error_context: &mut ErrorContext,
+) -> Result<Type, ()> {
+    resolve(schema_type, true, error_context)
+}
+
+fn resolve(
+    schema_type: &SchemaType,
+    own_only: bool,
+    error_context: &mut ErrorContext,
+) -> Result<Type, ()> {
+    let base_type = schema_type
+        .typedefs
+        .last()
+        .map_or(&schema_type.r#type, |typedef| &typedef.r#type);
+    let base_name = base_type.name.as_deref().unwrap_or_default();
+    let Some(built_in_type) = BUILD_IN_TYPES.get(base_name) else {
+        if !own_only {
+            error_context.add_error(
+                base_type.argument_span,
+                format!("Type {} is not supported", base_name),
+            );
+        }
+        return Err(());
+    };
+
+    let derived = own_only && !schema_type.typedefs.is_empty();
+    let mut silenced = ErrorContext::new();
+    let base_context = if derived {
+        &mut silenced
+    } else {
+        &mut *error_context
+    };
+    let mut resolved = match built_in_type {
+        Type::Union(_) => Type::Union(UnionType {
+            members: schema_type
+                .members
+                .iter()
+                .map(|member| resolve(member, own_only, base_context))
+                .collect::<Result<_, _>>()?,
+        }),
+        // Errors of the target type are reported for the leaf it belongs to.
+        Type::Leafref(leafref_type) => Type::Leafref(LeafrefType {
+            require_instance: leafref_type.require_instance,
+            target: schema_type
+                .target
+                .as_deref()
+                .and_then(|target| resolve_type(target, &mut ErrorContext::new()).ok())
+                .map(Box::new),
+        }),
+        built_in_type => built_in_type.clone(),
+    };
+    for typedef in schema_type.typedefs.iter().rev() {
+        resolved = resolved.derive(&typedef.r#type, base_context)?;
+    }
+    // A typedef without the restrictions its base type requires is reported itself.
+    if derived && resolved.is_abstract_type() {
+        return Err(());
+    }
+    resolved = resolved.derive(&schema_type.r#type, error_context)?;
+    if resolved.is_abstract_type() {
+        error_context.add_error(
+            schema_type.r#type.argument_span,
+            format!("Type {} is missing restrictions", base_name),
+        );
+        return Err(());
+    }
+    Ok(resolved)
 }
 
 static BUILD_IN_TYPES: phf::Map<&'static str, Type> = phf_map! {
     "uint8" => Type::Int(IntType {
-        range: SingleItemOrVec::Single(IntRangePart { min_value: 0, max_value: 8 }),
+        range: RangeSet::single(0, 255),
         rust_type: "u8"
     }),
      "uint16" => Type::Int(IntType {
-        range: SingleItemOrVec::Single(IntRangePart { min_value: 0, max_value: 16 }),
+        range: RangeSet::single(0, 65535),
         rust_type: "u16"
     }),
      "uint32" => Type::Int(IntType {
-        range: SingleItemOrVec::Single(IntRangePart { min_value: 0, max_value: 32 }),
+        range: RangeSet::single(0, 4294967295),
         rust_type: "u32"
     }),
      "uint64" => Type::Int(IntType {
-        range: SingleItemOrVec::Single(IntRangePart { min_value: 0, max_value: 64 }),
+        range: RangeSet::single(0, 18446744073709551615),
         rust_type: "u64"
     }),
      "int8" => Type::Int(IntType {
-        range: SingleItemOrVec::Single(IntRangePart { min_value: -128, max_value: 127 }),
+        range: RangeSet::single(-128, 127),
         rust_type: "i8"
     }),
      "int16" => Type::Int(IntType {
-        range: SingleItemOrVec::Single(IntRangePart { min_value: -32768, max_value: 32767 }),
+        range: RangeSet::single(-32768, 32767),
         rust_type: "i16"
     }),
      "int32" => Type::Int(IntType {
-        range: SingleItemOrVec::Single(IntRangePart { min_value: -2147483648, max_value: 2147483647 }),
+        range: RangeSet::single(-2147483648, 2147483647),
         rust_type: "i32"
     }),
      "int64" => Type::Int(IntType {
-        range: SingleItemOrVec::Single(IntRangePart { min_value: -9223372036854775808, max_value: 9223372036854775807 }),
+        range: RangeSet::single(-9223372036854775808, 9223372036854775807),
         rust_type: "i64"
     }),
     "decimal64" => Type::Decimal(DecimalType {
@@ -129,9 +364,18 @@ static BUILD_IN_TYPES: phf::Map<&'static str, Type> = phf_map! {
         fraction_digits: None
     }),
     "string" => Type::String(StringType {
-        length: SingleItemOrVec::Single(IntRangePart { min_value: 0, max_value: usize::MAX as i128 }),
+        length: RangeSet::single(0, usize::MAX as i128),
         pattern: None
     }),
     "boolean" => Type::Bool(BoolType {}),
     "enumeration" => Type::Enumeration(EnumerationType { items: None }),
+    "empty" => Type::Empty(EmptyType {}),
+    "identityref" => Type::Identityref(IdentityrefType { bases: Vec::new() }),
+    "instance-identifier" => Type::InstanceIdentifier(InstanceIdentifierType { require_instance: true }),
+    "bits" => Type::Bits(BitsType { items: None }),
+    "binary" => Type::Binary(BinaryType {
+        length: RangeSet::single(0, usize::MAX as i128)
+    }),
+    "union" => Type::Union(UnionType { members: Vec::new() }),
+    "leafref" => Type::Leafref(LeafrefType { require_instance: true, target: None }),
 };
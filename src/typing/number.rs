@@ -1,11 +1,13 @@
 use crate::errors::ErrorContext;
 use crate::parser::model;
-use crate::typing::range::{derive_range, DecimalRangePart, IntRangePart, RangePart};
-use crate::typing::{collect_unused_fields, SingleItemOrVec, TypeTrait};
+use crate::typing::range::{derive_range, RangeSet};
+use crate::typing::{collect_unused_fields, TypeTrait};
+#[cfg(feature = "cli")]
+use crate::typing::Value;
 
 #[derive(Debug, Clone)]
 pub(crate) struct IntType {
-    pub(crate) range: SingleItemOrVec<IntRangePart>,
+    pub(crate) range: RangeSet<i128>,
     pub(crate) rust_type: &'static str,
 }
 
@@ -36,7 +38,11 @@ impl TypeTrait for IntType {
 
         let range = derive_range(
             &self.range,
-            model.range.as_ref().map(|range| &range.range_expression),
+            model
+                .range
+                .as_ref()
+                .map(|range| (&range.range_expression, range.argument_span)),
+            0,
             error_context,
         )?;
 
@@ -45,11 +51,23 @@ impl TypeTrait for IntType {
             rust_type: self.rust_type,
         })
     }
+
+    #[cfg(feature = "cli")]
+    fn parse_value(&self, text: &str) -> Result<Value, String> {
+        let value: i128 = text
+            .parse()
+            .map_err(|_| format!("Invalid integer {}", text))?;
+        if !self.range.contains(value) {
+            return Err(format!("Value {} is out of range", text));
+        }
+        Ok(Value::Int(value))
+    }
 }
 
 #[derive(Debug, Clone)]
 pub(crate) struct DecimalType {
-    pub(crate) range: Option<SingleItemOrVec<DecimalRangePart>>,
+    /// The allowed values, scaled by `10^fraction_digits` to be exact.
+    pub(crate) range: Option<RangeSet<i128>>,
     pub(crate) fraction_digits: Option<u8>,
 }
 
@@ -80,10 +98,10 @@ impl TypeTrait for DecimalType {
         let fraction_digits = model
             .fraction_digits
             .map(|fraction_digits| {
-                if fraction_digits < 0 || fraction_digits > 18 {
+                if !(1..=18).contains(&fraction_digits) {
                     error_context.add_error(
                         (0, 0),
-                        "Fraction digits must be between 0 and 18".to_string(),
+                        "Fraction digits must be between 1 and 18".to_string(),
                     );
                     return Err(());
                 }
@@ -92,21 +110,17 @@ impl TypeTrait for DecimalType {
             .transpose()?
             .or(self.fraction_digits);
 
-        let fraction_range = fraction_digits.map(|fraction_digits| {
-            SingleItemOrVec::Single(RangePart {
-                min_value: 10usize.pow(fraction_digits as u32 - 18) as f64 * 9.223372036854775808,
-                max_value: 10usize.pow(fraction_digits as u32 - 18) as f64 * -9.223372036854775808,
-            })
-        });
+        let fraction_range = RangeSet::single(i64::MIN as i128, i64::MAX as i128);
 
-        let range = self
-            .range
-            .as_ref()
-            .or(fraction_range.as_ref())
-            .map(|range| {
+        let range = fraction_digits
+            .map(|fraction_digits| {
                 derive_range(
-                    range,
-                    model.range.as_ref().map(|range| &range.range_expression),
+                    self.range.as_ref().unwrap_or(&fraction_range),
+                    model
+                        .range
+                        .as_ref()
+                        .map(|range| (&range.range_expression, range.argument_span)),
+                    fraction_digits,
                     error_context,
                 )
             })
@@ -121,4 +135,56 @@ impl TypeTrait for DecimalType {
     fn is_abstract_type(&self) -> bool {
         self.range.is_none() || self.fraction_digits.is_none()
     }
+
+    #[cfg(feature = "cli")]
+    fn parse_value(&self, text: &str) -> Result<Value, String> {
+        let unsigned = text.strip_prefix(['-', '+']).unwrap_or(text);
+        let (integer, fraction) = unsigned.split_once('.').unwrap_or((unsigned, ""));
+        let is_digits = |part: &str| part.chars().all(|c| c.is_ascii_digit());
+        if integer.is_empty()
+            || !is_digits(integer)
+            || !is_digits(fraction)
+            || unsigned.ends_with('.')
+        {
+            return Err(format!("Invalid decimal {}", text));
+        }
+        let value: f64 = text
+            .parse()
+            .map_err(|_| format!("Invalid decimal {}", text))?;
+        let fraction_digits = fraction.len();
+        if self
+            .fraction_digits
+            .is_some_and(|max_digits| fraction_digits > max_digits as usize)
+        {
+            return Err(format!("Decimal {} has too many fraction digits", text));
+        }
+        if let (Some(range), Some(fraction_digits)) = (&self.range, self.fraction_digits) {
+            if !scale_decimal(text, fraction_digits).is_some_and(|value| range.contains(value)) {
+                return Err(format!("Value {} is out of range", text));
+            }
+        }
+        Ok(Value::Decimal(value))
+    }
+}
+
+/// The decimal `text` as an integer scaled by `10^fraction_digits`, if it has no
+/// more fraction digits.
+pub(crate) fn scale_decimal(text: &str, fraction_digits: u8) -> Option<i128> {
+    let (negative, unsigned) = match text.strip_prefix('-') {
+        Some(unsigned) => (true, unsigned),
+        None => (false, text.strip_prefix('+').unwrap_or(text)),
+    };
+    let (integer, fraction) = unsigned.split_once('.').unwrap_or((unsigned, ""));
+    if fraction.len() > fraction_digits as usize {
+        return None;
+    }
+    let value: i128 = format!(
+        "{}{:0<width$}",
+        integer,
+        fraction,
+        width = fraction_digits as usize
+    )
+    .parse()
+    .ok()?;
+    Some(if negative { -value } else { value })
 }
@@ -1,83 +1,161 @@
-use std::ops::Add;
-use std::str::FromStr;
 use crate::errors::ErrorContext;
 use crate::parser::model;
 use crate::parser::model::{LengthBoundary, RangeBoundary};
-use crate::typing::SingleItemOrVec;
-
-pub (crate) type IntRangePart = RangePart<i128>;
-pub (crate) type DecimalRangePart = RangePart<f64>;
+use crate::typing::number::scale_decimal;
+use crate::typing::{SingleItemOrVec, SingleItemOrVecIter};
+use crate::Span;
+use std::str::FromStr;
 
-#[derive(Debug, Clone)]
-pub (crate) struct RangePart<T> {
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct RangePart<T> {
     pub(crate) min_value: T,
     pub(crate) max_value: T,
 }
 
-trait RangeElement: PartialOrd + Copy {
-    fn try_from_i128(value: i128) -> Option<Self>
-    where
-        Self: Sized;
+/// The values of a range or length restriction, as ascending range parts that are
+/// neither overlapping nor adjacent. Two sets with the same values are equal.
+#[derive(Debug, Clone)]
+pub(crate) struct RangeSet<T>(SingleItemOrVec<RangePart<T>>);
 
-    fn try_from_f64(value: f64) -> Option<Self>
-    where
-        Self: Sized;
+impl<T> RangeSet<T> {
+    /// The values from `min_value` to `max_value`, which must not be larger.
+    pub(crate) const fn single(min_value: T, max_value: T) -> Self {
+        RangeSet(SingleItemOrVec::Single(RangePart {
+            min_value,
+            max_value,
+        }))
+    }
 
-    fn next_value(&self) -> Self;
+    pub(crate) fn parts(&self) -> SingleItemOrVecIter<'_, RangePart<T>> {
+        self.0.iter()
+    }
 }
 
-impl RangeElement for i128 {
-    fn try_from_i128(value: i128) -> Option<Self> {
-        Some(value)
+impl<T: RangeElement> RangeSet<T> {
+    /// The values of any of `parts`, which may be unordered, overlapping or empty.
+    pub(crate) fn new(parts: impl IntoIterator<Item = RangePart<T>>) -> Self {
+        let mut parts: Vec<RangePart<T>> = parts
+            .into_iter()
+            .filter(|part| part.min_value <= part.max_value)
+            .collect();
+        parts.sort_by(|a, b| {
+            a.min_value
+                .partial_cmp(&b.min_value)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        let mut normalized: Vec<RangePart<T>> = Vec::with_capacity(parts.len());
+        for part in parts {
+            match normalized.last_mut() {
+                Some(last) if part.min_value <= last.max_value.next_value() => {
+                    if part.max_value > last.max_value {
+                        last.max_value = part.max_value;
+                    }
+                }
+                _ => normalized.push(part),
+            }
+        }
+        RangeSet(SingleItemOrVec::Vec(normalized))
     }
 
-    fn try_from_f64(_value: f64) -> Option<Self> {
-        None
+    pub(crate) fn is_empty(&self) -> bool {
+        self.parts().next().is_none()
     }
 
-    fn next_value(&self) -> Self {
-        self.add(1)
+    /// The smallest value of the set.
+    pub(crate) fn min(&self) -> Option<T> {
+        self.parts().next().map(|part| part.min_value)
+    }
+
+    /// The largest value of the set.
+    pub(crate) fn max(&self) -> Option<T> {
+        self.parts().last().map(|part| part.max_value)
+    }
+
+    #[cfg(any(test, feature = "cli"))]
+    pub(crate) fn contains(&self, value: T) -> bool {
+        self.parts()
+            .any(|part| part.min_value <= value && value <= part.max_value)
+    }
+
+    /// Whether all values of this set are values of `other`.
+    pub(crate) fn is_subset(&self, other: &RangeSet<T>) -> bool {
+        // As the parts of `other` are not adjacent, each part of this set has to be
+        // within a single one of them.
+        self.parts().all(|part| {
+            other.parts().any(|other_part| {
+                other_part.min_value <= part.min_value && part.max_value <= other_part.max_value
+            })
+        })
     }
-}
 
-impl RangeElement for f64 {
-    fn try_from_i128(value: i128) -> Option<Self> {
-        Some(value as f64)
+    pub(crate) fn union(&self, other: &RangeSet<T>) -> RangeSet<T> {
+        RangeSet::new(self.parts().chain(other.parts()).cloned())
+    }
+
+    pub(crate) fn intersection(&self, other: &RangeSet<T>) -> RangeSet<T> {
+        RangeSet::new(self.parts().flat_map(|part| {
+            other.parts().map(|other_part| RangePart {
+                min_value: if part.min_value > other_part.min_value {
+                    part.min_value
+                } else {
+                    other_part.min_value
+                },
+                max_value: if part.max_value < other_part.max_value {
+                    part.max_value
+                } else {
+                    other_part.max_value
+                },
+            })
+        }))
     }
+}
 
-    fn try_from_f64(value: f64) -> Option<Self> {
-        Some(value)
+impl<T: PartialEq> PartialEq for RangeSet<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.parts().eq(other.parts())
     }
+}
 
+pub(crate) trait RangeElement: PartialOrd + Copy {
+    /// The smallest value after this one, or this value if there is none.
+    fn next_value(&self) -> Self;
+}
+
+impl RangeElement for i128 {
     fn next_value(&self) -> Self {
-        *self
+        self.saturating_add(1)
     }
 }
 
-trait ResolveBoundary<T: RangeElement>: Copy + FromStr<Err = String> {
-    fn resolve(boundary: Self, base_range: &SingleItemOrVec<RangePart<T>>) -> Result<T, String>;
+pub(crate) trait ResolveBoundary: Copy + FromStr<Err = String> {
+    /// The value of `boundary`, scaled by `10^fraction_digits` like the values of
+    /// `base_range`.
+    fn resolve(
+        boundary: Self,
+        base_range: &RangeSet<i128>,
+        fraction_digits: u8,
+    ) -> Result<i128, String>;
 
     fn is_min(&self) -> bool;
 
     fn is_max(&self) -> bool;
 }
 
-impl<T: RangeElement> ResolveBoundary<T> for RangeBoundary {
+impl ResolveBoundary for RangeBoundary {
     fn resolve(
         boundary: RangeBoundary,
-        base_range: &SingleItemOrVec<RangePart<T>>,
-    ) -> Result<T, String> {
+        base_range: &RangeSet<i128>,
+        fraction_digits: u8,
+    ) -> Result<i128, String> {
         Ok(match boundary {
-            RangeBoundary::Min => base_range.iter().next().unwrap().min_value,
-            RangeBoundary::Max => base_range.iter().last().unwrap().max_value,
-            RangeBoundary::Integer(i) => T::try_from_i128(i as i128).unwrap(),
-            RangeBoundary::Decimal(d) => {
-                if let Some(d) = T::try_from_f64(d) {
-                    d
-                } else {
-                    return Err("Decimal is not allowed in integer type".to_string());
-                }
+            RangeBoundary::Min => base_range.min().unwrap(),
+            RangeBoundary::Max => base_range.max().unwrap(),
+            RangeBoundary::Integer(i) => i as i128 * 10i128.pow(fraction_digits as u32),
+            RangeBoundary::Decimal(_) if fraction_digits == 0 => {
+                return Err("Decimal is not allowed in integer type".to_string());
             }
+            RangeBoundary::Decimal(d) => scale_decimal(&d.to_string(), fraction_digits)
+                .ok_or_else(|| format!("Decimal {} has too many fraction digits", d))?,
         })
     }
 
@@ -90,15 +168,16 @@ impl<T: RangeElement> ResolveBoundary<T> for RangeBoundary {
     }
 }
 
-impl<T: RangeElement> ResolveBoundary<T> for LengthBoundary {
+impl ResolveBoundary for LengthBoundary {
     fn resolve(
         boundary: LengthBoundary,
-        base_range: &SingleItemOrVec<RangePart<T>>,
-    ) -> Result<T, String> {
+        base_range: &RangeSet<i128>,
+        _fraction_digits: u8,
+    ) -> Result<i128, String> {
         Ok(match boundary {
-            LengthBoundary::Min => base_range.iter().next().unwrap().min_value,
-            LengthBoundary::Max => base_range.iter().last().unwrap().max_value,
-            LengthBoundary::Value(i) => T::try_from_i128(i as i128).unwrap(),
+            LengthBoundary::Min => base_range.min().unwrap(),
+            LengthBoundary::Max => base_range.max().unwrap(),
+            LengthBoundary::Value(i) => i as i128,
         })
     }
 
@@ -111,82 +190,198 @@ impl<T: RangeElement> ResolveBoundary<T> for LengthBoundary {
     }
 }
 
-pub (crate) fn derive_range<T: RangeElement, B: ResolveBoundary<T>>(
-    base_range: &SingleItemOrVec<RangePart<T>>,
-    model_range: Option<&model::LengthRangePattern<B>>,
+/// Restricts `base_range` by the range or length expression `model_range`,
+/// reporting errors at its argument span. Values of decimal types are scaled by
+/// `10^fraction_digits`, which is 0 for integer types and lengths.
+pub(crate) fn derive_range<B: ResolveBoundary>(
+    base_range: &RangeSet<i128>,
+    model_range: Option<(&model::LengthRangePattern<B>, Span)>,
+    fraction_digits: u8,
     error_context: &mut ErrorContext,
-) -> Result<SingleItemOrVec<RangePart<T>>, ()> {
-    let mut last_range_part: Option<(T, T)> = None;
-    Ok(if let Some(range) = model_range.as_ref() {
-        let mut model_range_iter = range.0.iter().enumerate();
-
-        let mut new_range = vec![];
-        for (pos, model_range) in model_range_iter {
-            let model_upper_boundary = model_range
-                .upper_boundary
-                .unwrap_or(model_range.lower_boundary);
-
-            if pos != 0 && model_range.lower_boundary.is_min() {
-                error_context.add_error((0, 0), "Min can only be used in first part".to_string());
-                return Err(());
-            }
+) -> Result<RangeSet<i128>, ()> {
+    let Some((range, span)) = model_range else {
+        return Ok(base_range.clone());
+    };
+    let mut last_max: Option<i128> = None;
+    let mut new_range = vec![];
+    for (pos, model_range) in range.0.iter().enumerate() {
+        let model_upper_boundary = model_range
+            .upper_boundary
+            .unwrap_or(model_range.lower_boundary);
 
-            if pos != range.0.len() - 1 && model_range.lower_boundary.is_max() {
-                error_context.add_error((0, 0), "Max can only be used in last part".to_string());
-                return Err(());
-            }
+        if pos != 0 && model_range.lower_boundary.is_min() {
+            error_context.add_error(span, "Min can only be used in first part".to_string());
+            return Err(());
+        }
 
-            let min = B::resolve(model_range.lower_boundary, base_range).map_err(|e| {
-                error_context.add_error((0, 0), e);
-                ()
-            })?;
-            let max = B::resolve(model_upper_boundary, base_range).map_err(|e| {
-                error_context.add_error((0, 0), e);
-                ()
+        if pos != range.0.len() - 1 && model_range.lower_boundary.is_max() {
+            error_context.add_error(span, "Max can only be used in last part".to_string());
+            return Err(());
+        }
+
+        let min =
+            B::resolve(model_range.lower_boundary, base_range, fraction_digits).map_err(|e| {
+                error_context.add_error(span, e);
             })?;
+        let max = B::resolve(model_upper_boundary, base_range, fraction_digits).map_err(|e| {
+            error_context.add_error(span, e);
+        })?;
 
-            if min > max {
-                error_context.add_error((0, 0), "Min value is larger than max value".to_string());
-                return Err(());
-            }
+        if min > max {
+            error_context.add_error(span, "Min value is larger than max value".to_string());
+            return Err(());
+        }
 
-            if let Some((_last_min, last_max)) = last_range_part {
-                if last_max > min {
-                    error_context.add_error((0, 0), "Range parts not ascending sorted".to_string());
-                    return Err(());
-                }
+        if last_max.is_some_and(|last_max| last_max > min) {
+            error_context.add_error(span, "Range parts not ascending sorted".to_string());
+            return Err(());
+        }
+
+        if !RangeSet::single(min, max).is_subset(base_range) {
+            error_context.add_error(span, "Range is larger than range of base type".to_string());
+            return Err(());
+        }
+        new_range.push(RangePart::<i128> {
+            min_value: min,
+            max_value: max,
+        });
+        last_max = Some(max);
+    }
+    Ok(RangeSet::new(new_range))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::model::LengthRangePattern;
+    use crate::parser::model_mapper::ArgumentMapper;
+    use proptest::prelude::*;
+
+    fn set(parts: &[(i128, i128)]) -> RangeSet<i128> {
+        RangeSet::new(parts.iter().map(|&(min_value, max_value)| RangePart {
+            min_value,
+            max_value,
+        }))
+    }
+
+    fn derive_decimal(
+        base: &RangeSet<i128>,
+        range: &str,
+        fraction_digits: u8,
+    ) -> Result<RangeSet<i128>, ()> {
+        let mut error_context = ErrorContext::new();
+        let range = LengthRangePattern::<RangeBoundary>::map_argument(
+            range.to_string(),
+            (0, 0),
+            &mut error_context,
+        )
+        .unwrap();
+        derive_range(
+            base,
+            Some((&range, (0, 0))),
+            fraction_digits,
+            &mut error_context,
+        )
+    }
+
+    fn derive(base: &RangeSet<i128>, range: &str) -> Result<RangeSet<i128>, ()> {
+        derive_decimal(base, range, 0)
+    }
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(
+            set(&[(5, 9), (0, 2), (3, 4), (8, 12), (20, 19)]),
+            set(&[(0, 12)])
+        );
+        assert_eq!(set(&[(0, 2), (4, 5)]).parts().count(), 2);
+        assert!(set(&[(3, 2)]).is_empty());
+    }
+
+    #[test]
+    fn test_derive() {
+        let base = RangeSet::single(0, 255);
+        assert_eq!(
+            derive(&base, "min..10 | 11..20 | 100..max"),
+            Ok(set(&[(0, 20), (100, 255)]))
+        );
+        assert_eq!(derive(&base, "0..300"), Err(()));
+        assert_eq!(derive(&base, "10..5"), Err(()));
+        assert_eq!(derive(&base, "10..20 | 5"), Err(()));
+        let derived = derive(&base, "1..10 | 20..30").unwrap();
+        assert_eq!(derive(&derived, "5..25"), Err(()));
+        assert_eq!(
+            derive(&derived, "min..5 | 25..max"),
+            Ok(set(&[(1, 5), (25, 30)]))
+        );
+        assert_eq!(derive(&base, "1.5..2"), Err(()));
+    }
+
+    #[test]
+    fn test_derive_decimal() {
+        let base = RangeSet::single(-1000, 1000);
+        assert_eq!(
+            derive_decimal(&base, "1.5..2 | 2.01..3", 2),
+            Ok(set(&[(150, 300)]))
+        );
+        assert_eq!(
+            derive_decimal(&base, "min..-1 | 1.5", 2),
+            Ok(set(&[(-1000, -100), (150, 150)]))
+        );
+        assert_eq!(derive_decimal(&base, "1.505..2", 2), Err(()));
+        assert_eq!(derive_decimal(&base, "1..20", 2), Err(()));
+    }
+
+    fn parts() -> impl Strategy<Value = Vec<(i128, i128)>> {
+        prop::collection::vec((-50i128..50, 0i128..20), 0..6).prop_map(|parts| {
+            parts
+                .into_iter()
+                .map(|(min, length)| (min, min + length))
+                .collect()
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn normalized_parts_are_sorted_and_apart(parts in parts()) {
+            let range_set = set(&parts);
+            let normalized: Vec<&RangePart<i128>> = range_set.parts().collect();
+            for pair in normalized.windows(2) {
+                prop_assert!(pair[0].max_value + 1 < pair[1].min_value);
+            }
+            for value in -60..80 {
+                let in_parts = parts.iter().any(|&(min, max)| min <= value && value <= max);
+                prop_assert_eq!(range_set.contains(value), in_parts);
             }
+        }
 
-            let matched_range = base_range
-                .iter()
-                .find(|range_part| range_part.min_value <= min && range_part.max_value >= max)
-                .is_some();
-
-            if matched_range {
-                if let Some((last_min, _last_max)) =
-                    last_range_part.filter(|(_last_min, last_max)| T::next_value(last_max) == min)
-                {
-                    new_range.pop();
-                    new_range.push(RangePart::<T> {
-                        min_value: last_min,
-                        max_value: max,
-                    });
-                } else {
-                    new_range.push(RangePart::<T> {
-                        min_value: min,
-                        max_value: max,
-                    });
-                }
-            } else {
-                error_context.add_error(
-                    (0, 0),
-                    "Range is larger than range of base type".to_string(),
-                );
+        #[test]
+        fn order_does_not_matter(parts in parts()) {
+            let reversed: Vec<(i128, i128)> = parts.iter().rev().copied().collect();
+            prop_assert_eq!(set(&parts), set(&reversed));
+        }
+
+        #[test]
+        fn union_and_intersection(a in parts(), b in parts()) {
+            let (a, b) = (set(&a), set(&b));
+            let (union, intersection) = (a.union(&b), a.intersection(&b));
+            for value in -60..80 {
+                prop_assert_eq!(union.contains(value), a.contains(value) || b.contains(value));
+                prop_assert_eq!(intersection.contains(value), a.contains(value) && b.contains(value));
             }
-            last_range_part = Some((min, max));
+            prop_assert!(a.is_subset(&union) && b.is_subset(&union));
+            prop_assert!(intersection.is_subset(&a) && intersection.is_subset(&b));
+            prop_assert_eq!(union, b.union(&a));
         }
-        SingleItemOrVec::Vec(new_range)
-    } else {
-        base_range.clone()
-    })
-}
\ No newline at end of file
+
+        #[test]
+        fn subset(a in parts(), b in parts()) {
+            let (a, b) = (set(&a), set(&b));
+            let by_values = (-60..80).all(|value| !a.contains(value) || b.contains(value));
+            prop_assert_eq!(a.is_subset(&b), by_values);
+            prop_assert_eq!(a.is_subset(&b), a.union(&b) == b);
+            prop_assert_eq!(a.is_subset(&b), a.intersection(&b) == a);
+            prop_assert_eq!(a.is_subset(&b) && b.is_subset(&a), a == b);
+        }
+    }
+}
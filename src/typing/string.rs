@@ -1,16 +1,17 @@
-use regex::RegexSet;
 use crate::errors::ErrorContext;
 use crate::parser::model;
-use crate::typing::range::{derive_range, IntRangePart};
-use crate::typing::{collect_unused_fields, SingleItemOrVec, TypeTrait};
+use crate::typing::range::{derive_range, RangeSet};
+use crate::typing::{collect_unused_fields, TypeTrait};
+#[cfg(feature = "cli")]
+use crate::typing::Value;
+use regex::RegexSet;
 
 #[derive(Debug, Clone)]
 pub(crate) struct StringType {
-    pub(crate) length: SingleItemOrVec<IntRangePart>,
+    pub(crate) length: RangeSet<i128>,
     pub(crate) pattern: Option<RegexSet>,
 }
 
-
 impl TypeTrait for StringType {
     fn get_rust_type(&self) -> &'static str {
         "String"
@@ -40,7 +41,8 @@ impl TypeTrait for StringType {
             model
                 .length
                 .as_ref()
-                .map(|length| &length.length_expression),
+                .map(|length| (&length.length_expression, length.argument_span)),
+            0,
             error_context,
         )?;
 
@@ -53,7 +55,13 @@ impl TypeTrait for StringType {
                 .map(|pattern| pattern.patterns())
                 .unwrap_or(&[])
                 .iter()
-                .chain(model.pattern.iter().map(|pattern| &pattern.regex));
+                .cloned()
+                .chain(
+                    model
+                        .pattern
+                        .iter()
+                        .map(|pattern| format!("^(?:{})$", pattern.regex)),
+                );
             Some(RegexSet::new(base_pattern).map_err(|e| {
                 error_context.add_error((0, 0), e.to_string());
                 ()
@@ -64,4 +72,17 @@ impl TypeTrait for StringType {
 
         Ok(StringType { length, pattern })
     }
-}
\ No newline at end of file
+
+    #[cfg(feature = "cli")]
+    fn parse_value(&self, text: &str) -> Result<Value, String> {
+        if !self.length.contains(text.chars().count() as i128) {
+            return Err(format!("Length of {:?} is out of range", text));
+        }
+        if let Some(pattern) = &self.pattern {
+            if pattern.matches(text).iter().count() != pattern.len() {
+                return Err(format!("{:?} does not match pattern", text));
+            }
+        }
+        Ok(Value::String(text.to_string()))
+    }
+}
@@ -0,0 +1,62 @@
+use crate::errors::ErrorContext;
+use crate::parser::model;
+use crate::typing::{collect_unused_fields, Type, TypeTrait};
+#[cfg(feature = "cli")]
+use crate::typing::Value;
+
+#[derive(Clone)]
+pub(crate) struct UnionType {
+    pub(crate) members: Vec<Type>,
+}
+
+impl UnionType {
+    /// The error for a value `text` no member type accepts.
+    #[cfg(feature = "cli")]
+    pub(crate) fn mismatch(text: &str) -> String {
+        format!("{:?} matches no member type of the union", text)
+    }
+}
+
+impl TypeTrait for UnionType {
+    /// Code generation generates an enum with a variant for each member instead.
+    fn get_rust_type(&self) -> &'static str {
+        "String"
+    }
+
+    /// Members are resolved by [`resolve_type`](crate::typing::resolve_type), a
+    /// union cannot be restricted.
+    fn derive(&self, model: &model::Type, error_context: &mut ErrorContext) -> Result<Self, ()> {
+        let unused_fields = collect_unused_fields!(
+            model,
+            [
+                base,
+                bit,
+                r#enum,
+                fraction_digits,
+                length,
+                range,
+                path,
+                pattern,
+                require_instance
+            ]
+        );
+
+        if !unused_fields.is_empty() {
+            error_context.add_warning((0, 0), format!("Unused fields: {:?}", unused_fields));
+        }
+        Ok(self.clone())
+    }
+
+    fn is_abstract_type(&self) -> bool {
+        self.members.is_empty()
+    }
+
+    /// Parses `text` with the first member type accepting it.
+    #[cfg(feature = "cli")]
+    fn parse_value(&self, text: &str) -> Result<Value, String> {
+        self.members
+            .iter()
+            .find_map(|member| member.parse_value(text).ok())
+            .ok_or_else(|| Self::mismatch(text))
+    }
+}
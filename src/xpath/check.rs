@@ -0,0 +1,581 @@
+//! Static checks of XPath expressions against the schema tree.
+
+use crate::errors::ErrorContext;
+use crate::parser::model;
+use crate::schema::{Schema, SchemaNode, SchemaNodeKind, SchemaType};
+use crate::xpath::{
+    check_arguments, parse, source_span, Axis, BinaryOperator, Expr, NodeTest, Step, XPathError,
+};
+use crate::Span;
+use std::collections::HashMap;
+
+/// How many leafrefs referring to leafrefs are followed to the type of a value.
+const LEAFREF_DEPTH: usize = 16;
+
+/// A data node given by its ancestor data nodes and itself, the root being the
+/// empty chain.
+type Chain<'s> = Vec<&'s SchemaNode>;
+
+/// The schema nodes a node set may contain, if known.
+#[derive(Debug, Clone)]
+enum Nodes<'s> {
+    Known(Vec<Chain<'s>>),
+    Unknown,
+}
+
+fn is_data_node(node: &SchemaNode) -> bool {
+    !matches!(
+        node.kind,
+        SchemaNodeKind::Choice { .. }
+            | SchemaNodeKind::Case
+            | SchemaNodeKind::Input
+            | SchemaNodeKind::Output
+    )
+}
+
+/// Collects the data nodes among `nodes`, looking through choices, cases,
+/// inputs and outputs.
+fn collect_data_nodes<'s>(nodes: &'s [SchemaNode], data_nodes: &mut Vec<&'s SchemaNode>) {
+    for node in nodes {
+        if is_data_node(node) {
+            data_nodes.push(node);
+        } else {
+            collect_data_nodes(&node.children, data_nodes);
+        }
+    }
+}
+
+struct Checker<'s, 'a> {
+    schema: &'s Schema,
+    /// Prefixes of imported modules. Nodes of other modules are not known, so
+    /// steps naming them are not checked.
+    import_prefixes: &'a [&'a str],
+    /// The initial context node, as returned by `current()`.
+    current: Nodes<'s>,
+    errors: Vec<XPathError>,
+    /// The types of the leaves the leafrefs of the leaves and leaf-lists checked
+    /// refer to.
+    targets: HashMap<*const SchemaNode, SchemaType>,
+}
+
+impl<'s> Checker<'s, '_> {
+    fn add_error(&mut self, span: Span, message: String) {
+        self.errors.push(XPathError { span, message });
+    }
+
+    fn children(&self, chain: &Chain<'s>) -> Vec<&'s SchemaNode> {
+        let mut children = vec![];
+        match chain.last() {
+            Some(node) => collect_data_nodes(&node.children, &mut children),
+            None => {
+                collect_data_nodes(&self.schema.data, &mut children);
+                collect_data_nodes(&self.schema.rpcs, &mut children);
+                collect_data_nodes(&self.schema.notifications, &mut children);
+            }
+        }
+        children
+    }
+
+    /// Whether `prefix` refers to this module, or `None` if it is unknown.
+    fn is_local(&mut self, prefix: &Option<String>, span: Span) -> Option<bool> {
+        match prefix {
+            None => Some(true),
+            Some(prefix) if *prefix == self.schema.prefix => Some(true),
+            Some(prefix) if self.import_prefixes.contains(&prefix.as_str()) => Some(false),
+            Some(prefix) => {
+                self.add_error(span, format!("Unknown prefix {}", prefix));
+                None
+            }
+        }
+    }
+
+    fn check_step(&mut self, chains: &[Chain<'s>], step: &Step) -> Nodes<'s> {
+        let mut result = vec![];
+        let mut push = |chain: Chain<'s>| {
+            let is_same = |other: &Chain| {
+                other.len() == chain.len()
+                    && other.iter().zip(&chain).all(|(a, b)| std::ptr::eq(*a, *b))
+            };
+            if !result.iter().any(is_same) {
+                result.push(chain);
+            }
+        };
+        match (step.axis, &step.test) {
+            (Axis::Child, NodeTest::Name { prefix, name }) => {
+                if self.is_local(prefix, step.span) != Some(true) {
+                    return Nodes::Unknown;
+                }
+                for chain in chains {
+                    for child in self.children(chain) {
+                        if child.name == *name {
+                            let mut child_chain = chain.clone();
+                            child_chain.push(child);
+                            push(child_chain);
+                        }
+                    }
+                }
+                if result.is_empty() && !chains.is_empty() {
+                    self.add_error(step.span, format!("Node {} not found", name));
+                }
+            }
+            (Axis::Child, NodeTest::Wildcard { prefix: None } | NodeTest::Node) => {
+                for chain in chains {
+                    for child in self.children(chain) {
+                        let mut child_chain = chain.clone();
+                        child_chain.push(child);
+                        push(child_chain);
+                    }
+                }
+            }
+            (Axis::Parent | Axis::SelfNode, NodeTest::Node | NodeTest::Name { .. }) => {
+                for chain in chains {
+                    let mut chain = chain.clone();
+                    if step.axis == Axis::Parent && chain.pop().is_none() {
+                        self.add_error(step.span, "The root node has no parent".to_string());
+                        continue;
+                    }
+                    if let NodeTest::Name { prefix, name } = &step.test {
+                        if self.is_local(prefix, step.span) != Some(true) {
+                            return Nodes::Unknown;
+                        }
+                        if chain.last().is_none_or(|node| node.name != *name) {
+                            continue;
+                        }
+                    }
+                    push(chain);
+                }
+            }
+            _ => return Nodes::Unknown,
+        }
+        Nodes::Known(result)
+    }
+
+    fn check_steps(&mut self, mut nodes: Nodes<'s>, steps: &[Step]) -> Nodes<'s> {
+        for step in steps {
+            nodes = match &nodes {
+                Nodes::Known(chains) => self.check_step(chains, step),
+                Nodes::Unknown => Nodes::Unknown,
+            };
+            for predicate in &step.predicates {
+                self.check_expr(predicate, &nodes);
+            }
+        }
+        nodes
+    }
+
+    /// Checks `expr` evaluated with the nodes of `context`, returning the nodes
+    /// of its result.
+    fn check_expr(&mut self, expr: &Expr, context: &Nodes<'s>) -> Nodes<'s> {
+        match expr {
+            Expr::Binary {
+                operator,
+                left,
+                right,
+            } => {
+                let left = self.check_expr(left, context);
+                let right = self.check_expr(right, context);
+                match (left, right) {
+                    (Nodes::Known(mut left), Nodes::Known(right))
+                        if *operator == BinaryOperator::Union =>
+                    {
+                        left.extend(right);
+                        Nodes::Known(left)
+                    }
+                    _ => Nodes::Unknown,
+                }
+            }
+            Expr::Negate(expr) => {
+                self.check_expr(expr, context);
+                Nodes::Unknown
+            }
+            Expr::Path { absolute, steps } => {
+                let start = if *absolute {
+                    Nodes::Known(vec![vec![]])
+                } else {
+                    context.clone()
+                };
+                self.check_steps(start, steps)
+            }
+            Expr::Filter {
+                primary,
+                predicates,
+                steps,
+            } => {
+                let nodes = self.check_expr(primary, context);
+                for predicate in predicates {
+                    self.check_expr(predicate, &nodes);
+                }
+                self.check_steps(nodes, steps)
+            }
+            Expr::Literal(_) | Expr::Number(_) => Nodes::Unknown,
+            Expr::Variable { name, span } => {
+                self.add_error(*span, format!("Unknown variable ${}", name));
+                Nodes::Unknown
+            }
+            Expr::FunctionCall {
+                name,
+                arguments,
+                span,
+            } => {
+                for argument in arguments {
+                    self.check_expr(argument, context);
+                }
+                if let Err(message) = check_arguments(name, arguments.len()) {
+                    self.add_error(*span, message);
+                }
+                match name.as_str() {
+                    "current" => self.current.clone(),
+                    _ => Nodes::Unknown,
+                }
+            }
+        }
+    }
+
+    /// Parses and checks `expression` with the context node given by `context`,
+    /// or an unknown context node if `None`.
+    fn check(&mut self, expression: &str, context: Option<&Chain<'s>>) -> Nodes<'s> {
+        let expr = match parse(expression) {
+            Ok(expr) => expr,
+            Err(error) => {
+                self.errors.push(error);
+                return Nodes::Unknown;
+            }
+        };
+        self.current = match context {
+            Some(chain) => Nodes::Known(vec![chain.clone()]),
+            None => Nodes::Unknown,
+        };
+        let context = self.current.clone();
+        self.check_expr(&expr, &context)
+    }
+
+    fn report(&mut self, expression: &str, argument_span: Span, error_context: &mut ErrorContext) {
+        for error in self.errors.drain(..) {
+            error_context.add_error(
+                source_span(expression, argument_span, error.span),
+                error.message,
+            );
+        }
+    }
+
+    /// The type of the leaf or leaf-list `chain` ends with, with the target of
+    /// its leafref resolved, following at most `depth` further leafrefs. Errors
+    /// of the paths followed are reported for their own leaves.
+    fn leaf_type(&mut self, chain: &Chain<'s>, depth: usize) -> Option<SchemaType> {
+        let (SchemaNodeKind::Leaf { r#type, .. } | SchemaNodeKind::LeafList { r#type, .. }) =
+            &chain.last()?.kind
+        else {
+            return None;
+        };
+        let mut schema_type = r#type.clone();
+        if let (Some(leafref), Some(depth)) = (r#type.leafref(), depth.checked_sub(1)) {
+            let errors = std::mem::take(&mut self.errors);
+            let path = leafref.path.as_ref().map_or("", |path| &path.expression);
+            if let Nodes::Known(chains) = self.check(path, Some(chain)) {
+                schema_type.target = chains
+                    .iter()
+                    .find_map(|chain| self.leaf_type(chain, depth))
+                    .map(Box::new);
+            }
+            self.errors = errors;
+        }
+        Some(schema_type)
+    }
+
+    /// Checks the leafref path of `schema_type`, if any, returning the type of
+    /// the leaf it refers to.
+    fn check_leafref(
+        &mut self,
+        schema_type: &SchemaType,
+        context: Option<&Chain<'s>>,
+        error_context: &mut ErrorContext,
+    ) -> Option<SchemaType> {
+        let path = schema_type.leafref()?.path.as_ref()?;
+        let mut target = None;
+        if let Nodes::Known(chains) = self.check(&path.expression, context) {
+            let is_leaf = |chain: &Chain| {
+                chain.last().is_some_and(|node| {
+                    matches!(
+                        node.kind,
+                        SchemaNodeKind::Leaf { .. } | SchemaNodeKind::LeafList { .. }
+                    )
+                })
+            };
+            if !chains.iter().all(is_leaf) {
+                self.add_error(
+                    (0, path.expression.len()),
+                    format!(
+                        "Path {} does not refer to a leaf or leaf-list",
+                        path.expression
+                    ),
+                );
+            }
+            let errors = std::mem::take(&mut self.errors);
+            target = chains
+                .iter()
+                .find_map(|chain| self.leaf_type(chain, LEAFREF_DEPTH));
+            self.errors = errors;
+        }
+        self.report(&path.expression, path.argument_span, error_context);
+        target
+    }
+
+    fn check_condition(
+        &mut self,
+        condition: &str,
+        argument_span: Span,
+        context: Option<&Chain<'s>>,
+        error_context: &mut ErrorContext,
+    ) {
+        self.check(condition, context);
+        self.report(condition, argument_span, error_context);
+    }
+
+    /// Checks the expressions of `nodes`, whose parent data node is given by
+    /// `parent`.
+    fn check_nodes(
+        &mut self,
+        nodes: &'s [SchemaNode],
+        parent: Option<&Chain<'s>>,
+        error_context: &mut ErrorContext,
+    ) {
+        for node in nodes {
+            let chain = match parent {
+                Some(parent) if is_data_node(node) => {
+                    let mut chain = parent.clone();
+                    chain.push(node);
+                    Some(chain)
+                }
+                Some(parent) => Some(parent.clone()),
+                None => None,
+            };
+            for when in &node.when {
+                // The context node of conditions of augment and uses statements,
+                // and of choices and cases, is the closest ancestor data node.
+                let context = if when.inherited {
+                    parent
+                } else {
+                    chain.as_ref()
+                };
+                self.check_condition(
+                    &when.when.condition,
+                    when.when.argument_span,
+                    context,
+                    error_context,
+                );
+            }
+            for must in &node.must {
+                self.check_condition(
+                    &must.condition,
+                    must.argument_span,
+                    chain.as_ref(),
+                    error_context,
+                );
+            }
+            if let SchemaNodeKind::Leaf { r#type, .. } | SchemaNodeKind::LeafList { r#type, .. } =
+                &node.kind
+            {
+                if let Some(target) = self.check_leafref(r#type, chain.as_ref(), error_context) {
+                    self.targets.insert(node, target);
+                }
+            }
+            self.check_nodes(&node.children, chain.as_ref(), error_context);
+        }
+    }
+}
+
+/// Sets the targets of the leafrefs of `nodes` and their descendants.
+fn assign_targets(nodes: &mut [SchemaNode], targets: &mut HashMap<*const SchemaNode, SchemaType>) {
+    for node in nodes {
+        if let Some(target) = targets.remove(&(node as *const SchemaNode)) {
+            if let SchemaNodeKind::Leaf { r#type, .. } | SchemaNodeKind::LeafList { r#type, .. } =
+                &mut node.kind
+            {
+                r#type.target = Some(Box::new(target));
+            }
+        }
+        assign_targets(&mut node.children, targets);
+    }
+}
+
+/// Checks the `must` and `when` conditions and leafref paths of `schema`: the
+/// expressions must be valid XPath using the functions of RFC 7950, and their
+/// location steps must name existing nodes of this module. Nodes augmenting
+/// other modules are only checked for syntax and functions. The targets of the
+/// leafrefs found are set in their types.
+pub(crate) fn check_schema(
+    schema: &mut Schema,
+    module: &model::Module,
+    error_context: &mut ErrorContext,
+) -> Result<(), ()> {
+    let import_prefixes: Vec<&str> = module
+        .import
+        .iter()
+        .map(|import| import.prefix.as_str())
+        .collect();
+    let mut checker = Checker {
+        schema,
+        import_prefixes: &import_prefixes,
+        current: Nodes::Unknown,
+        errors: vec![],
+        targets: HashMap::new(),
+    };
+    let had_errors = error_context.has_errors();
+    let root = vec![];
+    for nodes in [&schema.data, &schema.rpcs, &schema.notifications] {
+        checker.check_nodes(nodes, Some(&root), error_context);
+    }
+    for augment in &schema.augments {
+        checker.check_nodes(&augment.nodes, None, error_context);
+    }
+    let mut targets = checker.targets;
+    for nodes in [
+        &mut schema.data,
+        &mut schema.rpcs,
+        &mut schema.notifications,
+    ] {
+        assign_targets(nodes, &mut targets);
+    }
+    for augment in &mut schema.augments {
+        assign_targets(&mut augment.nodes, &mut targets);
+    }
+    if !had_errors && error_context.has_errors() {
+        return Err(());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::errors::ErrorContext;
+    use crate::parser::model::Module;
+    use crate::parser::model_mapper::Mapper;
+    use crate::parser::parser::parse;
+    use crate::repository::Repository;
+    use crate::schema::compile;
+
+    fn check(input: &str) -> Vec<(String, String)> {
+        let mut error_context = ErrorContext::new();
+        let module = Module::map(parse(input).unwrap(), &mut error_context).unwrap();
+        let _ = compile(&module, &Repository::default(), &mut error_context);
+        error_context
+            .diagnostics()
+            .iter()
+            .map(|diagnostic| {
+                let label = &diagnostic.labels.as_ref().unwrap()[0];
+                (
+                    input[label.offset()..label.offset() + label.len()].to_string(),
+                    diagnostic.message.clone(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_valid() {
+        let errors = check(
+            r#"module example {
+                namespace "urn:example";
+                prefix ex;
+                import other { prefix o; }
+                container interfaces {
+                    must "count(interface) < 10";
+                    list interface {
+                        key "name";
+                        leaf name { type string; }
+                        leaf type { type string; }
+                        leaf mtu {
+                            when "../type = 'ethernet' or derived-from(../o:kind, 'o:x')";
+                            must ". >= 68 and current()/../ex:name != ''";
+                            type uint16;
+                        }
+                        choice address {
+                            when "type != 'loopback'";
+                            leaf ipv4 { type string; }
+                        }
+                    }
+                }
+                container routing {
+                    leaf interface {
+                        type leafref { path "/interfaces/interface/name"; }
+                    }
+                    uses addresses { when "interface != ''"; }
+                }
+                grouping addresses {
+                    leaf-list address {
+                        type leafref {
+                            path "/interfaces/interface[name = current()/../interface]/ipv4";
+                        }
+                    }
+                }
+                rpc reset {
+                    input {
+                        must "not(force) or /interfaces";
+                        leaf force { type boolean; }
+                    }
+                }
+                augment "/o:system" {
+                    when "o:enabled = 'true'";
+                    leaf reset { type boolean; }
+                }
+            }"#,
+        );
+        assert_eq!(errors, []);
+    }
+
+    #[test]
+    fn test_errors() {
+        let errors = check(
+            r#"module example {
+                namespace "urn:example";
+                prefix ex;
+                container interfaces {
+                    must "count(interface, 1) < $max";
+                    list interface {
+                        key "name";
+                        leaf name { type string; }
+                        leaf mtu {
+                            when "../speed > 0 and x:mtu";
+                            must "../../../../name and size(.)";
+                            type uint16;
+                        }
+                    }
+                }
+                container routing {
+                    leaf interface {
+                        type leafref { path "/interfaces/interface"; }
+                    }
+                    leaf gateway {
+                        type leafref { path "/interfaces/interface/address"; }
+                    }
+                    uses addresses { when "mtu"; }
+                    leaf broken { type string; must "a = = b"; }
+                }
+                grouping addresses {
+                    leaf address { type string; }
+                }
+            }"#,
+        );
+        assert_eq!(
+            errors,
+            [
+                (
+                    "count".to_string(),
+                    "Function count takes 1 arguments, found 2".to_string()
+                ),
+                ("$max".to_string(), "Unknown variable $max".to_string()),
+                ("speed".to_string(), "Node speed not found".to_string()),
+                ("x:mtu".to_string(), "Unknown prefix x".to_string()),
+                ("..".to_string(), "The root node has no parent".to_string()),
+                ("size".to_string(), "Unknown function size".to_string()),
+                (
+                    "/interfaces/interface".to_string(),
+                    "Path /interfaces/interface does not refer to a leaf or leaf-list".to_string()
+                ),
+                ("address".to_string(), "Node address not found".to_string()),
+                ("mtu".to_string(), "Node mtu not found".to_string()),
+                ("=".to_string(), "Unexpected =".to_string()),
+            ]
+        );
+    }
+}
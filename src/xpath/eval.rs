@@ -0,0 +1,905 @@
+//! Evaluation of XPath expressions over a [`DataTree`], with the function library
+//! of XPath 1.0 and RFC 7950, section 10.
+
+use crate::data::{leaf_type, value_string, DataNode, DataNodeKind, DataTree};
+use crate::schema::{Schema, SchemaNode, SchemaNodeKind};
+use crate::typing::{Type, Value};
+use crate::xpath::{check_arguments, parse, Axis, BinaryOperator, Expr, NodeTest, Step};
+use regex::Regex;
+
+/// A node of a data tree, given by the indices of it and its ancestors among
+/// their siblings. The root node is the empty path. Ordering node references
+/// orders them in document order.
+pub(crate) type NodeRef = Vec<usize>;
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum XPathValue {
+    /// Nodes in document order.
+    NodeSet(Vec<NodeRef>),
+    Boolean(bool),
+    Number(f64),
+    String(String),
+}
+
+fn number_string(number: f64) -> String {
+    if number.is_nan() {
+        "NaN".to_string()
+    } else if number.is_infinite() {
+        if number > 0.0 {
+            "Infinity"
+        } else {
+            "-Infinity"
+        }
+        .to_string()
+    } else if number.fract() == 0.0 && number.abs() < 1e15 {
+        format!("{}", number as i64)
+    } else {
+        number.to_string()
+    }
+}
+
+fn string_number(text: &str) -> f64 {
+    let text = text.trim();
+    let is_number = !text.is_empty()
+        && text
+            .strip_prefix('-')
+            .unwrap_or(text)
+            .chars()
+            .all(|char| char.is_ascii_digit() || char == '.');
+    if !is_number {
+        return f64::NAN;
+    }
+    text.parse().unwrap_or(f64::NAN)
+}
+
+/// Rounds as the `round()` function of XPath 1.0.
+fn round(number: f64) -> f64 {
+    (number + 0.5).floor()
+}
+
+fn sort_nodes(nodes: &mut Vec<NodeRef>) {
+    nodes.sort();
+    nodes.dedup();
+}
+
+/// The context of evaluating an expression: the context node and its position
+/// among the nodes being filtered.
+struct Context<'n> {
+    node: &'n NodeRef,
+    position: usize,
+    size: usize,
+}
+
+#[derive(Clone)]
+struct Evaluator<'a> {
+    schema: &'a Schema,
+    tree: &'a DataTree,
+    /// The initial context node, as returned by `current()`.
+    current: NodeRef,
+}
+
+impl<'a> Evaluator<'a> {
+    fn node(&self, node: &NodeRef) -> Option<&'a DataNode> {
+        let (first, rest) = node.split_first()?;
+        let mut data_node = &self.tree.nodes[*first];
+        for index in rest {
+            data_node = &data_node.children()[*index];
+        }
+        Some(data_node)
+    }
+
+    fn children(&self, node: &NodeRef) -> &'a [DataNode] {
+        match self.node(node) {
+            Some(data_node) => data_node.children(),
+            None => &self.tree.nodes,
+        }
+    }
+
+    fn schema_node(&self, node: &NodeRef) -> Option<&'a SchemaNode> {
+        let mut schema_nodes = &self.schema.data[..];
+        let mut schema_node = None;
+        for depth in 1..=node.len() {
+            let name = &self.node(&node[..depth].to_vec())?.name;
+            let (found, _) = Schema::find_data_child(schema_nodes, name)?;
+            schema_nodes = &found.children;
+            schema_node = Some(found);
+        }
+        schema_node
+    }
+
+    fn string_value(&self, node: &NodeRef) -> String {
+        match self.node(node).map(|data_node| &data_node.kind) {
+            Some(DataNodeKind::Leaf(value) | DataNodeKind::LeafListEntry(value)) => {
+                value_string(value)
+            }
+            Some(DataNodeKind::AnyData(content)) => content.clone(),
+            _ => self
+                .descendants(node)
+                .iter()
+                .filter_map(|descendant| self.node(descendant)?.value())
+                .map(value_string)
+                .collect(),
+        }
+    }
+
+    fn descendants(&self, node: &NodeRef) -> Vec<NodeRef> {
+        let mut descendants = vec![];
+        for index in 0..self.children(node).len() {
+            let mut child = node.clone();
+            child.push(index);
+            let child_descendants = self.descendants(&child);
+            descendants.push(child);
+            descendants.extend(child_descendants);
+        }
+        descendants
+    }
+
+    /// The nodes on `axis` from `node`, in the order of the axis.
+    fn axis_nodes(&self, node: &NodeRef, axis: Axis) -> Vec<NodeRef> {
+        let siblings = |range: &mut dyn Iterator<Item = usize>| -> Vec<NodeRef> {
+            let Some((_, parent)) = node.split_last() else {
+                return vec![];
+            };
+            range
+                .map(|index| {
+                    let mut sibling = parent.to_vec();
+                    sibling.push(index);
+                    sibling
+                })
+                .collect()
+        };
+        let index = node.last().copied().unwrap_or_default();
+        let sibling_count = match node.split_last() {
+            Some((_, parent)) => self.children(&parent.to_vec()).len(),
+            None => 0,
+        };
+        match axis {
+            Axis::Child => (0..self.children(node).len())
+                .map(|index| {
+                    let mut child = node.clone();
+                    child.push(index);
+                    child
+                })
+                .collect(),
+            Axis::Descendant => self.descendants(node),
+            Axis::DescendantOrSelf => {
+                let mut nodes = vec![node.clone()];
+                nodes.extend(self.descendants(node));
+                nodes
+            }
+            Axis::Parent => node
+                .split_last()
+                .map(|(_, parent)| vec![parent.to_vec()])
+                .unwrap_or_default(),
+            Axis::Ancestor => (0..node.len())
+                .rev()
+                .map(|depth| node[..depth].to_vec())
+                .collect(),
+            Axis::AncestorOrSelf => (0..=node.len())
+                .rev()
+                .map(|depth| node[..depth].to_vec())
+                .collect(),
+            Axis::FollowingSibling => siblings(&mut (index + 1..sibling_count)),
+            Axis::PrecedingSibling => siblings(&mut (0..index).rev()),
+            Axis::Following => {
+                let mut nodes: Vec<NodeRef> = self
+                    .descendants(&vec![])
+                    .into_iter()
+                    .filter(|other| other > node && !other.starts_with(node))
+                    .collect();
+                nodes.sort();
+                nodes
+            }
+            Axis::Preceding => {
+                let mut nodes: Vec<NodeRef> = self
+                    .descendants(&vec![])
+                    .into_iter()
+                    .filter(|other| other < node && !node.starts_with(other))
+                    .collect();
+                nodes.sort();
+                nodes.reverse();
+                nodes
+            }
+            Axis::SelfNode => vec![node.clone()],
+            Axis::Attribute | Axis::Namespace => vec![],
+        }
+    }
+
+    /// Whether a node qualified by `prefix` may be in the module `module`.
+    fn is_module(&self, prefix: &Option<String>, module: &str) -> bool {
+        match prefix {
+            None => module == self.schema.name,
+            Some(prefix) if *prefix == self.schema.prefix => module == self.schema.name,
+            Some(prefix) => module == prefix,
+        }
+    }
+
+    fn matches_test(&self, node: &NodeRef, test: &NodeTest) -> bool {
+        match (test, self.node(node)) {
+            (NodeTest::Node, _) => true,
+            (NodeTest::Name { prefix, name }, Some(data_node)) => {
+                data_node.name == *name && self.is_module(prefix, &data_node.module)
+            }
+            (NodeTest::Wildcard { prefix: None }, Some(_)) => true,
+            (NodeTest::Wildcard { prefix }, Some(data_node)) => {
+                self.is_module(prefix, &data_node.module)
+            }
+            _ => false,
+        }
+    }
+
+    /// Filters `nodes`, given in the order they are numbered in, by `predicates`.
+    fn filter(&self, mut nodes: Vec<NodeRef>, predicates: &[Expr]) -> Result<Vec<NodeRef>, String> {
+        for predicate in predicates {
+            let size = nodes.len();
+            let mut filtered = vec![];
+            for (index, node) in nodes.into_iter().enumerate() {
+                let context = Context {
+                    node: &node,
+                    position: index + 1,
+                    size,
+                };
+                let keep = match self.eval(predicate, &context)? {
+                    XPathValue::Number(number) => number == (index + 1) as f64,
+                    value => self.to_boolean(&value),
+                };
+                if keep {
+                    filtered.push(node);
+                }
+            }
+            nodes = filtered;
+        }
+        Ok(nodes)
+    }
+
+    fn eval_steps(&self, mut nodes: Vec<NodeRef>, steps: &[Step]) -> Result<Vec<NodeRef>, String> {
+        for step in steps {
+            let mut result = vec![];
+            for node in &nodes {
+                let candidates = self
+                    .axis_nodes(node, step.axis)
+                    .into_iter()
+                    .filter(|candidate| self.matches_test(candidate, &step.test))
+                    .collect();
+                result.extend(self.filter(candidates, &step.predicates)?);
+            }
+            sort_nodes(&mut result);
+            nodes = result;
+        }
+        Ok(nodes)
+    }
+
+    fn to_boolean(&self, value: &XPathValue) -> bool {
+        match value {
+            XPathValue::NodeSet(nodes) => !nodes.is_empty(),
+            XPathValue::Boolean(value) => *value,
+            XPathValue::Number(number) => *number != 0.0 && !number.is_nan(),
+            XPathValue::String(text) => !text.is_empty(),
+        }
+    }
+
+    fn to_number(&self, value: &XPathValue) -> f64 {
+        match value {
+            XPathValue::Boolean(value) => f64::from(u8::from(*value)),
+            XPathValue::Number(number) => *number,
+            _ => string_number(&self.to_string(value)),
+        }
+    }
+
+    fn to_string(&self, value: &XPathValue) -> String {
+        match value {
+            XPathValue::NodeSet(nodes) => nodes
+                .first()
+                .map(|node| self.string_value(node))
+                .unwrap_or_default(),
+            XPathValue::Boolean(value) => value.to_string(),
+            XPathValue::Number(number) => number_string(*number),
+            XPathValue::String(text) => text.clone(),
+        }
+    }
+
+    /// Compares two values that are not node sets, as in XPath 1.0, section 3.4.
+    fn compare_atoms(
+        &self,
+        operator: BinaryOperator,
+        left: &XPathValue,
+        right: &XPathValue,
+    ) -> bool {
+        match operator {
+            BinaryOperator::Equal | BinaryOperator::NotEqual => {
+                let is_equal = match (left, right) {
+                    (XPathValue::Boolean(_), _) | (_, XPathValue::Boolean(_)) => {
+                        self.to_boolean(left) == self.to_boolean(right)
+                    }
+                    (XPathValue::Number(_), _) | (_, XPathValue::Number(_)) => {
+                        self.to_number(left) == self.to_number(right)
+                    }
+                    _ => self.to_string(left) == self.to_string(right),
+                };
+                is_equal == (operator == BinaryOperator::Equal)
+            }
+            _ => {
+                let (left, right) = (self.to_number(left), self.to_number(right));
+                match operator {
+                    BinaryOperator::Less => left < right,
+                    BinaryOperator::LessOrEqual => left <= right,
+                    BinaryOperator::Greater => left > right,
+                    _ => left >= right,
+                }
+            }
+        }
+    }
+
+    fn compare(&self, operator: BinaryOperator, left: &XPathValue, right: &XPathValue) -> bool {
+        let node_strings = |nodes: &[NodeRef]| -> Vec<XPathValue> {
+            nodes
+                .iter()
+                .map(|node| XPathValue::String(self.string_value(node)))
+                .collect()
+        };
+        match (left, right) {
+            (XPathValue::NodeSet(left), XPathValue::NodeSet(right)) => {
+                let right = node_strings(right);
+                node_strings(left).iter().any(|left| {
+                    right
+                        .iter()
+                        .any(|right| self.compare_atoms(operator, left, right))
+                })
+            }
+            (XPathValue::NodeSet(nodes), XPathValue::Boolean(_)) => {
+                self.compare_atoms(operator, &XPathValue::Boolean(!nodes.is_empty()), right)
+            }
+            (XPathValue::Boolean(_), XPathValue::NodeSet(nodes)) => {
+                self.compare_atoms(operator, left, &XPathValue::Boolean(!nodes.is_empty()))
+            }
+            (XPathValue::NodeSet(nodes), _) => node_strings(nodes)
+                .iter()
+                .any(|left| self.compare_atoms(operator, left, right)),
+            (_, XPathValue::NodeSet(nodes)) => node_strings(nodes)
+                .iter()
+                .any(|right| self.compare_atoms(operator, left, right)),
+            _ => self.compare_atoms(operator, left, right),
+        }
+    }
+
+    fn eval(&self, expr: &Expr, context: &Context) -> Result<XPathValue, String> {
+        Ok(match expr {
+            Expr::Binary {
+                operator: BinaryOperator::Or,
+                left,
+                right,
+            } => XPathValue::Boolean(
+                self.to_boolean(&self.eval(left, context)?)
+                    || self.to_boolean(&self.eval(right, context)?),
+            ),
+            Expr::Binary {
+                operator: BinaryOperator::And,
+                left,
+                right,
+            } => XPathValue::Boolean(
+                self.to_boolean(&self.eval(left, context)?)
+                    && self.to_boolean(&self.eval(right, context)?),
+            ),
+            Expr::Binary {
+                operator,
+                left,
+                right,
+            } => {
+                let left = self.eval(left, context)?;
+                let right = self.eval(right, context)?;
+                match operator {
+                    BinaryOperator::Union => match (left, right) {
+                        (XPathValue::NodeSet(mut left), XPathValue::NodeSet(right)) => {
+                            left.extend(right);
+                            sort_nodes(&mut left);
+                            XPathValue::NodeSet(left)
+                        }
+                        _ => return Err("Operands of | must be node sets".to_string()),
+                    },
+                    BinaryOperator::Add
+                    | BinaryOperator::Subtract
+                    | BinaryOperator::Multiply
+                    | BinaryOperator::Divide
+                    | BinaryOperator::Modulo => {
+                        let (left, right) = (self.to_number(&left), self.to_number(&right));
+                        XPathValue::Number(match operator {
+                            BinaryOperator::Add => left + right,
+                            BinaryOperator::Subtract => left - right,
+                            BinaryOperator::Multiply => left * right,
+                            BinaryOperator::Divide => left / right,
+                            _ => left % right,
+                        })
+                    }
+                    _ => XPathValue::Boolean(self.compare(*operator, &left, &right)),
+                }
+            }
+            Expr::Negate(expr) => XPathValue::Number(-self.to_number(&self.eval(expr, context)?)),
+            Expr::Path { absolute, steps } => {
+                let start = if *absolute {
+                    vec![]
+                } else {
+                    context.node.clone()
+                };
+                XPathValue::NodeSet(self.eval_steps(vec![start], steps)?)
+            }
+            Expr::Filter {
+                primary,
+                predicates,
+                steps,
+            } => {
+                let XPathValue::NodeSet(nodes) = self.eval(primary, context)? else {
+                    return Err("Only node sets can be filtered".to_string());
+                };
+                let nodes = self.filter(nodes, predicates)?;
+                XPathValue::NodeSet(self.eval_steps(nodes, steps)?)
+            }
+            Expr::Literal(text) => XPathValue::String(text.clone()),
+            Expr::Number(number) => XPathValue::Number(*number),
+            Expr::Variable { name, .. } => return Err(format!("Unknown variable ${}", name)),
+            Expr::FunctionCall {
+                name, arguments, ..
+            } => {
+                check_arguments(name, arguments.len())?;
+                let arguments = arguments
+                    .iter()
+                    .map(|argument| self.eval(argument, context))
+                    .collect::<Result<Vec<_>, _>>()?;
+                self.call(name, &arguments, context)?
+            }
+        })
+    }
+
+    /// The first node of a node set argument, or the context node if there is
+    /// no argument.
+    fn first_node(
+        &self,
+        arguments: &[XPathValue],
+        context: &Context,
+    ) -> Result<Option<NodeRef>, String> {
+        match arguments.first() {
+            None => Ok(Some(context.node.clone())),
+            Some(XPathValue::NodeSet(nodes)) => Ok(nodes.first().cloned()),
+            Some(_) => Err("Expected a node set argument".to_string()),
+        }
+    }
+
+    fn call(
+        &self,
+        name: &str,
+        arguments: &[XPathValue],
+        context: &Context,
+    ) -> Result<XPathValue, String> {
+        let string = |index: usize| -> String {
+            match arguments.get(index) {
+                Some(argument) => self.to_string(argument),
+                None => self.string_value(context.node),
+            }
+        };
+        let number = |index: usize| self.to_number(&arguments[index]);
+        Ok(match name {
+            "last" => XPathValue::Number(context.size as f64),
+            "position" => XPathValue::Number(context.position as f64),
+            "count" => match &arguments[0] {
+                XPathValue::NodeSet(nodes) => XPathValue::Number(nodes.len() as f64),
+                _ => return Err("Expected a node set argument".to_string()),
+            },
+            "id" => XPathValue::NodeSet(vec![]),
+            "local-name" | "name" => XPathValue::String(
+                self.first_node(arguments, context)?
+                    .and_then(|node| self.node(&node))
+                    .map(|data_node| data_node.name.clone())
+                    .unwrap_or_default(),
+            ),
+            "namespace-uri" => XPathValue::String(
+                self.first_node(arguments, context)?
+                    .and_then(|node| self.node(&node))
+                    .filter(|data_node| data_node.module == self.schema.name)
+                    .map(|_| self.schema.namespace.clone())
+                    .unwrap_or_default(),
+            ),
+            "string" => XPathValue::String(string(0)),
+            "concat" => XPathValue::String((0..arguments.len()).map(string).collect()),
+            "starts-with" => XPathValue::Boolean(string(0).starts_with(&string(1))),
+            "contains" => XPathValue::Boolean(string(0).contains(&string(1))),
+            "substring-before" => {
+                let text = string(0);
+                XPathValue::String(
+                    text.split_once(&string(1))
+                        .map(|(before, _)| before.to_string())
+                        .unwrap_or_default(),
+                )
+            }
+            "substring-after" => {
+                let text = string(0);
+                XPathValue::String(
+                    text.split_once(&string(1))
+                        .map(|(_, after)| after.to_string())
+                        .unwrap_or_default(),
+                )
+            }
+            "substring" => {
+                let start = round(number(1));
+                let end = match arguments.get(2) {
+                    Some(length) => start + round(self.to_number(length)),
+                    None => f64::INFINITY,
+                };
+                XPathValue::String(
+                    string(0)
+                        .chars()
+                        .enumerate()
+                        .filter(|(index, _)| {
+                            let position = (index + 1) as f64;
+                            position >= start && position < end
+                        })
+                        .map(|(_, char)| char)
+                        .collect(),
+                )
+            }
+            "string-length" => XPathValue::Number(string(0).chars().count() as f64),
+            "normalize-space" => {
+                XPathValue::String(string(0).split_whitespace().collect::<Vec<_>>().join(" "))
+            }
+            "translate" => {
+                let from: Vec<char> = string(1).chars().collect();
+                let to: Vec<char> = string(2).chars().collect();
+                XPathValue::String(
+                    string(0)
+                        .chars()
+                        .filter_map(|char| match from.iter().position(|from| *from == char) {
+                            Some(index) => to.get(index).copied(),
+                            None => Some(char),
+                        })
+                        .collect(),
+                )
+            }
+            "boolean" => XPathValue::Boolean(self.to_boolean(&arguments[0])),
+            "not" => XPathValue::Boolean(!self.to_boolean(&arguments[0])),
+            "true" => XPathValue::Boolean(true),
+            "false" => XPathValue::Boolean(false),
+            "lang" => XPathValue::Boolean(false),
+            "number" => XPathValue::Number(match arguments.first() {
+                Some(argument) => self.to_number(argument),
+                None => string_number(&self.string_value(context.node)),
+            }),
+            "sum" => match &arguments[0] {
+                XPathValue::NodeSet(nodes) => XPathValue::Number(
+                    nodes
+                        .iter()
+                        .map(|node| string_number(&self.string_value(node)))
+                        .sum(),
+                ),
+                _ => return Err("Expected a node set argument".to_string()),
+            },
+            "floor" => XPathValue::Number(number(0).floor()),
+            "ceiling" => XPathValue::Number(number(0).ceil()),
+            "round" => XPathValue::Number(round(number(0))),
+            "current" => XPathValue::NodeSet(vec![self.current.clone()]),
+            "re-match" => {
+                let pattern = string(1);
+                let regex = Regex::new(&format!("^(?:{})$", pattern))
+                    .map_err(|_| format!("Invalid pattern {}", pattern))?;
+                XPathValue::Boolean(regex.is_match(&string(0)))
+            }
+            "deref" => match self.first_node(arguments, context)? {
+                Some(node) => XPathValue::NodeSet(self.deref(&node)?),
+                None => XPathValue::NodeSet(vec![]),
+            },
+            "derived-from" | "derived-from-or-self" => {
+                let XPathValue::NodeSet(nodes) = &arguments[0] else {
+                    return Err("Expected a node set argument".to_string());
+                };
+                let identity = string(1);
+                let or_self = name == "derived-from-or-self";
+                XPathValue::Boolean(
+                    nodes
+                        .iter()
+                        .any(|node| self.is_derived_from(node, &identity, or_self)),
+                )
+            }
+            "enum-value" => {
+                let value = self.first_node(arguments, context)?.and_then(|node| {
+                    let name = self.string_value(&node);
+                    let Some(Type::Enumeration(enumeration)) = leaf_type(self.schema_node(&node)?)
+                    else {
+                        return None;
+                    };
+                    let items = enumeration.items?;
+                    items
+                        .into_iter()
+                        .find(|(item, _)| *item == name)
+                        .map(|(_, value)| f64::from(value))
+                });
+                XPathValue::Number(value.unwrap_or(f64::NAN))
+            }
+            "bit-is-set" => {
+                let bit = string(1);
+                let bits = match self.first_node(arguments, context)? {
+                    Some(node) => self.string_value(&node),
+                    None => String::new(),
+                };
+                XPathValue::Boolean(bits.split_whitespace().any(|set| set == bit))
+            }
+            _ => return Err(format!("Unknown function {}", name)),
+        })
+    }
+
+    /// The nodes a leafref or instance-identifier node refers to.
+    fn deref(&self, node: &NodeRef) -> Result<Vec<NodeRef>, String> {
+        if let Some(Value::InstanceIdentifier(path)) = self.node(node).and_then(DataNode::value) {
+            let expr = parse(path).map_err(|error| error.message)?;
+            let evaluator = Evaluator {
+                current: node.clone(),
+                ..self.clone()
+            };
+            return match evaluator.eval(
+                &expr,
+                &Context {
+                    node,
+                    position: 1,
+                    size: 1,
+                },
+            )? {
+                XPathValue::NodeSet(nodes) => Ok(nodes),
+                _ => Ok(vec![]),
+            };
+        }
+
+        let Some(SchemaNodeKind::Leaf { r#type, .. } | SchemaNodeKind::LeafList { r#type, .. }) =
+            self.schema_node(node).map(|schema_node| &schema_node.kind)
+        else {
+            return Ok(vec![]);
+        };
+        let path = std::iter::once(&r#type.r#type)
+            .chain(r#type.typedefs.iter().map(|typedef| &typedef.r#type))
+            .find_map(|r#type| r#type.path.as_ref());
+        let Some(path) = path else {
+            return Ok(vec![]);
+        };
+        let expr = parse(&path.expression).map_err(|error| error.message)?;
+        // The path is evaluated with the leafref node as context and current node.
+        let evaluator = Evaluator {
+            current: node.clone(),
+            ..self.clone()
+        };
+        let XPathValue::NodeSet(targets) = evaluator.eval(
+            &expr,
+            &Context {
+                node,
+                position: 1,
+                size: 1,
+            },
+        )?
+        else {
+            return Ok(vec![]);
+        };
+        let value = self.string_value(node);
+        Ok(targets
+            .into_iter()
+            .filter(|target| self.string_value(target) == value)
+            .collect())
+    }
+
+    fn is_derived_from(&self, node: &NodeRef, identity: &str, or_self: bool) -> bool {
+        let Some(Value::Identityref { prefix, name }) = self.node(node).and_then(DataNode::value)
+        else {
+            return false;
+        };
+        // The value is qualified by a module name, the identity by a prefix.
+        let module = match prefix.as_deref() {
+            None => self.schema.name.as_str(),
+            Some(prefix) if prefix == self.schema.prefix => self.schema.name.as_str(),
+            Some(module) => module,
+        };
+        let (base_prefix, base) = match identity.split_once(':') {
+            Some((prefix, name)) => (Some(prefix), name),
+            None => (None, identity),
+        };
+        let Some(base_module) = self.schema.module_name(base_prefix) else {
+            return or_self && prefix.as_deref() == base_prefix && name == base;
+        };
+        (or_self && (module, name.as_str()) == (base_module, base))
+            || self
+                .schema
+                .is_derived_identity((module, name), (base_module, base))
+    }
+}
+
+/// Evaluates `expr` over `tree` with `context` as the context node and the
+/// initial context node returned by `current()`.
+pub(crate) fn evaluate(
+    expr: &Expr,
+    schema: &Schema,
+    tree: &DataTree,
+    context: &NodeRef,
+) -> Result<XPathValue, String> {
+    let evaluator = Evaluator {
+        schema,
+        tree,
+        current: context.clone(),
+    };
+    evaluator.eval(
+        expr,
+        &Context {
+            node: context,
+            position: 1,
+            size: 1,
+        },
+    )
+}
+
+/// Evaluates the condition `expression` as a boolean.
+pub(crate) fn evaluate_condition(
+    expression: &str,
+    schema: &Schema,
+    tree: &DataTree,
+    context: &NodeRef,
+) -> Result<bool, String> {
+    let expr = parse(expression).map_err(|error| error.message)?;
+    let value = evaluate(&expr, schema, tree, context)?;
+    let evaluator = Evaluator {
+        schema,
+        tree,
+        current: context.clone(),
+    };
+    Ok(evaluator.to_boolean(&value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::json::parse_json_modules;
+    use crate::errors::ErrorContext;
+    use crate::parser::model::Module;
+    use crate::parser::model_mapper::Mapper;
+    use crate::parser::parser::parse as parse_yang;
+    use crate::repository::Repository;
+    use crate::schema::compile;
+
+    const MODULE: &str = r#"module example {
+        namespace "urn:example";
+        prefix ex;
+        identity interface-type;
+        identity ethernet { base interface-type; }
+        identity fast-ethernet { base ethernet; }
+        container interfaces {
+            list interface {
+                key "name";
+                leaf name { type string; }
+                leaf type { type identityref { base interface-type; } }
+                leaf mtu { type uint16; }
+                leaf speed { type enumeration { enum slow; enum fast { value 10; } } }
+                leaf-list tag { type string; }
+            }
+        }
+        container routing {
+            leaf interface {
+                type leafref { path "/interfaces/interface/name"; }
+            }
+            leaf origin { type instance-identifier; }
+        }
+    }"#;
+
+    const DATA: &str = r#"{
+        "example:interfaces": {
+            "interface": [
+                {
+                    "name": "eth0",
+                    "type": "example:fast-ethernet",
+                    "mtu": 1500,
+                    "speed": "fast",
+                    "tag": ["uplink", "core"]
+                },
+                { "name": "eth1", "type": "example:ethernet", "mtu": 9000 },
+                { "name": "lo" }
+            ]
+        },
+        "example:routing": {
+            "interface": "eth1",
+            "origin": "/example:interfaces/interface[name='eth0']/mtu"
+        }
+    }"#;
+
+    fn eval(expression: &str, context: &[usize]) -> Result<XPathValue, String> {
+        let mut error_context = ErrorContext::new();
+        let module = Module::map(parse_yang(MODULE).unwrap(), &mut error_context).unwrap();
+        let schema = compile(&module, &Repository::default(), &mut error_context).unwrap();
+        let tree = parse_json_modules(DATA, &[&schema]).unwrap().remove(0);
+        let expr = parse(expression).unwrap();
+        evaluate(&expr, &schema, &tree, &context.to_vec())
+    }
+
+    fn string(text: &str) -> XPathValue {
+        XPathValue::String(text.to_string())
+    }
+
+    #[test]
+    fn test_paths() {
+        let eth0_mtu = vec![0, 0, 2];
+        assert_eq!(
+            eval("/interfaces/interface[mtu > 1000]/name", &[]),
+            Ok(XPathValue::NodeSet(vec![vec![0, 0, 0], vec![0, 1, 0]]))
+        );
+        assert_eq!(
+            eval("../../interface[2]/ex:name = 'eth1'", &eth0_mtu),
+            Ok(XPathValue::Boolean(true))
+        );
+        assert_eq!(
+            eval("count(//tag) + count(preceding-sibling::*)", &eth0_mtu),
+            Ok(XPathValue::Number(4.0))
+        );
+        assert_eq!(
+            eval("current()/../tag[last()]", &eth0_mtu),
+            Ok(XPathValue::NodeSet(vec![vec![0, 0, 5]]))
+        );
+        assert_eq!(
+            eval("sum(/interfaces/interface/mtu) div 2", &[]),
+            Ok(XPathValue::Number(5250.0))
+        );
+        assert_eq!(
+            eval(
+                "/interfaces/interface[not(mtu)]/name | /routing/interface",
+                &[]
+            ),
+            Ok(XPathValue::NodeSet(vec![vec![0, 2, 0], vec![1, 0]]))
+        );
+    }
+
+    #[test]
+    fn test_functions() {
+        let cases = [
+            ("concat('a', 1, true())", string("a1true")),
+            ("substring('12345', 1.5, 2.6)", string("234")),
+            ("substring-after('a:b', ':')", string("b")),
+            ("translate('bar', 'abc', 'AB')", string("BAr")),
+            ("normalize-space('  a  b ')", string("a b")),
+            ("string-length(/routing/interface)", XPathValue::Number(4.0)),
+            ("round(-1.5) + floor(1.5)", XPathValue::Number(0.0)),
+            ("number('x') != number('x')", XPathValue::Boolean(true)),
+            ("re-match('eth0', '[a-z]+\\d')", XPathValue::Boolean(true)),
+            ("re-match('eth0x', '[a-z]+\\d')", XPathValue::Boolean(false)),
+            (
+                "derived-from(/interfaces/interface/type, 'ex:interface-type')",
+                XPathValue::Boolean(true),
+            ),
+            (
+                "derived-from(/interfaces/interface[2]/type, 'ethernet')",
+                XPathValue::Boolean(false),
+            ),
+            (
+                "derived-from-or-self(/interfaces/interface[2]/type, 'ethernet')",
+                XPathValue::Boolean(true),
+            ),
+            (
+                "enum-value(/interfaces/interface[1]/speed)",
+                XPathValue::Number(10.0),
+            ),
+            (
+                "bit-is-set(/interfaces/interface[1]/tag, 'uplink')",
+                XPathValue::Boolean(true),
+            ),
+            (
+                "deref(/routing/interface)/../mtu",
+                XPathValue::Number(9000.0),
+            ),
+            ("deref(/routing/origin)", XPathValue::Number(1500.0)),
+        ];
+        for (expression, expected) in cases {
+            let value = eval(expression, &[]).unwrap();
+            let value = match (&expected, value) {
+                (XPathValue::Number(_), XPathValue::NodeSet(nodes)) => {
+                    assert_eq!(nodes.len(), 1, "{}", expression);
+                    let string = eval(&format!("number({})", expression), &[]).unwrap();
+                    string
+                }
+                (_, value) => value,
+            };
+            assert_eq!(value, expected, "{}", expression);
+        }
+
+        assert_eq!(eval("$x", &[]), Err("Unknown variable $x".to_string()));
+        assert_eq!(
+            eval("size(.)", &[]),
+            Err("Unknown function size".to_string())
+        );
+    }
+}
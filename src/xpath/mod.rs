@@ -0,0 +1,210 @@
+//! XPath 1.0 expressions as used by `must`, `when` and leafref `path` statements.
+//!
+//! Spans of expressions are byte offsets into the expression string.
+
+use crate::Span;
+
+mod check;
+#[cfg(feature = "cli")]
+mod eval;
+mod parser;
+
+pub(crate) use check::check_schema;
+#[cfg(feature = "cli")]
+pub(crate) use eval::{evaluate_condition, NodeRef};
+pub(crate) use parser::parse;
+
+/// Functions of XPath 1.0 and RFC 7950, section 10, with their minimum and
+/// maximum number of arguments.
+static FUNCTIONS: &[(&str, usize, Option<usize>)] = &[
+    ("last", 0, Some(0)),
+    ("position", 0, Some(0)),
+    ("count", 1, Some(1)),
+    ("id", 1, Some(1)),
+    ("local-name", 0, Some(1)),
+    ("namespace-uri", 0, Some(1)),
+    ("name", 0, Some(1)),
+    ("string", 0, Some(1)),
+    ("concat", 2, None),
+    ("starts-with", 2, Some(2)),
+    ("contains", 2, Some(2)),
+    ("substring-before", 2, Some(2)),
+    ("substring-after", 2, Some(2)),
+    ("substring", 2, Some(3)),
+    ("string-length", 0, Some(1)),
+    ("normalize-space", 0, Some(1)),
+    ("translate", 3, Some(3)),
+    ("boolean", 1, Some(1)),
+    ("not", 1, Some(1)),
+    ("true", 0, Some(0)),
+    ("false", 0, Some(0)),
+    ("lang", 1, Some(1)),
+    ("number", 0, Some(1)),
+    ("sum", 1, Some(1)),
+    ("floor", 1, Some(1)),
+    ("ceiling", 1, Some(1)),
+    ("round", 1, Some(1)),
+    ("current", 0, Some(0)),
+    ("re-match", 2, Some(2)),
+    ("deref", 1, Some(1)),
+    ("derived-from", 2, Some(2)),
+    ("derived-from-or-self", 2, Some(2)),
+    ("enum-value", 1, Some(1)),
+    ("bit-is-set", 2, Some(2)),
+];
+
+/// Checks that `name` is a function taking `count` arguments.
+fn check_arguments(name: &str, count: usize) -> Result<(), String> {
+    let Some((_, min, max)) = FUNCTIONS.iter().find(|(function, ..)| *function == name) else {
+        return Err(format!("Unknown function {}", name));
+    };
+    if count < *min || max.is_some_and(|max| count > max) {
+        let expected = match max {
+            Some(max) if max == min => min.to_string(),
+            Some(max) => format!("{} to {}", min, max),
+            None => format!("at least {}", min),
+        };
+        return Err(format!(
+            "Function {} takes {} arguments, found {}",
+            name, expected, count
+        ));
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum BinaryOperator {
+    Or,
+    And,
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Modulo,
+    Union,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Axis {
+    Ancestor,
+    AncestorOrSelf,
+    Attribute,
+    Child,
+    Descendant,
+    DescendantOrSelf,
+    Following,
+    FollowingSibling,
+    Namespace,
+    Parent,
+    Preceding,
+    PrecedingSibling,
+    SelfNode,
+}
+
+impl Axis {
+    fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "ancestor" => Axis::Ancestor,
+            "ancestor-or-self" => Axis::AncestorOrSelf,
+            "attribute" => Axis::Attribute,
+            "child" => Axis::Child,
+            "descendant" => Axis::Descendant,
+            "descendant-or-self" => Axis::DescendantOrSelf,
+            "following" => Axis::Following,
+            "following-sibling" => Axis::FollowingSibling,
+            "namespace" => Axis::Namespace,
+            "parent" => Axis::Parent,
+            "preceding" => Axis::Preceding,
+            "preceding-sibling" => Axis::PrecedingSibling,
+            "self" => Axis::SelfNode,
+            _ => return None,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum NodeTest {
+    /// A node name, with its prefix if any.
+    Name {
+        prefix: Option<String>,
+        name: String,
+    },
+    /// `*` or `prefix:*`.
+    Wildcard {
+        prefix: Option<String>,
+    },
+    Node,
+    Text,
+    Comment,
+    ProcessingInstruction(Option<String>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Step {
+    pub(crate) axis: Axis,
+    pub(crate) test: NodeTest,
+    pub(crate) predicates: Vec<Expr>,
+    pub(crate) span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Expr {
+    Binary {
+        operator: BinaryOperator,
+        left: Box<Expr>,
+        right: Box<Expr>,
+    },
+    Negate(Box<Expr>),
+    /// A location path, starting at the root if absolute.
+    Path {
+        absolute: bool,
+        steps: Vec<Step>,
+    },
+    /// A primary expression filtered by predicates and followed by steps, as in
+    /// `current()/../name`.
+    Filter {
+        primary: Box<Expr>,
+        predicates: Vec<Expr>,
+        steps: Vec<Step>,
+    },
+    Literal(String),
+    Number(f64),
+    Variable {
+        name: String,
+        span: Span,
+    },
+    FunctionCall {
+        name: String,
+        arguments: Vec<Expr>,
+        span: Span,
+    },
+}
+
+/// An error in an expression, with its span in the expression string.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct XPathError {
+    pub(crate) span: Span,
+    pub(crate) message: String,
+}
+
+/// Maps `span` in the expression `argument` to the source, given the span of
+/// the argument's first token. Offsets are only exact if the argument is a
+/// single token, otherwise the whole token is used.
+pub(crate) fn source_span(argument: &str, argument_span: Span, span: Span) -> Span {
+    let token_length = argument_span.1 - argument_span.0;
+    let start = if token_length == argument.len() + 2 {
+        // Quoted
+        argument_span.0 + 1
+    } else if token_length == argument.len() {
+        argument_span.0
+    } else {
+        return argument_span;
+    };
+    (start + span.0, start + span.1)
+}
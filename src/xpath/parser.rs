@@ -0,0 +1,798 @@
+use crate::xpath::{Axis, BinaryOperator, Expr, NodeTest, Step, XPathError};
+use crate::Span;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    LeftParen,
+    RightParen,
+    LeftBracket,
+    RightBracket,
+    Dot,
+    DotDot,
+    At,
+    Comma,
+    ColonColon,
+    Slash,
+    DoubleSlash,
+    Operator(BinaryOperator),
+    Minus,
+    /// A name test, with `None` as name for `*`.
+    NameTest {
+        prefix: Option<String>,
+        name: Option<String>,
+    },
+    NodeType(String),
+    FunctionName(String),
+    AxisName(String),
+    Literal(String),
+    Number(f64),
+    Variable(String),
+}
+
+fn is_name_start(char: char) -> bool {
+    char.is_alphabetic() || char == '_'
+}
+
+fn is_name_char(char: char) -> bool {
+    char.is_alphanumeric() || matches!(char, '_' | '-' | '.')
+}
+
+struct Lexer<'a> {
+    input: &'a str,
+    position: usize,
+    tokens: Vec<(Token, Span)>,
+}
+
+impl Lexer<'_> {
+    fn peek_char(&self) -> Option<char> {
+        self.input[self.position..].chars().next()
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = &self.input[self.position..];
+        self.position += rest.len() - rest.trim_start().len();
+    }
+
+    fn read_name(&mut self) -> &str {
+        let start = self.position;
+        let rest = &self.input[start..];
+        let length = rest.find(|char| !is_name_char(char)).unwrap_or(rest.len());
+        self.position += length;
+        &self.input[start..self.position]
+    }
+
+    /// Whether the next token may be an operator, following the disambiguation
+    /// rules of XPath 1.0, section 3.7.
+    fn expects_operator(&self) -> bool {
+        match self.tokens.last() {
+            None => false,
+            Some((token, _)) => !matches!(
+                token,
+                Token::At
+                    | Token::ColonColon
+                    | Token::LeftParen
+                    | Token::LeftBracket
+                    | Token::Comma
+                    | Token::Operator(_)
+                    | Token::Minus
+                    | Token::Slash
+                    | Token::DoubleSlash
+            ),
+        }
+    }
+
+    /// The character following the current position after whitespace.
+    fn next_significant_char(&self) -> Option<char> {
+        self.input[self.position..].trim_start().chars().next()
+    }
+
+    fn tokenize(mut self) -> Result<Vec<(Token, Span)>, XPathError> {
+        loop {
+            self.skip_whitespace();
+            let start = self.position;
+            let Some(char) = self.peek_char() else {
+                return Ok(self.tokens);
+            };
+            let rest = &self.input[start..];
+            let symbol = [
+                ("//", Token::DoubleSlash),
+                ("::", Token::ColonColon),
+                ("..", Token::DotDot),
+                ("!=", Token::Operator(BinaryOperator::NotEqual)),
+                ("<=", Token::Operator(BinaryOperator::LessOrEqual)),
+                (">=", Token::Operator(BinaryOperator::GreaterOrEqual)),
+                ("(", Token::LeftParen),
+                (")", Token::RightParen),
+                ("[", Token::LeftBracket),
+                ("]", Token::RightBracket),
+                ("@", Token::At),
+                (",", Token::Comma),
+                ("/", Token::Slash),
+                ("|", Token::Operator(BinaryOperator::Union)),
+                ("+", Token::Operator(BinaryOperator::Add)),
+                ("-", Token::Minus),
+                ("=", Token::Operator(BinaryOperator::Equal)),
+                ("<", Token::Operator(BinaryOperator::Less)),
+                (">", Token::Operator(BinaryOperator::Greater)),
+            ]
+            .into_iter()
+            .find(|(symbol, _)| rest.starts_with(symbol));
+
+            let token = if char == '.' && !rest[1..].starts_with(|char: char| char.is_ascii_digit())
+                || symbol.is_some()
+            {
+                let (symbol, token) = symbol.unwrap_or((".", Token::Dot));
+                self.position += symbol.len();
+                token
+            } else if char.is_ascii_digit() || char == '.' {
+                let length = rest
+                    .find(|char: char| !char.is_ascii_digit() && char != '.')
+                    .unwrap_or(rest.len());
+                self.position += length;
+                match rest[..length].parse() {
+                    Ok(number) => Token::Number(number),
+                    Err(_) => {
+                        return Err(XPathError {
+                            span: (start, self.position),
+                            message: format!("Invalid number {}", &rest[..length]),
+                        })
+                    }
+                }
+            } else if char == '"' || char == '\'' {
+                let Some(length) = rest[1..].find(char) else {
+                    return Err(XPathError {
+                        span: (start, self.input.len()),
+                        message: "Unterminated literal".to_string(),
+                    });
+                };
+                self.position += length + 2;
+                Token::Literal(rest[1..length + 1].to_string())
+            } else if char == '*' {
+                self.position += 1;
+                if self.expects_operator() {
+                    Token::Operator(BinaryOperator::Multiply)
+                } else {
+                    Token::NameTest {
+                        prefix: None,
+                        name: None,
+                    }
+                }
+            } else if char == '$' {
+                self.position += 1;
+                let name = self.read_name().to_string();
+                if name.is_empty() {
+                    return Err(XPathError {
+                        span: (start, self.position),
+                        message: "Expected variable name".to_string(),
+                    });
+                }
+                Token::Variable(name)
+            } else if is_name_start(char) {
+                let name = self.read_name().to_string();
+                if self.expects_operator() {
+                    let operator = match name.as_str() {
+                        "and" => BinaryOperator::And,
+                        "or" => BinaryOperator::Or,
+                        "mod" => BinaryOperator::Modulo,
+                        "div" => BinaryOperator::Divide,
+                        _ => {
+                            return Err(XPathError {
+                                span: (start, self.position),
+                                message: format!("Expected operator, found {}", name),
+                            })
+                        }
+                    };
+                    Token::Operator(operator)
+                } else if self.input[self.position..].starts_with("::") {
+                    Token::AxisName(name)
+                } else if self.input[self.position..].starts_with(':') {
+                    self.position += 1;
+                    if self.peek_char() == Some('*') {
+                        self.position += 1;
+                        Token::NameTest {
+                            prefix: Some(name),
+                            name: None,
+                        }
+                    } else if self.peek_char().is_some_and(is_name_start) {
+                        let local_name = self.read_name().to_string();
+                        if self.next_significant_char() == Some('(') {
+                            Token::FunctionName(format!("{}:{}", name, local_name))
+                        } else {
+                            Token::NameTest {
+                                prefix: Some(name),
+                                name: Some(local_name),
+                            }
+                        }
+                    } else {
+                        return Err(XPathError {
+                            span: (start, self.position),
+                            message: format!("Expected name after {}:", name),
+                        });
+                    }
+                } else if self.next_significant_char() == Some('(') {
+                    match name.as_str() {
+                        "node" | "text" | "comment" | "processing-instruction" => {
+                            Token::NodeType(name)
+                        }
+                        _ => Token::FunctionName(name),
+                    }
+                } else {
+                    Token::NameTest {
+                        prefix: None,
+                        name: Some(name),
+                    }
+                }
+            } else {
+                return Err(XPathError {
+                    span: (start, start + char.len_utf8()),
+                    message: format!("Unexpected character {}", char),
+                });
+            };
+            self.tokens.push((token, (start, self.position)));
+        }
+    }
+}
+
+struct Parser<'a> {
+    input: &'a str,
+    tokens: Vec<(Token, Span)>,
+    position: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position).map(|(token, _)| token)
+    }
+
+    fn span(&self) -> Span {
+        match self.tokens.get(self.position) {
+            Some((_, span)) => *span,
+            None => (self.input.len(), self.input.len()),
+        }
+    }
+
+    fn previous_end(&self) -> usize {
+        self.position
+            .checked_sub(1)
+            .map_or(0, |index| self.tokens[index].1 .1)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self
+            .tokens
+            .get(self.position)
+            .map(|(token, _)| token.clone());
+        self.position += 1;
+        token
+    }
+
+    fn unexpected(&self) -> XPathError {
+        let message = match self.tokens.get(self.position) {
+            Some((_, span)) => format!("Unexpected {}", &self.input[span.0..span.1]),
+            None => "Unexpected end of expression".to_string(),
+        };
+        XPathError {
+            span: self.span(),
+            message,
+        }
+    }
+
+    fn expect(&mut self, token: Token, description: &str) -> Result<(), XPathError> {
+        if self.peek() != Some(&token) {
+            return Err(XPathError {
+                span: self.span(),
+                message: format!("Expected {}", description),
+            });
+        }
+        self.position += 1;
+        Ok(())
+    }
+
+    fn parse_binary(
+        &mut self,
+        operators: &[BinaryOperator],
+        operand: fn(&mut Self) -> Result<Expr, XPathError>,
+    ) -> Result<Expr, XPathError> {
+        let mut left = operand(self)?;
+        while let Some(Token::Operator(operator)) = self.peek() {
+            let operator = *operator;
+            if !operators.contains(&operator) {
+                break;
+            }
+            self.position += 1;
+            let right = operand(self)?;
+            left = Expr::Binary {
+                operator,
+                left: Box::new(left),
+                right: Box::new(right),
+            };
+        }
+        Ok(left)
+    }
+
+    fn parse_or(&mut self) -> Result<Expr, XPathError> {
+        self.parse_binary(&[BinaryOperator::Or], Self::parse_and)
+    }
+
+    fn parse_and(&mut self) -> Result<Expr, XPathError> {
+        self.parse_binary(&[BinaryOperator::And], Self::parse_equality)
+    }
+
+    fn parse_equality(&mut self) -> Result<Expr, XPathError> {
+        self.parse_binary(
+            &[BinaryOperator::Equal, BinaryOperator::NotEqual],
+            Self::parse_relational,
+        )
+    }
+
+    fn parse_relational(&mut self) -> Result<Expr, XPathError> {
+        self.parse_binary(
+            &[
+                BinaryOperator::Less,
+                BinaryOperator::LessOrEqual,
+                BinaryOperator::Greater,
+                BinaryOperator::GreaterOrEqual,
+            ],
+            Self::parse_additive,
+        )
+    }
+
+    fn parse_additive(&mut self) -> Result<Expr, XPathError> {
+        let mut left = self.parse_multiplicative()?;
+        loop {
+            let operator = match self.peek() {
+                Some(Token::Operator(BinaryOperator::Add)) => BinaryOperator::Add,
+                Some(Token::Minus) => BinaryOperator::Subtract,
+                _ => return Ok(left),
+            };
+            self.position += 1;
+            let right = self.parse_multiplicative()?;
+            left = Expr::Binary {
+                operator,
+                left: Box::new(left),
+                right: Box::new(right),
+            };
+        }
+    }
+
+    fn parse_multiplicative(&mut self) -> Result<Expr, XPathError> {
+        self.parse_binary(
+            &[
+                BinaryOperator::Multiply,
+                BinaryOperator::Divide,
+                BinaryOperator::Modulo,
+            ],
+            Self::parse_unary,
+        )
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr, XPathError> {
+        if self.peek() == Some(&Token::Minus) {
+            self.position += 1;
+            return Ok(Expr::Negate(Box::new(self.parse_unary()?)));
+        }
+        self.parse_binary(&[BinaryOperator::Union], Self::parse_path)
+    }
+
+    fn parse_path(&mut self) -> Result<Expr, XPathError> {
+        match self.peek() {
+            Some(
+                Token::Variable(_)
+                | Token::LeftParen
+                | Token::Literal(_)
+                | Token::Number(_)
+                | Token::FunctionName(_),
+            ) => {
+                let primary = self.parse_primary()?;
+                let predicates = self.parse_predicates()?;
+                let steps = match self.peek() {
+                    Some(Token::Slash | Token::DoubleSlash) => self.parse_relative_path()?,
+                    _ => vec![],
+                };
+                if predicates.is_empty() && steps.is_empty() {
+                    return Ok(primary);
+                }
+                Ok(Expr::Filter {
+                    primary: Box::new(primary),
+                    predicates,
+                    steps,
+                })
+            }
+            Some(Token::Slash) => {
+                self.position += 1;
+                let steps = if self.starts_step() {
+                    self.parse_steps()?
+                } else {
+                    vec![]
+                };
+                Ok(Expr::Path {
+                    absolute: true,
+                    steps,
+                })
+            }
+            Some(Token::DoubleSlash) => Ok(Expr::Path {
+                absolute: true,
+                steps: self.parse_relative_path()?,
+            }),
+            _ if self.starts_step() => Ok(Expr::Path {
+                absolute: false,
+                steps: self.parse_steps()?,
+            }),
+            _ => Err(self.unexpected()),
+        }
+    }
+
+    fn starts_step(&self) -> bool {
+        matches!(
+            self.peek(),
+            Some(
+                Token::NameTest { .. }
+                    | Token::NodeType(_)
+                    | Token::AxisName(_)
+                    | Token::At
+                    | Token::Dot
+                    | Token::DotDot
+            )
+        )
+    }
+
+    /// Parses steps each preceded by `/` or `//`.
+    fn parse_relative_path(&mut self) -> Result<Vec<Step>, XPathError> {
+        let mut steps = vec![];
+        loop {
+            match self.peek() {
+                Some(Token::Slash) => self.position += 1,
+                Some(Token::DoubleSlash) => {
+                    let span = self.span();
+                    self.position += 1;
+                    steps.push(Step {
+                        axis: Axis::DescendantOrSelf,
+                        test: NodeTest::Node,
+                        predicates: vec![],
+                        span,
+                    });
+                }
+                _ => return Ok(steps),
+            }
+            steps.push(self.parse_step()?);
+        }
+    }
+
+    /// Parses a step followed by steps each preceded by `/` or `//`.
+    fn parse_steps(&mut self) -> Result<Vec<Step>, XPathError> {
+        let mut steps = vec![self.parse_step()?];
+        steps.extend(self.parse_relative_path()?);
+        Ok(steps)
+    }
+
+    fn parse_step(&mut self) -> Result<Step, XPathError> {
+        let start = self.span().0;
+        let axis = match self.peek() {
+            Some(Token::Dot | Token::DotDot) => {
+                let axis = match self.next() {
+                    Some(Token::Dot) => Axis::SelfNode,
+                    _ => Axis::Parent,
+                };
+                return Ok(Step {
+                    axis,
+                    test: NodeTest::Node,
+                    predicates: vec![],
+                    span: (start, self.previous_end()),
+                });
+            }
+            Some(Token::AxisName(name)) => {
+                let Some(axis) = Axis::from_name(name) else {
+                    return Err(XPathError {
+                        span: self.span(),
+                        message: format!("Unknown axis {}", name),
+                    });
+                };
+                self.position += 1;
+                self.expect(Token::ColonColon, "::")?;
+                axis
+            }
+            Some(Token::At) => {
+                self.position += 1;
+                Axis::Attribute
+            }
+            _ => Axis::Child,
+        };
+        let test = match self.next() {
+            Some(Token::NameTest {
+                prefix,
+                name: Some(name),
+            }) => NodeTest::Name { prefix, name },
+            Some(Token::NameTest { prefix, name: None }) => NodeTest::Wildcard { prefix },
+            Some(Token::NodeType(node_type)) => {
+                self.expect(Token::LeftParen, "(")?;
+                let test = match node_type.as_str() {
+                    "node" => NodeTest::Node,
+                    "text" => NodeTest::Text,
+                    "comment" => NodeTest::Comment,
+                    _ => match self.peek() {
+                        Some(Token::Literal(literal)) => {
+                            let literal = literal.clone();
+                            self.position += 1;
+                            NodeTest::ProcessingInstruction(Some(literal))
+                        }
+                        _ => NodeTest::ProcessingInstruction(None),
+                    },
+                };
+                self.expect(Token::RightParen, ")")?;
+                test
+            }
+            _ => {
+                self.position -= 1;
+                return Err(XPathError {
+                    span: self.span(),
+                    message: "Expected node test".to_string(),
+                });
+            }
+        };
+        let span = (start, self.previous_end());
+        Ok(Step {
+            axis,
+            test,
+            predicates: self.parse_predicates()?,
+            span,
+        })
+    }
+
+    fn parse_predicates(&mut self) -> Result<Vec<Expr>, XPathError> {
+        let mut predicates = vec![];
+        while self.peek() == Some(&Token::LeftBracket) {
+            self.position += 1;
+            predicates.push(self.parse_or()?);
+            self.expect(Token::RightBracket, "]")?;
+        }
+        Ok(predicates)
+    }
+
+    fn parse_primary(&mut self) -> Result<Expr, XPathError> {
+        let span = self.span();
+        match self.next() {
+            Some(Token::Variable(name)) => Ok(Expr::Variable { name, span }),
+            Some(Token::LeftParen) => {
+                let expr = self.parse_or()?;
+                self.expect(Token::RightParen, ")")?;
+                Ok(expr)
+            }
+            Some(Token::Literal(literal)) => Ok(Expr::Literal(literal)),
+            Some(Token::Number(number)) => Ok(Expr::Number(number)),
+            Some(Token::FunctionName(name)) => {
+                self.expect(Token::LeftParen, "(")?;
+                let mut arguments = vec![];
+                if self.peek() != Some(&Token::RightParen) {
+                    arguments.push(self.parse_or()?);
+                    while self.peek() == Some(&Token::Comma) {
+                        self.position += 1;
+                        arguments.push(self.parse_or()?);
+                    }
+                }
+                self.expect(Token::RightParen, ")")?;
+                Ok(Expr::FunctionCall {
+                    name,
+                    arguments,
+                    span,
+                })
+            }
+            _ => {
+                self.position -= 1;
+                Err(self.unexpected())
+            }
+        }
+    }
+}
+
+/// Parses the XPath 1.0 expression `input`.
+pub(crate) fn parse(input: &str) -> Result<Expr, XPathError> {
+    let tokens = Lexer {
+        input,
+        position: 0,
+        tokens: vec![],
+    }
+    .tokenize()?;
+    let mut parser = Parser {
+        input,
+        tokens,
+        position: 0,
+    };
+    let expr = parser.parse_or()?;
+    if parser.position < parser.tokens.len() {
+        return Err(parser.unexpected());
+    }
+    Ok(expr)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn name(name: &str) -> NodeTest {
+        NodeTest::Name {
+            prefix: None,
+            name: name.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            parse("../ex:type = 'ethernet' and mtu * 2 > -1").unwrap(),
+            Expr::Binary {
+                operator: BinaryOperator::And,
+                left: Box::new(Expr::Binary {
+                    operator: BinaryOperator::Equal,
+                    left: Box::new(Expr::Path {
+                        absolute: false,
+                        steps: vec![
+                            Step {
+                                axis: Axis::Parent,
+                                test: NodeTest::Node,
+                                predicates: vec![],
+                                span: (0, 2),
+                            },
+                            Step {
+                                axis: Axis::Child,
+                                test: NodeTest::Name {
+                                    prefix: Some("ex".to_string()),
+                                    name: "type".to_string()
+                                },
+                                predicates: vec![],
+                                span: (3, 10),
+                            },
+                        ],
+                    }),
+                    right: Box::new(Expr::Literal("ethernet".to_string())),
+                }),
+                right: Box::new(Expr::Binary {
+                    operator: BinaryOperator::Greater,
+                    left: Box::new(Expr::Binary {
+                        operator: BinaryOperator::Multiply,
+                        left: Box::new(Expr::Path {
+                            absolute: false,
+                            steps: vec![Step {
+                                axis: Axis::Child,
+                                test: name("mtu"),
+                                predicates: vec![],
+                                span: (28, 31),
+                            }],
+                        }),
+                        right: Box::new(Expr::Number(2.0)),
+                    }),
+                    right: Box::new(Expr::Negate(Box::new(Expr::Number(1.0)))),
+                }),
+            }
+        );
+
+        assert_eq!(
+            parse("current()/../interface[name = current()]//*").unwrap(),
+            Expr::Filter {
+                primary: Box::new(Expr::FunctionCall {
+                    name: "current".to_string(),
+                    arguments: vec![],
+                    span: (0, 7),
+                }),
+                predicates: vec![],
+                steps: vec![
+                    Step {
+                        axis: Axis::Parent,
+                        test: NodeTest::Node,
+                        predicates: vec![],
+                        span: (10, 12),
+                    },
+                    Step {
+                        axis: Axis::Child,
+                        test: name("interface"),
+                        predicates: vec![Expr::Binary {
+                            operator: BinaryOperator::Equal,
+                            left: Box::new(Expr::Path {
+                                absolute: false,
+                                steps: vec![Step {
+                                    axis: Axis::Child,
+                                    test: name("name"),
+                                    predicates: vec![],
+                                    span: (23, 27),
+                                }],
+                            }),
+                            right: Box::new(Expr::FunctionCall {
+                                name: "current".to_string(),
+                                arguments: vec![],
+                                span: (30, 37),
+                            }),
+                        }],
+                        span: (13, 22),
+                    },
+                    Step {
+                        axis: Axis::DescendantOrSelf,
+                        test: NodeTest::Node,
+                        predicates: vec![],
+                        span: (40, 42),
+                    },
+                    Step {
+                        axis: Axis::Child,
+                        test: NodeTest::Wildcard { prefix: None },
+                        predicates: vec![],
+                        span: (42, 43),
+                    },
+                ],
+            }
+        );
+
+        assert_eq!(
+            parse("ancestor-or-self::node()/@x | / | $v").unwrap(),
+            Expr::Binary {
+                operator: BinaryOperator::Union,
+                left: Box::new(Expr::Binary {
+                    operator: BinaryOperator::Union,
+                    left: Box::new(Expr::Path {
+                        absolute: false,
+                        steps: vec![
+                            Step {
+                                axis: Axis::AncestorOrSelf,
+                                test: NodeTest::Node,
+                                predicates: vec![],
+                                span: (0, 24),
+                            },
+                            Step {
+                                axis: Axis::Attribute,
+                                test: name("x"),
+                                predicates: vec![],
+                                span: (25, 27),
+                            },
+                        ],
+                    }),
+                    right: Box::new(Expr::Path {
+                        absolute: true,
+                        steps: vec![],
+                    }),
+                }),
+                right: Box::new(Expr::Variable {
+                    name: "v".to_string(),
+                    span: (34, 36),
+                }),
+            }
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        let error = |input| parse(input).unwrap_err();
+        assert_eq!(
+            error("a = 'b"),
+            XPathError {
+                span: (4, 6),
+                message: "Unterminated literal".to_string()
+            }
+        );
+        assert_eq!(
+            error("a b"),
+            XPathError {
+                span: (2, 3),
+                message: "Expected operator, found b".to_string()
+            }
+        );
+        assert_eq!(
+            error("count(a"),
+            XPathError {
+                span: (7, 7),
+                message: "Expected )".to_string()
+            }
+        );
+        assert_eq!(
+            error("a[1]]"),
+            XPathError {
+                span: (4, 5),
+                message: "Unexpected ]".to_string()
+            }
+        );
+        assert_eq!(
+            error("up::a"),
+            XPathError {
+                span: (0, 2),
+                message: "Unknown axis up".to_string()
+            }
+        );
+        assert_eq!(error("a and").message, "Unexpected end of expression");
+    }
+}
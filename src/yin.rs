@@ -0,0 +1,674 @@
+//! YIN, the XML serialization of YANG defined in RFC 7950, section 13.
+//!
+//! [`parse_yin`] produces the same [`Statement`] tree as the YANG parser, so all of
+//! the model mapping applies unchanged, and [`write_yin`] prints a statement tree
+//! as YIN again.
+
+use crate::parser::parser::{ParseError, Statement};
+use crate::Span;
+use std::collections::HashMap;
+
+pub(crate) const YIN_NAMESPACE: &str = "urn:ietf:params:xml:ns:yang:yin:1";
+
+/// How the argument of a statement is encoded: the name of the attribute or child
+/// element, and whether it is a child element (`yin-element true`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct YinArgument {
+    pub(crate) name: String,
+    pub(crate) yin_element: bool,
+}
+
+/// Mapping of YANG keywords to their YIN argument, see RFC 7950, section 13.1.
+static KEYWORD_ARGUMENTS: phf::Map<&'static str, Option<(&'static str, bool)>> = phf::phf_map! {
+    "action" => Some(("name", false)),
+    "anydata" => Some(("name", false)),
+    "anyxml" => Some(("name", false)),
+    "argument" => Some(("name", false)),
+    "augment" => Some(("target-node", false)),
+    "base" => Some(("name", false)),
+    "belongs-to" => Some(("module", false)),
+    "bit" => Some(("name", false)),
+    "case" => Some(("name", false)),
+    "choice" => Some(("name", false)),
+    "config" => Some(("value", false)),
+    "contact" => Some(("text", true)),
+    "container" => Some(("name", false)),
+    "default" => Some(("value", false)),
+    "description" => Some(("text", true)),
+    "deviate" => Some(("value", false)),
+    "deviation" => Some(("target-node", false)),
+    "enum" => Some(("name", false)),
+    "error-app-tag" => Some(("value", false)),
+    "error-message" => Some(("value", true)),
+    "extension" => Some(("name", false)),
+    "feature" => Some(("name", false)),
+    "fraction-digits" => Some(("value", false)),
+    "grouping" => Some(("name", false)),
+    "identity" => Some(("name", false)),
+    "if-feature" => Some(("name", false)),
+    "import" => Some(("module", false)),
+    "include" => Some(("module", false)),
+    "input" => None,
+    "key" => Some(("value", false)),
+    "leaf" => Some(("name", false)),
+    "leaf-list" => Some(("name", false)),
+    "length" => Some(("value", false)),
+    "list" => Some(("name", false)),
+    "mandatory" => Some(("value", false)),
+    "max-elements" => Some(("value", false)),
+    "min-elements" => Some(("value", false)),
+    "modifier" => Some(("value", false)),
+    "module" => Some(("name", false)),
+    "must" => Some(("condition", false)),
+    "namespace" => Some(("uri", false)),
+    "notification" => Some(("name", false)),
+    "ordered-by" => Some(("value", false)),
+    "organization" => Some(("text", true)),
+    "output" => None,
+    "path" => Some(("value", false)),
+    "pattern" => Some(("value", false)),
+    "position" => Some(("value", false)),
+    "prefix" => Some(("value", false)),
+    "presence" => Some(("value", false)),
+    "range" => Some(("value", false)),
+    "reference" => Some(("text", true)),
+    "refine" => Some(("target-node", false)),
+    "require-instance" => Some(("value", false)),
+    "revision" => Some(("date", false)),
+    "revision-date" => Some(("date", false)),
+    "rpc" => Some(("name", false)),
+    "status" => Some(("value", false)),
+    "submodule" => Some(("name", false)),
+    "type" => Some(("name", false)),
+    "typedef" => Some(("name", false)),
+    "unique" => Some(("tag", false)),
+    "units" => Some(("name", false)),
+    "uses" => Some(("name", false)),
+    "value" => Some(("value", false)),
+    "when" => Some(("condition", false)),
+    "yang-version" => Some(("value", false)),
+    "yin-element" => Some(("value", false)),
+};
+
+/// Whether `keyword` is a YANG keyword rather than an extension or unknown statement.
+pub(crate) fn is_yang_keyword(keyword: &str) -> bool {
+    KEYWORD_ARGUMENTS.contains_key(keyword)
+}
+
+/// Information about modules other than the one being converted.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct YinContext {
+    /// Namespaces of imported modules, by module name.
+    #[cfg_attr(not(feature = "cli"), allow(dead_code))]
+    pub(crate) namespaces: HashMap<String, String>,
+    /// Arguments of extensions defined in imported modules, by module and extension
+    /// name. `None` if the extension takes no argument.
+    pub(crate) extensions: HashMap<(String, String), Option<YinArgument>>,
+}
+
+/// Extension arguments defined by the `extension` statements of `statements`.
+fn local_extensions<'a>(
+    statements: impl Iterator<Item = &'a Statement>,
+) -> HashMap<String, Option<YinArgument>> {
+    statements
+        .filter(|statement| statement.keyword == "extension")
+        .filter_map(|extension| {
+            let argument = extension
+                .statements
+                .iter()
+                .find(|statement| statement.keyword == "argument")
+                .and_then(|argument| {
+                    Some(YinArgument {
+                        name: argument.argument.clone()?,
+                        yin_element: argument.statements.iter().any(|statement| {
+                            statement.keyword == "yin-element"
+                                && statement.argument.as_deref() == Some("true")
+                        }),
+                    })
+                });
+            Some((extension.argument.clone()?, argument))
+        })
+        .collect()
+}
+
+fn argument_of<'a>(statement: &'a Statement, keyword: &str) -> Option<&'a str> {
+    statement
+        .statements
+        .iter()
+        .find(|statement| statement.keyword == keyword)
+        .and_then(|statement| statement.argument.as_deref())
+}
+
+impl YinContext {
+    /// Records the namespace and the extensions of the module `statement`, for
+    /// converting modules importing it.
+    #[cfg(feature = "cli")]
+    pub(crate) fn add_module(&mut self, statement: &Statement) {
+        let Some(name) = &statement.argument else {
+            return;
+        };
+        if let Some(namespace) = argument_of(statement, "namespace") {
+            self.namespaces.insert(name.clone(), namespace.to_string());
+        }
+        for (extension, argument) in local_extensions(statement.statements.iter()) {
+            self.extensions.insert((name.clone(), extension), argument);
+        }
+    }
+}
+
+/// The module name and prefix a (sub)module uses to refer to its own definitions.
+fn own_module_and_prefix(root: &Statement) -> (Option<&str>, Option<&str>) {
+    match root.keyword.as_str() {
+        "submodule" => {
+            let belongs_to = root
+                .statements
+                .iter()
+                .find(|statement| statement.keyword == "belongs-to");
+            (
+                belongs_to.and_then(|belongs_to| belongs_to.argument.as_deref()),
+                belongs_to.and_then(|belongs_to| argument_of(belongs_to, "prefix")),
+            )
+        }
+        _ => (root.argument.as_deref(), argument_of(root, "prefix")),
+    }
+}
+
+/// Maps the prefixes usable in `root` to module names.
+fn prefix_modules(root: &Statement) -> HashMap<String, String> {
+    let mut prefixes = HashMap::new();
+    if let (Some(module), Some(prefix)) = own_module_and_prefix(root) {
+        prefixes.insert(prefix.to_string(), module.to_string());
+    }
+    for import in root
+        .statements
+        .iter()
+        .filter(|statement| statement.keyword == "import")
+    {
+        if let (Some(module), Some(prefix)) = (&import.argument, argument_of(import, "prefix")) {
+            prefixes.insert(prefix.to_string(), module.clone());
+        }
+    }
+    prefixes
+}
+
+struct ExtensionResolver<'a> {
+    context: &'a YinContext,
+    own_module: Option<String>,
+    local_extensions: HashMap<String, Option<YinArgument>>,
+    prefix_modules: HashMap<String, String>,
+}
+
+impl<'a> ExtensionResolver<'a> {
+    fn new(root: &Statement, context: &'a YinContext) -> Self {
+        ExtensionResolver {
+            context,
+            own_module: own_module_and_prefix(root).0.map(str::to_string),
+            local_extensions: local_extensions(root.statements.iter()),
+            prefix_modules: prefix_modules(root),
+        }
+    }
+
+    /// Looks up the argument of the extension `prefix:name`. Returns `None` if the
+    /// extension is unknown.
+    fn resolve(&self, prefix: &str, name: &str) -> Option<Option<YinArgument>> {
+        let module = self.prefix_modules.get(prefix)?;
+        if Some(module) == self.own_module.as_ref() {
+            return self.local_extensions.get(name).cloned();
+        }
+        self.context
+            .extensions
+            .get(&(module.clone(), name.to_string()))
+            .cloned()
+    }
+}
+
+#[cfg(feature = "cli")]
+fn escape_xml(value: &str, escape_quotes: bool) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for ch in value.chars() {
+        match ch {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' if escape_quotes => escaped.push_str("&quot;"),
+            ch => escaped.push(ch),
+        }
+    }
+    escaped
+}
+
+#[cfg(feature = "cli")]
+struct YinWriter<'a> {
+    resolver: ExtensionResolver<'a>,
+    output: String,
+}
+
+#[cfg(feature = "cli")]
+impl YinWriter<'_> {
+    fn write_indent(&mut self, depth: usize) {
+        self.output.extend(std::iter::repeat_n(' ', depth * 2));
+    }
+
+    fn argument_encoding(&self, keyword: &str) -> Option<YinArgument> {
+        if let Some((prefix, name)) = keyword.split_once(':') {
+            return self.resolver.resolve(prefix, name).unwrap_or_else(|| {
+                Some(YinArgument {
+                    name: "value".to_string(),
+                    yin_element: false,
+                })
+            });
+        }
+        KEYWORD_ARGUMENTS
+            .get(keyword)
+            .cloned()
+            .flatten()
+            .map(|(name, yin_element)| YinArgument {
+                name: name.to_string(),
+                yin_element,
+            })
+    }
+
+    fn write_statement(
+        &mut self,
+        statement: &Statement,
+        depth: usize,
+        namespaces: &[(String, String)],
+    ) {
+        self.write_indent(depth);
+        self.output.push('<');
+        self.output.push_str(&statement.keyword);
+
+        let encoding = self.argument_encoding(&statement.keyword);
+        let argument = statement.argument.as_ref().zip(encoding.as_ref());
+        if let Some((argument, encoding)) = argument.filter(|(_, encoding)| !encoding.yin_element) {
+            self.output.push_str(&format!(
+                " {}=\"{}\"",
+                encoding.name,
+                escape_xml(argument, true)
+            ));
+        }
+        for (prefix, namespace) in namespaces {
+            self.output.push('\n');
+            self.write_indent(depth);
+            self.output.push_str(&format!(
+                "{}xmlns{}=\"{}\"",
+                " ".repeat(statement.keyword.len() + 2),
+                if prefix.is_empty() {
+                    String::new()
+                } else {
+                    format!(":{}", prefix)
+                },
+                escape_xml(namespace, true)
+            ));
+        }
+
+        let element_argument = argument.filter(|(_, encoding)| encoding.yin_element);
+        if element_argument.is_none() && statement.statements.is_empty() {
+            self.output.push_str("/>\n");
+            return;
+        }
+        self.output.push_str(">\n");
+        if let Some((argument, encoding)) = element_argument {
+            let element_name = match statement.keyword.split_once(':') {
+                Some((prefix, _)) => format!("{}:{}", prefix, encoding.name),
+                None => encoding.name.clone(),
+            };
+            self.write_indent(depth + 1);
+            self.output.push_str(&format!(
+                "<{}>{}</{}>\n",
+                element_name,
+                escape_xml(argument, false),
+                element_name
+            ));
+        }
+        for substatement in &statement.statements {
+            self.write_statement(substatement, depth + 1, &[]);
+        }
+        self.write_indent(depth);
+        self.output.push_str(&format!("</{}>\n", statement.keyword));
+    }
+}
+
+/// Prints a module or submodule statement tree as YIN.
+///
+/// Namespaces of imported modules are taken from `context`. Unknown namespaces are
+/// assumed to follow the IETF convention `urn:ietf:params:xml:ns:yang:<module>`.
+#[cfg(feature = "cli")]
+pub(crate) fn write_yin(statement: &Statement, context: &YinContext) -> String {
+    let resolver = ExtensionResolver::new(statement, context);
+    let own_namespace = argument_of(statement, "namespace").map(str::to_string);
+    let mut namespaces = vec![(String::new(), YIN_NAMESPACE.to_string())];
+    let mut prefixes = resolver.prefix_modules.iter().collect::<Vec<_>>();
+    prefixes.sort();
+    for (prefix, module) in prefixes {
+        let namespace = if Some(module) == resolver.own_module.as_ref() {
+            own_namespace.clone()
+        } else {
+            None
+        }
+        .or_else(|| context.namespaces.get(module).cloned())
+        .unwrap_or_else(|| format!("urn:ietf:params:xml:ns:yang:{}", module));
+        namespaces.push((prefix.clone(), namespace));
+    }
+
+    let mut writer = YinWriter {
+        resolver,
+        output: "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n".to_string(),
+    };
+    writer.write_statement(statement, 0, &namespaces);
+    writer.output
+}
+
+fn to_span(range: std::ops::Range<usize>) -> Span {
+    (range.start, range.end)
+}
+
+fn text_pos_to_loc(input: &str, pos: roxmltree::TextPos) -> usize {
+    input
+        .split_inclusive('\n')
+        .take(pos.row as usize - 1)
+        .map(str::len)
+        .sum::<usize>()
+        + input
+            .lines()
+            .nth(pos.row as usize - 1)
+            .map(|line| {
+                line.char_indices()
+                    .nth(pos.col as usize - 1)
+                    .map(|(loc, _)| loc)
+                    .unwrap_or(line.len())
+            })
+            .unwrap_or(0)
+}
+
+struct YinReader<'a> {
+    resolver: ExtensionResolver<'a>,
+}
+
+fn element_children<'a, 'input>(
+    node: roxmltree::Node<'a, 'input>,
+) -> impl Iterator<Item = roxmltree::Node<'a, 'input>> {
+    node.children().filter(|child| child.is_element())
+}
+
+fn parse_error(node: roxmltree::Node, message: String) -> ParseError {
+    ParseError {
+        span: Some(to_span(node.range())),
+        message,
+    }
+}
+
+impl YinReader<'_> {
+    fn keyword<'a>(node: roxmltree::Node<'a, '_>) -> Result<(String, Option<&'a str>), ParseError> {
+        let name = node.tag_name();
+        match name.namespace() {
+            Some(YIN_NAMESPACE) => Ok((name.name().to_string(), None)),
+            Some(namespace) => {
+                let prefix = node.lookup_prefix(namespace).ok_or_else(|| {
+                    parse_error(
+                        node,
+                        format!("No prefix declared for namespace {}", namespace),
+                    )
+                })?;
+                Ok((format!("{}:{}", prefix, name.name()), Some(namespace)))
+            }
+            None => Err(parse_error(
+                node,
+                format!("Element {} is not in the YIN namespace", name.name()),
+            )),
+        }
+    }
+
+    fn argument_encoding(&self, node: roxmltree::Node, keyword: &str) -> Option<YinArgument> {
+        if let Some((prefix, name)) = keyword.split_once(':') {
+            if let Some(argument) = self.resolver.resolve(prefix, name) {
+                return argument;
+            }
+            // Unknown extension, a single attribute is assumed to be its argument.
+            let mut attributes = node.attributes();
+            return match (attributes.next(), attributes.next()) {
+                (Some(attribute), None) => Some(YinArgument {
+                    name: attribute.name().to_string(),
+                    yin_element: false,
+                }),
+                _ => None,
+            };
+        }
+        KEYWORD_ARGUMENTS
+            .get(keyword)
+            .cloned()
+            .flatten()
+            .map(|(name, yin_element)| YinArgument {
+                name: name.to_string(),
+                yin_element,
+            })
+    }
+
+    fn read_statement(&self, node: roxmltree::Node) -> Result<Statement, ParseError> {
+        let (keyword, namespace) = Self::keyword(node)?;
+        let keyword_start = node.range().start + 1;
+        let keyword_span = (keyword_start, keyword_start + keyword.len());
+        let encoding = self.argument_encoding(node, &keyword);
+
+        let mut argument = None;
+        let mut argument_span = keyword_span;
+        let mut argument_element = None;
+        match &encoding {
+            Some(encoding) if encoding.yin_element => {
+                let element = element_children(node)
+                    .find(|child| {
+                        child.tag_name().name() == encoding.name
+                            && child.tag_name().namespace() == namespace.or(Some(YIN_NAMESPACE))
+                    })
+                    .ok_or_else(|| {
+                        parse_error(node, format!("Expected {} element", encoding.name))
+                    })?;
+                argument = Some(element.text().unwrap_or_default().to_string());
+                argument_span = element
+                    .first_child()
+                    .map(|text| to_span(text.range()))
+                    .unwrap_or_else(|| to_span(element.range()));
+                argument_element = Some(element);
+            }
+            Some(encoding) => {
+                let attribute = node
+                    .attributes()
+                    .find(|attribute| {
+                        attribute.name() == encoding.name && attribute.namespace().is_none()
+                    })
+                    .ok_or_else(|| {
+                        parse_error(node, format!("Expected {} attribute", encoding.name))
+                    })?;
+                argument = Some(attribute.value().to_string());
+                argument_span = to_span(attribute.range_value());
+            }
+            None => {}
+        }
+
+        let statements = element_children(node)
+            .filter(|child| Some(*child) != argument_element)
+            .map(|child| self.read_statement(child))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Statement {
+            keyword_span,
+            keyword,
+            argument_span,
+            argument,
+            statements,
+        })
+    }
+}
+
+/// Parses a YIN document into the statement tree of its module or submodule.
+pub(crate) fn parse_yin(input: &str, context: &YinContext) -> Result<Statement, ParseError> {
+    let document = roxmltree::Document::parse(input).map_err(|err| ParseError {
+        span: Some({
+            let loc = text_pos_to_loc(input, err.pos());
+            (loc, loc)
+        }),
+        message: err.to_string(),
+    })?;
+
+    // The extension definitions are needed before the statements using them are
+    // read, so they are read in a first pass without any extensions known.
+    let root = document.root_element();
+    let header_reader = YinReader {
+        resolver: ExtensionResolver {
+            context,
+            own_module: None,
+            local_extensions: HashMap::new(),
+            prefix_modules: HashMap::new(),
+        },
+    };
+    let (root_keyword, _) = YinReader::keyword(root)?;
+    let header = Statement {
+        keyword_span: (0, 0),
+        keyword: root_keyword,
+        argument_span: (0, 0),
+        argument: root.attribute("name").map(str::to_string),
+        statements: element_children(root)
+            .filter(|child| {
+                child.tag_name().namespace() == Some(YIN_NAMESPACE)
+                    && matches!(
+                        child.tag_name().name(),
+                        "belongs-to" | "extension" | "import" | "prefix"
+                    )
+            })
+            .map(|child| header_reader.read_statement(child))
+            .collect::<Result<Vec<_>, _>>()?,
+    };
+
+    let reader = YinReader {
+        resolver: ExtensionResolver::new(&header, context),
+    };
+    reader.read_statement(root)
+}
+
+#[cfg(all(test, feature = "cli"))]
+mod tests {
+    use super::*;
+    use crate::errors::ErrorContext;
+    use crate::format::{format_statement, FormatOptions};
+    use crate::parser::model::Module;
+    use crate::parser::model_mapper::Mapper;
+    use crate::parser::parser::parse;
+    use std::path::PathBuf;
+
+    fn yang_text(statement: &Statement) -> String {
+        format_statement(
+            statement,
+            &FormatOptions {
+                reflow_text: false,
+                sort_statements: false,
+                ..FormatOptions::default()
+            },
+        )
+    }
+
+    #[test]
+    fn test_roundtrip_corpus() {
+        let corpus_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/corpus");
+        for entry in std::fs::read_dir(corpus_dir).unwrap() {
+            let path = entry.unwrap().path();
+            let statement = parse(&std::fs::read_to_string(&path).unwrap()).unwrap();
+            let yin = write_yin(&statement, &YinContext::default());
+            let read_back = parse_yin(&yin, &YinContext::default())
+                .unwrap_or_else(|e| panic!("{}: {:?}\n{}", path.display(), e, yin));
+            assert_eq!(
+                yang_text(&read_back),
+                yang_text(&statement),
+                "{}",
+                path.display()
+            );
+        }
+    }
+
+    #[test]
+    fn test_write() {
+        let statement = parse(
+            r#"module m {
+                namespace "urn:m";
+                prefix m;
+                extension note { argument text { yin-element true; } }
+                extension tag { argument name; }
+                description "a < b & \"c\"";
+                container c { m:note "remark"; m:tag t; presence "x"; }
+            }"#,
+        )
+        .unwrap();
+        let yin = write_yin(&statement, &YinContext::default());
+        assert_eq!(
+            yin,
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<module name="m"
+        xmlns="urn:ietf:params:xml:ns:yang:yin:1"
+        xmlns:m="urn:m">
+  <namespace uri="urn:m"/>
+  <prefix value="m"/>
+  <extension name="note">
+    <argument name="text">
+      <yin-element value="true"/>
+    </argument>
+  </extension>
+  <extension name="tag">
+    <argument name="name"/>
+  </extension>
+  <description>
+    <text>a &lt; b &amp; "c"</text>
+  </description>
+  <container name="c">
+    <m:note>
+      <m:text>remark</m:text>
+    </m:note>
+    <m:tag name="t"/>
+    <presence value="x"/>
+  </container>
+</module>
+"#
+        );
+        assert_eq!(
+            yang_text(&parse_yin(&yin, &YinContext::default()).unwrap()),
+            yang_text(&statement)
+        );
+    }
+
+    #[test]
+    fn test_read_maps_to_model() {
+        let input = r#"<module name="acme-system"
+        xmlns="urn:ietf:params:xml:ns:yang:yin:1"
+        xmlns:acme="http://acme.example.com/system">
+  <namespace uri="http://acme.example.com/system"/>
+  <prefix value="acme"/>
+  <container name="system">
+    <leaf name="host-name">
+      <type name="string"/>
+    </leaf>
+  </container>
+</module>"#;
+        let statement = parse_yin(input, &YinContext::default()).unwrap();
+        let leaf = &statement.statements[2].statements[0];
+        assert_eq!(
+            &input[leaf.argument_span.0..leaf.argument_span.1],
+            "host-name"
+        );
+        assert_eq!(&input[leaf.keyword_span.0..leaf.keyword_span.1], "leaf");
+
+        let mut error_context = ErrorContext::new();
+        let module = Module::map(statement, &mut error_context).unwrap();
+        assert_eq!(module.container[0].leaf[0].name, "host-name");
+    }
+
+    #[test]
+    fn test_read_errors() {
+        let error = parse_yin("<module name=\"m\"/>", &YinContext::default()).unwrap_err();
+        assert_eq!(error.span, Some((0, 18)));
+        let error = parse_yin(
+            "<module xmlns=\"urn:ietf:params:xml:ns:yang:yin:1\"><leaf/></module>",
+            &YinContext::default(),
+        )
+        .unwrap_err();
+        assert_eq!(error.message, "Expected name attribute");
+        assert!(parse_yin("<module", &YinContext::default()).is_err());
+    }
+}